@@ -16,7 +16,7 @@ use std::sync::{atomic, Arc};
 use std::time::Instant;
 
 use clap::{App, Arg, ArgMatches, SubCommand};
-use psqlpack::{ChainedError, PsqlpackResult, Semver};
+use psqlpack::{ChainedError, PsqlpackResult, ReportFormat, Semver};
 use slog::{Drain, Logger};
 
 /// A thread safe toggle.
@@ -80,6 +80,26 @@ fn main() {
     let matches = App::new("psqlpack")
         .version(crate_version!())
         .author(crate_authors!())
+        .subcommand(
+            SubCommand::with_name("drift")
+                .about("Reports objects within the specified target that have changed outside of psqlpack")
+                .arg(
+                    Arg::with_name("SOURCE")
+                        .long("source")
+                        .short("s")
+                        .required(true)
+                        .takes_value(true)
+                        .help("The package that was last deployed to the target"),
+                )
+                .arg(
+                    Arg::with_name("TARGET")
+                        .long("target")
+                        .short("t")
+                        .required(true)
+                        .takes_value(true)
+                        .help("The database connection string to check for drift"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("extension")
                 .about("Creates a psqlpack from an extension installed on an existing database")
@@ -136,6 +156,18 @@ fn main() {
                         .help("The folder location to export the psqlpack to"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Lists the deployments that have been published to the specified target")
+                .arg(
+                    Arg::with_name("TARGET")
+                        .long("target")
+                        .short("t")
+                        .required(true)
+                        .takes_value(true)
+                        .help("The database connection string to list the deployments of"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("new")
                 .about("Creates a new project or publish profile based upon the specified template")
@@ -244,6 +276,15 @@ fn main() {
                         .required(true)
                         .takes_value(true)
                         .help("The report file to generate"),
+                )
+                .arg(
+                    Arg::with_name("FORMAT")
+                        .long("format")
+                        .short("f")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(&["json", "html", "markdown"])
+                        .help("The format of the report. Defaults to the extension of the report file"),
                 ),
         )
         .subcommand(
@@ -338,6 +379,14 @@ enum HandleResult {
 fn handle(log: &Logger, matches: &ArgMatches) -> HandleResult {
     // TODO: do some validation
     match matches.subcommand() {
+        (command @ "drift", Some(drift)) => {
+            let log = log.new(o!("command" => command.to_owned()));
+            // Source is the psqlpack, target is the DB
+            let source = Path::new(drift.value_of("SOURCE").unwrap());
+            let target = String::from(drift.value_of("TARGET").unwrap());
+            let result = operation::drift(log, source, &target);
+            HandleResult::Outcome(command.to_owned(), result)
+        }
         (command @ "extension", Some(extension)) => {
             let log = log.new(o!("command" => command.to_owned()));
             let source = String::from(extension.value_of("SOURCE").unwrap());
@@ -373,6 +422,12 @@ fn handle(log: &Logger, matches: &ArgMatches) -> HandleResult {
             let result = operation::extract_database(log, &source, output);
             HandleResult::Outcome(command.to_owned(), result)
         }
+        (command @ "history", Some(history)) => {
+            let log = log.new(o!("command" => command.to_owned()));
+            let target = String::from(history.value_of("TARGET").unwrap());
+            let result = operation::history(log, &target);
+            HandleResult::Outcome(command.to_owned(), result)
+        }
         (command @ "new", Some(new)) => {
             let log = log.new(o!("command" => command.to_owned()));
             let template = String::from(new.value_of("TEMPLATE").unwrap());
@@ -384,7 +439,7 @@ fn handle(log: &Logger, matches: &ArgMatches) -> HandleResult {
                 None => output.file_name().unwrap().to_str().unwrap().to_owned(),
             };
             info!(log, "Template name"; "name" => &name);
-            let result = operation::generate_template(log, &template, &output, &name);
+            let result = operation::generate_template(log, &template, output, &name);
             HandleResult::Outcome(command.to_owned(), result)
         }
         (command @ "package", Some(package)) => {
@@ -421,7 +476,14 @@ fn handle(log: &Logger, matches: &ArgMatches) -> HandleResult {
             let target = String::from(report.value_of("TARGET").unwrap());
             let profile = Path::new(report.value_of("PROFILE").unwrap());
             let output_file = Path::new(report.value_of("OUTPUT").unwrap());
-            let result = operation::generate_report(log, source, &target, profile, output_file);
+            let format = match report.value_of("FORMAT") {
+                Some(format) => match ReportFormat::from_str(format) {
+                    Ok(format) => format,
+                    Err(_) => return HandleResult::InvalidArgument("format".into(), "Unknown report format".into()),
+                },
+                None => ReportFormat::from_path(output_file),
+            };
+            let result = operation::generate_report(log, source, &target, profile, output_file, format);
             HandleResult::Outcome(command.to_owned(), result)
         }
         (command @ "script", Some(script)) => {
@@ -3,7 +3,8 @@ use std::path::Path;
 use slog::Logger;
 
 use psqlpack::{
-    template, Capabilities, Delta, Package, Project, PsqlpackErrorKind, PsqlpackResult, PublishProfile, Semver,
+    template, Capabilities, Delta, Deployment, Package, Project, PsqlpackErrorKind, PsqlpackResult, PublishProfile,
+    ReportFormat, Semver,
 };
 
 pub fn package<L: Into<Logger>>(log: L, project_file: &Path, output_path: &Path) -> PsqlpackResult<()> {
@@ -121,6 +122,47 @@ pub fn publish<L: Into<Logger>>(
     delta.apply(&log, &connection)
 }
 
+pub fn history<L: Into<Logger>>(log: L, target_connection_string: &str) -> PsqlpackResult<()> {
+    let log = log.into().new(o!("operation" => "history"));
+    let connection = target_connection_string.parse()?;
+
+    trace!(log, "Loading deployment history");
+    let deployments = Deployment::history(&log, &connection)?;
+    if deployments.is_empty() {
+        info!(log, "No deployments have been recorded");
+    }
+    for deployment in deployments {
+        info!(log, "Deployment {} {}", deployment.id, deployment.status;
+            "started_at" => deployment.started_at.to_rfc3339(),
+            "finished_at" => deployment.finished_at.map(|f| f.to_rfc3339()).unwrap_or_default(),
+            "deployed_by" => deployment.deployed_by,
+            "package_version" => deployment.package_version,
+            "package_source" => deployment.package_source,
+            "package_hash" => deployment.package_hash);
+    }
+    Ok(())
+}
+
+pub fn drift<L: Into<Logger>>(
+    log: L,
+    source_package_path: &Path,
+    target_connection_string: &str,
+) -> PsqlpackResult<()> {
+    let log = log.into().new(o!("operation" => "drift"));
+    let package = Package::from_path(&log, source_package_path)?;
+    let connection = target_connection_string.parse()?;
+
+    trace!(log, "Comparing Package with target");
+    let drift = package.compare(&log, &connection)?;
+    if drift.is_empty() {
+        info!(log, "No drift detected");
+    }
+    for item in drift {
+        warn!(log, "Drift detected: {}", item; "kind" => item.kind.to_string(), "object" => &item.object);
+    }
+    Ok(())
+}
+
 pub fn generate_sql<L: Into<Logger>>(
     log: L,
     source_package_path: &Path,
@@ -156,6 +198,7 @@ pub fn generate_report<L: Into<Logger>>(
     target_connection_string: &str,
     publish_profile: &Path,
     output_file: &Path,
+    format: ReportFormat,
 ) -> PsqlpackResult<()> {
     let log = log.into().new(o!("operation" => "generate_report"));
     let package = Package::from_path(&log, source_package_path)?;
@@ -176,5 +219,5 @@ pub fn generate_report<L: Into<Logger>>(
         &capabilities,
         &publish_profile,
     )?;
-    delta.write_report(output_file)
+    delta.write_report(&log, output_file, format)
 }
@@ -78,6 +78,33 @@ error_chain! {
         PackageQueryIndexesError {
             description("Couldn't query indexes")
         }
+        PackageQueryViewsError {
+            description("Couldn't query views")
+        }
+        PackageQueryTriggersError {
+            description("Couldn't query triggers")
+        }
+        PackageQueryPoliciesError {
+            description("Couldn't query policies")
+        }
+        PackageQueryGrantsError {
+            description("Couldn't query grants")
+        }
+        PackageQuerySequencesError {
+            description("Couldn't query sequences")
+        }
+        PackageQueryRenamesError {
+            description("Couldn't query applied renames")
+        }
+        PackageQueryRolesError {
+            description("Couldn't query roles")
+        }
+        QueryDeploymentsError {
+            description("Couldn't query deployment history")
+        }
+        RecordDeploymentError {
+            description("Couldn't record deployment history")
+        }
         PackageFunctionArgsInspectError(args: String) {
             description("Couldn't inspect function args")
             display("Couldn't inspect function args: {}", args)
@@ -86,6 +113,14 @@ error_chain! {
             description("Couldn't inspect function return type")
             display("Couldn't inspect function return type: {}", return_type)
         }
+        PackageTriggerInspectError(definition: String) {
+            description("Couldn't inspect trigger")
+            display("Couldn't inspect trigger: {}", definition)
+        }
+        PackagePartitionInspectError(definition: String) {
+            description("Couldn't inspect table partitioning")
+            display("Couldn't inspect table partitioning: {}", definition)
+        }
         PublishInvalidOperationError(message: String) {
             description("Couldn't publish database due to an invalid operation")
             display("Couldn't publish database due to an invalid operation: {}", message)
@@ -94,6 +129,10 @@ error_chain! {
             description("Unsafe Operation")
             display("Couldn't publish database due to an unsafe operation: {}", message)
         }
+        PublishPhaseError(phase: usize, phases: usize, name: String) {
+            description("Couldn't publish database")
+            display("Couldn't publish database during phase {} of {} ({})", phase, phases, name)
+        }
         GlobPatternError(err: PatternError) {
             description("An error in the glob pattern was found")
             display("An error in the glob pattern was found: {}", err)
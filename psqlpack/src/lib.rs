@@ -6,12 +6,16 @@ extern crate error_chain;
 extern crate lalrpop_util;
 #[macro_use]
 extern crate lazy_static;
+extern crate native_tls;
+extern crate percent_encoding;
 extern crate petgraph;
 extern crate postgres;
+extern crate postgres_native_tls;
 extern crate regex;
 extern crate rust_decimal;
 extern crate serde;
 extern crate serde_json;
+extern crate sha2;
 #[macro_use]
 extern crate slog;
 extern crate glob;
@@ -28,10 +32,12 @@ mod sql;
 pub mod ast {
     pub use crate::sql::ast::*;
 }
-pub use crate::connection::ConnectionBuilder;
+pub use crate::connection::{ConnectionBuilder, TlsMode};
 pub use crate::errors::{PsqlpackErrorKind, PsqlpackResult};
 pub use crate::model::{
-    template, Capabilities, Delta, Dependency, GenerationOptions, Package, Project, PublishProfile, Toggle,
+    template, Capabilities, Delta, Dependency, Deployment, DeploymentStatus, Drift, DriftKind, GenerationOptions,
+    Package, Project, PublishProfile, Report, ReportChange, ReportFormat, ReportObject, ReportSummary, Risk, Toggle,
+    REPORT_VERSION,
 };
 pub use crate::semver::Semver;
 
@@ -5,10 +5,13 @@ use crate::ast::*;
 use crate::connection::Connection;
 use crate::errors::PsqlpackErrorKind::*;
 use crate::errors::{PsqlpackError, PsqlpackResult, PsqlpackResultExt};
-use crate::model::Extension;
+use crate::model::{Extension, TypeDependent, TypeDependentKind};
 use crate::semver::Semver;
 use crate::sql::lexer;
-use crate::sql::parser::{FunctionArgumentListParser, FunctionReturnTypeParser, SqlTypeParser};
+use crate::sql::parser::{
+    ExpressionParser, FunctionArgumentListParser, FunctionReturnTypeParser, PartitionBoundParser, PartitionKeyParser,
+    SqlTypeParser, StatementListParser,
+};
 
 use postgres::row::Row;
 use postgres::types::{FromSql, Type};
@@ -72,10 +75,107 @@ impl Capabilities {
             .iter()
             .filter(|x| x.name.eq(name) && (version.is_none() || version.unwrap().eq(&x.version)))
             .collect::<Vec<_>>();
-        available.sort_by(|a, b| b.version.cmp(&a.version));
+        available.sort_by_key(|x| std::cmp::Reverse(x.version));
         available
     }
 
+    // Renames that have already been applied are recorded within the database by the deployment
+    pub fn renames(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<RenameDefinition>> {
+        let exists = client
+            .query(Q_REFACTOR_LOG_EXISTS, &[])
+            .chain_err(|| PackageQueryRenamesError)?
+            .iter()
+            .any(|row| row.get(0));
+        if !exists {
+            return Ok(Vec::new());
+        }
+        let mut renames = Vec::new();
+        for row in &client.query(Q_RENAMES, &[]).chain_err(|| PackageQueryRenamesError)? {
+            let definition: String = row.get(0);
+            renames.push(serde_json::from_str(&definition).chain_err(|| PackageQueryRenamesError)?);
+        }
+        Ok(renames)
+    }
+
+    // Roles are global to the cluster so are read directly rather than through the catalog of a database
+    pub fn roles(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<RoleDefinition>> {
+        // Bypassing row level security was introduced alongside it in Postgres 9.5
+        let query = if self.server_version < Semver::new(9, 5, None) {
+            Q_ROLES.replace("rolbypassrls", "false")
+        } else {
+            Q_ROLES.to_owned()
+        };
+        let roles = client
+            .query(&query[..], &[])
+            .chain_err(|| PackageQueryRolesError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(roles)
+    }
+
+    // Roles that psqlpack created are recorded within the database by the deployment
+    pub fn managed_roles(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<String>> {
+        let exists = client
+            .query(Q_MANAGED_ROLES_EXISTS, &[])
+            .chain_err(|| PackageQueryRolesError)?
+            .iter()
+            .any(|row| row.get(0));
+        if !exists {
+            return Ok(Vec::new());
+        }
+        let roles = client
+            .query(Q_MANAGED_ROLES, &[])
+            .chain_err(|| PackageQueryRolesError)?
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        Ok(roles)
+    }
+
+    // Postgres can't remove values from an enum so it is rebuilt instead, which first requires dropping the views and
+    // routines that depend on it. These are found by following the dependencies recorded in the catalog.
+    pub(crate) fn type_dependents(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TypeDependent>> {
+        // Postgres 11 replaced the aggregate and window flags with the kind of routine
+        let query = if self.server_version < Semver::new(11, 0, None) {
+            Q_TYPE_DEPENDENTS
+                .replace("p.prokind::text", "'f'")
+                .replace("p.prokind IN ('f', 'p')", "NOT p.proisagg AND NOT p.proiswindow")
+        } else {
+            Q_TYPE_DEPENDENTS.to_owned()
+        };
+        let mut dependents = Vec::new();
+        for row in &client.query(&query[..], &[]).chain_err(|| PackageQueryTypesError)? {
+            let name = ObjectName {
+                schema: Some(row.get(3)),
+                name: row.get(4),
+            };
+            let (kind, name) = match &row.get::<_, String>(2)[..] {
+                "v" => (TypeDependentKind::View, name.to_string()),
+                "m" => (TypeDependentKind::MaterializedView, name.to_string()),
+                kind => {
+                    let arguments = parse_function_arguments(row.get(5))?;
+                    let signature = signature(&name, &FunctionArgument::identity(&arguments));
+                    if kind == "p" {
+                        (TypeDependentKind::Procedure, signature)
+                    } else {
+                        (TypeDependentKind::Function, signature)
+                    }
+                }
+            };
+            dependents.push(TypeDependent {
+                type_name: ObjectName {
+                    schema: Some(row.get(0)),
+                    name: row.get(1),
+                },
+                kind,
+                name,
+                depth: row.get(6),
+            });
+        }
+        Ok(dependents)
+    }
+
     // I'm not incredibly happy with this name, but it'll work for now
     pub(crate) fn with_context<'a>(&'a self, extension: &'a Extension) -> ExtensionCapabilities<'a> {
         ExtensionCapabilities {
@@ -94,8 +194,15 @@ pub trait DefinableCatalog {
     fn schemata(&self, client: &mut PostgresClient, database: &str) -> PsqlpackResult<Vec<SchemaDefinition>>;
     fn types(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TypeDefinition>>;
     fn functions(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<FunctionDefinition>>;
+    fn procedures(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ProcedureDefinition>>;
     fn tables(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TableDefinition>>;
     fn indexes(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<IndexDefinition>>;
+    fn views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ViewDefinition>>;
+    fn materialized_views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<MaterializedViewDefinition>>;
+    fn triggers(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TriggerDefinition>>;
+    fn policies(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<PolicyDefinition>>;
+    fn grants(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<GrantDefinition>>;
+    fn sequences(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<SequenceDefinition>>;
 }
 
 impl DefinableCatalog for Capabilities {
@@ -121,16 +228,33 @@ impl DefinableCatalog for Capabilities {
 
     fn functions(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<FunctionDefinition>> {
         let mut functions = Vec::new();
+        let cte = functions_cte(self.server_version);
         let query = &client
-            .query(&format!("{} {}", CTE_FUNCTIONS, Q_CTE_STANDARD)[..], &[])
+            .query(&format!("{} {}", cte, Q_CTE_STANDARD)[..], &[])
             .chain_err(|| PackageQueryFunctionsError)?;
         for row in query {
-            let function = parse_function(&row)?;
+            let function = parse_function(row)?;
             functions.push(function);
         }
         Ok(functions)
     }
 
+    fn procedures(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ProcedureDefinition>> {
+        // Procedures were introduced in Postgres 11
+        if self.server_version < Semver::new(11, 0, None) {
+            return Ok(Vec::new());
+        }
+        let mut procedures = Vec::new();
+        let query = &client
+            .query(&format!("{} {}", CTE_PROCEDURES, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryFunctionsError)?;
+        for row in query {
+            let procedure = parse_procedure(row)?;
+            procedures.push(procedure);
+        }
+        Ok(procedures)
+    }
+
     fn tables(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TableDefinition>> {
         let mut tables = HashMap::new();
         let query = &client
@@ -155,6 +279,30 @@ impl DefinableCatalog for Capabilities {
             }
         }
 
+        // Identity columns were introduced in Postgres 10
+        if self.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_IDENTITY_SEQUENCES, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryColumnsError)?;
+            set_identity_options(&mut tables, query);
+        }
+
+        // Declarative partitioning was introduced in Postgres 10
+        if self.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_PARTITIONING, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryTablesError)?;
+            set_partitioning(&mut tables, query)?;
+        }
+
+        // Row level security was introduced in Postgres 9.5
+        if self.server_version >= Semver::new(9, 5, None) {
+            let query = &client
+                .query(&format!("{} {}", CTE_ROW_SECURITY, Q_CTE_STANDARD)[..], &[])
+                .chain_err(|| PackageQueryTablesError)?;
+            set_row_security(&mut tables, query);
+        }
+
         // Get a list of table constraints
         let query = &client
             .query(
@@ -169,7 +317,7 @@ impl DefinableCatalog for Capabilities {
             }
         }
 
-        Ok(tables.into_iter().map(|(_, b)| b).collect())
+        Ok(tables.into_values().collect())
     }
 
     fn indexes(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<IndexDefinition>> {
@@ -188,6 +336,98 @@ impl DefinableCatalog for Capabilities {
         }
         Ok(indexes)
     }
+
+    fn views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ViewDefinition>> {
+        let sources = view_sources(client)?;
+        let views = client
+            .query(&format!("{} {}", CTE_VIEWS, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryViewsError)?
+            .iter()
+            .map(|row| {
+                let mut view = ViewDefinition::from(row);
+                if let Some(query) = published_query(&sources, row) {
+                    view.query = query;
+                }
+                view
+            })
+            .collect();
+        Ok(views)
+    }
+
+    fn materialized_views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<MaterializedViewDefinition>> {
+        let sources = view_sources(client)?;
+        let views = client
+            .query(&format!("{} {}", CTE_MATERIALIZED_VIEWS, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryViewsError)?
+            .iter()
+            .map(|row| {
+                let mut view = MaterializedViewDefinition::from(row);
+                if let Some(query) = published_query(&sources, row) {
+                    view.query = query;
+                }
+                view
+            })
+            .collect();
+        Ok(views)
+    }
+
+    fn triggers(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TriggerDefinition>> {
+        let mut triggers = Vec::new();
+        let query = &client
+            .query(
+                &format!("{} {}", triggers_cte(self.server_version), Q_CTE_STANDARD)[..],
+                &[],
+            )
+            .chain_err(|| PackageQueryTriggersError)?;
+        for row in query {
+            let trigger = parse_trigger(row)?;
+            triggers.push(trigger);
+        }
+        Ok(triggers)
+    }
+
+    fn policies(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<PolicyDefinition>> {
+        // Row level security was introduced in Postgres 9.5 with restrictive policies following in Postgres 10
+        let cte = match self.server_version {
+            version if version < Semver::new(9, 5, None) => return Ok(Vec::new()),
+            version if version < Semver::new(10, 0, None) => CTE_POLICIES_95_THRU_96,
+            _ => CTE_POLICIES,
+        };
+        let policies = client
+            .query(&format!("{} {}", cte, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryPoliciesError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(policies)
+    }
+
+    fn grants(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<GrantDefinition>> {
+        let cte = if self.server_version < Semver::new(9, 6, None) {
+            CTE_GRANTS_94_THRU_95
+        } else {
+            CTE_GRANTS
+        };
+        let mut grants = Vec::new();
+        for row in &client
+            .query(&format!("{} {}", cte, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQueryGrantsError)?
+        {
+            grants.push(parse_grant(row)?);
+        }
+        Ok(grants)
+    }
+
+    fn sequences(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<SequenceDefinition>> {
+        let cte = sequences_cte(self.server_version);
+        let sequences = client
+            .query(&format!("{} {}", cte, Q_CTE_STANDARD)[..], &[])
+            .chain_err(|| PackageQuerySequencesError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(sequences)
+    }
 }
 
 impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
@@ -211,17 +451,34 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
 
     fn functions(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<FunctionDefinition>> {
         let mut functions = Vec::new();
+        let cte = functions_cte(self.capabilities.server_version);
+        let query = &client
+            .query(&format!("{} {}", cte, Q_CTE_EXTENSION)[..], &[&self.extension.name])
+            .chain_err(|| PackageQueryFunctionsError)?;
+        for row in query {
+            let function = parse_function(row)?;
+            functions.push(function);
+        }
+        Ok(functions)
+    }
+
+    fn procedures(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ProcedureDefinition>> {
+        // Procedures were introduced in Postgres 11
+        if self.capabilities.server_version < Semver::new(11, 0, None) {
+            return Ok(Vec::new());
+        }
+        let mut procedures = Vec::new();
         let query = &client
             .query(
-                &format!("{} {}", CTE_FUNCTIONS, Q_CTE_EXTENSION)[..],
+                &format!("{} {}", CTE_PROCEDURES, Q_CTE_EXTENSION)[..],
                 &[&self.extension.name],
             )
             .chain_err(|| PackageQueryFunctionsError)?;
         for row in query {
-            let function = parse_function(&row)?;
-            functions.push(function);
+            let procedure = parse_procedure(row)?;
+            procedures.push(procedure);
         }
-        Ok(functions)
+        Ok(procedures)
     }
 
     fn tables(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TableDefinition>> {
@@ -251,6 +508,39 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
             }
         }
 
+        // Identity columns were introduced in Postgres 10
+        if self.capabilities.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(
+                    &format!("{} {}", CTE_IDENTITY_SEQUENCES, Q_CTE_EXTENSION)[..],
+                    &[&self.extension.name],
+                )
+                .chain_err(|| PackageQueryColumnsError)?;
+            set_identity_options(&mut tables, query);
+        }
+
+        // Declarative partitioning was introduced in Postgres 10
+        if self.capabilities.server_version >= Semver::new(10, 0, None) {
+            let query = &client
+                .query(
+                    &format!("{} {}", CTE_PARTITIONING, Q_CTE_EXTENSION)[..],
+                    &[&self.extension.name],
+                )
+                .chain_err(|| PackageQueryTablesError)?;
+            set_partitioning(&mut tables, query)?;
+        }
+
+        // Row level security was introduced in Postgres 9.5
+        if self.capabilities.server_version >= Semver::new(9, 5, None) {
+            let query = &client
+                .query(
+                    &format!("{} {}", CTE_ROW_SECURITY, Q_CTE_EXTENSION)[..],
+                    &[&self.extension.name],
+                )
+                .chain_err(|| PackageQueryTablesError)?;
+            set_row_security(&mut tables, query);
+        }
+
         // Get a list of table constraints
         let query = &client
             .query(
@@ -265,7 +555,7 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
             }
         }
 
-        Ok(tables.into_iter().map(|(_, b)| b).collect())
+        Ok(tables.into_values().collect())
     }
 
     fn indexes(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<IndexDefinition>> {
@@ -284,6 +574,78 @@ impl<'a> DefinableCatalog for ExtensionCapabilities<'a> {
         }
         Ok(indexes)
     }
+
+    fn views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<ViewDefinition>> {
+        let views = client
+            .query(
+                &format!("{} {}", CTE_VIEWS, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryViewsError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(views)
+    }
+
+    fn materialized_views(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<MaterializedViewDefinition>> {
+        let views = client
+            .query(
+                &format!("{} {}", CTE_MATERIALIZED_VIEWS, Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryViewsError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(views)
+    }
+
+    fn triggers(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<TriggerDefinition>> {
+        let mut triggers = Vec::new();
+        let query = &client
+            .query(
+                &format!("{} {}", triggers_cte(self.capabilities.server_version), Q_CTE_EXTENSION)[..],
+                &[&self.extension.name],
+            )
+            .chain_err(|| PackageQueryTriggersError)?;
+        for row in query {
+            let trigger = parse_trigger(row)?;
+            triggers.push(trigger);
+        }
+        Ok(triggers)
+    }
+
+    fn policies(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<PolicyDefinition>> {
+        let cte = match self.capabilities.server_version {
+            version if version < Semver::new(9, 5, None) => return Ok(Vec::new()),
+            version if version < Semver::new(10, 0, None) => CTE_POLICIES_95_THRU_96,
+            _ => CTE_POLICIES,
+        };
+        let policies = client
+            .query(&format!("{} {}", cte, Q_CTE_EXTENSION)[..], &[&self.extension.name])
+            .chain_err(|| PackageQueryPoliciesError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(policies)
+    }
+
+    fn grants(&self, _client: &mut PostgresClient) -> PsqlpackResult<Vec<GrantDefinition>> {
+        // Privileges on extension objects are managed by whoever installs the extension
+        Ok(Vec::new())
+    }
+
+    fn sequences(&self, client: &mut PostgresClient) -> PsqlpackResult<Vec<SequenceDefinition>> {
+        let cte = sequences_cte(self.capabilities.server_version);
+        let sequences = client
+            .query(&format!("{} {}", cte, Q_CTE_EXTENSION)[..], &[&self.extension.name])
+            .chain_err(|| PackageQuerySequencesError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(sequences)
+    }
 }
 
 impl<'a> FromSql<'a> for Semver {
@@ -301,10 +663,11 @@ impl<'a> FromSql<'a> for Semver {
 static Q_DATABASE_EXISTS: &str = "SELECT 1 FROM pg_database WHERE datname=$1;";
 static Q_EXTENSIONS: &str = "SELECT name, version, installed, requires
                                      FROM pg_available_extension_versions ";
+// The partition key columns of a partitioned table have an internal dependency on the table itself
 static Q_CTE_STANDARD: &str = "
     SELECT c.*
     FROM cte c
-    WHERE NOT EXISTS (SELECT 1 FROM pg_depend WHERE pg_depend.objid=c.oid AND deptype IN ('e','i'))";
+    WHERE NOT EXISTS (SELECT 1 FROM pg_depend WHERE pg_depend.objid=c.oid AND pg_depend.refobjid<>c.oid AND deptype IN ('e','i'))";
 static Q_CTE_EXTENSION: &str = "
     SELECT c.*
     FROM cte c
@@ -312,7 +675,7 @@ static Q_CTE_EXTENSION: &str = "
     INNER JOIN pg_extension e ON d.refobjid = e.oid
     WHERE d.deptype = 'e' and e.extname = $1";
 
-impl<'row> From<&Row> for Extension {
+impl From<&Row> for Extension {
     fn from(row: &Row) -> Self {
         Extension {
             name: row.get(0),
@@ -322,58 +685,296 @@ impl<'row> From<&Row> for Extension {
     }
 }
 
-static Q_SCHEMAS: &str = "SELECT schema_name FROM information_schema.schemata
-                                  WHERE catalog_name = $1 AND schema_name !~* 'pg_|information_schema'";
-impl<'row> From<&Row> for SchemaDefinition {
+// The refactor log lives in the reserved `psqlpack` schema which is excluded from all catalog queries
+static Q_REFACTOR_LOG_EXISTS: &str = "SELECT to_regclass('psqlpack.refactor_log') IS NOT NULL";
+static Q_RENAMES: &str = "SELECT definition::text FROM psqlpack.refactor_log ORDER BY applied_at";
+
+// Roles are shared by every database within the cluster, so only the roles that psqlpack created are ever dropped
+static Q_MANAGED_ROLES_EXISTS: &str = "SELECT to_regclass('psqlpack.managed_roles') IS NOT NULL";
+static Q_MANAGED_ROLES: &str = "SELECT name FROM psqlpack.managed_roles ORDER BY name";
+
+// Dependencies on an enum are followed from the type (and its array type) to anything that depends on those objects
+// in turn. Views depend on other objects through their rewrite rule so rules are swapped for the view they belong to.
+// Table columns are followed by column so that only the views using a converted column are included.
+static Q_TYPE_DEPENDENTS: &str = "
+    WITH RECURSIVE dependents(type_oid, classid, objid, objsubid, depth) AS (
+        SELECT t.oid, o.classid, o.objid, o.objsubid, 1
+        FROM pg_type t
+        JOIN pg_depend d ON
+            d.refclassid = 'pg_type'::regclass AND d.refobjid IN (t.oid, t.typarray) AND d.deptype = 'n'
+        LEFT JOIN pg_rewrite r ON d.classid = 'pg_rewrite'::regclass AND r.oid = d.objid
+        CROSS JOIN LATERAL (
+            SELECT
+                CASE WHEN r.oid IS NULL THEN d.classid ELSE 'pg_class'::regclass::oid END AS classid,
+                COALESCE(r.ev_class, d.objid) AS objid,
+                CASE WHEN r.oid IS NULL THEN d.objsubid ELSE 0 END AS objsubid
+        ) o
+        WHERE t.typtype = 'e'
+        UNION
+        SELECT dep.type_oid, o.classid, o.objid, o.objsubid, dep.depth + 1
+        FROM dependents dep
+        JOIN pg_depend d ON
+            d.refclassid = dep.classid AND d.refobjid = dep.objid AND d.deptype = 'n' AND
+            (dep.objsubid = 0 OR d.refobjsubid IN (0, dep.objsubid))
+        LEFT JOIN pg_rewrite r ON d.classid = 'pg_rewrite'::regclass AND r.oid = d.objid
+        CROSS JOIN LATERAL (
+            SELECT
+                CASE WHEN r.oid IS NULL THEN d.classid ELSE 'pg_class'::regclass::oid END AS classid,
+                COALESCE(r.ev_class, d.objid) AS objid,
+                CASE WHEN r.oid IS NULL THEN d.objsubid ELSE 0 END AS objsubid
+        ) o
+        WHERE o.objid <> dep.objid AND dep.depth < 32
+    ), objects AS (
+        SELECT 'pg_class'::regclass::oid AS classid, c.oid, c.relkind::text AS kind, nspname::text,
+            relname::text AS name, '' AS arguments
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.relkind IN ('v', 'm')
+        UNION ALL
+        SELECT 'pg_proc'::regclass::oid, p.oid, p.prokind::text, nspname::text, proname::text,
+            pg_get_function_arguments(p.oid)
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE p.prokind IN ('f', 'p')
+    )
+    SELECT tn.nspname::text, t.typname::text, o.kind, o.nspname, o.name, o.arguments, max(dep.depth)
+    FROM dependents dep
+    JOIN objects o ON o.classid = dep.classid AND o.oid = dep.objid
+    JOIN pg_type t ON t.oid = dep.type_oid
+    JOIN pg_namespace tn ON tn.oid = t.typnamespace
+    WHERE o.nspname !~* 'pg_|information_schema|^psqlpack$'
+    GROUP BY tn.nspname, t.typname, o.kind, o.nspname, o.name, o.arguments
+    ORDER BY max(dep.depth) DESC, o.nspname, o.name";
+
+// Passwords are never read. Roles reserved by Postgres are excluded.
+static Q_ROLES: &str = "
+    SELECT
+        rolname::text,
+        rolcanlogin,
+        rolsuper,
+        rolcreatedb,
+        rolcreaterole,
+        rolinherit,
+        rolreplication,
+        rolbypassrls,
+        rolconnlimit,
+        ARRAY(
+            SELECT groups.rolname::text
+            FROM pg_auth_members
+            INNER JOIN pg_roles groups ON groups.oid = pg_auth_members.roleid
+            WHERE pg_auth_members.member = pg_roles.oid
+            ORDER BY groups.rolname
+        ) AS member_of
+    FROM pg_roles
+    WHERE rolname !~ '^pg_'
+    ORDER BY rolname";
+impl From<&Row> for RoleDefinition {
+    fn from(row: &Row) -> Self {
+        RoleDefinition {
+            name: row.get(0),
+            login: row.get(1),
+            superuser: row.get(2),
+            create_db: row.get(3),
+            create_role: row.get(4),
+            inherit: row.get(5),
+            replication: row.get(6),
+            bypass_rls: row.get(7),
+            connection_limit: row.get(8),
+            member_of: row.get(9),
+        }
+    }
+}
+
+// The public schema is created with a comment of its own which is left alone
+static Q_SCHEMAS: &str = "SELECT schema_name, schema_owner::text,
+                                  (SELECT NULLIF(obj_description(oid, 'pg_namespace'), 'standard public schema')
+                                   FROM pg_namespace WHERE nspname = schema_name)
+                                  FROM information_schema.schemata
+                                  WHERE catalog_name = $1 AND schema_name !~* 'pg_|information_schema|^psqlpack$'";
+impl From<&Row> for SchemaDefinition {
     fn from(row: &Row) -> Self {
-        SchemaDefinition { name: row.get(0) }
+        SchemaDefinition {
+            name: row.get(0),
+            owner: row.get(1),
+            comment: row.get(2),
+        }
     }
 }
 
 // Types: https://www.postgresql.org/docs/9.6/sql-createtype.html
-// typcategory: https://www.postgresql.org/docs/9.6/catalog-pg-type.html#CATALOG-TYPCATEGORY-TABLE
+// typtype: https://www.postgresql.org/docs/9.6/catalog-pg-type.html
 static CTE_TYPES: &str = "
     WITH cte AS (
-        SELECT pg_type.oid, typcategory, nspname, typname, array_agg(labels.enumlabel) AS enumlabels
+        SELECT
+            pg_type.oid,
+            pg_type.typtype,
+            nspname,
+            pg_type.typname,
+            ARRAY(
+                SELECT enumlabel
+                FROM pg_enum
+                WHERE enumtypid=pg_type.oid
+                ORDER BY enumsortorder
+            ) AS enumlabels,
+            ARRAY(
+                SELECT attname::text
+                FROM pg_attribute
+                WHERE attrelid=pg_type.typrelid AND attnum > 0 AND NOT attisdropped
+                ORDER BY attnum
+            ) AS attribute_names,
+            ARRAY(
+                SELECT format_type(atttypid, atttypmod)
+                FROM pg_attribute
+                WHERE attrelid=pg_type.typrelid AND attnum > 0 AND NOT attisdropped
+                ORDER BY attnum
+            ) AS attribute_types,
+            format_type(rngsubtype, NULL) AS range_subtype,
+            CASE WHEN opcdefault THEN NULL ELSE opcname END AS range_opclass,
+            CASE WHEN rngcollation = subtype.typcollation THEN NULL ELSE collname END AS range_collation,
+            (
+                SELECT nspname || '.' || proname
+                FROM pg_proc
+                INNER JOIN pg_namespace ON pg_namespace.oid=pronamespace
+                WHERE pg_proc.oid=rngcanonical
+            ) AS range_canonical,
+            (
+                SELECT nspname || '.' || proname
+                FROM pg_proc
+                INNER JOIN pg_namespace ON pg_namespace.oid=pronamespace
+                WHERE pg_proc.oid=rngsubdiff
+            ) AS range_subtype_diff,
+            format_type(pg_type.typbasetype, pg_type.typtypmod) AS domain_type,
+            pg_type.typnotnull AS domain_not_null,
+            pg_type.typdefault AS domain_default,
+            ARRAY(
+                SELECT conname::text
+                FROM pg_constraint
+                WHERE contypid=pg_type.oid AND contype='c'
+                ORDER BY conname
+            ) AS domain_constraint_names,
+            ARRAY(
+                SELECT pg_get_constraintdef(oid)
+                FROM pg_constraint
+                WHERE contypid=pg_type.oid AND contype='c'
+                ORDER BY conname
+            ) AS domain_constraint_definitions,
+            obj_description(pg_type.oid, 'pg_type') AS comment
         FROM pg_type
-        INNER JOIN pg_namespace ON pg_namespace.oid=typnamespace
-        LEFT JOIN (
-            SELECT enumtypid, enumlabel
-            FROM pg_catalog.pg_enum
-            ORDER BY enumtypid, enumsortorder
-        ) labels ON labels.enumtypid=pg_type.oid
+        INNER JOIN pg_namespace ON pg_namespace.oid=pg_type.typnamespace
+        LEFT JOIN pg_class ON pg_class.oid=pg_type.typrelid
+        LEFT JOIN pg_range ON pg_range.rngtypid=pg_type.oid
+        LEFT JOIN pg_opclass ON pg_opclass.oid=rngsubopc
+        LEFT JOIN pg_collation ON pg_collation.oid=rngcollation
+        LEFT JOIN pg_type subtype ON subtype.oid=rngsubtype
         WHERE
             -- exclude pg schemas and information catalog
-            nspname !~* 'pg_|information_schema' AND
+            nspname !~* 'pg_|information_schema|^psqlpack$' AND
             -- Types beginning with _ are auto created (e.g. arrays)
-            typname !~ '^_'
-        GROUP BY pg_type.oid, typcategory, nspname, typname
-        ORDER BY pg_type.oid, typcategory, nspname, typname
+            pg_type.typname !~ '^_' AND
+            -- Pseudo types and multiranges are implied
+            pg_type.typtype IN ('b', 'c', 'd', 'e', 'r') AND
+            -- Tables also define a composite type
+            (pg_type.typtype <> 'c' OR relkind = 'c')
+        ORDER BY pg_type.oid
     )
 ";
 
-impl<'row> From<&Row> for TypeDefinition {
+impl From<&Row> for TypeDefinition {
     fn from(row: &Row) -> Self {
-        let category: i8 = row.get(1);
-        let category = category as u8;
+        let typtype: i8 = row.get(1);
+        let typtype = typtype as u8;
         let schema = row.get(2);
         let name = row.get(3);
-        let kind = match category as char {
-            // TODO: All types
-            'C' => TypeDefinitionKind::Composite, // TODO add composite details
-            'E' => TypeDefinitionKind::Enum(row.get(4)),
-            'R' => TypeDefinitionKind::Range, // TODO add range details
-            'U' => TypeDefinitionKind::UserDefined,
-            kind => panic!("Unexpected kind: {}", kind),
+        let kind = match typtype as char {
+            'c' => {
+                let names: Vec<String> = row.get(5);
+                let types: Vec<String> = row.get(6);
+                TypeDefinitionKind::Composite(
+                    names
+                        .into_iter()
+                        .zip(types)
+                        .map(|(name, sql_type)| TypeAttribute {
+                            name,
+                            sql_type: sql_type.into(),
+                        })
+                        .collect(),
+                )
+            }
+            'd' => {
+                let sql_type: String = row.get(12);
+                let default: Option<String> = row.get(14);
+                let names: Vec<String> = row.get(15);
+                let definitions: Vec<String> = row.get(16);
+                TypeDefinitionKind::Domain(DomainDefinition {
+                    sql_type: sql_type.into(),
+                    default: default.and_then(|d| parse_expression(&d)),
+                    not_null: row.get(13),
+                    constraints: names
+                        .into_iter()
+                        .zip(definitions)
+                        .map(|(name, definition)| DomainConstraint {
+                            name: Some(name),
+                            expression: check_expression(&definition),
+                        })
+                        .collect(),
+                })
+            }
+            'e' => TypeDefinitionKind::Enum(row.get(4)),
+            'r' => {
+                let subtype: String = row.get(7);
+                let canonical: Option<String> = row.get(10);
+                let subtype_diff: Option<String> = row.get(11);
+                TypeDefinitionKind::Range(RangeDefinition {
+                    subtype: subtype.into(),
+                    subtype_opclass: row.get(8),
+                    collation: row.get(9),
+                    canonical: canonical.map(|c| qualified_name(&c)),
+                    subtype_diff: subtype_diff.map(|d| qualified_name(&d)),
+                })
+            }
+            _ => TypeDefinitionKind::UserDefined,
         };
 
         TypeDefinition {
             name: ObjectName { schema, name },
             kind,
+            comment: row.get(17),
         }
     }
 }
 
+fn qualified_name(name: &str) -> ObjectName {
+    match name.find('.') {
+        Some(index) => ObjectName {
+            schema: Some(name[..index].to_owned()),
+            name: name[index + 1..].to_owned(),
+        },
+        None => ObjectName {
+            schema: None,
+            name: name.to_owned(),
+        },
+    }
+}
+
+// Constraint definitions are in the form CHECK (expression) with optional trailing modifiers
+fn check_expression(definition: &str) -> String {
+    let definition = definition.trim();
+    let definition = definition.strip_suffix("NOT VALID").unwrap_or(definition).trim_end();
+    let definition = definition.strip_suffix("NO INHERIT").unwrap_or(definition).trim_end();
+    let definition = definition.strip_prefix("CHECK").unwrap_or(definition).trim_start();
+    match definition.strip_prefix('(').and_then(|d| d.strip_suffix(')')) {
+        Some(expression) => expression.trim().to_owned(),
+        None => definition.to_owned(),
+    }
+}
+
+// Defaults are stored as expressions. Anything we don't understand is ignored.
+fn parse_expression(value: &str) -> Option<Expression> {
+    lexer::tokenize_body(value)
+        .ok()
+        .and_then(|tokens| ExpressionParser::new().parse(tokens).ok())
+}
+
 static CTE_FUNCTIONS: &str = "
     WITH cte AS (
         SELECT
@@ -383,16 +984,75 @@ static CTE_FUNCTIONS: &str = "
             prosrc,
             pg_get_function_arguments(pg_proc.oid),
             lanname,
-            pg_get_function_result(pg_proc.oid)
+            pg_get_function_result(pg_proc.oid),
+            obj_description(pg_proc.oid, 'pg_proc') AS comment,
+            provolatile::text,
+            proisstrict,
+            prosecdef,
+            proparallel::text,
+            procost::text,
+            prorows::text,
+            proconfig
+        FROM pg_proc
+        JOIN pg_namespace ON
+            pg_namespace.oid = pg_proc.pronamespace
+        JOIN pg_language ON
+            pg_language.oid = pg_proc.prolang
+        WHERE nspname !~* 'pg_|information_schema|^psqlpack$' AND
+            proname !~ '^_' AND
+            prokind = 'f'
+    )";
+
+// Parallel safety was introduced in Postgres 9.6 while Postgres 11 replaced the aggregate and window flags with the
+// kind of routine
+fn functions_cte(server_version: Semver) -> String {
+    let mut cte = CTE_FUNCTIONS.to_owned();
+    if server_version < Semver::new(9, 6, None) {
+        cte = cte.replace("proparallel::text", "'u'");
+    }
+    if server_version < Semver::new(11, 0, None) {
+        cte = cte.replace("prokind = 'f'", "NOT proisagg AND NOT proiswindow");
+    }
+    cte
+}
+
+static CTE_PROCEDURES: &str = "
+    WITH cte AS (
+        SELECT
+            pg_proc.oid,
+            nspname,
+            proname,
+            prosrc,
+            pg_get_function_arguments(pg_proc.oid),
+            lanname,
+            prosecdef,
+            proconfig,
+            obj_description(pg_proc.oid, 'pg_proc') AS comment
         FROM pg_proc
         JOIN pg_namespace ON
             pg_namespace.oid = pg_proc.pronamespace
         JOIN pg_language ON
             pg_language.oid = pg_proc.prolang
-        WHERE nspname !~* 'pg_|information_schema' AND
-            proname !~ '^_'
+        WHERE nspname !~* 'pg_|information_schema|^psqlpack$' AND
+            proname !~ '^_' AND
+            prokind = 'p'
     )";
 
+fn lexical(err: lexer::LexicalError) -> PsqlpackError {
+    LexicalError(
+        err.reason.to_owned(),
+        err.line.to_owned(),
+        err.line_number,
+        err.start_pos,
+        err.end_pos,
+    )
+    .into()
+}
+
+fn parse(err: lalrpop_util::ParseError<(), lexer::Token, &'static str>) -> PsqlpackError {
+    InlineParseError(err).into()
+}
+
 fn parse_function(row: &Row) -> PsqlpackResult<FunctionDefinition> {
     let schema_name: String = row.get(1);
     let function_name: String = row.get(2);
@@ -402,40 +1062,25 @@ fn parse_function(row: &Row) -> PsqlpackResult<FunctionDefinition> {
     let raw_result: String = row.get(6);
 
     // Parse some of the results
-    let language = match &lan_name[..] {
-        "internal" => FunctionLanguage::Internal,
-        "c" => FunctionLanguage::C,
-        "sql" => FunctionLanguage::SQL,
-        _ => FunctionLanguage::PostgreSQL,
-    };
-
-    fn lexical(err: lexer::LexicalError) -> PsqlpackError {
-        LexicalError(
-            err.reason.to_owned(),
-            err.line.to_owned(),
-            err.line_number,
-            err.start_pos,
-            err.end_pos,
-        )
-        .into()
-    }
-    fn parse(err: lalrpop_util::ParseError<(), lexer::Token, &'static str>) -> PsqlpackError {
-        InlineParseError(err).into()
-    }
-
-    let function_args = if raw_args.is_empty() {
-        Vec::new()
-    } else {
-        lexer::tokenize_body(&raw_args)
-            .map_err(lexical)
-            .and_then(|tokens| FunctionArgumentListParser::new().parse(tokens).map_err(parse))
-            .chain_err(|| PackageFunctionArgsInspectError(raw_args))?
-    };
+    let language = parse_function_language(&lan_name);
+    let function_args = parse_function_arguments(raw_args)?;
     let return_type = lexer::tokenize_body(&raw_result)
         .map_err(&lexical)
         .and_then(|tokens| FunctionReturnTypeParser::new().parse(tokens).map_err(parse))
         .chain_err(|| PackageFunctionReturnTypeInspectError(raw_result))?;
 
+    let volatility = match &row.get::<_, String>(8)[..] {
+        "i" => FunctionVolatility::Immutable,
+        "s" => FunctionVolatility::Stable,
+        _ => FunctionVolatility::Volatile,
+    };
+    let parallel = match &row.get::<_, String>(11)[..] {
+        "s" => FunctionParallel::Safe,
+        "r" => FunctionParallel::Restricted,
+        _ => FunctionParallel::Unsafe,
+    };
+    let settings = parse_function_settings(row.get(14));
+
     // Set up the function definition
     Ok(FunctionDefinition {
         name: ObjectName {
@@ -446,6 +1091,85 @@ fn parse_function(row: &Row) -> PsqlpackResult<FunctionDefinition> {
         return_type,
         body: function_src,
         language,
+        volatility,
+        strict: row.get(9),
+        security_definer: row.get(10),
+        parallel,
+        cost: row.get::<_, String>(12).parse().ok(),
+        rows: row.get::<_, String>(13).parse().ok(),
+        settings,
+        comment: row.get(7),
+    })
+}
+
+fn parse_procedure(row: &Row) -> PsqlpackResult<ProcedureDefinition> {
+    let schema_name: String = row.get(1);
+    let procedure_name: String = row.get(2);
+    let lan_name: String = row.get(5);
+    Ok(ProcedureDefinition {
+        name: ObjectName {
+            schema: Some(schema_name),
+            name: procedure_name,
+        },
+        arguments: parse_function_arguments(row.get(4))?,
+        body: row.get(3),
+        language: parse_function_language(&lan_name),
+        security_definer: row.get(6),
+        settings: parse_function_settings(row.get(7)),
+        comment: row.get(8),
+    })
+}
+
+fn parse_function_language(lan_name: &str) -> FunctionLanguage {
+    match lan_name {
+        "internal" => FunctionLanguage::Internal,
+        "c" => FunctionLanguage::C,
+        "sql" => FunctionLanguage::SQL,
+        _ => FunctionLanguage::PostgreSQL,
+    }
+}
+
+fn parse_function_arguments(raw_args: String) -> PsqlpackResult<Vec<FunctionArgument>> {
+    if raw_args.is_empty() {
+        return Ok(Vec::new());
+    }
+    lexer::tokenize_body(&raw_args)
+        .map_err(lexical)
+        .and_then(|tokens| FunctionArgumentListParser::new().parse(tokens).map_err(parse))
+        .chain_err(|| PackageFunctionArgsInspectError(raw_args))
+}
+
+fn parse_function_settings(config: Option<Vec<String>>) -> Vec<FunctionSetting> {
+    config
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|config| parse_function_setting(config))
+        .collect()
+}
+
+// Configuration is stored as `name=value`. List parameters separate their values with a comma, quoting any
+// that need it.
+fn parse_function_setting(config: &str) -> Option<FunctionSetting> {
+    let (name, value) = config.split_once('=')?;
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => values.push(std::mem::take(&mut current).trim().to_owned()),
+            c => current.push(c),
+        }
+    }
+    values.push(current.trim().to_owned());
+    Some(FunctionSetting {
+        name: name.to_owned(),
+        values,
     })
 }
 
@@ -454,14 +1178,15 @@ static CTE_TABLES: &str = "
         SELECT
             pg_class.oid,
             nspname,
-            relname
+            relname,
+            obj_description(pg_class.oid, 'pg_class') AS comment
         FROM pg_class
         JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
-        WHERE pg_class.relkind='r' AND
-              nspname !~* 'pg_|information_schema'
+        WHERE pg_class.relkind IN ('r', 'p') AND
+              nspname !~* 'pg_|information_schema|^psqlpack$'
     )";
 
-impl<'row> From<&Row> for TableDefinition {
+impl From<&Row> for TableDefinition {
     fn from(row: &Row) -> Self {
         TableDefinition {
             name: ObjectName {
@@ -470,6 +1195,88 @@ impl<'row> From<&Row> for TableDefinition {
             },
             columns: Vec::new(),     // This gets loaded later
             constraints: Vec::new(), // This gets loaded later
+            partition_by: None,      // This gets loaded later
+            partition_of: None,      // This gets loaded later
+            row_security: false,
+            force_row_security: false,
+            comment: row.get(3),
+        }
+    }
+}
+
+// Declarative partitioning was introduced in Postgres 10
+static CTE_PARTITIONING: &str = "
+    WITH cte AS (
+        SELECT
+            pgc.oid,
+            CONCAT(ns.nspname, '.', pgc.relname) AS fqn,
+            CASE WHEN pgc.relkind = 'p' THEN pg_get_partkeydef(pgc.oid) END AS partition_key,
+            parent_ns.nspname AS parent_schema,
+            parent.relname AS parent_name,
+            pg_get_expr(pgc.relpartbound, pgc.oid) AS partition_bound
+        FROM pg_class pgc
+        INNER JOIN pg_namespace ns ON ns.oid = pgc.relnamespace
+        LEFT JOIN pg_inherits inh ON inh.inhrelid = pgc.oid AND pgc.relispartition
+        LEFT JOIN pg_class parent ON parent.oid = inh.inhparent
+        LEFT JOIN pg_namespace parent_ns ON parent_ns.oid = parent.relnamespace
+        WHERE (pgc.relkind = 'p' OR pgc.relispartition) AND ns.nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+fn set_partitioning(tables: &mut HashMap<String, TableDefinition>, rows: &[Row]) -> PsqlpackResult<()> {
+    for row in rows {
+        let fqn: String = row.get(1);
+        let table = match tables.get_mut(&fqn) {
+            Some(table) => table,
+            None => continue,
+        };
+        if let Some(key) = row.get::<_, Option<String>>(2) {
+            let partition_key = lexer::tokenize_partition(&key)
+                .map_err(lexical)
+                .and_then(|tokens| PartitionKeyParser::new().parse(tokens).map_err(parse))
+                .chain_err(|| PackagePartitionInspectError(key))?;
+            table.partition_by = Some(partition_key);
+        }
+        if let (Some(schema), Some(name), Some(bound)) = (
+            row.get::<_, Option<String>>(3),
+            row.get::<_, Option<String>>(4),
+            row.get::<_, Option<String>>(5),
+        ) {
+            let bound = lexer::tokenize_partition(&bound)
+                .map_err(lexical)
+                .and_then(|tokens| PartitionBoundParser::new().parse(tokens).map_err(parse))
+                .chain_err(|| PackagePartitionInspectError(bound))?;
+            table.partition_of = Some(PartitionOf {
+                parent: ObjectName {
+                    schema: Some(schema),
+                    name,
+                },
+                bound,
+            });
+        }
+    }
+    Ok(())
+}
+
+static CTE_ROW_SECURITY: &str = "
+    WITH cte AS (
+        SELECT
+            pgc.oid,
+            CONCAT(ns.nspname, '.', pgc.relname) AS fqn,
+            pgc.relrowsecurity,
+            pgc.relforcerowsecurity
+        FROM pg_class pgc
+        INNER JOIN pg_namespace ns ON ns.oid = pgc.relnamespace
+        WHERE pgc.relkind IN ('r', 'p') AND
+              (pgc.relrowsecurity OR pgc.relforcerowsecurity) AND
+              ns.nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+fn set_row_security(tables: &mut HashMap<String, TableDefinition>, rows: &[Row]) {
+    for row in rows {
+        let fqn: String = row.get(1);
+        if let Some(table) = tables.get_mut(&fqn) {
+            table.row_security = row.get(2);
+            table.force_row_security = row.get(3);
         }
     }
 }
@@ -496,37 +1303,110 @@ static CTE_COLUMNS: &str = "
             END AS data_type,
             a.attnotnull as notnull,
             coalesce(i.indisprimary,false) as primary_key,
-            pg_get_expr(def.adbin, def.adrelid) as default
+            pg_get_expr(def.adbin, def.adrelid) as default,
+            -- Identity and generated columns don't exist in older versions of Postgres
+            row_to_json(a) ->> 'attidentity' AS identity,
+            row_to_json(a) ->> 'attgenerated' AS generated,
+            col_description(a.attrelid, a.attnum) AS comment
         FROM pg_attribute a
         INNER JOIN pg_class pgc ON pgc.oid = a.attrelid
         INNER JOIN pg_namespace ns ON ns.oid = pgc.relnamespace
         LEFT JOIN pg_index i ON pgc.oid = i.indrelid AND i.indkey[0] = a.attnum
         LEFT JOIN pg_attrdef def ON a.attrelid = def.adrelid AND a.attnum = def.adnum
-        WHERE attnum > 0 AND pgc.relkind='r' AND NOT a.attisdropped AND ns.nspname !~* 'pg_|information_schema'
+        WHERE attnum > 0 AND pgc.relkind IN ('r', 'p') AND NOT a.attisdropped AND ns.nspname !~* 'pg_|information_schema|^psqlpack$'
         ORDER BY pgc.relname, a.attnum
     )";
 
-impl<'row> From<&Row> for ColumnDefinition {
+impl From<&Row> for ColumnDefinition {
     fn from(row: &Row) -> Self {
         // Do the column constraints first
         let mut constraints = Vec::new();
         let not_null: bool = row.get(7);
         let primary_key: bool = row.get(8);
-        // TODO: Default value + unique
+        // TODO: Unique
         constraints.push(if not_null {
             ColumnConstraint::NotNull
         } else {
             ColumnConstraint::Null
         });
+        let sql_type: String = row.get(6);
+        // Serial types imply their sequence default so there is nothing to compare
+        let serial = matches!(&sql_type[..], "serial" | "bigserial" | "smallserial");
+        let default: Option<String> = row.get(9);
+        let identity: Option<String> = row.get(10);
+        let generated: Option<String> = row.get(11);
+        // The expression of a generated column is stored as the default
+        if let Some(default) = default.filter(|_| !serial).as_deref().and_then(parse_expression) {
+            constraints.push(match generated.as_deref() {
+                Some("s") => ColumnConstraint::Generated(default),
+                _ => ColumnConstraint::Default(default),
+            });
+        }
+        // The sequence options are set once the identity sequences have been queried
+        let generation = match identity.as_deref() {
+            Some("a") => Some(IdentityGeneration::Always),
+            Some("d") => Some(IdentityGeneration::ByDefault),
+            _ => None,
+        };
+        if let Some(generation) = generation {
+            constraints.push(ColumnConstraint::Identity(
+                IdentityDefinition::new(generation, Vec::new()).unwrap(),
+            ));
+        }
         if primary_key {
             constraints.push(ColumnConstraint::PrimaryKey);
         }
-        let sql_type: String = row.get(6);
 
         ColumnDefinition {
             name: row.get(5),
             sql_type: sql_type.into(),
             constraints,
+            comment: row.get(12),
+        }
+    }
+}
+
+static CTE_IDENTITY_SEQUENCES: &str = "
+    WITH cte AS (
+        SELECT
+            tcls.oid,
+            CONCAT(tns.nspname, '.', tcls.relname) AS fqn,
+            att.attname::text AS column_name,
+            seq.seqincrement,
+            seq.seqmin,
+            seq.seqmax,
+            seq.seqstart,
+            seq.seqcache,
+            seq.seqcycle
+        FROM pg_sequence seq
+        INNER JOIN pg_depend dep ON dep.classid = 'pg_class'::regclass AND dep.objid = seq.seqrelid AND
+                                    dep.refclassid = 'pg_class'::regclass AND dep.deptype = 'i'
+        INNER JOIN pg_class tcls ON tcls.oid = dep.refobjid
+        INNER JOIN pg_namespace tns ON tns.oid = tcls.relnamespace
+        INNER JOIN pg_attribute att ON att.attrelid = dep.refobjid AND att.attnum = dep.refobjsubid
+        WHERE tns.nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+fn set_identity_options(tables: &mut HashMap<String, TableDefinition>, rows: &[Row]) {
+    for row in rows {
+        let fqn: String = row.get(1);
+        let column_name: String = row.get(2);
+        let identity = tables
+            .get_mut(&fqn)
+            .and_then(|table| table.columns.iter_mut().find(|column| column.name.eq(&column_name)))
+            .and_then(|column| {
+                column.constraints.iter_mut().find_map(|constraint| match constraint {
+                    ColumnConstraint::Identity(identity) => Some(identity),
+                    _ => None,
+                })
+            });
+        if let Some(identity) = identity {
+            identity.increment = Some(row.get(3));
+            identity.min_value = Some(row.get(4));
+            identity.max_value = Some(row.get(5));
+            identity.start = Some(row.get(6));
+            identity.cache = Some(row.get(7));
+            identity.cycle = row.get(8);
         }
     }
 }
@@ -535,42 +1415,46 @@ static CTE_TABLE_CONSTRAINTS: &str = "
     WITH cte AS (
         SELECT
             tcls.oid,
-            CONCAT(tc.table_schema, '.', tc.table_name) fqn,
-            tc.constraint_schema,
-            tc.table_name,
-            tc.constraint_type,
-            tc.constraint_name,
-            string_agg(DISTINCT kcu.column_name, ',') as column_names,
-            ccu.table_name as foreign_table_name,
-            string_agg(DISTINCT ccu.column_name, ',') as foreign_column_names,
-            pgcls.reloptions as pk_parameters,
-            confupdtype,
-            confdeltype,
-            confmatchtype::text
-        FROM information_schema.table_constraints as tc
-        JOIN (SELECT DISTINCT column_name, constraint_name, table_name, ordinal_position
-            FROM information_schema.key_column_usage
-            ORDER BY ordinal_position ASC) kcu ON kcu.constraint_name = tc.constraint_name AND kcu.table_name = tc.table_name
-        JOIN information_schema.constraint_column_usage as ccu on ccu.constraint_name = tc.constraint_name
-        JOIN pg_catalog.pg_namespace pgn ON pgn.nspname = tc.constraint_schema
-        JOIN pg_catalog.pg_namespace tn ON tn.nspname = tc.table_schema
-        JOIN pg_catalog.pg_class tcls ON tcls.relname=tc.table_name AND tcls.relnamespace=tn.oid
-        LEFT JOIN pg_catalog.pg_class pgcls ON pgcls.relname=tc.constraint_name AND pgcls.relnamespace = pgn.oid
-        LEFT JOIN pg_catalog.pg_constraint pgcon ON pgcon.conname=tc.constraint_name AND pgcon.connamespace = pgn.oid
+            CONCAT(tn.nspname, '.', tcls.relname) fqn,
+            tn.nspname::text AS table_schema,
+            tcls.relname::text AS table_name,
+            CASE con.contype
+                WHEN 'p' THEN 'PRIMARY KEY'
+                WHEN 'f' THEN 'FOREIGN KEY'
+                WHEN 'u' THEN 'UNIQUE'
+                WHEN 'c' THEN 'CHECK'
+                WHEN 'x' THEN 'EXCLUDE'
+            END AS constraint_type,
+            con.conname::text AS constraint_name,
+            ARRAY(
+                SELECT attname::text
+                FROM unnest(con.conkey) WITH ORDINALITY AS k(num, position)
+                INNER JOIN pg_attribute ON attrelid = con.conrelid AND attnum = k.num
+                ORDER BY k.position
+            ) AS column_names,
+            ftn.nspname::text AS foreign_table_schema,
+            fcls.relname::text AS foreign_table_name,
+            ARRAY(
+                SELECT attname::text
+                FROM unnest(con.confkey) WITH ORDINALITY AS k(num, position)
+                INNER JOIN pg_attribute ON attrelid = con.confrelid AND attnum = k.num
+                ORDER BY k.position
+            ) AS foreign_column_names,
+            icls.reloptions AS index_parameters,
+            con.confupdtype,
+            con.confdeltype,
+            con.confmatchtype::text,
+            pg_get_constraintdef(con.oid) AS definition
+        FROM pg_constraint con
+        INNER JOIN pg_class tcls ON tcls.oid = con.conrelid
+        INNER JOIN pg_namespace tn ON tn.oid = tcls.relnamespace
+        LEFT JOIN pg_class fcls ON fcls.oid = con.confrelid
+        LEFT JOIN pg_namespace ftn ON ftn.oid = fcls.relnamespace
+        -- Foreign keys also record the index of the referenced table so we only want our own
+        LEFT JOIN pg_class icls ON icls.oid = con.conindid AND con.contype IN ('p', 'u', 'x')
         WHERE
-            constraint_type in ('PRIMARY KEY','FOREIGN KEY')
-        GROUP BY
-            tcls.oid,
-            fqn,
-            tc.constraint_schema,
-            tc.table_name,
-            tc.constraint_type,
-            tc.constraint_name,
-            ccu.table_name,
-            pgcls.reloptions,
-            confupdtype,
-            confdeltype,
-            confmatchtype::text
+            con.contype IN ('p', 'f', 'u', 'c', 'x') AND
+            tn.nspname !~* 'pg_|information_schema|^psqlpack$'
     )";
 lazy_static! {
     static ref FILL_FACTOR: Regex = Regex::new("fillfactor=(\\d+)").unwrap();
@@ -582,11 +1466,7 @@ fn parse_index_parameters(raw_parameters: Option<Vec<String>>) -> Option<Vec<Ind
             // We only have one type at the moment
             if let Some(parameters) = parameters.first() {
                 let ff = FILL_FACTOR.captures(&parameters[..]);
-                if let Some(ff) = ff {
-                    Some(vec![IndexParameter::FillFactor(ff[1].parse::<u32>().unwrap())])
-                } else {
-                    None
-                }
+                ff.map(|ff| vec![IndexParameter::FillFactor(ff[1].parse::<u32>().unwrap())])
             } else {
                 None
             }
@@ -595,29 +1475,23 @@ fn parse_index_parameters(raw_parameters: Option<Vec<String>>) -> Option<Vec<Ind
     }
 }
 
-impl<'row> From<&Row> for TableConstraint {
+impl From<&Row> for TableConstraint {
     fn from(row: &Row) -> Self {
-        let schema: String = row.get(2);
         let constraint_type: String = row.get(4);
         let constraint_name: String = row.get(5);
-
-        let raw_column_names: String = row.get(6);
-        let column_names: Vec<String> = raw_column_names.split_terminator(',').map(|s| s.into()).collect();
+        let column_names: Vec<String> = row.get(6);
 
         match &constraint_type[..] {
             "PRIMARY KEY" => TableConstraint::Primary {
                 name: constraint_name,
                 columns: column_names,
-                parameters: parse_index_parameters(row.get(9)),
+                parameters: parse_index_parameters(row.get(10)),
             },
             "FOREIGN KEY" => {
-                let foreign_table_name: String = row.get(7);
-                let raw_foreign_column_names: String = row.get(8);
-                let foreign_column_names: Vec<String> = raw_foreign_column_names
-                    .split_terminator(',')
-                    .map(|s| s.into())
-                    .collect();
-                let ev: String = row.get(12);
+                let foreign_table_schema: String = row.get(7);
+                let foreign_table_name: String = row.get(8);
+                let foreign_column_names: Vec<String> = row.get(9);
+                let ev: String = row.get(13);
                 let match_type = match &ev[..] {
                     "f" => Some(ForeignConstraintMatchType::Full),
                     "s" => Some(ForeignConstraintMatchType::Simple),
@@ -626,7 +1500,7 @@ impl<'row> From<&Row> for TableConstraint {
                 };
 
                 let mut events = Vec::new();
-                let update_event: i8 = row.get(10);
+                let update_event: i8 = row.get(11);
                 match update_event as u8 as char {
                     'r' => events.push(ForeignConstraintEvent::Update(ForeignConstraintAction::Restrict)),
                     'c' => events.push(ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade)),
@@ -635,7 +1509,7 @@ impl<'row> From<&Row> for TableConstraint {
                     'a' => events.push(ForeignConstraintEvent::Update(ForeignConstraintAction::NoAction)),
                     _ => {}
                 }
-                let delete_event: i8 = row.get(11);
+                let delete_event: i8 = row.get(12);
                 match delete_event as u8 as char {
                     'r' => events.push(ForeignConstraintEvent::Delete(ForeignConstraintAction::Restrict)),
                     'c' => events.push(ForeignConstraintEvent::Delete(ForeignConstraintAction::Cascade)),
@@ -649,7 +1523,7 @@ impl<'row> From<&Row> for TableConstraint {
                     name: constraint_name,
                     columns: column_names,
                     ref_table: ObjectName {
-                        schema: Some(schema),
+                        schema: Some(foreign_table_schema),
                         name: foreign_table_name,
                     },
                     ref_columns: foreign_column_names,
@@ -657,11 +1531,87 @@ impl<'row> From<&Row> for TableConstraint {
                     events: if events.is_empty() { None } else { Some(events) },
                 }
             }
+            "UNIQUE" => TableConstraint::Unique {
+                name: constraint_name,
+                columns: column_names,
+                parameters: parse_index_parameters(row.get(10)),
+            },
+            "CHECK" => {
+                let definition: String = row.get(14);
+                let modifiers = definition
+                    .trim()
+                    .strip_suffix("NOT VALID")
+                    .unwrap_or(&definition)
+                    .trim_end();
+                TableConstraint::Check {
+                    name: constraint_name,
+                    expression: check_expression(&definition),
+                    no_inherit: modifiers.ends_with("NO INHERIT"),
+                }
+            }
+            "EXCLUDE" => {
+                let definition: String = row.get(14);
+                let (index_type, elements, condition) = exclusion_constraint(&definition);
+                TableConstraint::Exclude {
+                    name: constraint_name,
+                    index_type,
+                    elements,
+                    parameters: parse_index_parameters(row.get(10)),
+                    condition,
+                }
+            }
             unknown => panic!("Unknown constraint type: {}", unknown),
         }
     }
 }
 
+// Exclusion constraints are in the form EXCLUDE USING method (elements) [WITH (options)] [WHERE (predicate)]
+fn exclusion_constraint(definition: &str) -> (Option<IndexType>, Vec<ExclusionElement>, Option<String>) {
+    // Returns the contents of the brackets starting at the given position along with the remainder
+    fn bracketed(text: &str) -> (&str, &str) {
+        let mut depth = 0;
+        let mut quote = None;
+        for (i, c) in text.char_indices() {
+            match quote {
+                Some(q) if q == c => quote = None,
+                Some(_) => {}
+                None => match c {
+                    '\'' | '"' => quote = Some(c),
+                    '(' => depth += 1,
+                    ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return (&text[1..i], &text[i + 1..]);
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+        (text, "")
+    }
+
+    let start = definition.find('(').unwrap_or(definition.len());
+    let index_type = match &definition[..start]
+        .trim()
+        .rsplit(' ')
+        .next()
+        .unwrap_or("")
+        .to_lowercase()[..]
+    {
+        "btree" => Some(IndexType::BTree),
+        "gin" => Some(IndexType::Gin),
+        "gist" => Some(IndexType::Gist),
+        "hash" => Some(IndexType::Hash),
+        _ => None,
+    };
+    let (elements, remainder) = bracketed(&definition[start..]);
+    let condition = remainder
+        .find("WHERE (")
+        .map(|position| bracketed(&remainder[position + 6..]).0.trim().to_owned());
+    (index_type, ExclusionElement::parse_list(elements), condition)
+}
+
 static CTE_INDEXES_94_THRU_96: &str = "
     WITH cte AS (
         SELECT
@@ -684,13 +1634,16 @@ static CTE_INDEXES_94_THRU_96: &str = "
                     generate_subscripts(idx.indkey, 1) AS k
                 ORDER BY k
             ) AS index_keys,
-            ic.reloptions AS storage_parameters
+            ic.reloptions AS storage_parameters,
+            obj_description(ic.oid, 'pg_class') AS comment
         FROM pg_index AS idx
         JOIN pg_class AS ic ON ic.oid = idx.indexrelid
         JOIN pg_am AS am ON ic.relam = am.oid
         JOIN pg_namespace AS ns ON ic.relnamespace = ns.OID
         JOIN pg_class AS tc ON tc.oid = idx.indrelid
-        WHERE ns.nspname !~* 'pg_|information_schema' AND idx.indisprimary = false
+        WHERE ns.nspname !~* 'pg_|information_schema|^psqlpack$' AND idx.indisprimary = false AND
+            -- Unique and exclusion constraints are backed by an index which is managed by the constraint
+            NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = idx.indexrelid AND con.contype IN ('u', 'x'))
     )
 ";
 
@@ -717,17 +1670,22 @@ static CTE_INDEXES: &str = "
                     generate_subscripts(idx.indkey, 1) AS k
                 ORDER BY k
             ) AS index_keys,
-            ic.reloptions AS storage_parameters
+            ic.reloptions AS storage_parameters,
+            obj_description(ic.oid, 'pg_class') AS comment
         FROM pg_index AS idx
         JOIN pg_class AS ic ON ic.oid = idx.indexrelid
         JOIN pg_am AS am ON ic.relam = am.oid
         JOIN pg_namespace AS ns ON ic.relnamespace = ns.OID
         JOIN pg_class AS tc ON tc.oid = idx.indrelid
-        WHERE ns.nspname !~* 'pg_|information_schema' AND idx.indisprimary = false
+        WHERE ns.nspname !~* 'pg_|information_schema|^psqlpack$' AND idx.indisprimary = false AND
+            -- Unique and exclusion constraints are backed by an index which is managed by the constraint
+            NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = idx.indexrelid AND con.contype IN ('u', 'x')) AND
+            -- Indexes of partitions are managed by the index of the partitioned table
+            NOT EXISTS (SELECT 1 FROM pg_inherits WHERE pg_inherits.inhrelid = idx.indexrelid)
     )
 ";
 
-impl<'row> From<&Row> for IndexDefinition {
+impl From<&Row> for IndexDefinition {
     fn from(row: &Row) -> Self {
         let schema: String = row.get(1);
         let table: String = row.get(2);
@@ -785,6 +1743,521 @@ impl<'row> From<&Row> for IndexDefinition {
             index_type,
 
             storage_parameters,
+            comment: row.get(8),
+        }
+    }
+}
+
+// Postgres only keeps the parse tree of a view, describing it with a query that differs from the one that was
+// published. The published query is recorded alongside that description when psqlpack creates the view, and is used
+// for as long as the description still matches (i.e. the view hasn't been changed outside of psqlpack).
+static Q_VIEW_SOURCES_EXISTS: &str = "SELECT to_regclass('psqlpack.view_sources') IS NOT NULL";
+static Q_VIEW_SOURCES: &str = "SELECT view_oid, query, definition FROM psqlpack.view_sources";
+
+fn view_sources(client: &mut PostgresClient) -> PsqlpackResult<HashMap<u32, (String, String)>> {
+    let exists = client
+        .query(Q_VIEW_SOURCES_EXISTS, &[])
+        .chain_err(|| PackageQueryViewsError)?
+        .iter()
+        .any(|row| row.get(0));
+    if !exists {
+        return Ok(HashMap::new());
+    }
+    let sources = client
+        .query(Q_VIEW_SOURCES, &[])
+        .chain_err(|| PackageQueryViewsError)?
+        .iter()
+        .map(|row| (row.get(0), (row.get(1), row.get(2))))
+        .collect();
+    Ok(sources)
+}
+
+fn published_query(sources: &HashMap<u32, (String, String)>, row: &Row) -> Option<String> {
+    let oid: u32 = row.get(0);
+    let definition: String = row.get(3);
+    sources
+        .get(&oid)
+        .filter(|(_, recorded)| recorded.eq(&definition))
+        .map(|(query, _)| query.to_owned())
+}
+
+static CTE_VIEWS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            nspname,
+            relname,
+            pg_get_viewdef(pg_class.oid),
+            obj_description(pg_class.oid, 'pg_class') AS comment,
+            ARRAY(
+                SELECT attname::text
+                FROM pg_attribute
+                WHERE attrelid = pg_class.oid AND attnum > 0 AND NOT attisdropped
+                ORDER BY attnum
+            ) AS columns
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE pg_class.relkind='v' AND
+              nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+impl From<&Row> for ViewDefinition {
+    fn from(row: &Row) -> Self {
+        let query: String = row.get(3);
+        ViewDefinition {
+            name: ObjectName {
+                schema: Some(row.get(1)),
+                name: row.get(2),
+            },
+            columns: Some(row.get(5)),
+            query: query.trim().trim_end_matches(';').to_owned(),
+            comment: row.get(4),
+        }
+    }
+}
+
+static CTE_MATERIALIZED_VIEWS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_class.oid,
+            nspname,
+            relname,
+            pg_get_viewdef(pg_class.oid),
+            obj_description(pg_class.oid, 'pg_class') AS comment,
+            ARRAY(
+                SELECT attname::text
+                FROM pg_attribute
+                WHERE attrelid = pg_class.oid AND attnum > 0 AND NOT attisdropped
+                ORDER BY attnum
+            ) AS columns
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE pg_class.relkind='m' AND
+              nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+impl From<&Row> for MaterializedViewDefinition {
+    fn from(row: &Row) -> Self {
+        let query: String = row.get(3);
+        MaterializedViewDefinition {
+            name: ObjectName {
+                schema: Some(row.get(1)),
+                name: row.get(2),
+            },
+            columns: Some(row.get(5)),
+            query: query.trim().trim_end_matches(';').to_owned(),
+            comment: row.get(4),
+        }
+    }
+}
+
+static CTE_TRIGGERS: &str = "
+    WITH cte AS (
+        SELECT
+            pg_trigger.oid,
+            table_namespace.nspname AS table_schema,
+            relname,
+            function_namespace.nspname AS function_schema,
+            proname,
+            pg_get_triggerdef(pg_trigger.oid)
+        FROM pg_trigger
+        JOIN pg_class ON pg_class.oid = pg_trigger.tgrelid
+        JOIN pg_namespace table_namespace ON table_namespace.oid = pg_class.relnamespace
+        JOIN pg_proc ON pg_proc.oid = pg_trigger.tgfoid
+        JOIN pg_namespace function_namespace ON function_namespace.oid = pg_proc.pronamespace
+        WHERE NOT pg_trigger.tgisinternal AND
+              pg_trigger.tgconstraint = 0 AND
+              pg_trigger.tgparentid = 0 AND
+              table_namespace.nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+// Triggers on a partitioned table are cloned onto each partition. From Postgres 13 the clones are no longer internal
+// and refer to their parent trigger, whereas before then a clone shares the name of a trigger on the parent table.
+fn triggers_cte(server_version: Semver) -> String {
+    let clones = if server_version < Semver::new(10, 0, None) {
+        ""
+    } else if server_version < Semver::new(13, 0, None) {
+        "NOT (pg_class.relispartition AND EXISTS (
+                  SELECT 1 FROM pg_inherits
+                  JOIN pg_trigger parent_trigger ON parent_trigger.tgrelid = pg_inherits.inhparent
+                  WHERE pg_inherits.inhrelid = pg_class.oid AND parent_trigger.tgname = pg_trigger.tgname)) AND"
+    } else {
+        return CTE_TRIGGERS.to_owned();
+    };
+    CTE_TRIGGERS.replace("pg_trigger.tgparentid = 0 AND", clones)
+}
+
+fn parse_trigger(row: &Row) -> PsqlpackResult<TriggerDefinition> {
+    let table_schema: String = row.get(1);
+    let table_name: String = row.get(2);
+    let function_schema: String = row.get(3);
+    let function_name: String = row.get(4);
+    let raw_definition: String = row.get(5);
+
+    let statements = lexer::tokenize_stmt(&raw_definition)
+        .map_err(lexical)
+        .and_then(|tokens| StatementListParser::new().parse(tokens).map_err(parse))
+        .chain_err(|| PackageTriggerInspectError(raw_definition.to_owned()))?;
+    let mut trigger = match statements.into_iter().next() {
+        Some(Statement::Trigger(trigger)) => trigger,
+        _ => bail!(PackageTriggerInspectError(raw_definition)),
+    };
+
+    // The definition only qualifies names outside of the search path so we use the catalog instead
+    trigger.table = ObjectName {
+        schema: Some(table_schema),
+        name: table_name,
+    };
+    trigger.function = ObjectName {
+        schema: Some(function_schema),
+        name: function_name,
+    };
+    Ok(trigger)
+}
+
+static CTE_POLICIES: &str = "
+    WITH cte AS (
+        SELECT
+            pg_policy.oid,
+            polname,
+            nspname,
+            relname,
+            polpermissive,
+            polcmd::text,
+            CASE WHEN polroles = '{0}' THEN ARRAY['public']
+                 ELSE ARRAY(SELECT rolname::text FROM pg_roles WHERE pg_roles.oid = ANY(polroles) ORDER BY rolname)
+            END AS roles,
+            pg_get_expr(polqual, polrelid) AS using_expression,
+            pg_get_expr(polwithcheck, polrelid) AS check_expression
+        FROM pg_policy
+        JOIN pg_class ON pg_class.oid = pg_policy.polrelid
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+// Restrictive policies were introduced in Postgres 10
+static CTE_POLICIES_95_THRU_96: &str = "
+    WITH cte AS (
+        SELECT
+            pg_policy.oid,
+            polname,
+            nspname,
+            relname,
+            true AS polpermissive,
+            polcmd::text,
+            CASE WHEN polroles = '{0}' THEN ARRAY['public']
+                 ELSE ARRAY(SELECT rolname::text FROM pg_roles WHERE pg_roles.oid = ANY(polroles) ORDER BY rolname)
+            END AS roles,
+            pg_get_expr(polqual, polrelid) AS using_expression,
+            pg_get_expr(polwithcheck, polrelid) AS check_expression
+        FROM pg_policy
+        JOIN pg_class ON pg_class.oid = pg_policy.polrelid
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE nspname !~* 'pg_|information_schema|^psqlpack$'
+    )";
+
+impl From<&Row> for PolicyDefinition {
+    fn from(row: &Row) -> Self {
+        let command: String = row.get(5);
+        PolicyDefinition {
+            name: row.get(1),
+            table: ObjectName {
+                schema: Some(row.get(2)),
+                name: row.get(3),
+            },
+            permissive: row.get(4),
+            command: match &command[..] {
+                "r" => PolicyCommand::Select,
+                "a" => PolicyCommand::Insert,
+                "w" => PolicyCommand::Update,
+                "d" => PolicyCommand::Delete,
+                _ => PolicyCommand::All,
+            },
+            roles: row.get(6),
+            using: row.get(7),
+            with_check: row.get(8),
+        }
+    }
+}
+
+// Only privileges that have been granted explicitly are extracted. Those the owner holds, the defaults that
+// Postgres grants to PUBLIC (e.g. EXECUTE on functions) and the initial privileges of objects created by initdb
+// (e.g. USAGE on the public schema) are left alone.
+static CTE_GRANTS: &str = "
+    WITH acl AS (
+        SELECT
+            pg_class.oid,
+            CASE WHEN relkind = 'S' THEN 'sequence' ELSE 'table' END AS kind,
+            nspname,
+            relname AS name,
+            relowner AS owner,
+            relacl AS acl,
+            acldefault(CASE WHEN relkind = 'S' THEN 's' ELSE 'r' END::\"char\", relowner) ||
+                COALESCE(initprivs, '{}') AS defaults,
+            NULL::text AS arguments
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        LEFT JOIN pg_init_privs ON objoid = pg_class.oid AND classoid = 'pg_class'::regclass AND objsubid = 0
+        WHERE relkind IN ('r', 'p', 'v', 'm', 'f', 'S') AND relacl IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_namespace.oid, 'schema', nspname, nspname, nspowner, nspacl,
+            acldefault('n', nspowner) || COALESCE(initprivs, '{}'),
+            NULL
+        FROM pg_namespace
+        LEFT JOIN pg_init_privs ON objoid = pg_namespace.oid AND classoid = 'pg_namespace'::regclass
+        WHERE nspacl IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_proc.oid, 'function', nspname, proname, proowner, proacl,
+            acldefault('f', proowner) || COALESCE(initprivs, '{}'),
+            pg_get_function_arguments(pg_proc.oid)
+        FROM pg_proc
+        JOIN pg_namespace ON pg_namespace.oid = pg_proc.pronamespace
+        LEFT JOIN pg_init_privs ON objoid = pg_proc.oid AND classoid = 'pg_proc'::regclass
+        WHERE proacl IS NOT NULL AND proname !~ '^_'
+    ),
+    cte AS (
+        SELECT
+            acl.oid,
+            acl.kind,
+            acl.nspname::text,
+            acl.name::text,
+            COALESCE(pg_roles.rolname::text, 'public') AS grantee,
+            privileges.is_grantable,
+            ARRAY_AGG(privileges.privilege_type::text ORDER BY privileges.privilege_type) AS privileges,
+            acl.arguments
+        FROM acl
+        CROSS JOIN LATERAL aclexplode(acl.acl) AS privileges
+        LEFT JOIN pg_roles ON pg_roles.oid = privileges.grantee
+        WHERE acl.nspname !~* 'pg_|information_schema|^psqlpack$' AND
+            privileges.grantee <> acl.owner AND
+            (privileges.grantee = 0 OR pg_roles.oid IS NOT NULL) AND
+            NOT EXISTS (
+                SELECT 1
+                FROM aclexplode(acl.defaults) AS defaults
+                WHERE defaults.grantee = privileges.grantee AND
+                    defaults.privilege_type = privileges.privilege_type AND
+                    defaults.is_grantable = privileges.is_grantable
+            )
+        GROUP BY acl.oid, acl.kind, acl.nspname, acl.name, acl.arguments, pg_roles.rolname, privileges.is_grantable
+    )";
+
+// Initial privileges were introduced in Postgres 9.6. Before then the only initial privileges that initdb grants
+// are USAGE and CREATE on the public schema.
+static CTE_GRANTS_94_THRU_95: &str = "
+    WITH acl AS (
+        SELECT
+            pg_class.oid,
+            CASE WHEN relkind = 'S' THEN 'sequence' ELSE 'table' END AS kind,
+            nspname,
+            relname AS name,
+            relowner AS owner,
+            relacl AS acl,
+            acldefault(CASE WHEN relkind = 'S' THEN 's' ELSE 'r' END::\"char\", relowner) AS defaults,
+            NULL::text AS arguments
+        FROM pg_class
+        JOIN pg_namespace ON pg_namespace.oid = pg_class.relnamespace
+        WHERE relkind IN ('r', 'p', 'v', 'm', 'f', 'S') AND relacl IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_namespace.oid, 'schema', nspname, nspname, nspowner, nspacl,
+            acldefault('n', nspowner) || CASE WHEN nspname = 'public'
+                THEN ARRAY[makeaclitem(0, nspowner, 'USAGE', false), makeaclitem(0, nspowner, 'CREATE', false)]
+                ELSE '{}'
+            END,
+            NULL
+        FROM pg_namespace
+        WHERE nspacl IS NOT NULL
+        UNION ALL
+        SELECT
+            pg_proc.oid, 'function', nspname, proname, proowner, proacl,
+            acldefault('f', proowner),
+            pg_get_function_arguments(pg_proc.oid)
+        FROM pg_proc
+        JOIN pg_namespace ON pg_namespace.oid = pg_proc.pronamespace
+        WHERE proacl IS NOT NULL AND proname !~ '^_'
+    ),
+    cte AS (
+        SELECT
+            acl.oid,
+            acl.kind,
+            acl.nspname::text,
+            acl.name::text,
+            COALESCE(pg_roles.rolname::text, 'public') AS grantee,
+            privileges.is_grantable,
+            ARRAY_AGG(privileges.privilege_type::text ORDER BY privileges.privilege_type) AS privileges,
+            acl.arguments
+        FROM acl
+        CROSS JOIN LATERAL aclexplode(acl.acl) AS privileges
+        LEFT JOIN pg_roles ON pg_roles.oid = privileges.grantee
+        WHERE acl.nspname !~* 'pg_|information_schema|^psqlpack$' AND
+            privileges.grantee <> acl.owner AND
+            (privileges.grantee = 0 OR pg_roles.oid IS NOT NULL) AND
+            NOT EXISTS (
+                SELECT 1
+                FROM aclexplode(acl.defaults) AS defaults
+                WHERE defaults.grantee = privileges.grantee AND
+                    defaults.privilege_type = privileges.privilege_type AND
+                    defaults.is_grantable = privileges.is_grantable
+            )
+        GROUP BY acl.oid, acl.kind, acl.nspname, acl.name, acl.arguments, pg_roles.rolname, privileges.is_grantable
+    )";
+
+// Functions are identified by their argument types, which need parsing
+fn parse_grant(row: &Row) -> PsqlpackResult<GrantDefinition> {
+    let kind: String = row.get(1);
+    let name = ObjectName {
+        schema: Some(row.get(2)),
+        name: row.get(3),
+    };
+    let privileges: Vec<String> = row.get(6);
+    let mut privileges = privileges
+        .iter()
+        .filter_map(|privilege| match &privilege[..] {
+            "SELECT" => Some(Privilege::Select),
+            "INSERT" => Some(Privilege::Insert),
+            "UPDATE" => Some(Privilege::Update),
+            "DELETE" => Some(Privilege::Delete),
+            "TRUNCATE" => Some(Privilege::Truncate),
+            "REFERENCES" => Some(Privilege::References),
+            "TRIGGER" => Some(Privilege::Trigger),
+            "USAGE" => Some(Privilege::Usage),
+            "CREATE" => Some(Privilege::Create),
+            "EXECUTE" => Some(Privilege::Execute),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    privileges.sort();
+    Ok(GrantDefinition {
+        object: match &kind[..] {
+            "schema" => GrantObject::Schema(name.name),
+            "sequence" => GrantObject::Sequence(name),
+            "function" => {
+                let arguments = parse_function_arguments(row.get(7))?;
+                GrantObject::Function(name, Some(FunctionArgument::identity(&arguments)))
+            }
+            _ => GrantObject::Table(name),
+        },
+        grantee: row.get(4),
+        privileges,
+        with_grant_option: row.get(5),
+    })
+}
+
+static CTE_SEQUENCES: &str = "
+    WITH cte AS (
+        SELECT
+            cls.oid,
+            ns.nspname::text AS sequence_schema,
+            cls.relname::text AS sequence_name,
+            format_type(seq.seqtypid, NULL) AS data_type,
+            seq.seqincrement,
+            seq.seqmin,
+            seq.seqmax,
+            seq.seqstart,
+            seq.seqcache,
+            seq.seqcycle,
+            tns.nspname::text AS owner_schema,
+            tcls.relname::text AS owner_table,
+            att.attname::text AS owner_column,
+            obj_description(cls.oid, 'pg_class') AS comment
+        FROM pg_sequence seq
+        INNER JOIN pg_class cls ON cls.oid = seq.seqrelid
+        INNER JOIN pg_namespace ns ON ns.oid = cls.relnamespace
+        LEFT JOIN pg_depend dep ON dep.classid = 'pg_class'::regclass AND dep.objid = cls.oid AND
+                                   dep.refclassid = 'pg_class'::regclass AND dep.deptype = 'a'
+        LEFT JOIN pg_class tcls ON tcls.oid = dep.refobjid
+        LEFT JOIN pg_namespace tns ON tns.oid = tcls.relnamespace
+        LEFT JOIN pg_attribute att ON att.attrelid = dep.refobjid AND att.attnum = dep.refobjsubid
+        LEFT JOIN pg_attrdef def ON def.adrelid = att.attrelid AND def.adnum = att.attnum
+        WHERE
+            ns.nspname !~* 'pg_|information_schema|^psqlpack$' AND
+            -- Sequences backing serial columns are implied by the column type. The name isn't checked since
+            -- renaming the table or column keeps the original sequence name.
+            NOT COALESCE(
+                pg_get_expr(def.adbin, def.adrelid) = 'nextval(''' || cls.oid::regclass || '''::regclass)',
+                false
+            )
+    )";
+
+// Prior to Postgres 10 there is no pg_sequence catalog. Sequences are always bigint and their parameters are held
+// within the sequence relation itself, with the cache size only being available by reading from it.
+static CTE_SEQUENCES_94_THRU_96: &str = "
+    WITH cte AS (
+        SELECT
+            cls.oid,
+            ns.nspname::text AS sequence_schema,
+            cls.relname::text AS sequence_name,
+            'bigint'::text AS data_type,
+            params.increment,
+            params.minimum_value,
+            params.maximum_value,
+            params.start_value,
+            (xpath(
+                '/row/cache_value/text()',
+                query_to_xml('SELECT cache_value FROM ' || cls.oid::regclass::text, false, true, '')
+            ))[1]::text::bigint AS cache_value,
+            params.cycle_option,
+            tns.nspname::text AS owner_schema,
+            tcls.relname::text AS owner_table,
+            att.attname::text AS owner_column,
+            obj_description(cls.oid, 'pg_class') AS comment
+        FROM pg_class cls
+        CROSS JOIN LATERAL pg_sequence_parameters(cls.oid) params
+        INNER JOIN pg_namespace ns ON ns.oid = cls.relnamespace
+        LEFT JOIN pg_depend dep ON dep.classid = 'pg_class'::regclass AND dep.objid = cls.oid AND
+                                   dep.refclassid = 'pg_class'::regclass AND dep.deptype = 'a'
+        LEFT JOIN pg_class tcls ON tcls.oid = dep.refobjid
+        LEFT JOIN pg_namespace tns ON tns.oid = tcls.relnamespace
+        LEFT JOIN pg_attribute att ON att.attrelid = dep.refobjid AND att.attnum = dep.refobjsubid
+        LEFT JOIN pg_attrdef def ON def.adrelid = att.attrelid AND def.adnum = att.attnum
+        WHERE
+            cls.relkind = 'S' AND
+            ns.nspname !~* 'pg_|information_schema|^psqlpack$' AND
+            NOT COALESCE(
+                pg_get_expr(def.adbin, def.adrelid) = 'nextval(''' || cls.oid::regclass || '''::regclass)',
+                false
+            )
+    )";
+
+fn sequences_cte(server_version: Semver) -> &'static str {
+    if server_version < Semver::new(10, 0, None) {
+        CTE_SEQUENCES_94_THRU_96
+    } else {
+        CTE_SEQUENCES
+    }
+}
+
+impl From<&Row> for SequenceDefinition {
+    fn from(row: &Row) -> Self {
+        let data_type: String = row.get(3);
+        let owner_schema: Option<String> = row.get(10);
+        let owner_table: Option<String> = row.get(11);
+        let owner_column: Option<String> = row.get(12);
+        SequenceDefinition {
+            name: ObjectName {
+                schema: Some(row.get(1)),
+                name: row.get(2),
+            },
+            data_type: Some(data_type.into()),
+            increment: Some(row.get(4)),
+            min_value: Some(row.get(5)),
+            max_value: Some(row.get(6)),
+            start: Some(row.get(7)),
+            cache: Some(row.get(8)),
+            cycle: row.get(9),
+            owned_by: match (owner_table, owner_column) {
+                (Some(table), Some(column)) => Some(SequenceOwner {
+                    table: ObjectName {
+                        schema: owner_schema,
+                        name: table,
+                    },
+                    column,
+                }),
+                _ => None,
+            },
+            comment: row.get(13),
         }
     }
 }
@@ -796,3 +2269,28 @@ impl From<String> for SqlType {
         SqlTypeParser::new().parse(tokens).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_only_reads_pg_sequence_from_postgres_10() {
+        assert_eq!(sequences_cte(Semver::new(9, 4, None)), CTE_SEQUENCES_94_THRU_96);
+        assert_eq!(sequences_cte(Semver::new(9, 6, None)), CTE_SEQUENCES_94_THRU_96);
+        assert!(!CTE_SEQUENCES_94_THRU_96.contains("pg_sequence "));
+        assert_eq!(sequences_cte(Semver::new(10, 0, None)), CTE_SEQUENCES);
+        assert_eq!(sequences_cte(Semver::new(15, 2, None)), CTE_SEQUENCES);
+    }
+
+    #[test]
+    fn it_skips_cloned_partition_triggers_by_server_version() {
+        let cte = triggers_cte(Semver::new(9, 6, None));
+        assert!(!cte.contains("tgparentid"));
+        assert!(!cte.contains("relispartition"));
+        let cte = triggers_cte(Semver::new(12, 4, None));
+        assert!(!cte.contains("tgparentid"));
+        assert!(cte.contains("pg_class.relispartition"));
+        assert_eq!(triggers_cte(Semver::new(13, 0, None)), CTE_TRIGGERS);
+    }
+}
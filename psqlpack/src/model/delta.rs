@@ -4,42 +4,68 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+use postgres::GenericClient;
 use serde::Serialize;
 use slog::Logger;
 
 use crate::connection::Connection;
-use crate::errors::PsqlpackErrorKind::*;
+use crate::errors::PsqlpackErrorKind::{self, *};
 use crate::errors::{PsqlpackResult, PsqlpackResultExt};
-use crate::model::{Capabilities, Dependency, Node, Package, PublishProfile, Toggle};
+use crate::model::{
+    Capabilities, Dependency, Deployment, DeploymentStatus, DeploymentSummary, Node, Package, PublishProfile, Report,
+    ReportFormat, Toggle, TypeDependentKind,
+};
 use crate::sql::ast::*;
 use crate::Semver;
 
 enum DbObject<'a> {
     Column(&'a TableDefinition, &'a ColumnDefinition),
+    Comment(CommentObject, Option<&'a String>), // 11
     Constraint(&'a TableDefinition, &'a TableConstraint),
-    ExtensionRequest(&'a Dependency), // 2
-    Function(&'a FunctionDefinition), // 6 (ordered)
-    Index(&'a IndexDefinition),       // 7
-    Schema(&'a SchemaDefinition),     // 3
-    Script(&'a ScriptDefinition),     // 1, 8
-    Table(&'a TableDefinition),       // 5 (ordered)
-    Type(&'a TypeDefinition),         // 4
+    ExtensionRequest(&'a Dependency),                 // 2
+    Function(&'a FunctionDefinition),                 // 6 (ordered)
+    Grant(&'a GrantDefinition),                       // 10
+    Index(&'a IndexDefinition),                       // 9
+    MaterializedView(&'a MaterializedViewDefinition), // 7 (ordered)
+    Policy(&'a PolicyDefinition),                     // 8
+    Procedure(&'a ProcedureDefinition),               // 8
+    Role(&'a RoleDefinition),                         // 2
+    RoleMembership(&'a RoleDefinition),               // 2
+    Schema(&'a SchemaDefinition),                     // 3
+    Script(&'a ScriptDefinition),                     // 1, 11
+    Sequence(&'a SequenceDefinition),                 // 4
+    SequenceOwner(&'a SequenceDefinition),            // 5
+    Table(&'a TableDefinition),                       // 5 (ordered)
+    Trigger(&'a TriggerDefinition),                   // 8 (ordered)
+    Type(&'a TypeDefinition),                         // 4
+    View(&'a ViewDefinition),                         // 7 (ordered)
 }
 
 impl<'a> fmt::Display for DbObject<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             DbObject::Column(table, column) => write!(f, "Table: {}, Column: {}", table.name, column.name),
+            DbObject::Comment(ref object, _) => write!(f, "Comment: {}", object),
             DbObject::Constraint(table, constraint) => {
                 write!(f, "Table: {}, Constraint: {}", table.name, constraint.name())
             }
             DbObject::ExtensionRequest(extension) => write!(f, "ExtensionRequest: {}", extension.name),
             DbObject::Function(function) => write!(f, "Function: {}", function.name),
+            DbObject::Grant(grant) => write!(f, "Grant: {}", grant.fully_qualified_name()),
             DbObject::Index(index) => write!(f, "Index: {}", index.name),
+            DbObject::MaterializedView(view) => write!(f, "Materialized View: {}", view.name),
+            DbObject::Policy(policy) => write!(f, "Policy: {}", policy.fully_qualified_name()),
+            DbObject::Procedure(procedure) => write!(f, "Procedure: {}", procedure.name),
+            DbObject::Role(role) => write!(f, "Role: {}", role.name),
+            DbObject::RoleMembership(role) => write!(f, "Role Membership: {}", role.name),
             DbObject::Schema(schema) => write!(f, "Schema: {}", schema.name),
             DbObject::Script(script) => write!(f, "Script: {}", script.name),
+            DbObject::Sequence(sequence) => write!(f, "Sequence: {}", sequence.name),
+            DbObject::SequenceOwner(sequence) => write!(f, "Sequence Owner: {}", sequence.name),
             DbObject::Table(table) => write!(f, "Table: {}", table.name),
+            DbObject::Trigger(trigger) => write!(f, "Trigger: {}", trigger.fully_qualified_name()),
             DbObject::Type(tipe) => write!(f, "Type: {}", tipe.name),
+            DbObject::View(view) => write!(f, "View: {}", view.name),
         }
     }
 }
@@ -65,16 +91,23 @@ impl<'a> Diffable<'a, Package> for DbObject<'a> {
         log: &Logger,
     ) -> PsqlpackResult<()> {
         match *self {
-            DbObject::Column(table, column) => LinkedColumn {
-                table: &table,
-                column: &column,
+            DbObject::Column(table, column) => {
+                LinkedColumn { table, column }.generate(change_set, target, target_capabilities, publish_profile, log)
             }
-            .generate(change_set, target, target_capabilities, publish_profile, log),
-            DbObject::Constraint(table, constraint) => LinkedTableConstraint {
-                table: &table,
-                constraint: &constraint,
-            }
-            .generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Comment(ref object, comment) => LinkedComment { object, comment }.generate(
+                change_set,
+                target,
+                target_capabilities,
+                publish_profile,
+                log,
+            ),
+            DbObject::Constraint(table, constraint) => LinkedTableConstraint { table, constraint }.generate(
+                change_set,
+                target,
+                target_capabilities,
+                publish_profile,
+                log,
+            ),
             DbObject::ExtensionRequest(dependency) => ExtensionRequest {
                 name: &dependency.name,
                 version: &dependency.version,
@@ -83,11 +116,33 @@ impl<'a> Diffable<'a, Package> for DbObject<'a> {
             DbObject::Function(function) => {
                 function.generate(change_set, target, target_capabilities, publish_profile, log)
             }
+            DbObject::Grant(grant) => grant.generate(change_set, target, target_capabilities, publish_profile, log),
             DbObject::Index(index) => index.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::MaterializedView(view) => {
+                view.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
+            DbObject::Policy(policy) => policy.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Procedure(procedure) => {
+                procedure.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
+            DbObject::Role(role) => role.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::RoleMembership(role) => {
+                RoleMembership { role }.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Schema(schema) => schema.generate(change_set, target, target_capabilities, publish_profile, log),
             DbObject::Script(script) => script.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Sequence(sequence) => {
+                sequence.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
+            DbObject::SequenceOwner(sequence) => {
+                LinkedSequenceOwner { sequence }.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Table(table) => table.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::Trigger(trigger) => {
+                trigger.generate(change_set, target, target_capabilities, publish_profile, log)
+            }
             DbObject::Type(ty) => ty.generate(change_set, target, target_capabilities, publish_profile, log),
+            DbObject::View(view) => view.generate(change_set, target, target_capabilities, publish_profile, log),
         }
     }
 }
@@ -193,20 +248,30 @@ impl<'a> Diffable<'a, Package> for &'a FunctionDefinition {
     fn generate(
         &self,
         change_set: &mut Vec<ChangeInstruction<'a>>,
-        _target: &Package,
+        target: &Package,
         _target_capabilities: &Capabilities,
         _publish_profile: &PublishProfile,
         _log: &Logger,
     ) -> PsqlpackResult<()> {
-        // Since we don't really need to worry about this in PG we just
-        // add it as is and rely on CREATE OR REPLACE. In the future, it'd
-        // be good to check the hash or something to only do this when required
-        change_set.push(ChangeInstruction::ModifyFunction(self));
+        // Functions are identified by their signature so that overloads are kept apart. Changed functions are
+        // replaced with CREATE OR REPLACE, unless Postgres would reject the change in which case the function
+        // is dropped and created again.
+        let signature = self.signature();
+        let existing = target.functions.iter().find(|f| f.signature().eq(&signature));
+        match existing.filter(|_| !is_dropped(change_set, &signature)) {
+            Some(existing) if self.is_same_definition(existing) => {}
+            Some(existing) if !self.can_replace(existing) => {
+                change_set.push(ChangeInstruction::DropFunction(signature));
+                change_set.push(ChangeInstruction::AddFunction(self));
+            }
+            Some(_) => change_set.push(ChangeInstruction::ModifyFunction(self)),
+            None => change_set.push(ChangeInstruction::AddFunction(self)),
+        }
         Ok(())
     }
 }
 
-impl<'a> Diffable<'a, Package> for &'a SchemaDefinition {
+impl<'a> Diffable<'a, Package> for &'a ProcedureDefinition {
     fn generate(
         &self,
         change_set: &mut Vec<ChangeInstruction<'a>>,
@@ -215,10 +280,122 @@ impl<'a> Diffable<'a, Package> for &'a SchemaDefinition {
         _publish_profile: &PublishProfile,
         _log: &Logger,
     ) -> PsqlpackResult<()> {
-        // Only add schema's, we do not drop them at this point
-        let schema_exists = target.schemas.iter().any(|s| s.name == self.name);
-        if !schema_exists {
-            change_set.push(ChangeInstruction::AddSchema(self));
+        // Procedures follow the same rules as functions, being identified by their signature
+        let signature = self.signature();
+        let existing = target.procedures.iter().find(|p| p.signature().eq(&signature));
+        match existing.filter(|_| !is_dropped(change_set, &signature)) {
+            Some(existing) if self.is_same_definition(existing) => {}
+            Some(existing) if !self.can_replace(existing) => {
+                change_set.push(ChangeInstruction::DropProcedure(signature));
+                change_set.push(ChangeInstruction::AddProcedure(self));
+            }
+            Some(_) => change_set.push(ChangeInstruction::ModifyProcedure(self)),
+            None => change_set.push(ChangeInstruction::AddProcedure(self)),
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a SchemaDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Schemas are dropped separately. Ownership is only changed when the project declares an owner.
+        let owner = self
+            .owner
+            .as_ref()
+            .map(|owner| publish_profile.role_name(owner).to_owned());
+        match target.schemas.iter().find(|s| s.name == self.name) {
+            Some(schema) => {
+                if let Some(owner) = owner {
+                    if schema.owner.as_ref() != Some(&owner) {
+                        change_set.push(ChangeInstruction::AlterSchemaOwner(self.name.to_owned(), owner));
+                    }
+                }
+            }
+            None => change_set.push(ChangeInstruction::AddSchema(SchemaDefinition {
+                owner,
+                ..(*self).clone()
+            })),
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a RoleDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Roles are named per environment so the target role may be named differently
+        let role = RoleDefinition {
+            name: publish_profile.role_name(&self.name).to_owned(),
+            ..(*self).clone()
+        };
+        match target.roles.iter().find(|r| r.name.eq(&role.name)) {
+            Some(existing) => {
+                if !existing.has_same_attributes(&role) {
+                    change_set.push(ChangeInstruction::AlterRole(role));
+                }
+            }
+            None => change_set.push(ChangeInstruction::CreateRole(role)),
+        }
+        Ok(())
+    }
+}
+
+// Memberships are generated separately once every role has been created
+struct RoleMembership<'a> {
+    role: &'a RoleDefinition,
+}
+
+impl<'a> Diffable<'a, Package> for RoleMembership<'a> {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let name = publish_profile.role_name(&self.role.name);
+        let groups = self
+            .role
+            .member_of
+            .iter()
+            .map(|group| publish_profile.role_name(group))
+            .collect::<Vec<_>>();
+        let existing = target
+            .roles
+            .iter()
+            .find(|r| r.name.eq(name))
+            .map(|r| &r.member_of[..])
+            .unwrap_or(&[]);
+        for group in &groups {
+            if !existing.iter().any(|g| g.eq(group)) {
+                change_set.push(ChangeInstruction::GrantRole(group.to_string(), name.to_owned()));
+            }
+        }
+        for group in existing {
+            if !groups.contains(&&group[..]) {
+                match publish_profile.generation_options.revoke_privileges {
+                    Toggle::Allow => change_set.push(ChangeInstruction::RevokeRole(group.to_owned(), name.to_owned())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to revoke membership of {} in {} however revoking privileges is currently disabled",
+                        name, group
+                    ))),
+                    _ => {}
+                }
+            }
         }
         Ok(())
     }
@@ -238,6 +415,53 @@ impl<'a> Diffable<'a, Package> for &'a ScriptDefinition {
     }
 }
 
+impl<'a> Diffable<'a, Package> for &'a SequenceDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Ownership is set once the tables exist
+        let sequence = target.sequences.iter().find(|s| s.name.eq(&self.name));
+        if let Some(sequence) = sequence {
+            if !sequence.is_same_definition(self) {
+                change_set.push(ChangeInstruction::ModifySequence(self));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddSequence(self));
+        }
+        Ok(())
+    }
+}
+
+struct LinkedSequenceOwner<'a> {
+    sequence: &'a SequenceDefinition,
+}
+
+impl<'a> Diffable<'a, Package> for LinkedSequenceOwner<'a> {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let owned_by = target
+            .sequences
+            .iter()
+            .find(|s| s.name.eq(&self.sequence.name))
+            .and_then(|s| s.owned_by.as_ref());
+        if owned_by != self.sequence.owned_by.as_ref() {
+            change_set.push(ChangeInstruction::ModifySequenceOwner(self.sequence));
+        }
+        Ok(())
+    }
+}
+
 impl<'a> Diffable<'a, Package> for &'a TableDefinition {
     fn generate(
         &self,
@@ -249,6 +473,47 @@ impl<'a> Diffable<'a, Package> for &'a TableDefinition {
     ) -> PsqlpackResult<()> {
         let table_result = target.tables.iter().find(|t| t.name == self.name);
         if let Some(target_table) = table_result {
+            // Postgres can't change how an existing table is partitioned
+            let partition_key = self.partition_by.as_ref().map(|key| key.normalize());
+            if partition_key != target_table.partition_by.as_ref().map(|key| key.normalize()) {
+                bail!(PublishInvalidOperationError(format!(
+                    "Unable to change the partitioning of table {} as it would need to be recreated",
+                    self.name
+                )));
+            }
+
+            // Row level security is enabled and forced independently
+            if self.row_security != target_table.row_security {
+                change_set.push(ChangeInstruction::ModifyRowSecurity(self));
+            }
+            if self.force_row_security != target_table.force_row_security {
+                change_set.push(ChangeInstruction::ModifyForceRowSecurity(self));
+            }
+
+            // Partitions are attached or detached as required, keeping any data they have
+            match (&self.partition_of, &target_table.partition_of) {
+                (Some(source), Some(target)) => {
+                    if source.parent != target.parent || source.bound.normalize() != target.bound.normalize() {
+                        change_set.push(ChangeInstruction::DetachPartition(
+                            self.name.to_string(),
+                            target.parent.to_string(),
+                        ));
+                        change_set.push(ChangeInstruction::AttachPartition(self));
+                    }
+                }
+                (Some(_), None) => change_set.push(ChangeInstruction::AttachPartition(self)),
+                (None, Some(target)) => change_set.push(ChangeInstruction::DetachPartition(
+                    self.name.to_string(),
+                    target.parent.to_string(),
+                )),
+                (None, None) => {}
+            }
+
+            // The columns and constraints of a partition are managed by the partitioned table
+            if self.partition_of.is_some() {
+                return Ok(());
+            }
+
             // We check for column removals here
             for tgt in target_table.columns.iter() {
                 if !self.columns.iter().any(|src| tgt.name.eq(&src.name)) {
@@ -268,36 +533,24 @@ impl<'a> Diffable<'a, Package> for &'a TableDefinition {
 
             // We also check for table constraint removals here
             for tgt in target_table.constraints.iter() {
-                if !self.constraints.iter().any(|src| tgt.name().eq(src.name())) {
-                    let remove_ok = match tgt {
-                        TableConstraint::Primary { .. } => {
-                            match publish_profile.generation_options.drop_primary_key_constraints {
-                                Toggle::Allow => true,
-                                Toggle::Ignore => false,
-                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                                    "Unable to drop constraint as dropping PKs is currently disabled: {}",
-                                    tgt.name()
-                                ))),
-                            }
-                        }
-                        TableConstraint::Foreign { .. } => {
-                            match publish_profile.generation_options.drop_foreign_key_constraints {
-                                Toggle::Allow => true,
-                                Toggle::Ignore => false,
-                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                                    "Unable to drop constraint as dropping FKs is currently disabled: {}",
-                                    tgt.name()
-                                ))),
-                            }
-                        }
-                    };
-                    if remove_ok {
-                        change_set.push(ChangeInstruction::DropConstraint(self, tgt.name().to_owned()));
-                    }
+                if !self.constraints.iter().any(|src| tgt.name().eq(src.name()))
+                    && can_drop_constraint(tgt, publish_profile, "drop")?
+                {
+                    change_set.push(ChangeInstruction::DropConstraint(
+                        self,
+                        tgt.name().to_owned(),
+                        tgt.kind(),
+                    ));
                 }
             }
         } else {
             change_set.push(ChangeInstruction::AddTable(self));
+            if self.row_security {
+                change_set.push(ChangeInstruction::ModifyRowSecurity(self));
+            }
+            if self.force_row_security {
+                change_set.push(ChangeInstruction::ModifyForceRowSecurity(self));
+            }
         }
         Ok(())
     }
@@ -313,8 +566,8 @@ impl<'a> Diffable<'a, Package> for LinkedColumn<'a> {
         &self,
         change_set: &mut Vec<ChangeInstruction<'a>>,
         target: &Package,
-        _target_capabilities: &Capabilities,
-        _publish_profile: &PublishProfile,
+        target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
         _log: &Logger,
     ) -> PsqlpackResult<()> {
         // We only generate items here if the table doesn't exist (for the time being)
@@ -324,9 +577,53 @@ impl<'a> Diffable<'a, Package> for LinkedColumn<'a> {
             // Check if the column exists on the target
             let target_column = target_table.columns.iter().find(|tgt| tgt.name.eq(&self.column.name));
             if let Some(target_column) = target_column {
+                // The expression of a generated column can't be altered so the column is added again
+                match (self.column.generated(), target_column.generated()) {
+                    (Some(source), Some(target)) if !is_same_generation(source, target) => {
+                        change_set.push(ChangeInstruction::RecreateGeneratedColumn(self.table, self.column));
+                        return Ok(());
+                    }
+                    (Some(_), None) => {
+                        // The existing values of the column are replaced so this is the same as dropping it
+                        match publish_profile.generation_options.drop_columns {
+                            Toggle::Allow => {
+                                change_set.push(ChangeInstruction::DropColumn(self.table, self.column.name.to_owned()));
+                                change_set.push(ChangeInstruction::AddColumn(self.table, self.column));
+                            }
+                            Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                "Unable to replace column with a generated column as dropping columns is currently \
+                                 disabled: {}",
+                                self.column.name
+                            ))),
+                            _ => {}
+                        }
+                        return Ok(());
+                    }
+                    (None, Some(_)) => {
+                        if target_capabilities.server_version < Semver::new(13, 0, None) {
+                            bail!(PublishInvalidOperationError(format!(
+                                "Unable to remove the expression of generated column {}.{} prior to Postgres 13",
+                                self.table.name, self.column.name
+                            )));
+                        }
+                        change_set.push(ChangeInstruction::DropColumnExpression(self.table, self.column));
+                    }
+                    _ => {}
+                }
+
+                // A serial column is converted to an identity column in place so that the sequence continues on
+                let serial_type = serial_integer_type(&target_column.sql_type);
+                let convert_serial = self.column.identity().is_some() && serial_type.is_some();
+                if convert_serial {
+                    change_set.push(ChangeInstruction::ConvertSerialToIdentity(self.table, self.column));
+                }
+
                 // Check the type
-                if !self.column.sql_type.eq(&target_column.sql_type) {
-                    change_set.push(ChangeInstruction::ModifyColumnType(self.table, &self.column));
+                let target_type = serial_type
+                    .filter(|_| convert_serial)
+                    .unwrap_or_else(|| target_column.sql_type.clone());
+                if !self.column.sql_type.eq(&target_type) {
+                    change_set.push(ChangeInstruction::ModifyColumnType(self.table, self.column));
                 }
 
                 // Check column constraints
@@ -341,29 +638,47 @@ impl<'a> Diffable<'a, Package> for LinkedColumn<'a> {
                             // If it doesn't then it's likely implicitly implied to be null.
                             // Also, we only check not null as if null is specified then we've got nothing to change!
                             if self.column.constraints.iter().any(|c| ColumnConstraint::NotNull.eq(c)) {
-                                change_set.push(ChangeInstruction::ModifyColumnNull(self.table, &self.column));
+                                change_set.push(ChangeInstruction::ModifyColumnNull(self.table, self.column));
                             }
                         }
                         ColumnConstraint::NotNull => {
-                            change_set.push(ChangeInstruction::ModifyColumnNull(self.table, &self.column))
+                            change_set.push(ChangeInstruction::ModifyColumnNull(self.table, self.column))
                         }
-                        ColumnConstraint::Default(_) => {
-                            change_set.push(ChangeInstruction::ModifyColumnDefault(self.table, &self.column))
+                        // Defaults, identities and generated columns are compared separately below
+                        ColumnConstraint::Default(_)
+                        | ColumnConstraint::Identity(_)
+                        | ColumnConstraint::Generated(_) => {}
+                        ColumnConstraint::Unique => {
+                            change_set.push(ChangeInstruction::ModifyColumnUniqueConstraint(self.table, self.column))
                         }
-                        ColumnConstraint::Unique => change_set.push(ChangeInstruction::ModifyColumnUniqueConstraint(
-                            self.table,
-                            &self.column,
-                        )),
                         ColumnConstraint::PrimaryKey => change_set.push(
-                            ChangeInstruction::ModifyColumnPrimaryKeyConstraint(self.table, &self.column),
+                            ChangeInstruction::ModifyColumnPrimaryKeyConstraint(self.table, self.column),
                         ),
                     }
                 }
 
+                // A column can't have both a default and an identity so the identity is dropped first and added last
+                let identity = (self.column.identity(), target_column.identity());
+                if let (None, Some(_)) = identity {
+                    change_set.push(ChangeInstruction::DropColumnIdentity(self.table, self.column));
+                }
+                if !is_same_default(self.column.default_value(), target_column.default_value()) {
+                    change_set.push(ChangeInstruction::ModifyColumnDefault(self.table, self.column));
+                }
+                match identity {
+                    (Some(_), None) if !convert_serial => {
+                        change_set.push(ChangeInstruction::AddColumnIdentity(self.table, self.column))
+                    }
+                    (Some(source), Some(target)) if source.ne(target) => {
+                        change_set.push(ChangeInstruction::ModifyColumnIdentity(self.table, self.column))
+                    }
+                    _ => {}
+                }
+
             // TODO: src_sec - target_set (e.g. what column constraints have been removed)
             } else {
                 // Doesn't exist, add it
-                change_set.push(ChangeInstruction::AddColumn(self.table, &self.column));
+                change_set.push(ChangeInstruction::AddColumn(self.table, self.column));
             }
         }
         Ok(())
@@ -431,7 +746,7 @@ impl<'a> Diffable<'a, Package> for LinkedTableConstraint<'a> {
                                 vec_different(src_columns, columns)
                                     || optional_vec_different(src_parameters, parameters)
                             }
-                            TableConstraint::Foreign { .. } => true,
+                            _ => true,
                         }
                     }
                     TableConstraint::Foreign {
@@ -448,7 +763,6 @@ impl<'a> Diffable<'a, Package> for LinkedTableConstraint<'a> {
                         let src_match_type = match_type;
                         let src_events = events;
                         match target_constraint {
-                            TableConstraint::Primary { .. } => true,
                             TableConstraint::Foreign {
                                 ref columns,
                                 ref ref_table,
@@ -468,51 +782,138 @@ impl<'a> Diffable<'a, Package> for LinkedTableConstraint<'a> {
                                     || vec_different(src_ref_columns, ref_columns)
                                     || optional_vec_different(src_events, events)
                             }
+                            _ => true,
                         }
                     }
-                };
-                if has_changed {
-                    let remove_ok = match self.constraint {
-                        TableConstraint::Primary { .. } => {
-                            match publish_profile.generation_options.drop_primary_key_constraints {
-                                Toggle::Allow => true,
-                                Toggle::Ignore => false,
-                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                                    "Unable to modify constraint as dropping PKs is currently disabled: {}",
-                                    self.constraint.name()
-                                ))),
+                    TableConstraint::Unique {
+                        ref columns,
+                        ref parameters,
+                        ..
+                    } => {
+                        let src_columns = columns;
+                        let src_parameters = parameters;
+                        match target_constraint {
+                            TableConstraint::Unique {
+                                ref columns,
+                                ref parameters,
+                                ..
+                            } => {
+                                vec_different(src_columns, columns)
+                                    || optional_vec_different(src_parameters, parameters)
                             }
+                            _ => true,
                         }
-                        TableConstraint::Foreign { .. } => {
-                            match publish_profile.generation_options.drop_foreign_key_constraints {
-                                Toggle::Allow => true,
-                                Toggle::Ignore => false,
-                                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                                    "Unable to modify constraint as dropping FKs is currently disabled: {}",
-                                    self.constraint.name()
-                                ))),
+                    }
+                    TableConstraint::Check {
+                        ref expression,
+                        no_inherit,
+                        ..
+                    } => {
+                        let src_expression = expression;
+                        let src_no_inherit = no_inherit;
+                        match *target_constraint {
+                            TableConstraint::Check {
+                                ref expression,
+                                no_inherit,
+                                ..
+                            } => {
+                                src_no_inherit != no_inherit
+                                    || normalize_expression(src_expression).ne(&normalize_expression(expression))
                             }
+                            _ => true,
+                        }
+                    }
+                    TableConstraint::Exclude {
+                        ref index_type,
+                        ref elements,
+                        ref parameters,
+                        ref condition,
+                        ..
+                    } => {
+                        let src_index_type = index_type;
+                        let src_elements = elements;
+                        let src_parameters = parameters;
+                        let src_condition = condition;
+                        match target_constraint {
+                            TableConstraint::Exclude {
+                                ref index_type,
+                                ref elements,
+                                ref parameters,
+                                ref condition,
+                                ..
+                            } => {
+                                let condition_different = match (src_condition, condition) {
+                                    (Some(src), Some(tgt)) => normalize_expression(src).ne(&normalize_expression(tgt)),
+                                    (src, tgt) => src.is_none() ^ tgt.is_none(),
+                                };
+                                src_index_type.ne(index_type)
+                                    || src_elements.len() != elements.len()
+                                    || src_elements.iter().zip(elements).any(|(s, t)| !s.is_same_element(t))
+                                    || optional_vec_different(src_parameters, parameters)
+                                    || condition_different
+                            }
+                            _ => true,
                         }
-                    };
-                    if remove_ok {
-                        change_set.push(ChangeInstruction::DropConstraint(
-                            self.table,
-                            self.constraint.name().to_owned(),
-                        ));
-                        change_set.push(ChangeInstruction::AddConstraint(self.table, self.constraint));
                     }
+                };
+                if has_changed && can_drop_constraint(self.constraint, publish_profile, "modify")? {
+                    change_set.push(ChangeInstruction::DropConstraint(
+                        self.table,
+                        self.constraint.name().to_owned(),
+                        self.constraint.kind(),
+                    ));
+                    self.add_to_existing_table(change_set);
                 }
             } else {
                 // Doesn't exist, add it
-                change_set.push(ChangeInstruction::AddConstraint(self.table, &self.constraint));
+                self.add_to_existing_table(change_set);
             }
         } else {
-            change_set.push(ChangeInstruction::AddConstraint(self.table, &self.constraint));
+            change_set.push(ChangeInstruction::AddConstraint(self.table, self.constraint, false));
         }
         Ok(())
     }
 }
 
+impl<'a> LinkedTableConstraint<'a> {
+    fn add_to_existing_table(&self, change_set: &mut Vec<ChangeInstruction<'a>>) {
+        // Check constraints are added without scanning the table so that the table isn't locked while the
+        // existing rows are validated.
+        if let TableConstraint::Check { ref name, .. } = *self.constraint {
+            change_set.push(ChangeInstruction::AddConstraint(self.table, self.constraint, true));
+            change_set.push(ChangeInstruction::ValidateConstraint(self.table, name.to_owned()));
+        } else {
+            change_set.push(ChangeInstruction::AddConstraint(self.table, self.constraint, false));
+        }
+    }
+}
+
+fn can_drop_constraint(
+    constraint: &TableConstraint,
+    publish_profile: &PublishProfile,
+    action: &str,
+) -> PsqlpackResult<bool> {
+    let options = &publish_profile.generation_options;
+    let (toggle, kind) = match *constraint {
+        TableConstraint::Primary { .. } => (&options.drop_primary_key_constraints, "PKs"),
+        TableConstraint::Foreign { .. } => (&options.drop_foreign_key_constraints, "FKs"),
+        TableConstraint::Unique { .. } | TableConstraint::Exclude { .. } => {
+            (&options.drop_unique_constraints, "unique constraints")
+        }
+        TableConstraint::Check { .. } => (&options.drop_check_constraints, "check constraints"),
+    };
+    match *toggle {
+        Toggle::Allow => Ok(true),
+        Toggle::Ignore => Ok(false),
+        Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+            "Unable to {} constraint as dropping {} is currently disabled: {}",
+            action,
+            kind,
+            constraint.name()
+        ))),
+    }
+}
+
 impl<'a> Diffable<'a, Package> for &'a IndexDefinition {
     fn generate(
         &self,
@@ -525,9 +926,17 @@ impl<'a> Diffable<'a, Package> for &'a IndexDefinition {
         // Indexes are unique across schema (implied by table)
         let index = target.indexes.iter().find(|idx| idx.is_same_index(self));
         let concurrently = publish_profile.generation_options.force_concurrent_indexes;
-        if let Some(index) = index {
-            // We should be able to just use an eq for this since column ordering is significant
-            if index.ne(self) {
+
+        // Rebuilding a materialized view will also have dropped any indexes on it
+        let rebuilt = change_set.iter().any(|change| match *change {
+            ChangeInstruction::ReplaceMaterializedView(view) => view.name.eq(&self.table),
+            _ => false,
+        });
+        if rebuilt {
+            change_set.push(ChangeInstruction::AddIndex(self, concurrently));
+        } else if let Some(index) = index {
+            // Column ordering is significant so the columns can be compared directly
+            if !index.is_same_definition(self) {
                 change_set.push(ChangeInstruction::DropIndex(self.fully_qualified_name(), concurrently));
                 change_set.push(ChangeInstruction::AddIndex(self, concurrently));
             }
@@ -538,123 +947,728 @@ impl<'a> Diffable<'a, Package> for &'a IndexDefinition {
     }
 }
 
-impl<'a> Diffable<'a, Package> for &'a TypeDefinition {
+struct LinkedComment<'a, 'b> {
+    object: &'b CommentObject,
+    comment: Option<&'a String>,
+}
+
+impl<'a, 'b> Diffable<'a, Package> for LinkedComment<'a, 'b> {
     fn generate(
         &self,
         change_set: &mut Vec<ChangeInstruction<'a>>,
         target: &Package,
         _target_capabilities: &Capabilities,
-        publish_profile: &PublishProfile,
-        log: &Logger,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
     ) -> PsqlpackResult<()> {
-        let ty = target.types.iter().find(|t| t.name == self.name);
-        if let Some(ty) = ty {
-            self.generate(change_set, ty, _target_capabilities, publish_profile, log)
-        } else {
-            change_set.push(ChangeInstruction::AddType(self));
-            Ok(())
+        // Objects that are rebuilt lose their comment along the way
+        let rebuilt = change_set.iter().any(|change| match (change, self.object) {
+            (ChangeInstruction::AddIndex(index, _), CommentObject::Index(name)) => {
+                index.name.eq(&name.name) && index.table.schema.eq(&name.schema)
+            }
+            (ChangeInstruction::ReplaceMaterializedView(view), CommentObject::MaterializedView(name)) => {
+                view.name.eq(name)
+            }
+            (ChangeInstruction::AddView(view), CommentObject::View(name)) => view.name.eq(name),
+            (ChangeInstruction::AddMaterializedView(view), CommentObject::MaterializedView(name)) => view.name.eq(name),
+            (ChangeInstruction::AddFunction(function), CommentObject::Function(name, _)) => function.name.eq(name),
+            (ChangeInstruction::AddProcedure(procedure), CommentObject::Procedure(name, _)) => procedure.name.eq(name),
+            (ChangeInstruction::RecreateGeneratedColumn(table, column), CommentObject::Column(name, column_name)) => {
+                table.name.eq(name) && column.name.eq(column_name)
+            }
+            (
+                ChangeInstruction::ModifyType(ty, TypeModificationAction::RemoveEnumValues { .. }),
+                CommentObject::Type(name),
+            ) => ty.name.eq(name),
+            _ => false,
+        });
+        let existing = if rebuilt { None } else { target.comment_of(self.object) };
+        if existing != self.comment {
+            change_set.push(ChangeInstruction::SetComment(
+                self.object.clone(),
+                self.comment.cloned(),
+            ));
         }
+        Ok(())
     }
 }
 
-impl<'a> Diffable<'a, TypeDefinition> for &'a TypeDefinition {
+impl<'a> Diffable<'a, Package> for &'a ViewDefinition {
     fn generate(
         &self,
         change_set: &mut Vec<ChangeInstruction<'a>>,
-        target: &TypeDefinition,
+        target: &Package,
         _target_capabilities: &Capabilities,
         publish_profile: &PublishProfile,
         _log: &Logger,
     ) -> PsqlpackResult<()> {
-        if self.name.ne(&target.name) {
-            bail!(PublishInvalidOperationError(format!(
-                "Types not diffable: {} != {}",
-                self.name, target.name
-            )))
-        }
-        // We can only diff types of the same kind. Only one type right now, but future proofing.
-        match self.kind {
-            TypeDefinitionKind::Enum(ref source_values) => {
-                match target.kind {
-                    TypeDefinitionKind::Enum(ref target_values) => {
-                        // Detect if anything needs to be deleted in the target
-                        let mut to_delete = target_values
-                            .iter()
-                            .filter(|v| !source_values.contains(v))
-                            .map(|v| TypeModificationAction::RemoveEnumValue { value: v.to_owned() })
-                            .collect::<Vec<_>>();
-                        if !to_delete.is_empty() {
-                            match publish_profile.generation_options.drop_enum_values {
-                                Toggle::Allow => {
-                                    change_set
-                                        .extend(to_delete.drain(..).map(|d| ChangeInstruction::ModifyType(self, d)));
-                                }
-                                Toggle::Error => {
-                                    bail!(PublishUnsafeOperationError(format!(
-                                        "Unable to remove enum value(s) as unsafe operations are disabled: {:?}",
-                                        to_delete
-                                    )));
-                                }
-                                _ => {}
-                            }
-                        }
-
-                        // Our working group after items being deleted
-                        let mut working = target_values
-                            .iter()
-                            .filter(|v| source_values.contains(v))
-                            .collect::<Vec<_>>();
-
-                        // Detect what needs adding
-                        for (index, value) in source_values.iter().enumerate() {
-                            if !working.contains(&value) {
-                                if index == 0 {
-                                    change_set.push(ChangeInstruction::ModifyType(
-                                        self,
-                                        TypeModificationAction::AddEnumValueBefore {
-                                            value: value.to_owned(),
-                                            before: working[0].to_owned(),
-                                        },
-                                    ));
-                                    working.insert(0, value);
-                                } else {
-                                    change_set.push(ChangeInstruction::ModifyType(
-                                        self,
-                                        TypeModificationAction::AddEnumValueAfter {
-                                            value: value.to_owned(),
-                                            after: working[index - 1].to_owned(),
-                                        },
-                                    ));
-                                    working.insert(index, value);
-                                }
-                            }
-                        }
+        // View queries are compared textually. When they differ we rely on CREATE OR REPLACE, unless the columns
+        // have changed in a way that Postgres can't replace in which case the view is dropped and created again.
+        let view = target
+            .views
+            .iter()
+            .find(|v| v.name == self.name)
+            .filter(|v| !is_dropped(change_set, &v.name.to_string()));
+        if let Some(view) = view {
+            if self.is_same_definition(view) {
+                return Ok(());
+            }
+            if self.can_replace(view) {
+                change_set.push(ChangeInstruction::ReplaceView(self));
+            } else {
+                match publish_profile.generation_options.drop_views {
+                    Toggle::Allow => {
+                        change_set.push(ChangeInstruction::DropView(self.name.to_string()));
+                        change_set.push(ChangeInstruction::AddView(self));
                     }
-                    ref unknown_target_kind => panic!("Unknown target kind: {}", unknown_target_kind), // TODO
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to recreate view {} since its columns have changed however dropping views is \
+                         currently disabled",
+                        self.name
+                    ))),
+                    _ => {}
                 }
             }
-            ref unknown_source_kind => panic!("Unknown source kind: {}", unknown_source_kind), // TODO
+        } else {
+            change_set.push(ChangeInstruction::AddView(self));
         }
         Ok(())
     }
 }
 
-#[derive(Debug)]
-pub struct Delta<'package>(Vec<ChangeInstruction<'package>>);
-
-impl<'package> Delta<'package> {
-    pub fn generate(
-        log: &Logger,
-        package: &'package Package,
-        target: Option<Package>,
-        target_database_name: &str,
-        target_capabilities: &Capabilities,
-        publish_profile: &PublishProfile,
-    ) -> PsqlpackResult<Delta<'package>> {
-        let log = log.new(o!("delta" => "generate"));
-
-        // Start the change_set
-        let mut change_set = Vec::new();
+impl<'a> Diffable<'a, Package> for &'a MaterializedViewDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Materialized views can't be replaced so any change results in the view being rebuilt
+        let view = target
+            .materialized_views
+            .iter()
+            .find(|v| v.name == self.name)
+            .filter(|v| !is_dropped(change_set, &v.name.to_string()));
+        if let Some(view) = view {
+            if !view.is_same_query(self) {
+                change_set.push(ChangeInstruction::ReplaceMaterializedView(self));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddMaterializedView(self));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a TriggerDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Triggers are unique per table
+        let trigger = target.triggers.iter().find(|t| t.is_same_trigger(self));
+        if let Some(trigger) = trigger {
+            if !trigger.is_same_definition(self) {
+                change_set.push(ChangeInstruction::ReplaceTrigger(self));
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddTrigger(self));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a PolicyDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        _publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Policies are unique per table
+        let policy = target.policies.iter().find(|p| p.is_same_policy(self));
+        if let Some(policy) = policy {
+            if !policy.is_same_definition(self) {
+                // The command or kind of a policy can't be altered, nor can an expression be removed from it
+                if policy.command != self.command
+                    || policy.permissive != self.permissive
+                    || (policy.using.is_some() && self.using.is_none())
+                    || (policy.with_check.is_some() && self.with_check.is_none())
+                {
+                    change_set.push(ChangeInstruction::ReplacePolicy(self));
+                } else {
+                    change_set.push(ChangeInstruction::ModifyPolicy(self));
+                }
+            }
+        } else {
+            change_set.push(ChangeInstruction::AddPolicy(self));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Diffable<'a, Package> for &'a GrantDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        // Only grant the privileges that the grantee doesn't already hold. Views that are rebuilt lose them.
+        let grantee = publish_profile.role_name(&self.grantee);
+        let rebuilt = change_set.iter().any(|change| match (change, &self.object) {
+            (ChangeInstruction::AddView(view), GrantObject::Table(name)) => view.name.eq(name),
+            (ChangeInstruction::ReplaceMaterializedView(view), GrantObject::Table(name))
+            | (ChangeInstruction::AddMaterializedView(view), GrantObject::Table(name)) => view.name.eq(name),
+            (ChangeInstruction::AddFunction(function), GrantObject::Function(name, argument_types)) => {
+                function.name.eq(name)
+                    && argument_types
+                        .as_ref()
+                        .is_none_or(|types| function.argument_types().eq(types))
+            }
+            _ => false,
+        });
+        let privileges = self
+            .privileges
+            .iter()
+            .filter(|&privilege| {
+                rebuilt || !holds_privilege(&target.grants, &self.object, grantee, privilege, self.with_grant_option)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if !privileges.is_empty() {
+            change_set.push(ChangeInstruction::Grant(GrantDefinition {
+                object: self.object.clone(),
+                grantee: grantee.to_owned(),
+                privileges,
+                with_grant_option: self.with_grant_option,
+            }));
+        }
+        Ok(())
+    }
+}
+
+fn holds_privilege(
+    grants: &[GrantDefinition],
+    object: &GrantObject,
+    grantee: &str,
+    privilege: &Privilege,
+    with_grant_option: bool,
+) -> bool {
+    grants.iter().any(|g| {
+        g.object.eq(object)
+            && g.grantee.eq(grantee)
+            && g.privileges.contains(privilege)
+            && (g.with_grant_option || !with_grant_option)
+    })
+}
+
+impl<'a> Diffable<'a, Package> for &'a TypeDefinition {
+    fn generate(
+        &self,
+        change_set: &mut Vec<ChangeInstruction<'a>>,
+        target: &Package,
+        _target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+        _log: &Logger,
+    ) -> PsqlpackResult<()> {
+        let ty = target.types.iter().find(|t| t.name == self.name);
+        if let Some(ty) = ty {
+            modify_type(self, ty, target, change_set, publish_profile)
+        } else if let TypeDefinitionKind::UserDefined = self.kind {
+            bail!(PublishInvalidOperationError(format!(
+                "User defined type {} can't be created",
+                self.name
+            )))
+        } else {
+            change_set.push(ChangeInstruction::AddType(self));
+            Ok(())
+        }
+    }
+}
+
+// Modifies an existing type to match the source definition
+fn modify_type<'a>(
+    source: &'a TypeDefinition,
+    target: &TypeDefinition,
+    target_package: &Package,
+    change_set: &mut Vec<ChangeInstruction<'a>>,
+    publish_profile: &PublishProfile,
+) -> PsqlpackResult<()> {
+    if source.name.ne(&target.name) {
+        bail!(PublishInvalidOperationError(format!(
+            "Types not diffable: {} != {}",
+            source.name, target.name
+        )))
+    }
+    // We can only diff types of the same kind
+    match source.kind {
+        TypeDefinitionKind::Enum(ref source_values) => {
+            match target.kind {
+                TypeDefinitionKind::Enum(ref target_values) => {
+                    // Detect if anything needs to be deleted in the target. Postgres can't remove enum values so
+                    // the type is rebuilt with the source values, which also takes care of any additions.
+                    let to_delete = target_values
+                        .iter()
+                        .filter(|v| !source_values.contains(v))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if !to_delete.is_empty() {
+                        match publish_profile.generation_options.drop_enum_values {
+                            Toggle::Allow => {
+                                let columns = columns_using_type(target_package, &source.name, &to_delete);
+                                drop_type_dependents(target_package, &source.name, change_set, publish_profile)?;
+                                change_set.push(ChangeInstruction::ModifyType(
+                                    source,
+                                    TypeModificationAction::RemoveEnumValues {
+                                        values: to_delete,
+                                        columns,
+                                    },
+                                ));
+                                return Ok(());
+                            }
+                            Toggle::Error => {
+                                bail!(PublishUnsafeOperationError(format!(
+                                    "Unable to remove enum value(s) as unsafe operations are disabled: {:?}",
+                                    to_delete
+                                )));
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    // Our working group after items being deleted
+                    let mut working = target_values
+                        .iter()
+                        .filter(|v| source_values.contains(v))
+                        .collect::<Vec<_>>();
+
+                    // Detect what needs adding
+                    for (index, value) in source_values.iter().enumerate() {
+                        if !working.contains(&value) {
+                            if index == 0 {
+                                change_set.push(ChangeInstruction::ModifyType(
+                                    source,
+                                    TypeModificationAction::AddEnumValueBefore {
+                                        value: value.to_owned(),
+                                        before: working[0].to_owned(),
+                                    },
+                                ));
+                                working.insert(0, value);
+                            } else {
+                                change_set.push(ChangeInstruction::ModifyType(
+                                    source,
+                                    TypeModificationAction::AddEnumValueAfter {
+                                        value: value.to_owned(),
+                                        after: working[index - 1].to_owned(),
+                                    },
+                                ));
+                                working.insert(index, value);
+                            }
+                        }
+                    }
+                }
+                ref target_kind => bail!(kind_mismatch(source, target_kind)),
+            }
+        }
+        TypeDefinitionKind::Composite(ref source_attributes) => match target.kind {
+            TypeDefinitionKind::Composite(ref target_attributes) => {
+                // Detect if any attributes need to be dropped first
+                for attribute in target_attributes {
+                    if !source_attributes.iter().any(|a| a.name.eq(&attribute.name)) {
+                        match publish_profile.generation_options.drop_columns {
+                            Toggle::Allow => change_set.push(ChangeInstruction::ModifyType(
+                                source,
+                                TypeModificationAction::DropAttribute {
+                                    name: attribute.name.to_owned(),
+                                },
+                            )),
+                            Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                                "Attempted to drop attribute {} from type {} however dropping columns is currently disabled",
+                                attribute.name, source.name
+                            ))),
+                            _ => {}
+                        }
+                    }
+                }
+
+                // Then add or modify the remaining attributes
+                for attribute in source_attributes {
+                    match target_attributes.iter().find(|a| a.name.eq(&attribute.name)) {
+                        Some(target_attribute) => {
+                            if target_attribute.sql_type.ne(&attribute.sql_type) {
+                                change_set.push(ChangeInstruction::ModifyType(
+                                    source,
+                                    TypeModificationAction::AlterAttributeType {
+                                        name: attribute.name.to_owned(),
+                                        sql_type: attribute.sql_type.clone(),
+                                    },
+                                ));
+                            }
+                        }
+                        None => change_set.push(ChangeInstruction::ModifyType(
+                            source,
+                            TypeModificationAction::AddAttribute {
+                                name: attribute.name.to_owned(),
+                                sql_type: attribute.sql_type.clone(),
+                            },
+                        )),
+                    }
+                }
+            }
+            ref target_kind => bail!(kind_mismatch(source, target_kind)),
+        },
+        TypeDefinitionKind::Domain(ref source_domain) => match target.kind {
+            TypeDefinitionKind::Domain(ref target_domain) => {
+                if source_domain.sql_type.ne(&target_domain.sql_type) {
+                    bail!(PublishInvalidOperationError(format!(
+                        "Unable to change the base type of domain {} from {} to {}",
+                        source.name, target_domain.sql_type, source_domain.sql_type
+                    )))
+                }
+
+                if !is_same_default(source_domain.default.as_ref(), target_domain.default.as_ref()) {
+                    change_set.push(ChangeInstruction::ModifyType(
+                        source,
+                        match source_domain.default {
+                            Some(ref value) => TypeModificationAction::SetDomainDefault { value: value.clone() },
+                            None => TypeModificationAction::DropDomainDefault,
+                        },
+                    ));
+                }
+                if source_domain.not_null != target_domain.not_null {
+                    change_set.push(ChangeInstruction::ModifyType(
+                        source,
+                        if source_domain.not_null {
+                            TypeModificationAction::SetDomainNotNull
+                        } else {
+                            TypeModificationAction::DropDomainNotNull
+                        },
+                    ));
+                }
+
+                // Constraints are matched by name. Unnamed constraints can only be matched by expression.
+                let matches = |source: &DomainConstraint, target: &DomainConstraint| match source.name {
+                    Some(ref name) => target.name.as_ref() == Some(name),
+                    None => target.is_same_expression(source),
+                };
+                for constraint in &target_domain.constraints {
+                    if let Some(ref name) = constraint.name {
+                        if !source_domain.constraints.iter().any(|c| matches(c, constraint)) {
+                            change_set.push(ChangeInstruction::ModifyType(
+                                source,
+                                TypeModificationAction::DropDomainConstraint { name: name.to_owned() },
+                            ));
+                        }
+                    }
+                }
+                for constraint in &source_domain.constraints {
+                    let existing = target_domain.constraints.iter().find(|c| matches(constraint, c));
+                    if let Some(existing) = existing {
+                        if existing.is_same_expression(constraint) {
+                            continue;
+                        }
+                        // Check constraints can't be altered so they are recreated
+                        if let Some(ref name) = existing.name {
+                            change_set.push(ChangeInstruction::ModifyType(
+                                source,
+                                TypeModificationAction::DropDomainConstraint { name: name.to_owned() },
+                            ));
+                        }
+                    }
+                    change_set.push(ChangeInstruction::ModifyType(
+                        source,
+                        TypeModificationAction::AddDomainConstraint {
+                            name: constraint.name.clone(),
+                            expression: constraint.expression.to_owned(),
+                        },
+                    ));
+                }
+            }
+            ref target_kind => bail!(kind_mismatch(source, target_kind)),
+        },
+        TypeDefinitionKind::Range(ref source_range) => match target.kind {
+            TypeDefinitionKind::Range(ref target_range) => {
+                // Range types can't be altered once created
+                if source_range.ne(target_range) {
+                    bail!(PublishInvalidOperationError(format!(
+                        "Range type {} can't be modified once created",
+                        source.name
+                    )))
+                }
+            }
+            ref target_kind => bail!(kind_mismatch(source, target_kind)),
+        },
+        TypeDefinitionKind::UserDefined => match target.kind {
+            TypeDefinitionKind::UserDefined => {}
+            ref target_kind => bail!(kind_mismatch(source, target_kind)),
+        },
+    }
+    Ok(())
+}
+
+// The table columns within the target that use the type, either directly or as an array. Column defaults are kept so
+// that they can be put back once the column has been converted, unless they use one of the values being removed.
+fn columns_using_type(target: &Package, name: &ObjectName, removed: &[String]) -> Vec<EnumColumn> {
+    target
+        .tables
+        .iter()
+        // Partitions are converted along with the partitioned table
+        .filter(|table| table.partition_of.is_none())
+        .flat_map(|table| {
+            table.columns.iter().filter_map(move |column| match column.sql_type {
+                SqlType::Custom(ref type_name, _, dim) if is_same_type_name(type_name, name) => {
+                    let default = column.default_value().filter(|expr| {
+                        let expr = expr.to_string();
+                        !removed.iter().any(|value| expr.contains(&format!("'{}'", value)))
+                    });
+                    Some(EnumColumn {
+                        table: table.name.clone(),
+                        column: column.name.to_owned(),
+                        array: dim.is_some(),
+                        default: default.cloned(),
+                    })
+                }
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+// Extracted types are left unqualified when their schema is on the search path, which is public by default
+fn is_same_type_name(left: &ObjectName, right: &ObjectName) -> bool {
+    left.name.eq(&right.name)
+        && left.schema.as_deref().unwrap_or("public") == right.schema.as_deref().unwrap_or("public")
+}
+
+// Views and routines that depend on a rebuilt enum stop the previous type from being dropped, and views also stop
+// their columns from being converted. They are found from the catalog and dropped up front, deepest first, so that
+// they get created again from the source.
+fn drop_type_dependents(
+    target: &Package,
+    name: &ObjectName,
+    change_set: &mut Vec<ChangeInstruction>,
+    publish_profile: &PublishProfile,
+) -> PsqlpackResult<()> {
+    let options = &publish_profile.generation_options;
+    let mut dependents = target
+        .type_dependents
+        .iter()
+        .filter(|d| is_same_type_name(&d.type_name, name))
+        .collect::<Vec<_>>();
+    dependents.sort_by_key(|d| std::cmp::Reverse(d.depth));
+    for dependent in dependents {
+        if is_dropped(change_set, &dependent.name) {
+            continue;
+        }
+        let (kind, drop, toggle) = match dependent.kind {
+            TypeDependentKind::View => (
+                "view",
+                ChangeInstruction::DropView(dependent.name.to_owned()),
+                &options.drop_views,
+            ),
+            TypeDependentKind::MaterializedView => (
+                "materialized view",
+                ChangeInstruction::DropMaterializedView(dependent.name.to_owned()),
+                &options.drop_views,
+            ),
+            TypeDependentKind::Function => (
+                "function",
+                ChangeInstruction::DropFunction(dependent.name.to_owned()),
+                &options.drop_functions,
+            ),
+            TypeDependentKind::Procedure => (
+                "procedure",
+                ChangeInstruction::DropProcedure(dependent.name.to_owned()),
+                &options.drop_procedures,
+            ),
+        };
+        // The type can't be rebuilt while the dependent exists, so anything other than dropping it is an error
+        if *toggle != Toggle::Allow {
+            bail!(PublishUnsafeOperationError(format!(
+                "Attempted to recreate {} {} since it depends on type {} however dropping it is currently disabled",
+                kind, dependent.name, name
+            )));
+        }
+        change_set.push(drop);
+    }
+    Ok(())
+}
+
+// Whether a view or routine has already been dropped by the changes so far
+fn is_dropped(change_set: &[ChangeInstruction], name: &str) -> bool {
+    change_set.iter().any(|change| match *change {
+        ChangeInstruction::DropView(ref dropped)
+        | ChangeInstruction::DropMaterializedView(ref dropped)
+        | ChangeInstruction::DropFunction(ref dropped)
+        | ChangeInstruction::DropProcedure(ref dropped) => dropped == name,
+        _ => false,
+    })
+}
+
+// The objects within a schema that remain once the changes so far have been made
+fn remaining_schema_objects(target: &Package, schema: &str, change_set: &[ChangeInstruction]) -> Vec<String> {
+    let dropped = change_set
+        .iter()
+        .filter_map(|change| match *change {
+            ChangeInstruction::DropTable(ref name)
+            | ChangeInstruction::DropPartition(ref name)
+            | ChangeInstruction::DropView(ref name)
+            | ChangeInstruction::DropMaterializedView(ref name)
+            | ChangeInstruction::DropFunction(ref name)
+            | ChangeInstruction::DropProcedure(ref name)
+            | ChangeInstruction::DropType(ref name) => Some(&name[..]),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // Functions and procedures are identified by their signature since overloads are dropped separately
+    let named = |kind: &'static str, name: &ObjectName| (kind, name.schema().to_owned(), name.to_string());
+    let tables = target.tables.iter().map(|t| named("table", &t.name));
+    let views = target.views.iter().map(|v| named("view", &v.name));
+    let materialized_views = target
+        .materialized_views
+        .iter()
+        .map(|v| named("materialized view", &v.name));
+    let functions = target
+        .functions
+        .iter()
+        .map(|f| ("function", f.name.schema().to_owned(), f.signature()));
+    let procedures = target
+        .procedures
+        .iter()
+        .map(|p| ("procedure", p.name.schema().to_owned(), p.signature()));
+    let types = target.types.iter().map(|t| named("type", &t.name));
+    // Sequences owned by a column are dropped along with the table
+    let sequences = target
+        .sequences
+        .iter()
+        .filter(|s| match s.owned_by {
+            Some(ref owner) => !dropped.contains(&&owner.table.to_string()[..]),
+            None => true,
+        })
+        .map(|s| named("sequence", &s.name));
+    tables
+        .chain(views)
+        .chain(materialized_views)
+        .chain(functions)
+        .chain(procedures)
+        .chain(types)
+        .chain(sequences)
+        .filter(|(_, object_schema, name)| object_schema == schema && !dropped.contains(&&name[..]))
+        .map(|(kind, _, name)| format!("{} {}", kind, name))
+        .collect()
+}
+
+fn kind_mismatch(ty: &TypeDefinition, target_kind: &TypeDefinitionKind) -> PsqlpackErrorKind {
+    PublishInvalidOperationError(format!(
+        "Unable to change type {} from {} to {}",
+        ty.name, target_kind, ty.kind
+    ))
+}
+
+// Postgres describes defaults with explicit casts and additional brackets so they are compared once normalized
+fn is_same_default(source: Option<&Expression>, target: Option<&Expression>) -> bool {
+    match (source, target) {
+        (Some(source), Some(target)) => source.normalize().eq(&target.normalize()),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn is_same_generation(source: &Expression, target: &Expression) -> bool {
+    source
+        .normalize()
+        .without_casts()
+        .eq(&target.normalize().without_casts())
+}
+
+// The integer type behind a serial type
+fn serial_integer_type(sql_type: &SqlType) -> Option<SqlType> {
+    match *sql_type {
+        SqlType::Simple(SimpleSqlType::SmallSerial, None) => Some(SqlType::Simple(SimpleSqlType::SmallInteger, None)),
+        SqlType::Simple(SimpleSqlType::Serial, None) => Some(SqlType::Simple(SimpleSqlType::Integer, None)),
+        SqlType::Simple(SimpleSqlType::BigSerial, None) => Some(SqlType::Simple(SimpleSqlType::BigInteger, None)),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+pub struct Delta<'package>(Vec<ChangeInstruction<'package>>, DeploymentSummary, Option<Vec<Phase>>);
+
+/// When publishing transactionally, changes are applied in the following explicitly ordered phases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Phase {
+    /// Database level changes which are made against the host
+    Database,
+    /// Pre-deployment scripts, which get a transaction of their own when changes need to be made before the transaction
+    PreDeployment,
+    /// Changes which can't be made within a transaction but may be depended upon
+    BeforeTransaction,
+    /// Everything that can be made within a transaction
+    Transaction,
+    /// Changes which can't be made within a transaction and nothing depends upon
+    AfterTransaction,
+}
+
+impl Phase {
+    fn of(change: &ChangeInstruction, server_version: Semver) -> Phase {
+        match *change {
+            ChangeInstruction::KillConnections(..)
+            | ChangeInstruction::DropDatabase(..)
+            | ChangeInstruction::CreateDatabase(..)
+            | ChangeInstruction::UseDatabase(..) => Phase::Database,
+            // Prior to Postgres 12 enum values couldn't be added within a transaction block
+            ChangeInstruction::ModifyType(_, TypeModificationAction::AddEnumValueBefore { .. })
+            | ChangeInstruction::ModifyType(_, TypeModificationAction::AddEnumValueAfter { .. })
+                if server_version < Semver::new(12, 0, None) =>
+            {
+                Phase::BeforeTransaction
+            }
+            // Dropping concurrently checks that the index exists since the transaction may have already dropped its
+            // table or column
+            ChangeInstruction::AddIndex(_, true) | ChangeInstruction::DropIndex(_, true) => Phase::AfterTransaction,
+            // The index may not exist until it has been built concurrently
+            ChangeInstruction::SetComment(CommentObject::Index(_), _) => Phase::AfterTransaction,
+            _ => Phase::Transaction,
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Phase::Database => write!(f, "database"),
+            Phase::PreDeployment => write!(f, "pre-deployment"),
+            Phase::BeforeTransaction => write!(f, "before transaction"),
+            Phase::Transaction => write!(f, "transaction"),
+            Phase::AfterTransaction => write!(f, "after transaction"),
+        }
+    }
+}
+
+impl<'package> Delta<'package> {
+    pub fn generate(
+        log: &Logger,
+        package: &'package Package,
+        target: Option<Package>,
+        target_database_name: &str,
+        target_capabilities: &Capabilities,
+        publish_profile: &PublishProfile,
+    ) -> PsqlpackResult<Delta<'package>> {
+        let log = log.new(o!("delta" => "generate"));
+
+        // Start the change_set
+        let mut change_set = Vec::new();
 
         // If we always recreate then add a drop and set to false
         let mut target = target;
@@ -665,7 +1679,7 @@ impl<'package> Delta<'package> {
         }
 
         // For an empty database use an empty package, but also push a CREATE DB instruction
-        let target_package = match target {
+        let mut target_package = match target {
             Some(target_package) => target_package,
             None => {
                 change_set.push(ChangeInstruction::CreateDatabase(target_database_name.to_owned()));
@@ -676,6 +1690,14 @@ impl<'package> Delta<'package> {
         // Set the connection instruction
         change_set.push(ChangeInstruction::UseDatabase(target_database_name.to_owned()));
 
+        // Apply any renames that haven't yet been recorded against the target. Renaming the target package too means
+        // that the renamed objects get compared with the source rather than being dropped and added again.
+        for rename in &package.renames {
+            if !target_package.renames.contains(rename) && target_package.apply_rename(rename) {
+                change_set.push(ChangeInstruction::Rename(rename));
+            }
+        }
+
         // Create the build order - including all document types outside the topological sort.
         let mut build_order = Vec::new();
 
@@ -695,19 +1717,39 @@ impl<'package> Delta<'package> {
             build_order.push(DbObject::ExtensionRequest(extension));
         }
 
+        // Roles may be referenced by anything that follows. Memberships require both roles to exist.
+        for role in &package.roles {
+            build_order.push(DbObject::Role(role));
+        }
+        for role in &package.roles {
+            build_order.push(DbObject::RoleMembership(role));
+        }
+
         // Schemas
         for schema in &package.schemas {
             build_order.push(DbObject::Schema(schema));
         }
 
-        // Types
-        for t in &package.types {
+        // Types. Domains, ranges and composites may be built upon other types so these come last.
+        let mut types = package.types.iter().collect::<Vec<_>>();
+        types.sort_by_key(|t| match t.kind {
+            TypeDefinitionKind::Enum(_) | TypeDefinitionKind::UserDefined => 0,
+            TypeDefinitionKind::Domain(_) => 1,
+            TypeDefinitionKind::Range(_) => 2,
+            TypeDefinitionKind::Composite(_) => 3,
+        });
+        for t in types {
             build_order.push(DbObject::Type(t));
         }
 
+        // Sequences may be used by column defaults so come before tables
+        for sequence in &package.sequences {
+            build_order.push(DbObject::Sequence(sequence));
+        }
+
         // Drop indexes first
         for index in &target_package.indexes {
-            if !package.indexes.iter().any(|idx| idx.is_same_index(&index)) {
+            if !package.indexes.iter().any(|idx| idx.is_same_index(index)) {
                 match publish_profile.generation_options.drop_indexes {
                     Toggle::Allow => change_set.push(ChangeInstruction::DropIndex(
                         index.fully_qualified_name(),
@@ -722,61 +1764,331 @@ impl<'package> Delta<'package> {
             }
         }
 
-        // Drop functions next - first figure out if there are any to drop
-        for function in &target_package.functions {
-            if !package.functions.iter().any(|t| t.name.eq(&function.name)) {
-                match publish_profile.generation_options.drop_functions {
-                    Toggle::Allow => change_set.push(ChangeInstruction::DropFunction(function.name.to_string())),
+        // Drop policies next since they may reference functions and tables
+        for policy in &target_package.policies {
+            if !package.policies.iter().any(|p| p.is_same_policy(policy)) {
+                match publish_profile.generation_options.drop_policies {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropPolicy(
+                        policy.name.to_string(),
+                        policy.table.to_string(),
+                    )),
                     Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                        "Attempted to drop function {} however dropping functions is currently disabled",
-                        function.name
+                        "Attempted to drop policy {} however dropping policies is currently disabled",
+                        policy.fully_qualified_name()
                     ))),
                     _ => {}
                 }
             }
         }
 
-        // Drop tables next - first figure out if there are any to drop
-        for table in &target_package.tables {
-            if !package.tables.iter().any(|t| t.name.eq(&table.name)) {
-                match publish_profile.generation_options.drop_tables {
-                    Toggle::Allow => change_set.push(ChangeInstruction::DropTable(table.name.to_string())),
+        // Revoke any privileges that aren't granted within the project. Objects that the project doesn't define are
+        // left alone.
+        let granted = package
+            .grants
+            .iter()
+            .map(|grant| GrantDefinition {
+                grantee: publish_profile.role_name(&grant.grantee).to_owned(),
+                ..grant.clone()
+            })
+            .collect::<Vec<_>>();
+        for grant in target_package.grants.iter().filter(|g| package.contains(&g.object)) {
+            let mut revocations = Vec::new();
+            let revoked = grant
+                .privileges
+                .iter()
+                .filter(|&p| !holds_privilege(&granted, &grant.object, &grant.grantee, p, false))
+                .cloned()
+                .collect::<Vec<_>>();
+            if !revoked.is_empty() {
+                revocations.push((revoked, false));
+            }
+            if grant.with_grant_option {
+                let revoked = grant
+                    .privileges
+                    .iter()
+                    .filter(|&p| {
+                        holds_privilege(&granted, &grant.object, &grant.grantee, p, false)
+                            && !holds_privilege(&granted, &grant.object, &grant.grantee, p, true)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !revoked.is_empty() {
+                    revocations.push((revoked, true));
+                }
+            }
+            for (privileges, with_grant_option) in revocations {
+                let revocation = GrantDefinition {
+                    object: grant.object.clone(),
+                    grantee: grant.grantee.clone(),
+                    privileges,
+                    with_grant_option,
+                };
+                match publish_profile.generation_options.revoke_privileges {
+                    Toggle::Allow => change_set.push(ChangeInstruction::Revoke(revocation)),
                     Toggle::Error => bail!(PublishUnsafeOperationError(format!(
-                        "Attempted to drop table {} however dropping tables is currently disabled",
-                        table.name
+                        "Attempted to revoke {} on {} from {} however revoking privileges is currently disabled",
+                        privilege_list(&revocation.privileges),
+                        revocation.object,
+                        revocation.grantee
                     ))),
                     _ => {}
                 }
             }
         }
 
-        // Now add everything else per the topological sort
-        for item in package.generate_dependency_graph(&log)? {
-            match item {
-                Node::Function(_) => {
-                    // for the moment, add these later.
-                }
-                Node::Table(table) => {
-                    build_order.push(DbObject::Table(table));
+        // Drop triggers next since they depend on functions and tables
+        for trigger in &target_package.triggers {
+            if !package.triggers.iter().any(|t| t.is_same_trigger(trigger)) {
+                match publish_profile.generation_options.drop_triggers {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropTrigger(
+                        trigger.name.to_string(),
+                        trigger.table.to_string(),
+                    )),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop trigger {} however dropping triggers is currently disabled",
+                        trigger.fully_qualified_name()
+                    ))),
+                    _ => {}
                 }
-                Node::Column(table, column) => {
-                    build_order.push(DbObject::Column(table, column));
+            }
+        }
+
+        // Drop views next since they may depend on functions or tables
+        for view in &target_package.materialized_views {
+            if !package.materialized_views.iter().any(|v| v.name.eq(&view.name)) {
+                match publish_profile.generation_options.drop_views {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropMaterializedView(view.name.to_string())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop materialized view {} however dropping views is currently disabled",
+                        view.name
+                    ))),
+                    _ => {}
                 }
-                Node::Constraint(table, constraint) => {
-                    build_order.push(DbObject::Constraint(table, constraint));
+            }
+        }
+        for view in &target_package.views {
+            if !package.views.iter().any(|v| v.name.eq(&view.name)) {
+                match publish_profile.generation_options.drop_views {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropView(view.name.to_string())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop view {} however dropping views is currently disabled",
+                        view.name
+                    ))),
+                    _ => {}
                 }
             }
         }
 
-        for function in &package.functions {
-            build_order.push(DbObject::Function(function));
-        }
+        // Drop functions next - first figure out if there are any to drop
+        for function in &target_package.functions {
+            let signature = function.signature();
+            if !package.functions.iter().any(|f| f.signature().eq(&signature)) {
+                match publish_profile.generation_options.drop_functions {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropFunction(signature)),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop function {} however dropping functions is currently disabled",
+                        signature
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Drop procedures next - first figure out if there are any to drop
+        for procedure in &target_package.procedures {
+            let signature = procedure.signature();
+            if !package.procedures.iter().any(|p| p.signature().eq(&signature)) {
+                match publish_profile.generation_options.drop_procedures {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropProcedure(signature)),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop procedure {} however dropping procedures is currently disabled",
+                        signature
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Drop tables next - first figure out if there are any to drop
+        for table in &target_package.tables {
+            if !package.tables.iter().any(|t| t.name.eq(&table.name)) {
+                // Partitions may be managed outside of the project so are configured separately
+                if table.partition_of.is_some() {
+                    match publish_profile.generation_options.drop_partitions {
+                        Toggle::Allow => change_set.push(ChangeInstruction::DropPartition(table.name.to_string())),
+                        Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                            "Attempted to drop partition {} however dropping partitions is currently disabled",
+                            table.name
+                        ))),
+                        _ => {}
+                    }
+                    continue;
+                }
+                match publish_profile.generation_options.drop_tables {
+                    Toggle::Allow => change_set.push(ChangeInstruction::DropTable(table.name.to_string())),
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop table {} however dropping tables is currently disabled",
+                        table.name
+                    ))),
+                    _ => {}
+                }
+            }
+        }
+
+        // Drop types once the tables and functions using them have been dropped. Types are dropped in the reverse order
+        // that they were created so that domains and composites go before the types they use.
+        for ty in target_package.types.iter().rev() {
+            // Base types can't be created by psqlpack so typically belong to an extension
+            if let TypeDefinitionKind::UserDefined = ty.kind {
+                continue;
+            }
+            if package.types.iter().any(|t| t.name.eq(&ty.name)) {
+                continue;
+            }
+            match publish_profile.generation_options.drop_types {
+                Toggle::Allow => change_set.push(ChangeInstruction::DropType(ty.name.to_string())),
+                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                    "Attempted to drop type {} however dropping types is currently disabled",
+                    ty.name
+                ))),
+                _ => {}
+            }
+        }
+
+        // Drop schemas once the objects within them have been dropped. The public schema is created along with the
+        // database so it is always left alone.
+        for schema in &target_package.schemas {
+            if schema.name == "public" || package.schemas.iter().any(|s| s.name == schema.name) {
+                continue;
+            }
+            match publish_profile.generation_options.drop_schemas {
+                Toggle::Allow => {
+                    let remaining = remaining_schema_objects(&target_package, &schema.name, &change_set);
+                    if !remaining.is_empty() && !publish_profile.generation_options.cascade_schema_drops {
+                        bail!(PublishUnsafeOperationError(format!(
+                            "Unable to drop schema {} as it still contains {}. Dropping these requires CASCADE.",
+                            schema.name,
+                            remaining.join(", ")
+                        )));
+                    }
+                    change_set.push(ChangeInstruction::DropSchema(
+                        schema.name.to_owned(),
+                        !remaining.is_empty(),
+                    ));
+                }
+                Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                    "Attempted to drop schema {} however dropping schemas is currently disabled",
+                    schema.name
+                ))),
+                _ => {}
+            }
+        }
+
+        // Drop roles last since objects may still depend on them. Roles are shared across the cluster so are only
+        // dropped when the project manages them, and then only when psqlpack created them. Superusers are always
+        // left alone.
+        if !package.roles.is_empty() {
+            let declared = package
+                .roles
+                .iter()
+                .flat_map(|r| std::iter::once(&r.name).chain(r.member_of.iter()))
+                .map(|name| publish_profile.role_name(name))
+                .collect::<Vec<_>>();
+            let mut dropped = Vec::new();
+            for role in &target_package.roles {
+                if role.superuser
+                    || declared.contains(&&role.name[..])
+                    || !target_package.managed_roles.contains(&role.name)
+                {
+                    continue;
+                }
+                match publish_profile.generation_options.drop_roles {
+                    Toggle::Allow => {
+                        change_set.push(ChangeInstruction::DropRole(role.name.to_owned()));
+                        dropped.push(role.name.to_owned());
+                    }
+                    Toggle::Error => bail!(PublishUnsafeOperationError(format!(
+                        "Attempted to drop role {} however dropping roles is currently disabled",
+                        role.name
+                    ))),
+                    _ => {}
+                }
+            }
+
+            // Dropping a role also removes its memberships
+            for role in &mut target_package.roles {
+                role.member_of.retain(|group| !dropped.contains(group));
+            }
+        }
+
+        // Now add everything else per the topological sort
+        let mut views = Vec::new();
+        let mut triggers = Vec::new();
+        for item in package.generate_dependency_graph(&log)? {
+            match item {
+                Node::Function(_) => {
+                    // for the moment, add these later.
+                }
+                Node::View(view) => {
+                    // Views are added after functions however keep the sorted order
+                    views.push(DbObject::View(view));
+                }
+                Node::MaterializedView(view) => {
+                    views.push(DbObject::MaterializedView(view));
+                }
+                Node::Trigger(trigger) => {
+                    // Triggers may be defined on views so they come last
+                    triggers.push(DbObject::Trigger(trigger));
+                }
+                Node::Table(table) => {
+                    build_order.push(DbObject::Table(table));
+                }
+                Node::Column(table, column) => {
+                    build_order.push(DbObject::Column(table, column));
+                }
+                Node::Constraint(table, constraint) => {
+                    build_order.push(DbObject::Constraint(table, constraint));
+                }
+            }
+        }
+
+        // Sequence ownership requires the owning column to exist
+        for sequence in &package.sequences {
+            build_order.push(DbObject::SequenceOwner(sequence));
+        }
+
+        for function in &package.functions {
+            build_order.push(DbObject::Function(function));
+        }
+
+        build_order.extend(views);
+        build_order.extend(triggers);
+
+        // Procedures may reference any function, view or table within their body
+        for procedure in &package.procedures {
+            build_order.push(DbObject::Procedure(procedure));
+        }
+
+        // Policies may reference functions, views and other tables within their expressions
+        for policy in &package.policies {
+            build_order.push(DbObject::Policy(policy));
+        }
 
         // Indexes come into play now (all objects and constraints are created)
         for index in &package.indexes {
             build_order.push(DbObject::Index(index));
         }
 
+        // Privileges are granted once every object exists
+        for grant in &package.grants {
+            build_order.push(DbObject::Grant(grant));
+        }
+
+        // Comments are set once every object exists
+        for (object, comment) in package.comment_objects() {
+            build_order.push(DbObject::Comment(object, comment));
+        }
+
         // Add in post deployment scripts
         let mut scripts = package
             .scripts
@@ -793,75 +2105,252 @@ impl<'package> Delta<'package> {
             item.generate(
                 &mut change_set,
                 &target_package,
-                &target_capabilities,
-                &publish_profile,
+                target_capabilities,
+                publish_profile,
                 &log,
             )?;
         }
 
-        Ok(Delta(change_set))
+        // Figure out the phase of each change if everything is to be applied transactionally
+        let phases = if publish_profile.generation_options.transactional {
+            let mut phases = change_set
+                .iter()
+                .map(|change| Phase::of(change, target_capabilities.server_version))
+                .collect::<Vec<_>>();
+            // Pre-deployment scripts still run first when changes need to be made before the transaction
+            if phases.contains(&Phase::BeforeTransaction) {
+                for (change, phase) in change_set.iter().zip(phases.iter_mut()) {
+                    if matches!(*change, ChangeInstruction::RunScript(script) if script.kind == ScriptKind::PreDeployment)
+                    {
+                        *phase = Phase::PreDeployment;
+                    }
+                }
+            }
+            Some(phases)
+        } else {
+            None
+        };
+
+        Ok(Delta(
+            change_set,
+            DeploymentSummary::new(package, publish_profile)?,
+            phases,
+        ))
     }
 
     pub fn apply(&self, log: &Logger, connection: &Connection) -> PsqlpackResult<()> {
         let log = log.new(o!("delta" => "apply"));
+        if self.2.is_some() {
+            return self.apply_phases(&log, connection);
+        }
 
         let change_set = &self.0;
 
         // These instructions turn into SQL statements that get executed
         let mut conn = connection.connect_host()?;
 
+        // The deployment is recorded once we're connected to the target database
+        let mut deployment = None;
         for change in change_set.iter() {
             if let ChangeInstruction::UseDatabase(..) = *change {
                 conn = connection.connect_database()?;
+                trace!(log, "Recording deployment");
+                deployment = Some(Deployment::start(&mut conn, &self.1, &self.to_script(&log))?);
                 continue;
             }
 
             // Execute SQL directly
-            trace!(log, "Executing: {}", change);
-            let sql = change.to_sql(&log);
-            conn.batch_execute(&sql).chain_err(|| DatabaseExecuteError(sql))?;
+            if let Err(e) = Self::execute(&log, &mut conn, change) {
+                if let Some(id) = deployment {
+                    if Deployment::finish(&mut conn, id, DeploymentStatus::Failed).is_err() {
+                        warn!(log, "Failed to record the deployment as failed");
+                    }
+                }
+                return Err(e);
+            }
+        }
+
+        if let Some(id) = deployment {
+            Deployment::finish(&mut conn, id, DeploymentStatus::Succeeded)?;
         }
 
         Ok(())
     }
 
-    pub fn write_report(&self, destination: &Path) -> PsqlpackResult<()> {
-        let change_set = &self.0;
+    fn apply_phases(&self, log: &Logger, connection: &Connection) -> PsqlpackResult<()> {
+        let phases = self.phases().unwrap();
+        let total = phases.len();
+
+        // Database level changes are made against the host before anything else. The deployment is recorded within
+        // the transaction so that it only remains if the changes are committed.
+        let mut conn = connection.connect_host()?;
+        let mut connected = false;
+        let mut deployment = None;
+        for (number, &(phase, ref changes)) in phases.iter().enumerate() {
+            if phase != Phase::Database && !connected {
+                conn = connection.connect_database()?;
+                connected = true;
+            }
+
+            trace!(log, "Applying phase {} of {}: {}", number + 1, total, phase);
+            let result = if phase == Phase::Transaction {
+                conn.transaction()
+                    .chain_err(|| DatabaseError("Failed to start transaction".into()))
+                    .and_then(|mut transaction| {
+                        trace!(log, "Recording deployment");
+                        let id = Deployment::start(&mut transaction, &self.1, &self.to_script(log))?;
+                        for change in changes {
+                            Self::execute(log, &mut transaction, change)?;
+                        }
+                        Deployment::finish(&mut transaction, id, DeploymentStatus::Succeeded)?;
+                        transaction
+                            .commit()
+                            .chain_err(|| DatabaseError("Failed to commit transaction".into()))?;
+                        deployment = Some(id);
+                        Ok(())
+                    })
+            } else if phase == Phase::PreDeployment {
+                conn.transaction()
+                    .chain_err(|| DatabaseError("Failed to start transaction".into()))
+                    .and_then(|mut transaction| {
+                        for change in changes {
+                            Self::execute(log, &mut transaction, change)?;
+                        }
+                        transaction
+                            .commit()
+                            .chain_err(|| DatabaseError("Failed to commit transaction".into()))
+                    })
+            } else {
+                changes
+                    .iter()
+                    .try_for_each(|change| Self::execute(log, &mut conn, change))
+            };
 
-        File::create(destination)
-            .chain_err(|| GenerationError("Failed to generate report".to_owned()))
-            .and_then(|writer| {
-                serde_json::to_writer_pretty(writer, &change_set)
-                    .chain_err(|| GenerationError("Failed to generate report".to_owned()))
-            })?;
+            if let Err(e) = result {
+                // A failed transaction takes the deployment with it so the failure is recorded on its own
+                if connected {
+                    let recorded = match deployment {
+                        Some(id) => Deployment::finish(&mut conn, id, DeploymentStatus::Failed),
+                        None => Deployment::start(&mut conn, &self.1, &self.to_script(log))
+                            .and_then(|id| Deployment::finish(&mut conn, id, DeploymentStatus::Failed)),
+                    };
+                    if recorded.is_err() {
+                        warn!(log, "Failed to record the deployment as failed");
+                    }
+                }
+                return Err(e).chain_err(|| PublishPhaseError(number + 1, total, phase.to_string()));
+            }
+        }
 
+        // Without a transaction there is nothing to record the deployment alongside
+        if deployment.is_none() {
+            if !connected {
+                conn = connection.connect_database()?;
+            }
+            let id = Deployment::start(&mut conn, &self.1, &self.to_script(log))?;
+            Deployment::finish(&mut conn, id, DeploymentStatus::Succeeded)?;
+        }
         Ok(())
     }
 
-    pub fn write_sql(&self, log: &Logger, destination: &Path) -> PsqlpackResult<()> {
-        let change_set = &self.0;
+    fn execute<C: GenericClient>(log: &Logger, client: &mut C, change: &ChangeInstruction) -> PsqlpackResult<()> {
+        trace!(log, "Executing: {}", change);
+        let sql = change.to_sql(log);
+        client.batch_execute(&sql).chain_err(|| DatabaseExecuteError(sql))
+    }
+
+    // Groups the changes into their phases, ordered by phase. Switching databases is implied by the phases.
+    fn phases(&self) -> Option<Vec<(Phase, Vec<&ChangeInstruction<'package>>)>> {
+        let phases = self.2.as_ref()?;
+        let mut ordered = self.0.iter().zip(phases.iter().cloned()).collect::<Vec<_>>();
+        ordered.sort_by_key(|&(_, phase)| phase);
+
+        let mut grouped: Vec<(Phase, Vec<&ChangeInstruction<'package>>)> = Vec::new();
+        for (change, phase) in ordered {
+            if let ChangeInstruction::UseDatabase(..) = *change {
+                continue;
+            }
+            match grouped.last_mut() {
+                Some(&mut (last, ref mut changes)) if last == phase => changes.push(change),
+                _ => grouped.push((phase, vec![change])),
+            }
+        }
+        Some(grouped)
+    }
+
+    pub(crate) fn changes(&self) -> &[ChangeInstruction<'package>] {
+        &self.0
+    }
+
+    /// Describes the changes grouped by object and classified by risk.
+    pub fn report(&self, log: &Logger) -> Report {
+        Report::new(log, &self.0, &self.1)
+    }
+
+    pub fn write_report(&self, log: &Logger, destination: &Path, format: ReportFormat) -> PsqlpackResult<()> {
+        let report = self.report(log);
+        let mut writer =
+            File::create(destination).chain_err(|| GenerationError("Failed to generate report".to_owned()))?;
+        match format {
+            ReportFormat::Json => serde_json::to_writer_pretty(writer, &report)
+                .chain_err(|| GenerationError("Failed to generate report".to_owned())),
+            ReportFormat::Html => writer
+                .write_all(report.to_html().as_bytes())
+                .chain_err(|| GenerationError("Failed to generate report".to_owned())),
+            ReportFormat::Markdown => writer
+                .write_all(report.to_markdown().as_bytes())
+                .chain_err(|| GenerationError("Failed to generate report".to_owned())),
+        }
+    }
 
+    pub fn write_sql(&self, log: &Logger, destination: &Path) -> PsqlpackResult<()> {
         // These instructions turn into a single SQL file
         let mut out = match File::create(destination) {
             Ok(o) => o,
             Err(e) => bail!(GenerationError(format!("Failed to generate SQL file: {}", e))),
         };
+        match out.write_all(self.to_script(log).as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(e) => bail!(GenerationError(format!("Failed to generate SQL file: {}", e))),
+        }
+    }
 
-        for change in change_set.iter() {
-            let sql = change.to_sql(log);
-            match out.write_all(sql.as_bytes()) {
-                Ok(_) => {
-                    // New line
-                    match out.write(&[59u8, 10u8, 10u8]) {
-                        Ok(_) => {}
-                        Err(e) => bail!(GenerationError(format!("Failed to generate SQL file: {}", e))),
-                    }
+    fn to_script(&self, log: &Logger) -> String {
+        let mut script = String::new();
+        let phases = match self.phases() {
+            Some(phases) => phases,
+            None => {
+                for change in self.0.iter() {
+                    script.push_str(&change.to_sql(log));
+                    script.push_str(";\n\n");
                 }
-                Err(e) => bail!(GenerationError(format!("Failed to generate SQL file: {}", e))),
+                return script;
             }
-        }
+        };
 
-        Ok(())
+        let total = phases.len();
+        let mut use_database = self.0.iter().find(|c| matches!(c, ChangeInstruction::UseDatabase(..)));
+        for (number, (phase, changes)) in phases.into_iter().enumerate() {
+            if phase != Phase::Database {
+                if let Some(use_database) = use_database.take() {
+                    script.push_str(&use_database.to_sql(log));
+                    script.push_str(";\n\n");
+                }
+            }
+            script.push_str(&format!("-- Phase {} of {}: {}\n", number + 1, total, phase));
+            let transaction = phase == Phase::PreDeployment || phase == Phase::Transaction;
+            if transaction {
+                script.push_str("BEGIN;\n\n");
+            }
+            for change in changes {
+                script.push_str(&change.to_sql(log));
+                script.push_str(";\n\n");
+            }
+            if transaction {
+                script.push_str("COMMIT;\n\n");
+            }
+        }
+        script
     }
 }
 
@@ -879,12 +2368,21 @@ pub enum ChangeInstruction<'input> {
     UpgradeExtension(String, Option<Semver>),
 
     // Schema
-    AddSchema(&'input SchemaDefinition),
-    //DropSchema(String),
+    AddSchema(SchemaDefinition),
+    AlterSchemaOwner(String, String),
+    DropSchema(String, bool),
 
     // Scripts
     RunScript(&'input ScriptDefinition),
 
+    // Renames
+    Rename(&'input RenameDefinition),
+
+    // Sequences - no delete for now
+    AddSequence(&'input SequenceDefinition),
+    ModifySequence(&'input SequenceDefinition),
+    ModifySequenceOwner(&'input SequenceDefinition),
+
     // Types
     AddType(&'input TypeDefinition),
     ModifyType(&'input TypeDefinition, TypeModificationAction),
@@ -893,19 +2391,29 @@ pub enum ChangeInstruction<'input> {
     // Tables
     AddTable(&'input TableDefinition),
     DropTable(String),
+    AttachPartition(&'input TableDefinition),
+    DetachPartition(String, String),
+    DropPartition(String),
 
     // Columns
     AddColumn(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnType(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnNull(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnDefault(&'input TableDefinition, &'input ColumnDefinition),
+    AddColumnIdentity(&'input TableDefinition, &'input ColumnDefinition),
+    ModifyColumnIdentity(&'input TableDefinition, &'input ColumnDefinition),
+    DropColumnIdentity(&'input TableDefinition, &'input ColumnDefinition),
+    ConvertSerialToIdentity(&'input TableDefinition, &'input ColumnDefinition),
+    RecreateGeneratedColumn(&'input TableDefinition, &'input ColumnDefinition),
+    DropColumnExpression(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnUniqueConstraint(&'input TableDefinition, &'input ColumnDefinition),
     ModifyColumnPrimaryKeyConstraint(&'input TableDefinition, &'input ColumnDefinition),
     DropColumn(&'input TableDefinition, String),
 
     // Constraints
-    AddConstraint(&'input TableDefinition, &'input TableConstraint),
-    DropConstraint(&'input TableDefinition, String),
+    AddConstraint(&'input TableDefinition, &'input TableConstraint, bool),
+    ValidateConstraint(&'input TableDefinition, String),
+    DropConstraint(&'input TableDefinition, String, TableConstraintKind),
 
     // Index
     AddIndex(&'input IndexDefinition, bool),
@@ -913,16 +2421,98 @@ pub enum ChangeInstruction<'input> {
 
     // Functions
     AddFunction(&'input FunctionDefinition),
-    ModifyFunction(&'input FunctionDefinition), // This is identical to add however it's for future possible support
+    ModifyFunction(&'input FunctionDefinition),
     DropFunction(String),
+
+    // Procedures
+    AddProcedure(&'input ProcedureDefinition),
+    ModifyProcedure(&'input ProcedureDefinition),
+    DropProcedure(String),
+
+    // Views
+    AddView(&'input ViewDefinition),
+    ReplaceView(&'input ViewDefinition),
+    DropView(String),
+    AddMaterializedView(&'input MaterializedViewDefinition),
+    ReplaceMaterializedView(&'input MaterializedViewDefinition),
+    DropMaterializedView(String),
+
+    // Triggers
+    AddTrigger(&'input TriggerDefinition),
+    ReplaceTrigger(&'input TriggerDefinition),
+    DropTrigger(String, String),
+
+    // Row level security
+    ModifyRowSecurity(&'input TableDefinition),
+    ModifyForceRowSecurity(&'input TableDefinition),
+    AddPolicy(&'input PolicyDefinition),
+    ModifyPolicy(&'input PolicyDefinition),
+    ReplacePolicy(&'input PolicyDefinition),
+    DropPolicy(String, String),
+
+    // Privileges
+    Grant(GrantDefinition),
+    Revoke(GrantDefinition),
+
+    // Comments
+    SetComment(CommentObject, Option<String>),
+
+    // Roles
+    CreateRole(RoleDefinition),
+    AlterRole(RoleDefinition),
+    DropRole(String),
+    GrantRole(String, String),
+    RevokeRole(String, String),
+}
+
+// A table column that needs to be converted when an enum type is rebuilt
+#[derive(Debug, Serialize)]
+pub struct EnumColumn {
+    pub table: ObjectName,
+    pub column: String,
+    pub array: bool,
+    pub default: Option<Expression>,
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Serialize)]
 pub enum TypeModificationAction {
-    AddEnumValueBefore { value: String, before: String },
-    AddEnumValueAfter { value: String, after: String },
-    RemoveEnumValue { value: String },
+    AddEnumValueBefore {
+        value: String,
+        before: String,
+    },
+    AddEnumValueAfter {
+        value: String,
+        after: String,
+    },
+    RemoveEnumValues {
+        values: Vec<String>,
+        columns: Vec<EnumColumn>,
+    },
+    AddAttribute {
+        name: String,
+        sql_type: SqlType,
+    },
+    AlterAttributeType {
+        name: String,
+        sql_type: SqlType,
+    },
+    DropAttribute {
+        name: String,
+    },
+    SetDomainDefault {
+        value: Expression,
+    },
+    DropDomainDefault,
+    SetDomainNotNull,
+    DropDomainNotNull,
+    AddDomainConstraint {
+        name: Option<String>,
+        expression: String,
+    },
+    DropDomainConstraint {
+        name: String,
+    },
 }
 
 impl<'input> fmt::Display for ChangeInstruction<'input> {
@@ -953,12 +2543,23 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
             }
 
             // Schema
-            AddSchema(schema) => write!(f, "Add schema: {}", schema.name),
+            AddSchema(ref schema) => write!(f, "Add schema: {}", schema.name),
+            AlterSchemaOwner(ref name, ref owner) => write!(f, "Alter owner of schema: {} to {}", name, owner),
+            DropSchema(ref name, false) => write!(f, "Drop schema: {}", name),
+            DropSchema(ref name, true) => write!(f, "Drop schema: {} (cascade)", name),
             //DropSchema(String),
 
             // Scripts
             RunScript(script) => write!(f, "Run script: {}", script.name),
 
+            // Renames
+            Rename(rename) => write!(f, "Rename {}", rename),
+
+            // Sequences
+            AddSequence(sequence) => write!(f, "Add sequence: {}", sequence.name),
+            ModifySequence(sequence) => write!(f, "Modify sequence: {}", sequence.name),
+            ModifySequenceOwner(sequence) => write!(f, "Modify owner for sequence: {}", sequence.name),
+
             // Types
             AddType(ty) => write!(f, "Add type: {}", ty.name),
             ModifyType(ty, ref action) => write!(
@@ -967,7 +2568,16 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
                 match *action {
                     TypeModificationAction::AddEnumValueBefore { .. } => "inserting an enum value",
                     TypeModificationAction::AddEnumValueAfter { .. } => "inserting an enum value",
-                    TypeModificationAction::RemoveEnumValue { .. } => "removing enum value",
+                    TypeModificationAction::RemoveEnumValues { .. } => "removing enum values",
+                    TypeModificationAction::AddAttribute { .. } => "adding an attribute",
+                    TypeModificationAction::AlterAttributeType { .. } => "changing an attribute type",
+                    TypeModificationAction::DropAttribute { .. } => "removing an attribute",
+                    TypeModificationAction::SetDomainDefault { .. } => "setting the default",
+                    TypeModificationAction::DropDomainDefault => "removing the default",
+                    TypeModificationAction::SetDomainNotNull => "setting not null",
+                    TypeModificationAction::DropDomainNotNull => "removing not null",
+                    TypeModificationAction::AddDomainConstraint { .. } => "adding a constraint",
+                    TypeModificationAction::DropDomainConstraint { .. } => "removing a constraint",
                 },
                 ty.name
             ),
@@ -976,6 +2586,9 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
             // Tables
             AddTable(table) => write!(f, "Add table: {}", table.name),
             DropTable(ref table_name) => write!(f, "Drop table: {}", table_name),
+            AttachPartition(table) => write!(f, "Attach partition: {}", table.name),
+            DetachPartition(ref table_name, _) => write!(f, "Detach partition: {}", table_name),
+            DropPartition(ref table_name) => write!(f, "Drop partition: {}", table_name),
 
             // Columns
             AddColumn(table, column) => write!(f, "Add column: {} to table: {}", column.name, table.name),
@@ -988,6 +2601,32 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
             ModifyColumnDefault(table, column) => {
                 write!(f, "Modify default for column: {} on table: {}", column.name, table.name)
             }
+            AddColumnIdentity(table, column) => {
+                write!(f, "Add identity for column: {} on table: {}", column.name, table.name)
+            }
+            ModifyColumnIdentity(table, column) => {
+                write!(
+                    f,
+                    "Modify identity for column: {} on table: {}",
+                    column.name, table.name
+                )
+            }
+            DropColumnIdentity(table, column) => {
+                write!(f, "Drop identity for column: {} on table: {}", column.name, table.name)
+            }
+            ConvertSerialToIdentity(table, column) => write!(
+                f,
+                "Convert serial to identity for column: {} on table: {}",
+                column.name, table.name
+            ),
+            RecreateGeneratedColumn(table, column) => {
+                write!(f, "Recreate generated column: {} on table: {}", column.name, table.name)
+            }
+            DropColumnExpression(table, column) => write!(
+                f,
+                "Drop generated expression for column: {} on table: {}",
+                column.name, table.name
+            ),
             ModifyColumnUniqueConstraint(table, column) => write!(
                 f,
                 "Modify unique constraint for column: {} on table: {}",
@@ -1001,10 +2640,17 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
             DropColumn(table, ref column_name) => write!(f, "Drop column: {} on table: {}", column_name, table.name),
 
             // Constraints
-            AddConstraint(table, constraint) => {
-                write!(f, "Add constraint: {} to table: {}", constraint.name(), table.name)
+            AddConstraint(table, constraint, not_valid) => write!(
+                f,
+                "Add constraint{}: {} to table: {}",
+                if not_valid { " without validation" } else { "" },
+                constraint.name(),
+                table.name
+            ),
+            ValidateConstraint(table, ref name) => {
+                write!(f, "Validate constraint: {} on table: {}", name, table.name)
             }
-            DropConstraint(table, ref name) => write!(f, "Drop constraint: {} to table: {}", name, table.name),
+            DropConstraint(table, ref name, _) => write!(f, "Drop constraint: {} to table: {}", name, table.name),
 
             // Indexes
             AddIndex(index, concurrently) => write!(
@@ -1021,16 +2667,77 @@ impl<'input> fmt::Display for ChangeInstruction<'input> {
             ),
 
             // Functions
-            AddFunction(function) => write!(f, "Add function: {}", function.name),
-            // Modify is identical to add however it's for future possible support
-            ModifyFunction(function) => write!(f, "Modify function: {}", function.name),
+            AddFunction(function) => write!(f, "Add function: {}", function.signature()),
+            ModifyFunction(function) => write!(f, "Modify function: {}", function.signature()),
             DropFunction(ref function_name) => write!(f, "Drop function: {}", function_name),
+
+            // Procedures
+            AddProcedure(procedure) => write!(f, "Add procedure: {}", procedure.signature()),
+            ModifyProcedure(procedure) => write!(f, "Modify procedure: {}", procedure.signature()),
+            DropProcedure(ref procedure_name) => write!(f, "Drop procedure: {}", procedure_name),
+
+            // Views
+            AddView(view) => write!(f, "Add view: {}", view.name),
+            ReplaceView(view) => write!(f, "Replace view: {}", view.name),
+            DropView(ref view_name) => write!(f, "Drop view: {}", view_name),
+            AddMaterializedView(view) => write!(f, "Add materialized view: {}", view.name),
+            ReplaceMaterializedView(view) => write!(f, "Replace materialized view: {}", view.name),
+            DropMaterializedView(ref view_name) => write!(f, "Drop materialized view: {}", view_name),
+
+            // Triggers
+            AddTrigger(trigger) => write!(f, "Add trigger: {}", trigger.fully_qualified_name()),
+            ReplaceTrigger(trigger) => write!(f, "Replace trigger: {}", trigger.fully_qualified_name()),
+            DropTrigger(ref trigger_name, ref table_name) => {
+                write!(f, "Drop trigger: {}.{}", table_name, trigger_name)
+            }
+
+            // Row level security
+            ModifyRowSecurity(table) => write!(f, "Modify row level security for table: {}", table.name),
+            ModifyForceRowSecurity(table) => {
+                write!(f, "Modify forced row level security for table: {}", table.name)
+            }
+            AddPolicy(policy) => write!(f, "Add policy: {}", policy.fully_qualified_name()),
+            ModifyPolicy(policy) => write!(f, "Modify policy: {}", policy.fully_qualified_name()),
+            ReplacePolicy(policy) => write!(f, "Replace policy: {}", policy.fully_qualified_name()),
+            DropPolicy(ref policy_name, ref table_name) => write!(f, "Drop policy: {}.{}", table_name, policy_name),
+
+            // Privileges
+            Grant(ref grant) => write!(
+                f,
+                "Grant {} on {} to {}",
+                privilege_list(&grant.privileges),
+                grant.object,
+                grant.grantee
+            ),
+            Revoke(ref revocation) => write!(
+                f,
+                "Revoke {}{} on {} from {}",
+                if revocation.with_grant_option {
+                    "grant option for "
+                } else {
+                    ""
+                },
+                privilege_list(&revocation.privileges),
+                revocation.object,
+                revocation.grantee
+            ),
+
+            // Comments
+            SetComment(ref object, Some(_)) => write!(f, "Set comment on {} {}", object.kind(), object.name()),
+            SetComment(ref object, None) => write!(f, "Remove comment on {} {}", object.kind(), object.name()),
+
+            // Roles
+            CreateRole(ref role) => write!(f, "Create role: {}", role.name),
+            AlterRole(ref role) => write!(f, "Alter role: {}", role.name),
+            DropRole(ref name) => write!(f, "Drop role: {}", name),
+            GrantRole(ref group, ref member) => write!(f, "Grant role {} to {}", group, member),
+            RevokeRole(ref group, ref member) => write!(f, "Revoke role {} from {}", group, member),
         }
     }
 }
 
 impl<'input> ChangeInstruction<'input> {
-    fn to_sql(&self, log: &Logger) -> String {
+    pub(crate) fn to_sql(&self, log: &Logger) -> String {
         match *self {
             // Database level
             ChangeInstruction::KillConnections(ref db) => {
@@ -1064,33 +2771,89 @@ impl<'input> ChangeInstruction<'input> {
             }
 
             // Schema level
-            ChangeInstruction::AddSchema(schema) => {
-                if schema.name == "public" {
+            ChangeInstruction::AddSchema(ref schema) => {
+                let mut instr = if schema.name == "public" {
                     format!("CREATE SCHEMA IF NOT EXISTS {}", schema.name)
                 } else {
                     format!("CREATE SCHEMA {}", schema.name)
+                };
+                if let Some(ref owner) = schema.owner {
+                    instr.push_str(&format!(" AUTHORIZATION {}", owner));
                 }
+                instr
+            }
+            ChangeInstruction::AlterSchemaOwner(ref name, ref owner) => {
+                format!("ALTER SCHEMA {} OWNER TO {}", name, owner)
+            }
+            ChangeInstruction::DropSchema(ref name, cascade) => format!(
+                "DROP SCHEMA IF EXISTS {}{}",
+                name,
+                if cascade { " CASCADE" } else { "" }
+            ),
+
+            // Sequence level
+            ChangeInstruction::AddSequence(sequence) => {
+                format!("CREATE SEQUENCE {}{}", sequence.name, sequence_options(sequence))
+            }
+            // The current value is never reset, i.e. RESTART is not used
+            ChangeInstruction::ModifySequence(sequence) => {
+                format!("ALTER SEQUENCE {}{}", sequence.name, sequence_options(sequence))
             }
+            ChangeInstruction::ModifySequenceOwner(sequence) => format!(
+                "ALTER SEQUENCE {} OWNED BY {}",
+                sequence.name,
+                match sequence.owned_by {
+                    Some(ref owner) => owner.to_string(),
+                    None => "NONE".into(),
+                }
+            ),
 
             // Type level
             ChangeInstruction::AddType(ty) => {
                 let mut def = String::new();
-                def.push_str(&format!("CREATE TYPE {} AS ", ty.name)[..]);
                 match ty.kind {
-                    TypeDefinitionKind::Enum(ref values) => {
-                        def.push_str("ENUM (\n");
-                        let mut enum_comma_required = false;
-                        for value in values {
-                            if enum_comma_required {
-                                def.push_str(",\n");
-                            } else {
-                                enum_comma_required = true;
-                            }
-                            def.push_str(&format!("  '{}'", value)[..]);
+                    TypeDefinitionKind::Enum(ref values) => def.push_str(&enum_definition(&ty.name, values)),
+                    TypeDefinitionKind::Composite(ref attributes) => {
+                        def.push_str(&format!("CREATE TYPE {} AS (\n", ty.name)[..]);
+                        let attributes = attributes
+                            .iter()
+                            .map(|a| format!("  {} {}", a.name, a.sql_type))
+                            .collect::<Vec<_>>();
+                        def.push_str(&attributes.join(",\n"));
+                        def.push_str("\n)");
+                    }
+                    TypeDefinitionKind::Range(ref range) => {
+                        def.push_str(&format!("CREATE TYPE {} AS RANGE (\n", ty.name)[..]);
+                        def.push_str(&format!("  SUBTYPE = {}", range.subtype)[..]);
+                        if let Some(ref opclass) = range.subtype_opclass {
+                            def.push_str(&format!(",\n  SUBTYPE_OPCLASS = {}", opclass)[..]);
+                        }
+                        if let Some(ref collation) = range.collation {
+                            def.push_str(&format!(",\n  COLLATION = \"{}\"", collation)[..]);
+                        }
+                        if let Some(ref canonical) = range.canonical {
+                            def.push_str(&format!(",\n  CANONICAL = {}", canonical)[..]);
+                        }
+                        if let Some(ref subtype_diff) = range.subtype_diff {
+                            def.push_str(&format!(",\n  SUBTYPE_DIFF = {}", subtype_diff)[..]);
                         }
                         def.push_str("\n)");
                     }
-                    ref unknown => panic!("Unknown kind: {}", unknown), // TODO
+                    TypeDefinitionKind::Domain(ref domain) => {
+                        def.push_str(&format!("CREATE DOMAIN {} AS {}", ty.name, domain.sql_type)[..]);
+                        if let Some(ref default) = domain.default {
+                            def.push_str(&format!(" DEFAULT {}", default)[..]);
+                        }
+                        if domain.not_null {
+                            def.push_str(" NOT NULL");
+                        }
+                        for constraint in &domain.constraints {
+                            def.push_str("\n  ");
+                            def.push_str(&domain_constraint(constraint));
+                        }
+                    }
+                    // This is rejected when generating the delta
+                    TypeDefinitionKind::UserDefined => unreachable!("User defined types can't be created"),
                 }
                 def
             }
@@ -1101,17 +2864,39 @@ impl<'input> ChangeInstruction<'input> {
                 TypeModificationAction::AddEnumValueAfter { ref value, ref after } => {
                     format!("ALTER TYPE {} ADD VALUE '{}' AFTER '{}'", ty.name, value, after)
                 }
-                TypeModificationAction::RemoveEnumValue { ref value } => format!(
-                    "DELETE FROM pg_enum \
-                     WHERE enumlabel='{}' AND \
-                     enumtypid=(SELECT oid FROM pg_type WHERE {})",
-                    value,
-                    if let Some(ref schema) = ty.name.schema {
-                        format!("nspname='{}' AND typname='{}'", schema, ty.name.name)
-                    } else {
-                        format!("typname='{}'", ty.name.name)
-                    },
+                TypeModificationAction::RemoveEnumValues {
+                    ref values,
+                    ref columns,
+                } => rebuild_enum(ty, values, columns),
+                TypeModificationAction::AddAttribute { ref name, ref sql_type } => {
+                    format!("ALTER TYPE {} ADD ATTRIBUTE {} {}", ty.name, name, sql_type)
+                }
+                TypeModificationAction::AlterAttributeType { ref name, ref sql_type } => {
+                    format!("ALTER TYPE {} ALTER ATTRIBUTE {} TYPE {}", ty.name, name, sql_type)
+                }
+                TypeModificationAction::DropAttribute { ref name } => {
+                    format!("ALTER TYPE {} DROP ATTRIBUTE IF EXISTS {}", ty.name, name)
+                }
+                TypeModificationAction::SetDomainDefault { ref value } => {
+                    format!("ALTER DOMAIN {} SET DEFAULT {}", ty.name, value)
+                }
+                TypeModificationAction::DropDomainDefault => format!("ALTER DOMAIN {} DROP DEFAULT", ty.name),
+                TypeModificationAction::SetDomainNotNull => format!("ALTER DOMAIN {} SET NOT NULL", ty.name),
+                TypeModificationAction::DropDomainNotNull => format!("ALTER DOMAIN {} DROP NOT NULL", ty.name),
+                TypeModificationAction::AddDomainConstraint {
+                    ref name,
+                    ref expression,
+                } => format!(
+                    "ALTER DOMAIN {} ADD {}",
+                    ty.name,
+                    domain_constraint(&DomainConstraint {
+                        name: name.clone(),
+                        expression: expression.to_owned(),
+                    })
                 ),
+                TypeModificationAction::DropDomainConstraint { ref name } => {
+                    format!("ALTER DOMAIN {} DROP CONSTRAINT IF EXISTS {}", ty.name, name)
+                }
             },
             ChangeInstruction::DropType(ref type_name) => format!("DROP TYPE IF EXISTS {}", type_name),
 
@@ -1157,57 +2942,236 @@ impl<'input> ChangeInstruction<'input> {
                 func.push_str("$$\n");
                 func.push_str("LANGUAGE ");
                 func.push_str(&function.language.to_string());
+
+                // Attributes not given are reset to their defaults by CREATE OR REPLACE
+                if function.volatility != FunctionVolatility::Volatile {
+                    func.push_str(&format!("\n{}", function.volatility)[..]);
+                }
+                if function.strict {
+                    func.push_str("\nSTRICT");
+                }
+                if function.security_definer {
+                    func.push_str("\nSECURITY DEFINER");
+                }
+                if function.parallel != FunctionParallel::Unsafe {
+                    func.push_str(&format!("\n{}", function.parallel)[..]);
+                }
+                if let Some(cost) = function.cost {
+                    func.push_str(&format!("\nCOST {}", cost)[..]);
+                }
+                if let Some(rows) = function.rows {
+                    func.push_str(&format!("\nROWS {}", rows)[..]);
+                }
+                for setting in &function.settings {
+                    func.push_str(&format!("\n{}", setting)[..]);
+                }
                 func
             }
             ChangeInstruction::DropFunction(ref function_name) => format!("DROP FUNCTION IF EXISTS {}", function_name),
 
-            // Table level
-            ChangeInstruction::AddTable(def) => {
-                let mut instr = String::new();
-                instr.push_str(&format!("CREATE TABLE {} (", def.name));
-                for (position, column) in def.columns.iter().enumerate() {
-                    if position > 0 {
-                        instr.push(',');
-                    }
-                    instr.push_str("\n\t");
-                    instr.push_str(&format!("{} {}", column.name, column.sql_type));
-                    for constraint in column.constraints.iter() {
-                        match *constraint {
-                            ColumnConstraint::Default(ref any_type) => {
-                                instr.push_str(&format!(" DEFAULT {}", any_type))
-                            }
-                            ColumnConstraint::NotNull => instr.push_str(" NOT NULL"),
-                            ColumnConstraint::Null => instr.push_str(" NULL"),
-                            ColumnConstraint::Unique => instr.push_str(" UNIQUE"),
-                            ColumnConstraint::PrimaryKey => instr.push_str(" PRIMARY KEY"),
-                        }
-                    }
+            // Procedure level
+            ChangeInstruction::AddProcedure(procedure) | ChangeInstruction::ModifyProcedure(procedure) => {
+                let arguments = procedure
+                    .arguments
+                    .iter()
+                    .map(|arg| arg.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let mut proc = format!(
+                    "CREATE OR REPLACE PROCEDURE {} ({})\nAS $${}$$\nLANGUAGE {}",
+                    procedure.name, arguments, procedure.body, procedure.language
+                );
+                if procedure.security_definer {
+                    proc.push_str("\nSECURITY DEFINER");
                 }
-                // Table constraints are added later
-                instr.push_str("\n)");
-                instr
-            }
-            ChangeInstruction::DropTable(ref table_name) => format!("DROP TABLE IF EXISTS {}", table_name),
-
-            // Column level
-            ChangeInstruction::AddColumn(table, column) => {
-                let mut instr = String::new();
-                instr.push_str(&format!(
-                    "ALTER TABLE {} ADD COLUMN {} {}",
-                    table.name, column.name, column.sql_type
-                ));
-                for constraint in column.constraints.iter() {
-                    match *constraint {
-                        ColumnConstraint::Default(ref any_type) => instr.push_str(&format!(" DEFAULT {}", any_type)),
-                        ColumnConstraint::NotNull => instr.push_str(" NOT NULL"),
-                        ColumnConstraint::Null => instr.push_str(" NULL"),
-                        ColumnConstraint::Unique => instr.push_str(" UNIQUE"),
-                        ColumnConstraint::PrimaryKey => instr.push_str(" PRIMARY KEY"),
-                    }
+                for setting in &procedure.settings {
+                    proc.push_str(&format!("\n{}", setting)[..]);
                 }
-                instr
+                proc
             }
-            ChangeInstruction::ModifyColumnType(table, column) => format!(
+            ChangeInstruction::DropProcedure(ref procedure_name) => {
+                format!("DROP PROCEDURE IF EXISTS {}", procedure_name)
+            }
+
+            // View level
+            ChangeInstruction::AddView(view) => format!(
+                "CREATE VIEW {}{} AS\n{}{}",
+                view.name,
+                view_columns(&view.columns),
+                view.query,
+                record_view_source(&view.name, &view.query)
+            ),
+            ChangeInstruction::ReplaceView(view) => format!(
+                "CREATE OR REPLACE VIEW {}{} AS\n{}{}",
+                view.name,
+                view_columns(&view.columns),
+                view.query,
+                record_view_source(&view.name, &view.query)
+            ),
+            ChangeInstruction::DropView(ref view_name) => format!("DROP VIEW IF EXISTS {}", view_name),
+            ChangeInstruction::AddMaterializedView(view) => format!(
+                "CREATE MATERIALIZED VIEW {}{} AS\n{}{}",
+                view.name,
+                view_columns(&view.columns),
+                view.query,
+                record_view_source(&view.name, &view.query)
+            ),
+            ChangeInstruction::ReplaceMaterializedView(view) => format!(
+                "DROP MATERIALIZED VIEW IF EXISTS {};\nCREATE MATERIALIZED VIEW {}{} AS\n{}{}",
+                view.name,
+                view.name,
+                view_columns(&view.columns),
+                view.query,
+                record_view_source(&view.name, &view.query)
+            ),
+            ChangeInstruction::DropMaterializedView(ref view_name) => {
+                format!("DROP MATERIALIZED VIEW IF EXISTS {}", view_name)
+            }
+
+            // Trigger level
+            ChangeInstruction::AddTrigger(trigger) => create_trigger(trigger),
+            ChangeInstruction::ReplaceTrigger(trigger) => format!(
+                "DROP TRIGGER IF EXISTS {} ON {};\n{}",
+                trigger.name,
+                trigger.table,
+                create_trigger(trigger)
+            ),
+            ChangeInstruction::DropTrigger(ref trigger_name, ref table_name) => {
+                format!("DROP TRIGGER IF EXISTS {} ON {}", trigger_name, table_name)
+            }
+
+            // Row level security
+            ChangeInstruction::ModifyRowSecurity(table) => format!(
+                "ALTER TABLE {} {} ROW LEVEL SECURITY",
+                table.name,
+                if table.row_security { "ENABLE" } else { "DISABLE" }
+            ),
+            ChangeInstruction::ModifyForceRowSecurity(table) => format!(
+                "ALTER TABLE {} {} ROW LEVEL SECURITY",
+                table.name,
+                if table.force_row_security { "FORCE" } else { "NO FORCE" }
+            ),
+            ChangeInstruction::AddPolicy(policy) => create_policy(policy),
+            ChangeInstruction::ModifyPolicy(policy) => {
+                let mut instr = format!(
+                    "ALTER POLICY {} ON {} TO {}",
+                    policy.name,
+                    policy.table,
+                    policy.normalized_roles().join(", ")
+                );
+                if let Some(ref using) = policy.using {
+                    instr.push_str(&format!("\nUSING ({})", using));
+                }
+                if let Some(ref with_check) = policy.with_check {
+                    instr.push_str(&format!("\nWITH CHECK ({})", with_check));
+                }
+                instr
+            }
+            ChangeInstruction::ReplacePolicy(policy) => format!(
+                "DROP POLICY IF EXISTS {} ON {};\n{}",
+                policy.name,
+                policy.table,
+                create_policy(policy)
+            ),
+            ChangeInstruction::DropPolicy(ref policy_name, ref table_name) => {
+                format!("DROP POLICY IF EXISTS {} ON {}", policy_name, table_name)
+            }
+
+            // Privileges
+            ChangeInstruction::Grant(ref grant) => format!(
+                "GRANT {} ON {} TO {}{}",
+                privilege_list(&grant.privileges),
+                grant.object,
+                grantee(&grant.grantee),
+                if grant.with_grant_option {
+                    " WITH GRANT OPTION"
+                } else {
+                    ""
+                }
+            ),
+            ChangeInstruction::Revoke(ref revocation) => format!(
+                "REVOKE {}{} ON {} FROM {}",
+                if revocation.with_grant_option {
+                    "GRANT OPTION FOR "
+                } else {
+                    ""
+                },
+                privilege_list(&revocation.privileges),
+                revocation.object,
+                grantee(&revocation.grantee)
+            ),
+
+            // Comments
+            ChangeInstruction::SetComment(ref object, ref comment) => format!(
+                "COMMENT ON {} IS {}",
+                object,
+                match *comment {
+                    Some(ref comment) => format!("'{}'", comment.replace('\'', "''")),
+                    None => "NULL".to_owned(),
+                }
+            ),
+
+            // Roles
+            ChangeInstruction::CreateRole(ref role) => format!(
+                "CREATE ROLE {0} WITH {1};\n\
+                 CREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+                 CREATE TABLE IF NOT EXISTS psqlpack.managed_roles (name text PRIMARY KEY);\n\
+                 INSERT INTO psqlpack.managed_roles (name) VALUES ('{0}') ON CONFLICT DO NOTHING",
+                role.name,
+                role_options(role)
+            ),
+            ChangeInstruction::AlterRole(ref role) => format!("ALTER ROLE {} WITH {}", role.name, role_options(role)),
+            ChangeInstruction::DropRole(ref name) => format!(
+                "DROP ROLE {0};\nDELETE FROM psqlpack.managed_roles WHERE name = '{0}'",
+                name
+            ),
+            ChangeInstruction::GrantRole(ref group, ref member) => format!("GRANT {} TO {}", group, member),
+            ChangeInstruction::RevokeRole(ref group, ref member) => format!("REVOKE {} FROM {}", group, member),
+
+            // Table level
+            ChangeInstruction::AddTable(def) => {
+                let mut instr = String::new();
+                if let Some(ref partition_of) = def.partition_of {
+                    instr.push_str(&format!(
+                        "CREATE TABLE {} PARTITION OF {} {}",
+                        def.name, partition_of.parent, partition_of.bound
+                    ));
+                } else {
+                    instr.push_str(&format!("CREATE TABLE {} (", def.name));
+                    for (position, column) in def.columns.iter().enumerate() {
+                        if position > 0 {
+                            instr.push(',');
+                        }
+                        instr.push_str("\n\t");
+                        instr.push_str(&column_definition(column));
+                    }
+                    // Table constraints are added later
+                    instr.push_str("\n)");
+                }
+                if let Some(ref partition_by) = def.partition_by {
+                    instr.push_str(&format!(" PARTITION BY {}", partition_by));
+                }
+                instr
+            }
+            ChangeInstruction::DropTable(ref table_name) | ChangeInstruction::DropPartition(ref table_name) => {
+                format!("DROP TABLE IF EXISTS {}", table_name)
+            }
+            ChangeInstruction::AttachPartition(def) => match def.partition_of {
+                Some(ref partition_of) => format!(
+                    "ALTER TABLE {} ATTACH PARTITION {} {}",
+                    partition_of.parent, def.name, partition_of.bound
+                ),
+                None => unreachable!("Only partitions can be attached"),
+            },
+            ChangeInstruction::DetachPartition(ref table_name, ref parent) => {
+                format!("ALTER TABLE {} DETACH PARTITION {}", parent, table_name)
+            }
+
+            // Column level
+            ChangeInstruction::AddColumn(table, column) => {
+                format!("ALTER TABLE {} ADD COLUMN {}", table.name, column_definition(column))
+            }
+            ChangeInstruction::ModifyColumnType(table, column) => format!(
                 "ALTER TABLE {} ALTER COLUMN {} TYPE {}",
                 table.name, column.name, column.sql_type
             ),
@@ -1229,21 +3193,77 @@ impl<'input> ChangeInstruction<'input> {
                 );
                 "".to_owned()
             }
-            ChangeInstruction::ModifyColumnDefault(table, column) => {
-                for constraint in column.constraints.iter() {
-                    if let ColumnConstraint::Default(ref any_type) = *constraint {
-                        return format!(
-                            ";\nALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}",
-                            table.name, column.name, any_type
-                        );
-                    }
-                }
-                error!(
-                    log,
-                    "Expected to modify column default constraint for {}.{}", table.name, column.name
+            ChangeInstruction::ModifyColumnDefault(table, column) => match column.default_value() {
+                Some(expr) => format!(
+                    "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}",
+                    table.name, column.name, expr
+                ),
+                None => format!("ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT", table.name, column.name),
+            },
+            ChangeInstruction::AddColumnIdentity(table, column) => format!(
+                "ALTER TABLE {} ALTER COLUMN {} ADD {}",
+                table.name,
+                column.name,
+                identity_definition(column.identity().unwrap())
+            ),
+            // The current value is never reset, i.e. RESTART is not used
+            ChangeInstruction::ModifyColumnIdentity(table, column) => {
+                let identity = column.identity().unwrap();
+                let mut options = vec![format!("SET GENERATED {}", identity_generation(identity))];
+                options.extend(
+                    identity_options(identity)
+                        .into_iter()
+                        .map(|option| format!("SET {}", option)),
                 );
-                "".to_owned()
+                format!(
+                    "ALTER TABLE {} ALTER COLUMN {} {}",
+                    table.name,
+                    column.name,
+                    options.join(" ")
+                )
             }
+            ChangeInstruction::DropColumnIdentity(table, column) => format!(
+                "ALTER TABLE {} ALTER COLUMN {} DROP IDENTITY IF EXISTS",
+                table.name, column.name
+            ),
+            // The identity continues on from the next value of the serial sequence which is then dropped
+            ChangeInstruction::ConvertSerialToIdentity(table, column) => {
+                let mut instr = String::new();
+                instr.push_str("DO $$\nDECLARE\n");
+                instr.push_str(&format!(
+                    "\tserial_sequence regclass := pg_get_serial_sequence('{}', '{}')::regclass;\n",
+                    table.name, column.name
+                ));
+                instr.push_str("BEGIN\n");
+                instr.push_str(&format!(
+                    "\tALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;\n",
+                    table.name, column.name
+                ));
+                instr.push_str("\tEXECUTE format('ALTER SEQUENCE %s OWNED BY NONE', serial_sequence);\n");
+                instr.push_str(&format!(
+                    "\tALTER TABLE {} ALTER COLUMN {} ADD {};\n",
+                    table.name,
+                    column.name,
+                    identity_definition(column.identity().unwrap())
+                ));
+                instr.push_str(&format!(
+                    "\tPERFORM setval(pg_get_serial_sequence('{}', '{}'), nextval(serial_sequence), false);\n",
+                    table.name, column.name
+                ));
+                instr.push_str("\tEXECUTE format('DROP SEQUENCE %s', serial_sequence);\n");
+                instr.push_str("END\n$$");
+                instr
+            }
+            ChangeInstruction::RecreateGeneratedColumn(table, column) => format!(
+                "ALTER TABLE {0} DROP COLUMN {1};\nALTER TABLE {0} ADD COLUMN {2}",
+                table.name,
+                column.name,
+                column_definition(column)
+            ),
+            ChangeInstruction::DropColumnExpression(table, column) => format!(
+                "ALTER TABLE {} ALTER COLUMN {} DROP EXPRESSION IF EXISTS",
+                table.name, column.name
+            ),
             ChangeInstruction::ModifyColumnUniqueConstraint(table, column) => {
                 for constraint in column.constraints.iter() {
                     if let ColumnConstraint::Unique = *constraint {
@@ -1272,7 +3292,7 @@ impl<'input> ChangeInstruction<'input> {
                 format!("ALTER TABLE {} DROP COLUMN {}", table.name, column_name)
             }
 
-            ChangeInstruction::AddConstraint(table, constraint) => {
+            ChangeInstruction::AddConstraint(table, constraint, not_valid) => {
                 let mut instr = String::new();
                 instr.push_str(&format!("ALTER TABLE {}\nADD ", table.name));
                 match *constraint {
@@ -1284,18 +3304,7 @@ impl<'input> ChangeInstruction<'input> {
                         instr.push_str(&format!("CONSTRAINT {} PRIMARY KEY ({})", name, columns.join(", ")));
 
                         // Do the WITH options too
-                        if let Some(ref unwrapped) = *parameters {
-                            instr.push_str(" WITH (");
-                            for (position, value) in unwrapped.iter().enumerate() {
-                                if position > 0 {
-                                    instr.push_str(", ");
-                                }
-                                match *value {
-                                    IndexParameter::FillFactor(i) => instr.push_str(&format!("FILLFACTOR={}", i)[..]),
-                                }
-                            }
-                            instr.push(')');
-                        }
+                        instr.push_str(&index_parameters(parameters));
                     }
                     TableConstraint::Foreign {
                         ref name,
@@ -1323,11 +3332,62 @@ impl<'input> ChangeInstruction<'input> {
                             }
                         }
                     }
+                    TableConstraint::Unique {
+                        ref name,
+                        ref columns,
+                        ref parameters,
+                    } => {
+                        instr.push_str(&format!("CONSTRAINT {} UNIQUE ({})", name, columns.join(", ")));
+                        instr.push_str(&index_parameters(parameters));
+                    }
+                    TableConstraint::Check {
+                        ref name,
+                        ref expression,
+                        no_inherit,
+                    } => {
+                        instr.push_str(&format!("CONSTRAINT {} CHECK ({})", name, expression));
+                        if no_inherit {
+                            instr.push_str(" NO INHERIT");
+                        }
+                    }
+                    TableConstraint::Exclude {
+                        ref name,
+                        ref index_type,
+                        ref elements,
+                        ref parameters,
+                        ref condition,
+                    } => {
+                        instr.push_str(&format!("CONSTRAINT {} EXCLUDE", name));
+                        if let Some(ref method) = *index_type {
+                            instr.push_str(match method {
+                                IndexType::BTree => " USING btree",
+                                IndexType::Gin => " USING gin",
+                                IndexType::Gist => " USING gist",
+                                IndexType::Hash => " USING hash",
+                            });
+                        }
+                        let elements = elements
+                            .iter()
+                            .map(|e| format!("{} WITH {}", e.expression, e.operator))
+                            .collect::<Vec<_>>();
+                        instr.push_str(&format!(" ({})", elements.join(", ")));
+                        instr.push_str(&index_parameters(parameters));
+                        if let Some(ref condition) = *condition {
+                            instr.push_str(&format!(" WHERE ({})", condition));
+                        }
+                    }
+                }
+                if not_valid {
+                    instr.push_str(" NOT VALID");
                 }
                 instr
             }
 
-            ChangeInstruction::DropConstraint(table, ref name) => {
+            ChangeInstruction::ValidateConstraint(table, ref name) => {
+                format!("ALTER TABLE {}\nVALIDATE CONSTRAINT {}", table.name, name)
+            }
+
+            ChangeInstruction::DropConstraint(table, ref name, _) => {
                 format!("ALTER TABLE {}\nDROP CONSTRAINT {}", table.name, name)
             }
 
@@ -1340,6 +3400,36 @@ impl<'input> ChangeInstruction<'input> {
                 instr
             }
 
+            // Renames
+            ChangeInstruction::Rename(rename) => {
+                let mut instr = match *rename {
+                    RenameDefinition::Table { ref from, ref to } => format!("ALTER TABLE {} RENAME TO {}", from, to),
+                    RenameDefinition::Column {
+                        ref table,
+                        ref from,
+                        ref to,
+                    } => format!("ALTER TABLE {} RENAME COLUMN {} TO {}", table, from, to),
+                    RenameDefinition::Index { ref from, ref to } => format!("ALTER INDEX {} RENAME TO {}", from, to),
+                    RenameDefinition::Constraint {
+                        ref table,
+                        ref from,
+                        ref to,
+                    } => format!("ALTER TABLE {} RENAME CONSTRAINT {} TO {}", table, from, to),
+                    RenameDefinition::Type { ref from, ref to } => format!("ALTER TYPE {} RENAME TO {}", from, to),
+                };
+
+                // Record the rename so that it is only ever applied once
+                let definition = serde_json::to_string(rename).unwrap().replace('\'', "''");
+                instr.push_str(";\nCREATE SCHEMA IF NOT EXISTS psqlpack;\n");
+                instr.push_str("CREATE TABLE IF NOT EXISTS psqlpack.refactor_log (");
+                instr.push_str("definition jsonb PRIMARY KEY, applied_at timestamptz NOT NULL DEFAULT now());\n");
+                instr.push_str(&format!(
+                    "INSERT INTO psqlpack.refactor_log (definition) VALUES ('{}') ON CONFLICT DO NOTHING",
+                    definition
+                ));
+                instr
+            }
+
             // Indexes
             ChangeInstruction::AddIndex(index, concurrently) => {
                 let mut instr = String::new();
@@ -1408,6 +3498,282 @@ impl<'input> ChangeInstruction<'input> {
     }
 }
 
+fn index_parameters(parameters: &Option<Vec<IndexParameter>>) -> String {
+    match *parameters {
+        Some(ref parameters) => {
+            let parameters = parameters
+                .iter()
+                .map(|p| match *p {
+                    IndexParameter::FillFactor(i) => format!("FILLFACTOR={}", i),
+                })
+                .collect::<Vec<_>>();
+            format!(" WITH ({})", parameters.join(", "))
+        }
+        None => String::new(),
+    }
+}
+
+fn enum_definition(name: &ObjectName, values: &[String]) -> String {
+    let values = values.iter().map(|v| format!("  '{}'", v)).collect::<Vec<_>>();
+    format!("CREATE TYPE {} AS ENUM (\n{}\n)", name, values.join(",\n"))
+}
+
+// Enum values can't be removed so the type is renamed out of the way, created again and the columns using it are
+// converted. Rows still using a removed value would fail the conversion so they are counted up front instead.
+fn rebuild_enum(ty: &TypeDefinition, values: &[String], columns: &[EnumColumn]) -> String {
+    let source_values = match ty.kind {
+        TypeDefinitionKind::Enum(ref source_values) => source_values,
+        _ => unreachable!("Only enum types can be rebuilt"),
+    };
+    let removed = values.iter().map(|v| format!("'{}'", v)).collect::<Vec<_>>().join(", ");
+    let mut sql = Vec::new();
+    if !columns.is_empty() {
+        let mut check = String::from("DO $$\nDECLARE\n  usages text[] := '{}';\n  total bigint;\nBEGIN\n");
+        for column in columns {
+            let condition = if column.array {
+                format!("{} && ARRAY[{}]::{}[]", column.column, removed, ty.name)
+            } else {
+                format!("{} IN ({})", column.column, removed)
+            };
+            check.push_str(&format!(
+                "  SELECT count(*) INTO total FROM {} WHERE {};\n  \
+                 IF total > 0 THEN usages := usages || ('{}.{} (' || total || ' rows)'); END IF;\n",
+                column.table, condition, column.table, column.column
+            ));
+        }
+        check.push_str(&format!(
+            "  IF array_length(usages, 1) > 0 THEN\n    \
+             RAISE EXCEPTION 'Unable to remove values from type {} as they are still used by %', \
+             array_to_string(usages, ', ');\n  END IF;\nEND\n$$",
+            ty.name
+        ));
+        sql.push(check);
+    }
+
+    let previous = ObjectName {
+        schema: ty.name.schema.clone(),
+        name: format!("{}_previous", ty.name.name),
+    };
+    sql.push(format!("ALTER TYPE {} RENAME TO {}", ty.name, previous.name));
+    sql.push(enum_definition(&ty.name, source_values));
+    for column in columns {
+        let array = if column.array { "[]" } else { "" };
+        if column.default.is_some() {
+            sql.push(format!(
+                "ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT",
+                column.table, column.column
+            ));
+        }
+        sql.push(format!(
+            "ALTER TABLE {0} ALTER COLUMN {1} TYPE {2}{3} USING {1}::text{3}::{2}{3}",
+            column.table, column.column, ty.name, array
+        ));
+        if let Some(ref default) = column.default {
+            sql.push(format!(
+                "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {}",
+                column.table, column.column, default
+            ));
+        }
+    }
+    sql.push(format!("DROP TYPE {}", previous));
+    sql.join(";\n")
+}
+
+fn domain_constraint(constraint: &DomainConstraint) -> String {
+    match constraint.name {
+        Some(ref name) => format!("CONSTRAINT {} CHECK ({})", name, constraint.expression),
+        None => format!("CHECK ({})", constraint.expression),
+    }
+}
+
+fn column_definition(column: &ColumnDefinition) -> String {
+    let mut def = format!("{} {}", column.name, column.sql_type);
+    for constraint in column.constraints.iter() {
+        match *constraint {
+            ColumnConstraint::Default(ref expr) => def.push_str(&format!(" DEFAULT {}", expr)),
+            ColumnConstraint::NotNull => def.push_str(" NOT NULL"),
+            ColumnConstraint::Null => def.push_str(" NULL"),
+            ColumnConstraint::Unique => def.push_str(" UNIQUE"),
+            ColumnConstraint::PrimaryKey => def.push_str(" PRIMARY KEY"),
+            ColumnConstraint::Identity(ref identity) => def.push_str(&format!(" {}", identity_definition(identity))),
+            ColumnConstraint::Generated(ref expr) => def.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expr)),
+        }
+    }
+    def
+}
+
+fn identity_definition(identity: &IdentityDefinition) -> String {
+    format!(
+        "GENERATED {} AS IDENTITY ({})",
+        identity_generation(identity),
+        identity_options(identity).join(" ")
+    )
+}
+
+fn identity_generation(identity: &IdentityDefinition) -> &'static str {
+    match identity.generation {
+        IdentityGeneration::Always => "ALWAYS",
+        IdentityGeneration::ByDefault => "BY DEFAULT",
+    }
+}
+
+fn identity_options(identity: &IdentityDefinition) -> Vec<String> {
+    let mut options = Vec::new();
+    if let Some(increment) = identity.increment {
+        options.push(format!("INCREMENT BY {}", increment));
+    }
+    options.push(match identity.min_value {
+        Some(min_value) => format!("MINVALUE {}", min_value),
+        None => "NO MINVALUE".to_owned(),
+    });
+    options.push(match identity.max_value {
+        Some(max_value) => format!("MAXVALUE {}", max_value),
+        None => "NO MAXVALUE".to_owned(),
+    });
+    if let Some(start) = identity.start {
+        options.push(format!("START WITH {}", start));
+    }
+    if let Some(cache) = identity.cache {
+        options.push(format!("CACHE {}", cache));
+    }
+    options.push(if identity.cycle { "CYCLE" } else { "NO CYCLE" }.to_owned());
+    options
+}
+
+fn sequence_options(sequence: &SequenceDefinition) -> String {
+    let mut options = String::new();
+    if let Some(ref data_type) = sequence.data_type {
+        options.push_str(&format!(" AS {}", data_type));
+    }
+    if let Some(increment) = sequence.increment {
+        options.push_str(&format!(" INCREMENT BY {}", increment));
+    }
+    match sequence.min_value {
+        Some(min_value) => options.push_str(&format!(" MINVALUE {}", min_value)),
+        None => options.push_str(" NO MINVALUE"),
+    }
+    match sequence.max_value {
+        Some(max_value) => options.push_str(&format!(" MAXVALUE {}", max_value)),
+        None => options.push_str(" NO MAXVALUE"),
+    }
+    if let Some(start) = sequence.start {
+        options.push_str(&format!(" START WITH {}", start));
+    }
+    if let Some(cache) = sequence.cache {
+        options.push_str(&format!(" CACHE {}", cache));
+    }
+    options.push_str(if sequence.cycle { " CYCLE" } else { " NO CYCLE" });
+    options
+}
+
+fn view_columns(columns: &Option<Vec<String>>) -> String {
+    match *columns {
+        Some(ref columns) => format!(" ({})", columns.join(", ")),
+        None => String::new(),
+    }
+}
+
+// Postgres describes a view with its own rendering of the query, so the published query is recorded alongside it
+// in order to compare against it when publishing again
+fn record_view_source(name: &ObjectName, query: &str) -> String {
+    format!(
+        ";\nCREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+         CREATE TABLE IF NOT EXISTS psqlpack.view_sources (\
+         view_oid oid PRIMARY KEY, query text NOT NULL, definition text NOT NULL);\n\
+         INSERT INTO psqlpack.view_sources (view_oid, query, definition) \
+         VALUES ('{0}'::regclass, '{1}', pg_get_viewdef('{0}'::regclass)) \
+         ON CONFLICT (view_oid) DO UPDATE SET query = EXCLUDED.query, definition = EXCLUDED.definition",
+        name,
+        query.replace('\'', "''")
+    )
+}
+
+fn create_trigger(trigger: &TriggerDefinition) -> String {
+    let mut instr = String::new();
+    instr.push_str(&format!(
+        "CREATE TRIGGER {} {} {} ON {} {}",
+        trigger.name,
+        trigger.timing,
+        trigger
+            .events
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join(" OR "),
+        trigger.table,
+        trigger.for_each
+    ));
+    if let Some(ref condition) = trigger.condition {
+        instr.push_str(&format!("\nWHEN ({})", condition));
+    }
+    // EXECUTE PROCEDURE is supported by all server versions
+    instr.push_str(&format!(
+        "\nEXECUTE PROCEDURE {}({})",
+        trigger.function,
+        trigger
+            .arguments
+            .iter()
+            .map(|a| format!("'{}'", a.replace('\'', "''")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    instr
+}
+
+fn privilege_list(privileges: &[Privilege]) -> String {
+    privileges.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn grantee(role: &str) -> &str {
+    if role == "public" {
+        "PUBLIC"
+    } else {
+        role
+    }
+}
+
+// Every attribute is specified so that altering a role resets anything changed outside of the project. Passwords
+// are never part of a role definition.
+fn role_options(role: &RoleDefinition) -> String {
+    let flag = |enabled: bool, name: &str| {
+        if enabled {
+            name.to_owned()
+        } else {
+            format!("NO{}", name)
+        }
+    };
+    [
+        flag(role.login, "LOGIN"),
+        flag(role.superuser, "SUPERUSER"),
+        flag(role.create_db, "CREATEDB"),
+        flag(role.create_role, "CREATEROLE"),
+        flag(role.inherit, "INHERIT"),
+        flag(role.replication, "REPLICATION"),
+        flag(role.bypass_rls, "BYPASSRLS"),
+        format!("CONNECTION LIMIT {}", role.connection_limit),
+    ]
+    .join(" ")
+}
+
+fn create_policy(policy: &PolicyDefinition) -> String {
+    let mut instr = format!("CREATE POLICY {} ON {}", policy.name, policy.table);
+    // Policies are permissive by default, which also allows older server versions to create them
+    if !policy.permissive {
+        instr.push_str(" AS RESTRICTIVE");
+    }
+    instr.push_str(&format!(" FOR {}", policy.command));
+    if !policy.roles.is_empty() {
+        instr.push_str(&format!(" TO {}", policy.roles.join(", ")));
+    }
+    if let Some(ref using) = policy.using {
+        instr.push_str(&format!("\nUSING ({})", using));
+    }
+    if let Some(ref with_check) = policy.with_check {
+        instr.push_str(&format!("\nWITH CHECK ({})", with_check));
+    }
+    instr
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1417,6 +3783,7 @@ mod tests {
     use crate::sql::ast;
     use crate::Semver;
 
+    use rust_decimal::Decimal;
     use slog::{Discard, Drain, Logger};
 
     fn empty_logger() -> Logger {
@@ -1430,6 +3797,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["red".into(), "green".into(), "blue".into()]),
+            comment: None,
         }
     }
 
@@ -1460,7 +3828,7 @@ mod tests {
         // We should have a single instruction to add
         assert_eq!(change_set.len(), 1);
         match change_set[0] {
-            ChangeInstruction::AddType(ref ty) => {
+            ChangeInstruction::AddType(ty) => {
                 assert_eq!(
                     ty.name,
                     ast::ObjectName {
@@ -1530,6 +3898,7 @@ mod tests {
                 "blue".to_owned(),
                 "black".to_owned(),
             ]),
+            comment: None,
         };
 
         // Create a package with the type already defined
@@ -1597,6 +3966,7 @@ mod tests {
                 "green".to_owned(),
                 "blue".to_owned(),
             ]),
+            comment: None,
         };
 
         // Create a package with the type already defined
@@ -1664,6 +4034,7 @@ mod tests {
                 "black".to_owned(),
                 "blue".to_owned(),
             ]),
+            comment: None,
         };
 
         // Create a package with the type already defined
@@ -1726,6 +4097,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["black".to_owned(), "green".to_owned(), "blue".to_owned()]),
+            comment: None,
         };
 
         // Create a package with the type already defined
@@ -1749,64 +4121,27 @@ mod tests {
         );
         assert!(result.is_ok());
 
-        // We should have a single instruction to modify the enum with an additional value
-        assert_eq!(change_set.len(), 2);
-
-        // Removals first
+        // The enum is rebuilt with the new values rather than inserting them individually
+        assert_eq!(change_set.len(), 1);
         match change_set[0] {
-            ChangeInstruction::ModifyType(ty, ref action) => {
-                assert_eq!(
-                    ty.name,
-                    ast::ObjectName {
-                        schema: Some("public".to_string()),
-                        name: "colors".to_string(),
-                    }
-                );
-
-                // Also, match the action
-                match *action {
-                    TypeModificationAction::RemoveEnumValue { ref value } => {
-                        assert_eq!(*value, "red");
-                    }
-                    ref unexpected => panic!("Unexpected enum modification action: {:?}", unexpected),
-                }
+            ChangeInstruction::ModifyType(
+                ty,
+                TypeModificationAction::RemoveEnumValues {
+                    ref values,
+                    ref columns,
+                },
+            ) => {
+                assert_eq!(ty.name.to_string(), "public.colors");
+                assert_eq!(*values, vec!["red".to_owned()]);
+                assert!(columns.is_empty());
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
-        // Check the SQL generation
         assert_eq!(
             change_set[0].to_sql(&log),
-            "DELETE FROM pg_enum \
-             WHERE enumlabel='red' AND \
-             enumtypid=(SELECT oid FROM pg_type WHERE nspname='public' AND typname='colors')",
-        );
-
-        // Additions second
-        match change_set[1] {
-            ChangeInstruction::ModifyType(ty, ref action) => {
-                assert_eq!(
-                    ty.name,
-                    ast::ObjectName {
-                        schema: Some("public".to_string()),
-                        name: "colors".to_string(),
-                    }
-                );
-
-                // Also, match the action
-                match *action {
-                    TypeModificationAction::AddEnumValueBefore { ref value, ref before } => {
-                        assert_eq!(value, "black");
-                        assert_eq!(before, "green");
-                    }
-                    ref unexpected => panic!("Unexpected enum modification action: {:?}", unexpected),
-                }
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-        // Check the SQL generation
-        assert_eq!(
-            change_set[1].to_sql(&log),
-            "ALTER TYPE public.colors ADD VALUE 'black' BEFORE 'green'"
+            "ALTER TYPE public.colors RENAME TO colors_previous;\n\
+             CREATE TYPE public.colors AS ENUM (\n  'black',\n  'green',\n  'blue'\n);\n\
+             DROP TYPE public.colors_previous"
         );
     }
 
@@ -1819,6 +4154,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["black".to_owned(), "green".to_owned(), "blue".to_owned()]),
+            comment: None,
         };
 
         // Create a package with the type already defined
@@ -1855,6 +4191,7 @@ mod tests {
                 name: "colors".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(vec!["green".to_owned(), "blue".to_owned()]),
+            comment: None,
         };
 
         // Create a package with the type already defined
@@ -1878,82 +4215,85 @@ mod tests {
         );
         assert!(result.is_ok());
 
-        // We should have a single instruction to modify the enum with an additional value
+        // We should have a single instruction to rebuild the enum without the value
         assert_eq!(change_set.len(), 1);
-
-        // Removals first
         match change_set[0] {
-            ChangeInstruction::ModifyType(ty, ref action) => {
-                assert_eq!(
-                    ty.name,
-                    ast::ObjectName {
-                        schema: Some("public".to_string()),
-                        name: "colors".to_string(),
-                    }
-                );
-
-                // Also, match the action
-                match *action {
-                    TypeModificationAction::RemoveEnumValue { ref value } => {
-                        assert_eq!(*value, "red");
-                    }
-                    ref unexpected => panic!("Unexpected enum modification action: {:?}", unexpected),
-                }
+            ChangeInstruction::ModifyType(
+                ty,
+                TypeModificationAction::RemoveEnumValues {
+                    ref values,
+                    ref columns,
+                },
+            ) => {
+                assert_eq!(ty.name.to_string(), "public.colors");
+                assert_eq!(*values, vec!["red".to_owned()]);
+                assert!(columns.is_empty());
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
-        // Check the SQL generation
         assert_eq!(
             change_set[0].to_sql(&log),
-            "DELETE FROM pg_enum \
-             WHERE enumlabel='red' AND \
-             enumtypid=(SELECT oid FROM pg_type WHERE nspname='public' AND typname='colors')",
+            "ALTER TYPE public.colors RENAME TO colors_previous;\n\
+             CREATE TYPE public.colors AS ENUM (\n  'green',\n  'blue'\n);\n\
+             DROP TYPE public.colors_previous"
         );
     }
 
-    fn base_table() -> ast::TableDefinition {
-        ast::TableDefinition {
-            name: ObjectName {
-                schema: Some("my".to_owned()),
-                name: "contacts".to_owned(),
+    #[test]
+    fn it_checks_and_converts_columns_using_a_rebuilt_enum() {
+        let log = empty_logger();
+        let source_type = ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "colors".to_string(),
             },
-            columns: vec![
-                ColumnDefinition {
-                    name: "id".to_owned(),
-                    sql_type: SqlType::Simple(SimpleSqlType::Serial, None),
-                    constraints: vec![ColumnConstraint::NotNull, ColumnConstraint::PrimaryKey],
-                },
-                ColumnDefinition {
-                    name: "company_id".to_owned(),
-                    sql_type: SqlType::Simple(SimpleSqlType::BigInteger, None),
-                    constraints: vec![ColumnConstraint::NotNull],
-                },
-                ColumnDefinition {
-                    name: "first_name".to_owned(),
-                    sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-                    constraints: vec![ColumnConstraint::NotNull],
-                },
-            ],
-            constraints: Vec::new(),
-        }
-    }
-
-    #[test]
-    fn it_can_add_new_table() {
-        let log = empty_logger();
-        let source_table = base_table();
+            kind: ast::TypeDefinitionKind::Enum(vec!["green".to_owned(), "blue".to_owned()]),
+            comment: None,
+        };
 
-        // Create an empty database
-        let existing_database = Package::new();
+        // Columns use the type directly, as an array and with defaults
+        let colors = ast::ObjectName {
+            schema: Some("public".to_string()),
+            name: "colors".to_string(),
+        };
+        let color_default = |value: &str| {
+            ast::ColumnConstraint::Default(ast::Expression::Literal(ast::AnyValue::String(
+                value.into(),
+                Some(ast::SqlType::Custom(colors.clone(), Vec::new(), None)),
+            )))
+        };
+        let mut existing_database = Package::new();
+        existing_database.types.push(base_type());
+        let mut table = base_table();
+        table.columns.push(ast::ColumnDefinition {
+            name: "color".into(),
+            sql_type: ast::SqlType::Custom(colors.clone(), Vec::new(), None),
+            constraints: vec![color_default("green")],
+            comment: None,
+        });
+        table.columns.push(ast::ColumnDefinition {
+            name: "palette".into(),
+            sql_type: ast::SqlType::Custom(colors.clone(), Vec::new(), Some(1)),
+            constraints: Vec::new(),
+            comment: None,
+        });
+        table.columns.push(ast::ColumnDefinition {
+            name: "warning".into(),
+            sql_type: ast::SqlType::Custom(colors.clone(), Vec::new(), None),
+            constraints: vec![color_default("red")],
+            comment: None,
+        });
+        existing_database.tables.push(table);
         let capabilities = Capabilities {
-            server_version: Semver::new(9, 6, None),
+            server_version: Semver::new(10, 0, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_enum_values = Toggle::Allow;
 
         let mut change_set = Vec::new();
-        let result = (&source_table).generate(
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -1961,69 +4301,128 @@ mod tests {
             &log,
         );
         assert!(result.is_ok());
-
-        // We should have a single instruction to create a new table
         assert_eq!(change_set.len(), 1);
-        match change_set[0] {
-            ChangeInstruction::AddTable(ref table) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(table.columns.len(), 3);
-                assert_eq!(table.columns[0].name, "id");
-                assert_eq!(table.columns[1].name, "company_id");
-                assert_eq!(table.columns[2].name, "first_name");
-                assert!(table.constraints.is_empty());
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
 
-        // Check the SQL generation
+        // Rows still using the value are counted before anything is changed. Defaults using a removed value aren't
+        // put back.
         assert_eq!(
             change_set[0].to_sql(&log),
-            "CREATE TABLE my.contacts (\n\
-             \tid serial NOT NULL PRIMARY KEY,\n\
-             \tcompany_id bigint NOT NULL,\n\
-             \tfirst_name varchar(100) NOT NULL\n\
-             )"
+            "DO $$\nDECLARE\n  usages text[] := '{}';\n  total bigint;\nBEGIN\n  \
+             SELECT count(*) INTO total FROM my.contacts WHERE color IN ('red');\n  \
+             IF total > 0 THEN usages := usages || ('my.contacts.color (' || total || ' rows)'); END IF;\n  \
+             SELECT count(*) INTO total FROM my.contacts WHERE palette && ARRAY['red']::public.colors[];\n  \
+             IF total > 0 THEN usages := usages || ('my.contacts.palette (' || total || ' rows)'); END IF;\n  \
+             SELECT count(*) INTO total FROM my.contacts WHERE warning IN ('red');\n  \
+             IF total > 0 THEN usages := usages || ('my.contacts.warning (' || total || ' rows)'); END IF;\n  \
+             IF array_length(usages, 1) > 0 THEN\n    \
+             RAISE EXCEPTION 'Unable to remove values from type public.colors as they are still used by %', \
+             array_to_string(usages, ', ');\n  END IF;\nEND\n$$;\n\
+             ALTER TYPE public.colors RENAME TO colors_previous;\n\
+             CREATE TYPE public.colors AS ENUM (\n  'green',\n  'blue'\n);\n\
+             ALTER TABLE my.contacts ALTER COLUMN color DROP DEFAULT;\n\
+             ALTER TABLE my.contacts ALTER COLUMN color TYPE public.colors USING color::text::public.colors;\n\
+             ALTER TABLE my.contacts ALTER COLUMN color SET DEFAULT 'green'::public.colors;\n\
+             ALTER TABLE my.contacts ALTER COLUMN palette TYPE public.colors[] USING palette::text[]::public.colors[];\n\
+             ALTER TABLE my.contacts ALTER COLUMN warning TYPE public.colors USING warning::text::public.colors;\n\
+             DROP TYPE public.colors_previous"
         );
     }
 
     #[test]
-    fn it_can_add_column_to_existing_table() {
+    fn it_drops_views_and_functions_depending_on_a_rebuilt_enum() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
+        let source_type = ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "colors".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Enum(vec!["green".to_owned(), "blue".to_owned()]),
+            comment: None,
+        };
 
-        // Create a database with the base table already defined.
+        // Types within the default schema are extracted without one
+        let colors = || {
+            ast::SqlType::Custom(
+                ast::ObjectName {
+                    schema: None,
+                    name: "colors".to_string(),
+                },
+                Vec::new(),
+                None,
+            )
+        };
         let mut existing_database = Package::new();
-        existing_database.tables.push(base_table());
+        existing_database.types.push(base_type());
+        let mut table = base_table();
+        table.columns.push(ast::ColumnDefinition {
+            name: "color".into(),
+            sql_type: colors(),
+            constraints: Vec::new(),
+            comment: None,
+        });
+        existing_database.tables.push(table);
+        let mut view = base_view();
+        view.name.name = "contact_colors".to_owned();
+        view.query = "SELECT id, color\n  FROM my.contacts".to_owned();
+        existing_database.views.push(view);
+        let mut view = base_view();
+        view.name.name = "contact_color_counts".to_owned();
+        view.query = "SELECT color, count(*)\n  FROM public.contact_colors\n GROUP BY color".to_owned();
+        existing_database.views.push(view);
+        // Mentioning the type by name doesn't make a view depend on it
+        let mut view = base_view();
+        view.name.name = "colors_legend".to_owned();
+        view.query = "SELECT 'colors' AS label".to_owned();
+        existing_database.views.push(view);
+        existing_database
+            .functions
+            .push(base_function(vec![("a", colors())], colors()));
+        // Dependents are found from the catalog, with nested views being further from the type
+        let dependent = |kind, name: &str, depth| TypeDependent {
+            type_name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "colors".to_string(),
+            },
+            kind,
+            name: name.to_owned(),
+            depth,
+        };
+        existing_database.type_dependents = vec![
+            dependent(TypeDependentKind::View, "public.contact_colors", 1),
+            dependent(TypeDependentKind::Function, "public.add(colors)", 1),
+            dependent(TypeDependentKind::View, "public.contact_color_counts", 2),
+        ];
         let capabilities = Capabilities {
-            server_version: Semver::new(9, 6, None),
+            server_version: Semver::new(10, 0, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_enum_values = Toggle::Allow;
+        publish_profile.generation_options.drop_views = Toggle::Error;
 
+        // Dropping the dependents has to be allowed
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert!(change_set.is_empty());
-
-        // Now we check with a linked column
-        let result = LinkedColumn {
-            table: &source_table,
-            column: &source_table.columns.last().unwrap(),
+        match result.err().unwrap() {
+            PsqlpackError(PublishUnsafeOperationError(message), _) => assert_eq!(
+                message,
+                "Attempted to recreate view public.contact_color_counts since it depends on type public.colors \
+                 however dropping it is currently disabled"
+            ),
+            unexpected => panic!("Expected unsafe operation error however saw {:?}", unexpected),
         }
-        .generate(
+
+        publish_profile.generation_options.drop_views = Toggle::Allow;
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2031,252 +4430,477 @@ mod tests {
             &log,
         );
         assert!(result.is_ok());
-
-        // We should have a single instruction to create a new table
-        assert_eq!(change_set.len(), 1);
-        match change_set[0] {
-            ChangeInstruction::AddColumn(ref table, ref column) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(column.name, "last_name");
-                assert_eq!(
-                    column.sql_type,
-                    SqlType::Simple(SimpleSqlType::VariableLengthString(100), None)
-                );
-                assert_eq!(column.constraints.len(), 1);
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
+        assert_eq!(change_set.len(), 4);
         assert_eq!(
             change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts ADD COLUMN last_name varchar(100) NOT NULL"
+            "DROP VIEW IF EXISTS public.contact_color_counts"
         );
+        assert_eq!(change_set[1].to_sql(&log), "DROP VIEW IF EXISTS public.contact_colors");
+        assert_eq!(change_set[2].to_sql(&log), "DROP FUNCTION IF EXISTS public.add(colors)");
+        match change_set[3] {
+            ChangeInstruction::ModifyType(_, TypeModificationAction::RemoveEnumValues { ref columns, .. }) => {
+                assert_eq!(columns.len(), 1);
+                assert_eq!(columns[0].column, "color");
+            }
+            ref unexpected => panic!("Unexpected change: {}", unexpected),
+        }
     }
 
     #[test]
-    fn it_can_widen_column_on_existing_table() {
+    fn it_only_drops_types_when_allowed() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(200), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
-
-        // Create a database with the base table already defined.
-        let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
+        let source_package = Package::new();
 
-        existing_database.tables.push(existing_table);
+        // Base types typically come from an extension so are always left alone
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.types.push(base_type());
+            existing_database.types.push(ast::TypeDefinition {
+                name: ast::ObjectName {
+                    schema: Some("public".to_string()),
+                    name: "citext".to_string(),
+                },
+                kind: ast::TypeDefinitionKind::UserDefined,
+                comment: None,
+            });
+            Some(existing_database)
+        }
         let capabilities = Capabilities {
-            server_version: Semver::new(9, 6, None),
+            server_version: Semver::new(10, 0, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
 
-        let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
-            &mut change_set,
-            &existing_database,
+        // By default types aren't dropped
+        let mut publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
             &capabilities,
             &publish_profile,
-            &log,
         );
-        assert!(change_set.is_empty());
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 1);
 
-        // Now we check with a linked column
-        let result = LinkedColumn {
-            table: &source_table,
-            column: &source_table.columns.last().unwrap(),
-        }
-        .generate(
-            &mut change_set,
-            &existing_database,
+        // Dropping types can be an error
+        publish_profile.generation_options.drop_types = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
             &capabilities,
             &publish_profile,
+        );
+        assert!(result.is_err());
+
+        // Or allowed
+        publish_profile.generation_options.drop_types = Toggle::Allow;
+        let result = Delta::generate(
             &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
         );
         assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(sql, vec!["DROP TYPE IF EXISTS public.colors"]);
+    }
 
-        // We should have a single instruction to create a new table
-        assert_eq!(change_set.len(), 1);
-        match change_set[0] {
-            ChangeInstruction::ModifyColumnType(ref table, ref column) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(column.name, "last_name");
-                assert_eq!(
-                    column.sql_type,
-                    SqlType::Simple(SimpleSqlType::VariableLengthString(200), None)
-                );
-                assert_eq!(column.constraints.len(), 1);
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+    fn base_domain() -> ast::TypeDefinition {
+        ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "quantity".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Domain(ast::DomainDefinition {
+                sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
+                default: Some(ast::Expression::Literal(ast::AnyValue::Integer(1, None))),
+                not_null: false,
+                constraints: vec![
+                    ast::DomainConstraint {
+                        name: Some("quantity_positive".into()),
+                        expression: "VALUE > 0".into(),
+                    },
+                    ast::DomainConstraint {
+                        name: Some("quantity_limit".into()),
+                        expression: "VALUE < 1000".into(),
+                    },
+                ],
+            }),
+            comment: None,
         }
+    }
 
-        // Check the SQL generation
-        assert_eq!(
-            change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts ALTER COLUMN last_name TYPE varchar(200)"
-        );
+    fn base_composite() -> ast::TypeDefinition {
+        ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "address".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Composite(vec![
+                ast::TypeAttribute {
+                    name: "street".into(),
+                    sql_type: ast::SqlType::Simple(ast::SimpleSqlType::VariableLengthString(50), None),
+                },
+                ast::TypeAttribute {
+                    name: "city".into(),
+                    sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Text, None),
+                },
+            ]),
+            comment: None,
+        }
     }
 
     #[test]
-    fn it_can_drop_column_on_existing_table() {
+    fn it_can_add_composite_range_and_domain_types() {
         let log = empty_logger();
-        let source_table = base_table();
-
-        // Create a database with the base table already defined.
-        let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.columns.push(ColumnDefinition {
-            name: "last_name".to_owned(),
-            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
-            constraints: vec![ColumnConstraint::NotNull],
-        });
+        let composite = base_composite();
+        let domain = base_domain();
+        let range = ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "floatrange".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Range(ast::RangeDefinition {
+                subtype: ast::SqlType::Simple(ast::SimpleSqlType::Double, None),
+                subtype_opclass: None,
+                collation: None,
+                canonical: None,
+                subtype_diff: Some(ast::ObjectName {
+                    schema: Some("public".into()),
+                    name: "float8mi".into(),
+                }),
+            }),
+            comment: None,
+        };
 
-        existing_database.tables.push(existing_table);
+        let existing_database = Package::new();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_columns = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        let result = (&source_table).generate(
-            &mut change_set,
-            &existing_database,
-            &capabilities,
-            &publish_profile,
-            &log,
-        );
-        assert!(result.is_ok());
-
-        // We should have a single instruction to create a new table
-        assert_eq!(change_set.len(), 1);
-        match change_set[0] {
-            ChangeInstruction::DropColumn(ref table, ref column_name) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(column_name, "last_name");
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        for ty in &[&composite, &range, &domain] {
+            let result = ty.generate(
+                &mut change_set,
+                &existing_database,
+                &capabilities,
+                &publish_profile,
+                &log,
+            );
+            assert!(result.is_ok());
         }
+        assert_eq!(change_set.len(), 3);
 
         // Check the SQL generation
         assert_eq!(
             change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts DROP COLUMN last_name"
+            "CREATE TYPE public.address AS (\n  street varchar(50),\n  city text\n)"
         );
-    }
-
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "CREATE TYPE public.floatrange AS RANGE (\n  SUBTYPE = double precision,\n  SUBTYPE_DIFF = public.float8mi\n)"
+        );
+        assert_eq!(
+            change_set[2].to_sql(&log),
+            "CREATE DOMAIN public.quantity AS int DEFAULT 1\n  \
+             CONSTRAINT quantity_positive CHECK (VALUE > 0)\n  \
+             CONSTRAINT quantity_limit CHECK (VALUE < 1000)"
+        );
+    }
+
     #[test]
-    fn it_can_add_a_new_primary_key() {
+    fn it_can_modify_composite_type_attributes() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
-        });
+        let source_type = ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "address".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Composite(vec![
+                ast::TypeAttribute {
+                    name: "street".into(),
+                    sql_type: ast::SqlType::Simple(ast::SimpleSqlType::VariableLengthString(100), None),
+                },
+                ast::TypeAttribute {
+                    name: "postcode".into(),
+                    sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
+                },
+            ]),
+            comment: None,
+        };
 
-        // Create a database with the base table already defined.
         let mut existing_database = Package::new();
-        existing_database.tables.push(base_table());
+        existing_database.types.push(base_composite());
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_columns = Toggle::Allow;
 
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert!(change_set.is_empty());
+        assert!(result.is_ok());
 
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
+        // Drop city, widen street and add postcode
+        assert_eq!(change_set.len(), 3);
+        match change_set[0] {
+            ChangeInstruction::ModifyType(_, TypeModificationAction::DropAttribute { ref name }) => {
+                assert_eq!(name, "city");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
-        .generate(
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TYPE public.address DROP ATTRIBUTE IF EXISTS city"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TYPE public.address ALTER ATTRIBUTE street TYPE varchar(100)"
+        );
+        assert_eq!(
+            change_set[2].to_sql(&log),
+            "ALTER TYPE public.address ADD ATTRIBUTE postcode int"
+        );
+
+        // By default dropping an attribute is rejected
+        let publish_profile = PublishProfile::default();
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
             &publish_profile,
             &log,
         );
-        assert!(result.is_ok());
+        assert!(result.is_err());
+        match result.err().unwrap() {
+            PsqlpackError(PublishUnsafeOperationError(_), _) => {}
+            unexpected => panic!("Expected unsafe operation error however saw {:?}", unexpected),
+        };
+    }
 
-        // We should have a single instruction to add a constraint
-        assert_eq!(change_set.len(), 1);
-        match change_set[0] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                match *constraint {
-                    TableConstraint::Primary {
-                        name,
-                        columns,
-                        parameters,
-                    } => {
-                        assert_eq!(name, "pk_my_contacts_id");
-                        assert_eq!(columns.len(), 1);
-                        assert!(columns.contains(&"id".to_owned()));
-                        assert!(parameters.is_some());
-                        assert_eq!(parameters.as_ref().unwrap().len(), 1);
-                    }
-                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
-                }
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
+    #[test]
+    fn it_can_modify_domain_constraints() {
+        let log = empty_logger();
+        let source_type = ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "quantity".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Domain(ast::DomainDefinition {
+                sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
+                default: None,
+                not_null: true,
+                constraints: vec![
+                    ast::DomainConstraint {
+                        name: Some("quantity_positive".into()),
+                        expression: "VALUE >= 0".into(),
+                    },
+                    ast::DomainConstraint {
+                        name: None,
+                        expression: "VALUE <> 13".into(),
+                    },
+                ],
+            }),
+            comment: None,
+        };
 
-        // Check the SQL generation
+        let mut existing_database = Package::new();
+        existing_database.types.push(base_domain());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        let sql = change_set.iter().map(|c| c.to_sql(&log)).collect::<Vec<_>>();
         assert_eq!(
-            change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts\nADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)"
+            sql,
+            vec![
+                "ALTER DOMAIN public.quantity DROP DEFAULT",
+                "ALTER DOMAIN public.quantity SET NOT NULL",
+                "ALTER DOMAIN public.quantity DROP CONSTRAINT IF EXISTS quantity_limit",
+                "ALTER DOMAIN public.quantity DROP CONSTRAINT IF EXISTS quantity_positive",
+                "ALTER DOMAIN public.quantity ADD CONSTRAINT quantity_positive CHECK (VALUE >= 0)",
+                "ALTER DOMAIN public.quantity ADD CHECK (VALUE <> 13)",
+            ]
         );
     }
 
     #[test]
-    fn it_can_remove_an_existing_primary_key() {
+    fn it_ignores_a_domain_with_an_equivalent_inspected_definition() {
         let log = empty_logger();
-        let source_table = base_table();
+        let source_type = base_domain();
 
-        // Create a database with the base table already defined.
+        // Postgres adds casts and parentheses when describing the domain
         let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        existing_database.types.push(ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "quantity".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Domain(ast::DomainDefinition {
+                sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
+                default: Some(ast::Expression::Literal(ast::AnyValue::Integer(
+                    1,
+                    Some(ast::SqlType::Simple(ast::SimpleSqlType::Integer, None)),
+                ))),
+                not_null: false,
+                constraints: vec![
+                    ast::DomainConstraint {
+                        name: Some("quantity_positive".into()),
+                        expression: "(VALUE > 0)".into(),
+                    },
+                    ast::DomainConstraint {
+                        name: Some("quantity_limit".into()),
+                        expression: "(VALUE < 1000)".into(),
+                    },
+                ],
+            }),
+            comment: None,
         });
-        existing_database.tables.push(existing_table);
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert!(change_set.is_empty());
+    }
+
+    #[test]
+    fn it_rejects_modifying_a_range_type() {
+        let log = empty_logger();
+        let range = |subtype| ast::TypeDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "numrange2".to_string(),
+            },
+            kind: ast::TypeDefinitionKind::Range(ast::RangeDefinition {
+                subtype: ast::SqlType::Simple(subtype, None),
+                subtype_opclass: None,
+                collation: None,
+                canonical: None,
+                subtype_diff: None,
+            }),
+            comment: None,
+        };
+        let source_type = range(ast::SimpleSqlType::BigInteger);
+
+        let mut existing_database = Package::new();
+        existing_database.types.push(range(ast::SimpleSqlType::Integer));
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_type).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_err());
+        match result.err().unwrap() {
+            PsqlpackError(PublishInvalidOperationError(_), _) => {}
+            unexpected => panic!("Expected invalid operation error however saw {:?}", unexpected),
+        };
+    }
+
+    fn base_table() -> ast::TableDefinition {
+        ast::TableDefinition {
+            name: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![
+                ColumnDefinition {
+                    name: "id".to_owned(),
+                    sql_type: SqlType::Simple(SimpleSqlType::Serial, None),
+                    constraints: vec![ColumnConstraint::NotNull, ColumnConstraint::PrimaryKey],
+                    comment: None,
+                },
+                ColumnDefinition {
+                    name: "company_id".to_owned(),
+                    sql_type: SqlType::Simple(SimpleSqlType::BigInteger, None),
+                    constraints: vec![ColumnConstraint::NotNull],
+                    comment: None,
+                },
+                ColumnDefinition {
+                    name: "first_name".to_owned(),
+                    sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+                    constraints: vec![ColumnConstraint::NotNull],
+                    comment: None,
+                },
+            ],
+            constraints: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            row_security: false,
+            force_row_security: false,
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn it_can_add_new_table() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create an empty database
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // This change_set gets generated at the table level
         let result = (&source_table).generate(
             &mut change_set,
             &existing_database,
@@ -2286,12 +4910,16 @@ mod tests {
         );
         assert!(result.is_ok());
 
-        // We should have a single instruction to remove the constraint
+        // We should have a single instruction to create a new table
         assert_eq!(change_set.len(), 1);
         match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
+            ChangeInstruction::AddTable(table) => {
                 assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(name, "pk_my_contacts_id");
+                assert_eq!(table.columns.len(), 3);
+                assert_eq!(table.columns[0].name, "id");
+                assert_eq!(table.columns[1].name, "company_id");
+                assert_eq!(table.columns[2].name, "first_name");
+                assert!(table.constraints.is_empty());
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
@@ -2299,54 +4927,3221 @@ mod tests {
         // Check the SQL generation
         assert_eq!(
             change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts\nDROP CONSTRAINT pk_my_contacts_id"
+            "CREATE TABLE my.contacts (\n\
+             \tid serial NOT NULL PRIMARY KEY,\n\
+             \tcompany_id bigint NOT NULL,\n\
+             \tfirst_name varchar(100) NOT NULL\n\
+             )"
         );
     }
 
     #[test]
-    fn it_can_modify_an_existing_primary_key() {
+    fn it_can_add_column_to_existing_table() {
         let log = empty_logger();
         let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        source_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            constraints: vec![ColumnConstraint::NotNull],
+            comment: None,
         });
 
         // Create a database with the base table already defined.
         let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Primary {
-            name: "pk_my_contacts_id".to_owned(),
-            columns: vec!["id".into(), "company_id".into()],
-            parameters: Some(vec![IndexParameter::FillFactor(80)]),
-        });
-        existing_database.tables.push(existing_table);
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(change_set.is_empty());
+
+        // Now we check with a linked column
+        let result = LinkedColumn {
+            table: &source_table,
+            column: source_table.columns.last().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to create a new table
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::AddColumn(table, column) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(column.name, "last_name");
+                assert_eq!(
+                    column.sql_type,
+                    SqlType::Simple(SimpleSqlType::VariableLengthString(100), None)
+                );
+                assert_eq!(column.constraints.len(), 1);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts ADD COLUMN last_name varchar(100) NOT NULL"
+        );
+    }
+
+    #[test]
+    fn it_can_widen_column_on_existing_table() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(200), None),
+            constraints: vec![ColumnConstraint::NotNull],
+            comment: None,
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            constraints: vec![ColumnConstraint::NotNull],
+            comment: None,
+        });
+
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(change_set.is_empty());
+
+        // Now we check with a linked column
+        let result = LinkedColumn {
+            table: &source_table,
+            column: source_table.columns.last().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to create a new table
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::ModifyColumnType(table, column) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(column.name, "last_name");
+                assert_eq!(
+                    column.sql_type,
+                    SqlType::Simple(SimpleSqlType::VariableLengthString(200), None)
+                );
+                assert_eq!(column.constraints.len(), 1);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts ALTER COLUMN last_name TYPE varchar(200)"
+        );
+    }
+
+    #[test]
+    fn it_can_drop_column_on_existing_table() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.columns.push(ColumnDefinition {
+            name: "last_name".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            constraints: vec![ColumnConstraint::NotNull],
+            comment: None,
+        });
+
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_columns = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to create a new table
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::DropColumn(table, ref column_name) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(column_name, "last_name");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts DROP COLUMN last_name"
+        );
+    }
+
+    #[test]
+    fn it_can_add_a_new_primary_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(change_set.is_empty());
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to add a constraint
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::AddConstraint(table, constraint, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                match constraint {
+                    TableConstraint::Primary {
+                        name,
+                        columns,
+                        parameters,
+                    } => {
+                        assert_eq!(name, "pk_my_contacts_id");
+                        assert_eq!(columns.len(), 1);
+                        assert!(columns.contains(&"id".to_owned()));
+                        assert!(parameters.is_some());
+                        assert_eq!(parameters.as_ref().unwrap().len(), 1);
+                    }
+                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\nADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)"
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_primary_key() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // This change_set gets generated at the table level
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to remove the constraint
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(table, ref name, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(name, "pk_my_contacts_id");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\nDROP CONSTRAINT pk_my_contacts_id"
+        );
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_primary_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Primary {
+            name: "pk_my_contacts_id".to_owned(),
+            columns: vec!["id".into(), "company_id".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(80)]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(change_set.is_empty());
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // Primary keys cannot be altered, so we drop/create
+        assert_eq!(change_set.len(), 2);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(table, ref name, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(name, "pk_my_contacts_id");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddConstraint(table, constraint, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                match constraint {
+                    TableConstraint::Primary {
+                        name,
+                        columns,
+                        parameters,
+                    } => {
+                        assert_eq!(name, "pk_my_contacts_id");
+                        assert_eq!(columns.len(), 1);
+                        assert!(columns.contains(&"id".to_owned()));
+                        assert!(parameters.is_some());
+                        assert_eq!(parameters.as_ref().unwrap().len(), 1);
+                    }
+                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\nDROP CONSTRAINT pk_my_contacts_id"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TABLE my.contacts\nADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)"
+        );
+    }
+
+    #[test]
+    fn it_can_add_a_new_foreign_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(change_set.is_empty());
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to create a new constraint
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::AddConstraint(table, constraint, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                match constraint {
+                    TableConstraint::Foreign {
+                        name,
+                        columns,
+                        ref_table,
+                        ref_columns,
+                        match_type,
+                        events,
+                    } => {
+                        assert_eq!(name, "fk_my_contacts_my_companies");
+                        assert_eq!(columns.len(), 1);
+                        assert!(columns.contains(&"company_id".to_owned()));
+                        assert_eq!(ref_table.to_string(), "my.companies");
+                        assert_eq!(ref_columns.len(), 1);
+                        assert!(ref_columns.contains(&"id".to_owned()));
+                        assert!(match_type.is_some());
+                        assert_eq!(match_type.as_ref().unwrap(), &ForeignConstraintMatchType::Simple);
+                        assert!(events.is_some());
+                        assert_eq!(events.as_ref().unwrap().len(), 2); // We test this further below
+                    }
+                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
+             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE CASCADE ON DELETE NO ACTION"
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_foreign_key() {
+        let log = empty_logger();
+        let source_table = base_table();
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // This change_set gets generated at the table level
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to remove a constraint
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(table, ref name, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(name, "fk_my_contacts_my_companies");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\nDROP CONSTRAINT fk_my_contacts_my_companies"
+        );
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_foreign_key() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::NoAction),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Foreign {
+            name: "fk_my_contacts_my_companies".to_owned(),
+            columns: vec!["company_id".into()],
+            ref_table: ObjectName {
+                schema: Some("my".into()),
+                name: "companies".into(),
+            },
+            ref_columns: vec!["id".into()],
+            match_type: Some(ForeignConstraintMatchType::Simple),
+            events: Some(vec![
+                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
+                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
+            ]),
+        });
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
+
+        let mut change_set = Vec::new();
+        // First, check that source table changes do nothing
+        let _ = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(change_set.is_empty());
+
+        // Now we check with a linked table constraint
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // Primary keys cannot be altered, so we drop/create
+        assert_eq!(change_set.len(), 2);
+        match change_set[0] {
+            ChangeInstruction::DropConstraint(table, ref name, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(name, "fk_my_contacts_my_companies");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddConstraint(table, constraint, _) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                match constraint {
+                    TableConstraint::Foreign {
+                        name,
+                        columns,
+                        ref_table,
+                        ref_columns,
+                        match_type,
+                        events,
+                    } => {
+                        assert_eq!(*name, "fk_my_contacts_my_companies");
+                        assert_eq!(columns.len(), 1);
+                        assert!(columns.contains(&"company_id".to_owned()));
+                        assert_eq!(ref_table.to_string(), "my.companies");
+                        assert_eq!(ref_columns.len(), 1);
+                        assert!(ref_columns.contains(&"id".to_owned()));
+                        assert!(match_type.is_some());
+                        assert_eq!(match_type.as_ref().unwrap(), &ForeignConstraintMatchType::Simple);
+                        assert!(events.is_some());
+                        assert_eq!(events.as_ref().unwrap().len(), 2); // We test this further below
+                    }
+                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
+                }
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\nDROP CONSTRAINT fk_my_contacts_my_companies"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
+             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE NO ACTION ON DELETE NO ACTION"
+        );
+    }
+
+    #[test]
+    fn it_can_add_a_check_constraint_to_an_existing_table_without_validation() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Check {
+            name: "ck_my_contacts_first_name".to_owned(),
+            expression: "first_name <> ''".to_owned(),
+            no_inherit: false,
+        });
+
+        // Create a database with the base table already defined.
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // The constraint is added without validation and then validated separately
+        assert_eq!(change_set.len(), 2);
+        match change_set[0] {
+            ChangeInstruction::AddConstraint(table, constraint, not_valid) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(constraint.name(), "ck_my_contacts_first_name");
+                assert!(not_valid);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             ADD CONSTRAINT ck_my_contacts_first_name CHECK (first_name <> '') NOT VALID"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             VALIDATE CONSTRAINT ck_my_contacts_first_name"
+        );
+
+        // A new table doesn't need this
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(&mut change_set, &Package::new(), &capabilities, &publish_profile, &log);
+        assert!(result.is_ok());
+        assert_eq!(change_set.len(), 1);
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             ADD CONSTRAINT ck_my_contacts_first_name CHECK (first_name <> '')"
+        );
+    }
+
+    #[test]
+    fn it_ignores_a_check_constraint_with_an_equivalent_inspected_expression() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Check {
+            name: "ck_my_contacts_company_id".to_owned(),
+            expression: "company_id > 0 AND company_id < 1000".to_owned(),
+            no_inherit: false,
+        });
+
+        // Postgres describes the expression with additional brackets and casts
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Check {
+            name: "ck_my_contacts_company_id".to_owned(),
+            expression: "((company_id > (0)::bigint) AND (company_id < (1000)::bigint))".to_owned(),
+            no_inherit: false,
+        });
+        let mut existing_database = Package::new();
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert!(change_set.is_empty());
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_unique_constraint() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Unique {
+            name: "uq_my_contacts_name".to_owned(),
+            columns: vec!["company_id".into(), "first_name".into()],
+            parameters: Some(vec![IndexParameter::FillFactor(70)]),
+        });
+
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Unique {
+            name: "uq_my_contacts_name".to_owned(),
+            columns: vec!["first_name".into()],
+            parameters: None,
+        });
+        let mut existing_database = Package::new();
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should drop and recreate the constraint
+        assert_eq!(change_set.len(), 2);
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             DROP CONSTRAINT uq_my_contacts_name"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             ADD CONSTRAINT uq_my_contacts_name UNIQUE (company_id, first_name) WITH (FILLFACTOR=70)"
+        );
+
+        // Unless dropping unique constraints has been disabled
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_unique_constraints = Toggle::Error;
+        let mut change_set = Vec::new();
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_err());
+        match result.err().unwrap() {
+            PsqlpackError(PublishUnsafeOperationError(_), _) => {}
+            unexpected => panic!("Expected unsafe operation error however saw {:?}", unexpected),
+        };
+    }
+
+    #[test]
+    fn it_can_add_and_remove_exclusion_constraints() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.constraints.push(TableConstraint::Exclude {
+            name: "ex_my_contacts_company".to_owned(),
+            index_type: Some(IndexType::Gist),
+            elements: vec![
+                ExclusionElement {
+                    expression: "company_id".into(),
+                    operator: "=".into(),
+                },
+                ExclusionElement {
+                    expression: "lower(first_name)".into(),
+                    operator: "<>".into(),
+                },
+            ],
+            parameters: None,
+            condition: Some("company_id > 0".into()),
+        });
+
+        // The existing table has a check constraint which is no longer needed
+        let mut existing_table = base_table();
+        existing_table.constraints.push(TableConstraint::Check {
+            name: "ck_my_contacts_first_name".to_owned(),
+            expression: "first_name <> ''".to_owned(),
+            no_inherit: true,
+        });
+        let mut existing_database = Package::new();
+        existing_database.tables.push(existing_table);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        let result = LinkedTableConstraint {
+            table: &source_table,
+            constraint: source_table.constraints.first().unwrap(),
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        assert_eq!(change_set.len(), 2);
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             DROP CONSTRAINT ck_my_contacts_first_name"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TABLE my.contacts\n\
+             ADD CONSTRAINT ex_my_contacts_company EXCLUDE USING gist \
+             (company_id WITH =, lower(first_name) WITH <>) WHERE (company_id > 0)"
+        );
+    }
+
+    #[test]
+    fn it_can_add_a_new_index() {
+        let log = empty_logger();
+        let source_index = IndexDefinition {
+            name: "idx_contacts_first_name".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![IndexColumn {
+                name: "first_name".to_owned(),
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: true,
+            index_type: Some(IndexType::BTree),
+            storage_parameters: None,
+            comment: None,
+        };
+
+        // Create a database with no indexes defined.
+        let existing_database = Package::new();
+        let publish_profile = PublishProfile::default();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_index).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to create a new index
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::AddIndex(index, concurrently) => {
+                assert_eq!(index.name, "idx_contacts_first_name");
+                assert_eq!(index.table.to_string(), "public.contacts");
+                assert!(concurrently);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "CREATE UNIQUE INDEX CONCURRENTLY idx_contacts_first_name \
+             ON public.contacts USING btree (first_name ASC NULLS LAST)"
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_index() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        // Create a database with the index already defined.
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.indexes.push(IndexDefinition {
+                name: "idx_contacts_first_name".to_owned(),
+                table: ObjectName {
+                    schema: Some("public".to_owned()),
+                    name: "contacts".to_owned(),
+                },
+                columns: vec![IndexColumn {
+                    name: "first_name".to_owned(),
+                    order: Some(IndexOrder::Ascending),
+                    null_position: Some(IndexPosition::Last),
+                }],
+                unique: true,
+                index_type: Some(IndexType::BTree),
+                storage_parameters: None,
+                comment: None,
+            });
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_indexes = Toggle::Error;
+
+        // First of all, make sure an error is generated
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+        // Now run it again - it should be ok now
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_indexes = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+
+        // We should have a single instruction to remove an index (first will be use database)
+        assert_eq!(change_set.len(), 2);
+        match change_set[1] {
+            ChangeInstruction::DropIndex(ref index, concurrently) => {
+                assert_eq!(*index, "public.idx_contacts_first_name");
+                assert!(concurrently);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_first_name"
+        );
+    }
+
+    #[test]
+    fn it_can_modify_an_existing_index() {
+        let log = empty_logger();
+        let source_index = IndexDefinition {
+            name: "idx_contacts_name".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![
+                IndexColumn {
+                    name: "first_name".to_owned(),
+                    order: Some(IndexOrder::Ascending),
+                    null_position: Some(IndexPosition::Last),
+                },
+                IndexColumn {
+                    name: "last_name".to_owned(),
+                    order: Some(IndexOrder::Descending),
+                    null_position: Some(IndexPosition::First),
+                },
+            ],
+            unique: false,
+            index_type: Some(IndexType::BTree),
+            storage_parameters: None,
+            comment: None,
+        };
+
+        // Create a database with a single index defined.
+        let mut existing_database = Package::new();
+        existing_database.indexes.push(IndexDefinition {
+            name: "idx_contacts_name".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: vec![IndexColumn {
+                name: "first_name".to_owned(),
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: true,
+            index_type: Some(IndexType::BTree),
+            storage_parameters: None,
+            comment: None,
+        });
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_index).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have two instructions to drop/create a new index
+        assert_eq!(change_set.len(), 2);
+        match change_set[0] {
+            ChangeInstruction::DropIndex(ref index_name, concurrently) => {
+                assert_eq!(*index_name, "public.idx_contacts_name");
+                assert!(concurrently);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddIndex(index, concurrently) => {
+                assert_eq!(index.name, "idx_contacts_name");
+                assert_eq!(index.table.to_string(), "public.contacts");
+                assert!(concurrently);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_name"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "CREATE INDEX CONCURRENTLY idx_contacts_name \
+             ON public.contacts USING btree (first_name ASC NULLS LAST, last_name DESC NULLS FIRST)"
+        );
+    }
+
+    #[test]
+    fn it_converts_a_serial_column_to_an_identity_in_place() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        let mut identity = IdentityDefinition::new(IdentityGeneration::Always, Vec::new()).unwrap();
+        identity.set_defaults(&SqlType::Simple(SimpleSqlType::Integer, None));
+        source_table.columns[0] = ColumnDefinition {
+            name: "id".to_owned(),
+            sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+            constraints: vec![
+                ColumnConstraint::NotNull,
+                ColumnConstraint::PrimaryKey,
+                ColumnConstraint::Identity(identity),
+            ],
+            comment: None,
+        };
+
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = LinkedColumn {
+            table: &source_table,
+            column: &source_table.columns[0],
+        }
+        .generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // The column isn't dropped and serial is the same type as integer so the type isn't changed either
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::ConvertSerialToIdentity(table, column) => {
+                assert_eq!(table.name.to_string(), "my.contacts");
+                assert_eq!(column.name, "id");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "DO $$\n\
+             DECLARE\n\
+             \tserial_sequence regclass := pg_get_serial_sequence('my.contacts', 'id')::regclass;\n\
+             BEGIN\n\
+             \tALTER TABLE my.contacts ALTER COLUMN id DROP DEFAULT;\n\
+             \tEXECUTE format('ALTER SEQUENCE %s OWNED BY NONE', serial_sequence);\n\
+             \tALTER TABLE my.contacts ALTER COLUMN id ADD GENERATED ALWAYS AS IDENTITY \
+             (INCREMENT BY 1 MINVALUE 1 MAXVALUE 2147483647 START WITH 1 CACHE 1 NO CYCLE);\n\
+             \tPERFORM setval(pg_get_serial_sequence('my.contacts', 'id'), nextval(serial_sequence), false);\n\
+             \tEXECUTE format('DROP SEQUENCE %s', serial_sequence);\n\
+             END\n\
+             $$"
+        );
+    }
+
+    fn partition(name: &str, year: i32) -> ast::TableDefinition {
+        let bound = |year: i32| {
+            vec![PartitionRangeValue::Value(Expression::Literal(AnyValue::String(
+                format!("{}-01-01", year),
+                None,
+            )))]
+        };
+        ast::TableDefinition {
+            name: ObjectName {
+                schema: Some("my".to_owned()),
+                name: name.to_owned(),
+            },
+            columns: Vec::new(),
+            constraints: Vec::new(),
+            partition_by: None,
+            partition_of: Some(PartitionOf {
+                parent: ObjectName {
+                    schema: Some("my".to_owned()),
+                    name: "contacts".to_owned(),
+                },
+                bound: PartitionBound::Range {
+                    from: bound(year),
+                    to: bound(year + 1),
+                },
+            }),
+            row_security: false,
+            force_row_security: false,
+            comment: None,
+        }
+    }
+
+    fn partitioned_table() -> ast::TableDefinition {
+        let mut table = base_table();
+        table.columns[0].sql_type = SqlType::Simple(SimpleSqlType::Date, None);
+        table.partition_by = Some(PartitionKey {
+            strategy: PartitionStrategy::Range,
+            columns: vec![Expression::Column("id".to_owned())],
+        });
+        table
+    }
+
+    #[test]
+    fn it_can_add_a_partitioned_table_and_its_partitions() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.tables.push(partition("contacts_2020", 2020));
+        source_package.tables.push(partitioned_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(12, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(Package::new()),
+            "dbname",
+            &capabilities,
+            &PublishProfile::default(),
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+
+        // The partitioned table is created first
+        assert_eq!(change_set.len(), 3);
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "CREATE TABLE my.contacts (\n\
+             \tid date NOT NULL PRIMARY KEY,\n\
+             \tcompany_id bigint NOT NULL,\n\
+             \tfirst_name varchar(100) NOT NULL\n\
+             ) PARTITION BY RANGE (id)"
+        );
+        assert_eq!(
+            change_set[2].to_sql(&log),
+            "CREATE TABLE my.contacts_2020 PARTITION OF my.contacts FOR VALUES FROM ('2020-01-01') TO ('2021-01-01')"
+        );
+    }
+
+    #[test]
+    fn it_can_attach_detach_and_drop_partitions() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.tables.push(partitioned_table());
+        source_package.tables.push(partition("contacts_2020", 2020));
+        source_package.tables.push(partition("contacts_2021", 2021));
+        let mut archive = base_table();
+        archive.name.name = "contacts_archive".to_owned();
+        source_package.tables.push(archive);
+
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.tables.push(partitioned_table());
+            // The bound has changed
+            existing_database.tables.push(partition("contacts_2020", 2019));
+            // Exists as a regular table
+            let mut standalone = base_table();
+            standalone.name.name = "contacts_2021".to_owned();
+            existing_database.tables.push(standalone);
+            // Is no longer a partition
+            let mut archive = partition("contacts_archive", 2000);
+            archive.columns = base_table().columns;
+            existing_database.tables.push(archive);
+            // Only exists in the target
+            existing_database.tables.push(partition("contacts_2022", 2022));
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(12, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // Dropping partitions can be an error
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_partitions = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        match result.err().unwrap() {
+            PsqlpackError(PublishUnsafeOperationError(_), _) => {}
+            unexpected => panic!("Expected unsafe operation error however saw {:?}", unexpected),
+        };
+
+        // By default partitions that aren't in the project are left alone
+        let mut publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        let position = |expected: &str| sql.iter().position(|s| s.eq(expected));
+        assert_eq!(sql.len(), 4);
+        // A partition with a different bound is detached before being attached again
+        let detach = position("ALTER TABLE my.contacts DETACH PARTITION my.contacts_2020");
+        let attach = position(
+            "ALTER TABLE my.contacts ATTACH PARTITION my.contacts_2020 FOR VALUES FROM ('2020-01-01') TO ('2021-01-01')",
+        );
+        assert!(detach.is_some() && attach.is_some() && detach < attach);
+        assert!(position(
+            "ALTER TABLE my.contacts ATTACH PARTITION my.contacts_2021 FOR VALUES FROM ('2021-01-01') TO ('2022-01-01')"
+        )
+        .is_some());
+        assert!(position("ALTER TABLE my.contacts DETACH PARTITION my.contacts_archive").is_some());
+
+        // Otherwise they can be dropped
+        publish_profile.generation_options.drop_partitions = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 6);
+        match change_set[1] {
+            ChangeInstruction::DropPartition(ref name) => assert_eq!(name, "my.contacts_2022"),
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_can_not_change_how_a_table_is_partitioned() {
+        let log = empty_logger();
+        let source_table = partitioned_table();
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(12, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &PublishProfile::default(),
+            &log,
+        );
+        match result.err().unwrap() {
+            PsqlpackError(PublishInvalidOperationError(_), _) => {}
+            unexpected => panic!("Expected invalid operation error however saw {:?}", unexpected),
+        };
+    }
+
+    fn base_view() -> ast::ViewDefinition {
+        ast::ViewDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "active_contacts".to_owned(),
+            },
+            columns: None,
+            query: "SELECT id, first_name\n  FROM public.contacts\n  WHERE active".to_owned(),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn it_can_add_a_new_view() {
+        let log = empty_logger();
+        let source_view = base_view();
+
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to create the view
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::AddView(view) => {
+                assert_eq!(view.name.to_string(), "public.active_contacts");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "CREATE VIEW public.active_contacts AS\n\
+             SELECT id, first_name\n  FROM public.contacts\n  WHERE active;\n\
+             CREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+             CREATE TABLE IF NOT EXISTS psqlpack.view_sources (\
+             view_oid oid PRIMARY KEY, query text NOT NULL, definition text NOT NULL);\n\
+             INSERT INTO psqlpack.view_sources (view_oid, query, definition) \
+             VALUES ('public.active_contacts'::regclass, 'SELECT id, first_name\n  FROM public.contacts\n  WHERE active', pg_get_viewdef('public.active_contacts'::regclass)) \
+             ON CONFLICT (view_oid) DO UPDATE SET query = EXCLUDED.query, definition = EXCLUDED.definition"
+        );
+    }
+
+    #[test]
+    fn it_ignores_a_view_with_an_equivalent_query() {
+        let log = empty_logger();
+        let source_view = base_view();
+
+        // The query recorded for the view may have been formatted differently within the project
+        let mut existing_database = Package::new();
+        let mut existing_view = base_view();
+        existing_view.query = " SELECT id, first_name FROM public.contacts WHERE active;".to_owned();
+        existing_database.views.push(existing_view);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert!(change_set.is_empty());
+    }
+
+    #[test]
+    fn it_can_replace_a_modified_view() {
+        let log = empty_logger();
+        let mut source_view = base_view();
+        source_view.columns = Some(vec!["id".to_owned(), "first_name".to_owned(), "last_name".to_owned()]);
+        source_view.query = "SELECT id, first_name, last_name FROM public.contacts".to_owned();
+
+        // Extracted views always describe their columns
+        let mut existing_database = Package::new();
+        let mut existing_view = base_view();
+        existing_view.columns = Some(vec!["id".to_owned(), "first_name".to_owned()]);
+        existing_database.views.push(existing_view);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // Appending a column means we can replace the view
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::ReplaceView(view) => {
+                assert_eq!(view.name.to_string(), "public.active_contacts");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "CREATE OR REPLACE VIEW public.active_contacts (id, first_name, last_name) AS\n\
+             SELECT id, first_name, last_name FROM public.contacts;\n\
+             CREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+             CREATE TABLE IF NOT EXISTS psqlpack.view_sources (\
+             view_oid oid PRIMARY KEY, query text NOT NULL, definition text NOT NULL);\n\
+             INSERT INTO psqlpack.view_sources (view_oid, query, definition) \
+             VALUES ('public.active_contacts'::regclass, 'SELECT id, first_name, last_name FROM public.contacts', \
+             pg_get_viewdef('public.active_contacts'::regclass)) \
+             ON CONFLICT (view_oid) DO UPDATE SET query = EXCLUDED.query, definition = EXCLUDED.definition"
+        );
+    }
+
+    #[test]
+    fn it_recreates_a_view_when_its_columns_cannot_be_replaced() {
+        let log = empty_logger();
+        let mut source_view = base_view();
+        source_view.columns = Some(vec!["id".to_owned(), "name".to_owned()]);
+
+        // Renaming a column isn't possible with CREATE OR REPLACE
+        let mut existing_database = Package::new();
+        let mut existing_view = base_view();
+        existing_view.columns = Some(vec!["id".to_owned(), "first_name".to_owned()]);
+        existing_database.views.push(existing_view);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // Recreating the view fails when dropping views is an error
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_views = Toggle::Error;
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_err());
+        match result.err().unwrap() {
+            PsqlpackError(PublishUnsafeOperationError(_), _) => {}
+            unexpected => panic!("Expected unsafe operation error however saw {:?}", unexpected),
+        };
+
+        publish_profile.generation_options.drop_views = Toggle::Allow;
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        let changes = change_set.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec!["Drop view: public.active_contacts", "Add view: public.active_contacts"]
+        );
+
+        // The same columns are left alone
+        let mut existing_database = Package::new();
+        let mut existing_view = base_view();
+        existing_view.columns = Some(vec!["id".to_owned(), "name".to_owned()]);
+        existing_database.views.push(existing_view);
+        let mut change_set = Vec::new();
+        let result = (&source_view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert!(change_set.is_empty());
+    }
+
+    #[test]
+    fn it_can_rebuild_a_modified_materialized_view_and_its_indexes() {
+        let log = empty_logger();
+        let view = ast::MaterializedViewDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contact_totals".to_owned(),
+            },
+            columns: None,
+            query: "SELECT company_id, count(*) AS total FROM public.contacts GROUP BY company_id".to_owned(),
+            comment: None,
+        };
+        let index = IndexDefinition {
+            name: "idx_contact_totals_company_id".to_owned(),
+            table: view.name.clone(),
+            columns: vec![IndexColumn {
+                name: "company_id".to_owned(),
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: false,
+            index_type: Some(IndexType::BTree),
+            storage_parameters: None,
+            comment: None,
+        };
+
+        let mut existing_database = Package::new();
+        existing_database
+            .materialized_views
+            .push(ast::MaterializedViewDefinition {
+                name: view.name.clone(),
+                columns: None,
+                query: "SELECT company_id FROM public.contacts".to_owned(),
+                comment: None,
+            });
+        existing_database.indexes.push(IndexDefinition {
+            name: index.name.clone(),
+            table: index.table.clone(),
+            columns: vec![IndexColumn {
+                name: "company_id".to_owned(),
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: false,
+            index_type: Some(IndexType::BTree),
+            storage_parameters: None,
+            comment: None,
+        });
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.force_concurrent_indexes = false;
+
+        let mut change_set = Vec::new();
+        let result = (&view).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        let result = (&index).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // The view is dropped and recreated which means the index needs to be recreated too
+        assert_eq!(change_set.len(), 2);
+        match change_set[0] {
+            ChangeInstruction::ReplaceMaterializedView(view) => {
+                assert_eq!(view.name.to_string(), "public.contact_totals");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        match change_set[1] {
+            ChangeInstruction::AddIndex(index, concurrently) => {
+                assert_eq!(index.name, "idx_contact_totals_company_id");
+                assert!(!concurrently);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "DROP MATERIALIZED VIEW IF EXISTS public.contact_totals;\n\
+             CREATE MATERIALIZED VIEW public.contact_totals AS\n\
+             SELECT company_id, count(*) AS total FROM public.contacts GROUP BY company_id;\n\
+             CREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+             CREATE TABLE IF NOT EXISTS psqlpack.view_sources (\
+             view_oid oid PRIMARY KEY, query text NOT NULL, definition text NOT NULL);\n\
+             INSERT INTO psqlpack.view_sources (view_oid, query, definition) \
+             VALUES ('public.contact_totals'::regclass, 'SELECT company_id, count(*) AS total FROM public.contacts GROUP BY company_id', pg_get_viewdef('public.contact_totals'::regclass)) \
+             ON CONFLICT (view_oid) DO UPDATE SET query = EXCLUDED.query, definition = EXCLUDED.definition"
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_view() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.views.push(base_view());
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // By default views that aren't in the project are left alone
+        let mut publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        let Delta(change_set, ..) = result.unwrap();
+        assert!(!change_set
+            .iter()
+            .any(|change| matches!(*change, ChangeInstruction::DropView(_))));
+
+        // Dropping views can also be an error
+        publish_profile.generation_options.drop_views = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+
+        // Now allow it
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_views = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+
+        // We should have a single instruction to remove the view (first will be use database)
+        assert_eq!(change_set.len(), 2);
+        match change_set[1] {
+            ChangeInstruction::DropView(ref view) => {
+                assert_eq!(*view, "public.active_contacts");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(change_set[1].to_sql(&log), "DROP VIEW IF EXISTS public.active_contacts");
+    }
+
+    fn base_trigger() -> ast::TriggerDefinition {
+        ast::TriggerDefinition {
+            name: "audit".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            timing: ast::TriggerTiming::After,
+            events: vec![ast::TriggerEvent::Insert, ast::TriggerEvent::Update(None)],
+            for_each: ast::TriggerForEach::Row,
+            condition: Some("NEW.active".to_owned()),
+            function: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "audit_contact".to_owned(),
+            },
+            arguments: vec!["contact's".to_owned()],
+        }
+    }
+
+    #[test]
+    fn it_can_add_a_new_trigger() {
+        let log = empty_logger();
+        let source_trigger = base_trigger();
+
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_trigger).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // We should have a single instruction to create the trigger
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::AddTrigger(trigger) => {
+                assert_eq!(trigger.fully_qualified_name(), "public.contacts.audit");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "CREATE TRIGGER audit AFTER INSERT OR UPDATE ON public.contacts FOR EACH ROW\n\
+             WHEN (NEW.active)\n\
+             EXECUTE PROCEDURE public.audit_contact('contact''s')"
+        );
+    }
+
+    #[test]
+    fn it_ignores_a_trigger_with_an_equivalent_definition() {
+        let log = empty_logger();
+        let source_trigger = base_trigger();
+
+        // Postgres reorders the events and reformats the condition
+        let mut existing_database = Package::new();
+        let mut existing_trigger = base_trigger();
+        existing_trigger.events = vec![ast::TriggerEvent::Update(None), ast::TriggerEvent::Insert];
+        existing_trigger.condition = Some("(new.active)".to_owned());
+        existing_database.triggers.push(existing_trigger);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_trigger).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert!(change_set.is_empty());
+    }
+
+    #[test]
+    fn it_can_replace_a_modified_trigger() {
+        let log = empty_logger();
+        let source_trigger = base_trigger();
+
+        let mut existing_database = Package::new();
+        let mut existing_trigger = base_trigger();
+        existing_trigger.timing = ast::TriggerTiming::Before;
+        existing_database.triggers.push(existing_trigger);
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_trigger).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // Triggers can't be altered so they are dropped and recreated
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::ReplaceTrigger(trigger) => {
+                assert_eq!(trigger.fully_qualified_name(), "public.contacts.audit");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert!(change_set[0]
+            .to_sql(&log)
+            .starts_with("DROP TRIGGER IF EXISTS audit ON public.contacts;\nCREATE TRIGGER audit AFTER"));
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_trigger() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.triggers.push(base_trigger());
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // By default triggers that aren't in the project are left alone
+        let mut publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        let Delta(change_set, ..) = result.unwrap();
+        assert!(!change_set
+            .iter()
+            .any(|change| matches!(*change, ChangeInstruction::DropTrigger(..))));
+
+        // Dropping triggers can also be an error
+        publish_profile.generation_options.drop_triggers = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+
+        // Now allow it
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_triggers = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+
+        // We should have a single instruction to remove the trigger (first will be use database)
+        assert_eq!(change_set.len(), 2);
+        match change_set[1] {
+            ChangeInstruction::DropTrigger(ref trigger, ref table) => {
+                assert_eq!(*trigger, "audit");
+                assert_eq!(*table, "public.contacts");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "DROP TRIGGER IF EXISTS audit ON public.contacts"
+        );
+    }
+
+    #[test]
+    fn it_can_enable_and_force_row_level_security_on_an_existing_table() {
+        let log = empty_logger();
+        let mut source_table = base_table();
+        source_table.row_security = true;
+        source_table.force_row_security = true;
+
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // Both flags are toggled independently
+        assert_eq!(change_set.len(), 2);
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts ENABLE ROW LEVEL SECURITY"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TABLE my.contacts FORCE ROW LEVEL SECURITY"
+        );
+
+        // And back again
+        let source_table = base_table();
+        let mut existing_database = Package::new();
+        let mut existing_table = base_table();
+        existing_table.row_security = true;
+        existing_table.force_row_security = true;
+        existing_database.tables.push(existing_table);
+
+        let mut change_set = Vec::new();
+        let result = (&source_table).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert_eq!(change_set.len(), 2);
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER TABLE my.contacts DISABLE ROW LEVEL SECURITY"
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "ALTER TABLE my.contacts NO FORCE ROW LEVEL SECURITY"
+        );
+    }
+
+    fn base_policy() -> ast::PolicyDefinition {
+        ast::PolicyDefinition {
+            name: "owner_only".to_owned(),
+            table: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            permissive: true,
+            command: ast::PolicyCommand::All,
+            roles: vec!["app_user".to_owned()],
+            using: Some("owner = current_user".to_owned()),
+            with_check: None,
+        }
+    }
+
+    #[test]
+    fn it_can_add_a_new_policy() {
+        let log = empty_logger();
+        let mut source_policy = base_policy();
+        source_policy.permissive = false;
+        source_policy.command = ast::PolicyCommand::Update;
+        source_policy.with_check = Some("owner = current_user".to_owned());
+
+        let existing_database = Package::new();
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::AddPolicy(policy) => {
+                assert_eq!(policy.fully_qualified_name(), "public.contacts.owner_only");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+
+        // Check the SQL generation
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "CREATE POLICY owner_only ON public.contacts AS RESTRICTIVE FOR UPDATE TO app_user\n\
+             USING (owner = current_user)\n\
+             WITH CHECK (owner = current_user)"
+        );
+    }
+
+    #[test]
+    fn it_can_alter_a_policy_with_modified_roles_or_expressions() {
+        let log = empty_logger();
+        let mut source_policy = base_policy();
+        source_policy.roles.push("admin".to_owned());
+
+        // Postgres brackets the expression
+        let mut existing_database = Package::new();
+        let mut existing_policy = base_policy();
+        existing_policy.using = Some("(owner = CURRENT_USER)".to_owned());
+        existing_database.policies.push(existing_policy);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::ModifyPolicy(policy) => {
+                assert_eq!(policy.fully_qualified_name(), "public.contacts.owner_only");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "ALTER POLICY owner_only ON public.contacts TO admin, app_user\n\
+             USING (owner = current_user)"
+        );
+
+        // Nothing to do once the roles match
+        let source_policy = base_policy();
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert!(change_set.is_empty());
+    }
+
+    #[test]
+    fn it_can_replace_a_policy_with_a_modified_command() {
+        let log = empty_logger();
+        let source_policy = base_policy();
+
+        let mut existing_database = Package::new();
+        let mut existing_policy = base_policy();
+        existing_policy.command = ast::PolicyCommand::Select;
+        existing_database.policies.push(existing_policy);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+
+        let mut change_set = Vec::new();
+        let result = (&source_policy).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        // The command of a policy can't be altered so it is dropped and recreated
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::ReplacePolicy(policy) => {
+                assert_eq!(policy.fully_qualified_name(), "public.contacts.owner_only");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "DROP POLICY IF EXISTS owner_only ON public.contacts;\n\
+             CREATE POLICY owner_only ON public.contacts FOR ALL TO app_user\n\
+             USING (owner = current_user)"
+        );
+    }
+
+    #[test]
+    fn it_can_remove_an_existing_policy() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.policies.push(base_policy());
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // By default policies that aren't in the project are left alone
+        let mut publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        let Delta(change_set, ..) = result.unwrap();
+        assert!(!change_set
+            .iter()
+            .any(|change| matches!(*change, ChangeInstruction::DropPolicy(..))));
+
+        // Dropping policies can also be an error
+        publish_profile.generation_options.drop_policies = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+
+        // Now allow it
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_policies = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+
+        // We should have a single instruction to remove the policy (first will be use database)
+        assert_eq!(change_set.len(), 2);
+        match change_set[1] {
+            ChangeInstruction::DropPolicy(ref policy, ref table) => {
+                assert_eq!(*policy, "owner_only");
+                assert_eq!(*table, "public.contacts");
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "DROP POLICY IF EXISTS owner_only ON public.contacts"
+        );
+    }
+
+    fn base_grant(grantee: &str, privileges: Vec<ast::Privilege>, with_grant_option: bool) -> ast::GrantDefinition {
+        ast::GrantDefinition {
+            object: ast::GrantObject::Table(ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
+            }),
+            grantee: grantee.to_owned(),
+            privileges,
+            with_grant_option,
+        }
+    }
+
+    #[test]
+    fn it_grants_missing_privileges_to_the_substituted_role() {
+        let log = empty_logger();
+        let source_grant = base_grant("app_user", vec![ast::Privilege::Select, ast::Privilege::Insert], true);
+
+        // The role already holds SELECT, however without the grant option
+        let mut existing_database = Package::new();
+        existing_database
+            .grants
+            .push(base_grant("app_user_prod", vec![ast::Privilege::Select], false));
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile
+            .role_names
+            .insert("App_User".to_owned(), "app_user_prod".to_owned());
+
+        let mut change_set = Vec::new();
+        let result = (&source_grant).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+
+        assert_eq!(change_set.len(), 1);
+        match change_set[0] {
+            ChangeInstruction::Grant(ref grant) => {
+                assert_eq!(grant.grantee, "app_user_prod");
+                assert_eq!(grant.privileges, vec![ast::Privilege::Select, ast::Privilege::Insert]);
+            }
+            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+        }
+        assert_eq!(
+            change_set[0].to_sql(&log),
+            "GRANT SELECT, INSERT ON TABLE my.contacts TO app_user_prod WITH GRANT OPTION"
+        );
+
+        // Nothing to do once the role holds them
+        existing_database.grants = vec![base_grant(
+            "app_user_prod",
+            vec![ast::Privilege::Select, ast::Privilege::Insert, ast::Privilege::Update],
+            true,
+        )];
+        let mut change_set = Vec::new();
+        let result = (&source_grant).generate(
+            &mut change_set,
+            &existing_database,
+            &capabilities,
+            &publish_profile,
+            &log,
+        );
+        assert!(result.is_ok());
+        assert!(change_set.is_empty());
+    }
+
+    #[test]
+    fn it_can_revoke_privileges_that_are_not_granted() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.tables.push(base_table());
+        source_package
+            .grants
+            .push(base_grant("reader", vec![ast::Privilege::Select], false));
+
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.tables.push(base_table());
+            existing_database.grants.push(base_grant(
+                "reader",
+                vec![ast::Privilege::Select, ast::Privilege::Delete],
+                true,
+            ));
+            existing_database
+                .grants
+                .push(base_grant("public", vec![ast::Privilege::Select], false));
+            // Privileges on objects outside of the project are left alone
+            existing_database.grants.push(ast::GrantDefinition {
+                object: ast::GrantObject::Schema("reporting".to_owned()),
+                grantee: "reader".to_owned(),
+                privileges: vec![ast::Privilege::Usage],
+                with_grant_option: false,
+            });
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // By default unexpected privileges are ignored
+        let publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 1);
+
+        // They can also be an error
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.revoke_privileges = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+
+        // Or revoked
+        publish_profile.generation_options.revoke_privileges = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(
+            sql,
+            vec![
+                "REVOKE DELETE ON TABLE my.contacts FROM reader",
+                "REVOKE GRANT OPTION FOR SELECT ON TABLE my.contacts FROM reader",
+                "REVOKE SELECT ON TABLE my.contacts FROM PUBLIC",
+            ]
+        );
+    }
+
+    fn base_role(name: &str, member_of: Vec<&str>) -> ast::RoleDefinition {
+        ast::RoleDefinition {
+            name: name.to_owned(),
+            login: false,
+            superuser: false,
+            create_db: false,
+            create_role: false,
+            inherit: true,
+            replication: false,
+            bypass_rls: false,
+            connection_limit: -1,
+            member_of: member_of.into_iter().map(|m| m.to_owned()).collect(),
+        }
+    }
+
+    #[test]
+    fn it_creates_roles_and_memberships_before_schemas() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.push_schema(ast::SchemaDefinition {
+            name: "my".to_owned(),
+            owner: None,
+            comment: None,
+        });
+        let mut app_user = base_role("app_user", vec!["app_readers"]);
+        app_user.login = true;
+        source_package.push_role(app_user);
+        source_package.push_role(base_role("app_readers", Vec::new()));
+
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile
+            .role_names
+            .insert("app_user".to_owned(), "app_user_prod".to_owned());
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(Package::new()),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(
+            sql,
+            vec![
+                "CREATE ROLE app_user_prod WITH LOGIN NOSUPERUSER NOCREATEDB NOCREATEROLE INHERIT NOREPLICATION \
+                 NOBYPASSRLS CONNECTION LIMIT -1;\n\
+                 CREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+                 CREATE TABLE IF NOT EXISTS psqlpack.managed_roles (name text PRIMARY KEY);\n\
+                 INSERT INTO psqlpack.managed_roles (name) VALUES ('app_user_prod') ON CONFLICT DO NOTHING",
+                "CREATE ROLE app_readers WITH NOLOGIN NOSUPERUSER NOCREATEDB NOCREATEROLE INHERIT NOREPLICATION \
+                 NOBYPASSRLS CONNECTION LIMIT -1;\n\
+                 CREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+                 CREATE TABLE IF NOT EXISTS psqlpack.managed_roles (name text PRIMARY KEY);\n\
+                 INSERT INTO psqlpack.managed_roles (name) VALUES ('app_readers') ON CONFLICT DO NOTHING",
+                "GRANT app_readers TO app_user_prod",
+                "CREATE SCHEMA my",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_alters_roles_and_only_drops_them_when_allowed() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.push_role(base_role("app_user", vec!["app_writers"]));
+
+        fn existing_db() -> Option<Package> {
+            let mut existing_database = Package::new();
+            let mut app_user = base_role("app_user", vec!["app_readers"]);
+            app_user.connection_limit = 5;
+            existing_database.push_role(app_user);
+            existing_database.push_role(base_role("app_readers", Vec::new()));
+            existing_database.push_role(base_role("app_writers", Vec::new()));
+            existing_database.push_role(base_role("reporting", Vec::new()));
+            let mut postgres = base_role("postgres", Vec::new());
+            postgres.superuser = true;
+            existing_database.push_role(postgres);
+            existing_database.managed_roles = vec!["app_readers".into(), "app_writers".into(), "postgres".into()];
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // By default roles aren't dropped and memberships aren't revoked
+        let publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(
+            sql,
+            vec![
+                "ALTER ROLE app_user WITH NOLOGIN NOSUPERUSER NOCREATEDB NOCREATEROLE INHERIT NOREPLICATION \
+                 NOBYPASSRLS CONNECTION LIMIT -1",
+                "GRANT app_writers TO app_user",
+            ]
+        );
+
+        // Memberships that the project doesn't declare can be revoked
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.revoke_privileges = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 4);
+        assert_eq!(change_set[3].to_sql(&log), "REVOKE app_readers FROM app_user");
+
+        // Dropping roles can be an error
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_roles = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+
+        // Or allowed, although roles that psqlpack didn't create and superusers are left alone
+        publish_profile.generation_options.drop_roles = Toggle::Allow;
+        publish_profile.generation_options.revoke_privileges = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(
+            sql,
+            vec![
+                "DROP ROLE app_readers;\nDELETE FROM psqlpack.managed_roles WHERE name = 'app_readers'",
+                "ALTER ROLE app_user WITH NOLOGIN NOSUPERUSER NOCREATEDB NOCREATEROLE INHERIT NOREPLICATION \
+                 NOBYPASSRLS CONNECTION LIMIT -1",
+                "GRANT app_writers TO app_user",
+            ]
+        );
+
+        // Roles aren't managed at all unless the project declares them
+        let source_package = Package::new();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 1);
+    }
+
+    fn base_function(arguments: Vec<(&str, ast::SqlType)>, return_type: ast::SqlType) -> ast::FunctionDefinition {
+        ast::FunctionDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_string()),
+                name: "add".to_string(),
+            },
+            arguments: arguments
+                .into_iter()
+                .map(|(name, sql_type)| ast::FunctionArgument {
+                    mode: None,
+                    name: Some(name.to_owned()),
+                    sql_type,
+                    default: None,
+                })
+                .collect(),
+            return_type: ast::FunctionReturnType::SqlType(return_type),
+            body: "SELECT 1".to_string(),
+            language: ast::FunctionLanguage::SQL,
+            volatility: ast::FunctionVolatility::Volatile,
+            strict: false,
+            security_definer: false,
+            parallel: ast::FunctionParallel::Unsafe,
+            cost: None,
+            rows: None,
+            settings: Vec::new(),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn it_identifies_overloaded_functions_by_signature() {
+        let log = empty_logger();
+        let int = || ast::SqlType::Simple(ast::SimpleSqlType::Integer, None);
+        let text = || ast::SqlType::Simple(ast::SimpleSqlType::Text, None);
+        let bigint = || ast::SqlType::Simple(ast::SimpleSqlType::BigInteger, None);
+
+        let mut source_package = Package::new();
+        source_package
+            .functions
+            .push(base_function(vec![("a", int()), ("b", int())], int()));
+        source_package
+            .functions
+            .push(base_function(vec![("a", text()), ("b", text())], text()));
+
+        let mut existing_database = Package::new();
+        existing_database
+            .functions
+            .push(base_function(vec![("a", int()), ("b", int())], int()));
+        existing_database
+            .functions
+            .push(base_function(vec![("a", bigint())], bigint()));
+
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        // The unchanged overload is left alone
+        let changes = change_set.iter().skip(1).map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                "Drop function: public.add(bigint)",
+                "Add function: public.add(text, text)"
+            ]
+        );
+        assert_eq!(change_set[1].to_sql(&log), "DROP FUNCTION IF EXISTS public.add(bigint)");
+    }
+
+    #[test]
+    fn it_recreates_a_function_that_cannot_be_replaced() {
+        let log = empty_logger();
+        let int = || ast::SqlType::Simple(ast::SimpleSqlType::Integer, None);
+        let bigint = || ast::SqlType::Simple(ast::SimpleSqlType::BigInteger, None);
+        let varchar = |size| ast::SqlType::Simple(ast::SimpleSqlType::VariableLengthString(size), None);
+        let unsized_varchar = || ast::SqlType::Simple(ast::SimpleSqlType::UnsizedVariableLengthString, None);
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+        let changes = |source: ast::FunctionDefinition, existing: ast::FunctionDefinition| {
+            let mut source_package = Package::new();
+            source_package.functions.push(source);
+            let mut existing_database = Package::new();
+            existing_database.functions.push(existing);
+            let result = Delta::generate(
+                &log,
+                &source_package,
+                Some(existing_database),
+                "dbname",
+                &capabilities,
+                &publish_profile,
+            );
+            assert!(result.is_ok());
+            let Delta(change_set, ..) = result.unwrap();
+            change_set.iter().skip(1).map(|c| c.to_string()).collect::<Vec<_>>()
+        };
+
+        // A changed return type requires the function to be dropped first
+        assert_eq!(
+            changes(
+                base_function(vec![("a", int())], bigint()),
+                base_function(vec![("a", int())], int())
+            ),
+            vec!["Drop function: public.add(int)", "Add function: public.add(int)"]
+        );
+
+        // As does renaming an argument
+        assert_eq!(
+            changes(
+                base_function(vec![("b", int())], int()),
+                base_function(vec![("a", int())], int())
+            ),
+            vec!["Drop function: public.add(int)", "Add function: public.add(int)"]
+        );
+
+        // Type modifiers aren't part of the signature or the return type so the function is replaced as is
+        assert_eq!(
+            changes(
+                base_function(vec![("a", varchar(50))], varchar(10)),
+                base_function(vec![("a", unsized_varchar())], unsized_varchar())
+            ),
+            vec!["Modify function: public.add(varchar)"]
+        );
+    }
+
+    #[test]
+    fn it_replaces_a_function_when_its_attributes_change() {
+        let log = empty_logger();
+        let int = || ast::SqlType::Simple(ast::SimpleSqlType::Integer, None);
+        let mut function = base_function(vec![("a", int())], int());
+        function.volatility = ast::FunctionVolatility::Immutable;
+        function.strict = true;
+        function.security_definer = true;
+        function.parallel = ast::FunctionParallel::Safe;
+        function.cost = Some(Decimal::from(10));
+        function.settings = vec![ast::FunctionSetting {
+            name: "search_path".to_owned(),
+            values: vec!["public".to_owned(), "pg_temp".to_owned()],
+        }];
+        let mut source_package = Package::new();
+        source_package.functions.push(function);
+
+        // An inspected function reports the default cost
+        let mut existing_function = base_function(vec![("a", int())], int());
+        existing_function.cost = Some(Decimal::from(100));
+        let mut existing_database = Package::new();
+        existing_database.functions.push(existing_function);
+
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 2);
+        assert_eq!(change_set[1].to_string(), "Modify function: public.add(int)");
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "CREATE OR REPLACE FUNCTION public.add (a int)\n\
+             RETURNS int AS $$SELECT 1$$\n\
+             LANGUAGE SQL\n\
+             IMMUTABLE\n\
+             STRICT\n\
+             SECURITY DEFINER\n\
+             PARALLEL SAFE\n\
+             COST 10\n\
+             SET search_path = 'public', 'pg_temp'"
+        );
+
+        // Whereas an inspected function with the same attributes is left alone
+        let mut existing_database = Package::new();
+        existing_database
+            .functions
+            .push(base_function(vec![("a", int())], int()));
+        existing_database.functions[0].cost = Some(Decimal::from(100));
+        let mut source_package = Package::new();
+        source_package.functions.push(base_function(vec![("a", int())], int()));
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 1);
+    }
+
+    fn procedure(name: &str, body: &str) -> ast::ProcedureDefinition {
+        ast::ProcedureDefinition {
+            name: ast::ObjectName {
+                schema: Some("public".to_owned()),
+                name: name.to_owned(),
+            },
+            arguments: vec![ast::FunctionArgument {
+                mode: None,
+                name: Some("a".to_owned()),
+                sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
+                default: None,
+            }],
+            body: body.to_owned(),
+            language: ast::FunctionLanguage::SQL,
+            security_definer: false,
+            settings: Vec::new(),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn it_can_add_modify_and_drop_procedures() {
+        let log = empty_logger();
+        let capabilities = Capabilities {
+            server_version: Semver::new(11, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut source_package = Package::new();
+        source_package.procedures.push(procedure("archive", "DELETE FROM log"));
+        source_package.procedures.push(procedure("purge", "TRUNCATE log"));
+        let existing_database = || {
+            let mut existing_database = Package::new();
+            existing_database.procedures.push(procedure("archive", "SELECT 1"));
+            existing_database.procedures.push(procedure("cleanup", "SELECT 1"));
+            existing_database
+        };
+
+        // By default procedures that aren't in the project are left alone
+        let mut publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database()),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        let Delta(change_set, ..) = result.unwrap();
+        assert!(!change_set
+            .iter()
+            .any(|change| matches!(*change, ChangeInstruction::DropProcedure(..))));
+
+        // Dropping procedures can also be an error
+        publish_profile.generation_options.drop_procedures = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database()),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+        match result.err().unwrap() {
+            PsqlpackError(PublishUnsafeOperationError(message), _) => assert_eq!(
+                message,
+                "Attempted to drop procedure public.cleanup(int) however dropping procedures is currently disabled"
+            ),
+            unexpected => panic!("Expected unsafe operation error however saw {:?}", unexpected),
+        };
+
+        publish_profile.generation_options.drop_procedures = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database()),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let changes = change_set.iter().skip(1).map(|c| c.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                "Drop procedure: public.cleanup(int)",
+                "Modify procedure: public.archive(int)",
+                "Add procedure: public.purge(int)",
+            ]
+        );
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "DROP PROCEDURE IF EXISTS public.cleanup(int)"
+        );
+        assert_eq!(
+            change_set[2].to_sql(&log),
+            "CREATE OR REPLACE PROCEDURE public.archive (a int)\n\
+             AS $$DELETE FROM log$$\n\
+             LANGUAGE SQL"
+        );
+    }
+
+    #[test]
+    fn it_sets_the_comment_of_a_procedure_once_it_is_recreated() {
+        let log = empty_logger();
+        let capabilities = Capabilities {
+            server_version: Semver::new(11, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut source_package = Package::new();
+        let mut archive = procedure("archive", "DELETE FROM log");
+        archive.arguments[0].name = Some("before".to_owned());
+        archive.comment = Some("Archives the log".to_owned());
+        source_package.procedures.push(archive);
+        let mut purge = procedure("purge", "TRUNCATE log");
+        purge.comment = Some("Empties the log".to_owned());
+        source_package.procedures.push(purge);
+        let mut existing_database = Package::new();
+        let mut archive = procedure("archive", "DELETE FROM log");
+        archive.comment = Some("Archives the log".to_owned());
+        existing_database.procedures.push(archive);
+        existing_database.procedures.push(procedure("purge", "TRUNCATE log"));
+
+        // Renaming an argument recreates the procedure which loses its comment along the way
+        let publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set
+            .iter()
+            .filter(|c| matches!(c, ChangeInstruction::SetComment(..)))
+            .map(|c| c.to_sql(&log))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sql,
+            vec![
+                "COMMENT ON PROCEDURE public.archive(int) IS 'Archives the log'",
+                "COMMENT ON PROCEDURE public.purge(int) IS 'Empties the log'",
+            ]
+        );
+    }
+
+    fn schema(name: &str, owner: Option<&str>) -> ast::SchemaDefinition {
+        ast::SchemaDefinition {
+            name: name.to_owned(),
+            owner: owner.map(|o| o.to_owned()),
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn it_creates_schemas_with_an_owner_and_alters_existing_owners() {
+        let log = empty_logger();
+        let mut source_package = Package::new();
+        source_package.push_schema(schema("my", Some("app_owner")));
+        source_package.push_schema(schema("reporting", Some("app_owner")));
+        source_package.push_schema(schema("audit", None));
+
+        let mut existing_database = Package::new();
+        existing_database.push_schema(schema("reporting", Some("postgres")));
+        existing_database.push_schema(schema("audit", Some("postgres")));
+
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile
+            .role_names
+            .insert("app_owner".to_owned(), "app_owner_prod".to_owned());
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(
+            sql,
+            vec![
+                "CREATE SCHEMA my AUTHORIZATION app_owner_prod",
+                "ALTER SCHEMA reporting OWNER TO app_owner_prod",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_only_drops_schemas_when_allowed() {
+        let log = empty_logger();
+        let source_package = Package::new();
+
+        fn existing_db(with_sequence: bool) -> Option<Package> {
+            let mut existing_database = Package::new();
+            existing_database.push_schema(schema("public", Some("postgres")));
+            existing_database.push_schema(schema("my", Some("postgres")));
+            if with_sequence {
+                existing_database.push_sequence(base_sequence());
+            }
+            Some(existing_database)
+        }
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        // By default schemas aren't dropped
+        let mut publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(false),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 1);
+
+        // Dropping schemas can be an error
+        publish_profile.generation_options.drop_schemas = Toggle::Error;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(false),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_err());
+
+        // Or allowed, although the public schema is left alone
+        publish_profile.generation_options.drop_schemas = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(false),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(sql, vec!["DROP SCHEMA IF EXISTS my"]);
+
+        // Schemas that still contain objects are only dropped when cascading is allowed
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(true),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        match result {
+            Err(error) => assert_eq!(
+                error.to_string(),
+                "Couldn't publish database due to an unsafe operation: Unable to drop schema my as it still contains sequence my.shared_id_seq. Dropping \
+                 these requires CASCADE."
+            ),
+            Ok(_) => panic!("Expected the schema drop to be refused"),
+        }
+
+        publish_profile.generation_options.cascade_schema_drops = true;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            existing_db(true),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
+        assert_eq!(sql, vec!["DROP SCHEMA IF EXISTS my CASCADE"]);
+    }
+
+    fn base_sequence() -> ast::SequenceDefinition {
+        let mut sequence = ast::SequenceDefinition {
+            name: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "shared_id_seq".to_owned(),
+            },
+            data_type: None,
+            increment: None,
+            min_value: None,
+            max_value: None,
+            start: None,
+            cache: None,
+            cycle: false,
+            owned_by: None,
+            comment: None,
+        };
+        sequence.set_defaults();
+        sequence
+    }
+
+    #[test]
+    fn it_can_add_a_new_sequence() {
+        let log = empty_logger();
+        let source_sequence = base_sequence();
+
+        let existing_database = Package::new();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_primary_key_constraints = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
-            &mut change_set,
-            &existing_database,
-            &capabilities,
-            &publish_profile,
-            &log,
-        );
-        assert!(change_set.is_empty());
-
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
-        }
-        .generate(
+        let result = (&source_sequence).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2355,32 +8150,11 @@ mod tests {
         );
         assert!(result.is_ok());
 
-        // Primary keys cannot be altered, so we drop/create
-        assert_eq!(change_set.len(), 2);
+        // We should have a single instruction to create the sequence
+        assert_eq!(change_set.len(), 1);
         match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(name, "pk_my_contacts_id");
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-        match change_set[1] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                match *constraint {
-                    TableConstraint::Primary {
-                        name,
-                        columns,
-                        parameters,
-                    } => {
-                        assert_eq!(name, "pk_my_contacts_id");
-                        assert_eq!(columns.len(), 1);
-                        assert!(columns.contains(&"id".to_owned()));
-                        assert!(parameters.is_some());
-                        assert_eq!(parameters.as_ref().unwrap().len(), 1);
-                    }
-                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
-                }
+            ChangeInstruction::AddSequence(sequence) => {
+                assert_eq!(sequence.name.to_string(), "my.shared_id_seq");
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
@@ -2388,36 +8162,33 @@ mod tests {
         // Check the SQL generation
         assert_eq!(
             change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts\nDROP CONSTRAINT pk_my_contacts_id"
-        );
-        assert_eq!(
-            change_set[1].to_sql(&log),
-            "ALTER TABLE my.contacts\nADD CONSTRAINT pk_my_contacts_id PRIMARY KEY (id) WITH (FILLFACTOR=80)"
+            "CREATE SEQUENCE my.shared_id_seq AS bigint INCREMENT BY 1 MINVALUE 1 \
+             MAXVALUE 9223372036854775807 START WITH 1 CACHE 1 NO CYCLE"
         );
     }
 
     #[test]
-    fn it_can_add_a_new_foreign_key() {
+    fn it_ignores_a_sequence_with_equivalent_default_options() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
-            },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
-        });
 
-        // Create a database with the base table already defined.
+        // A descending sequence defaults the bounds based upon the data type
+        let mut source_sequence = base_sequence();
+        source_sequence.data_type = Some(SqlType::Simple(SimpleSqlType::Integer, None));
+        source_sequence.increment = Some(-1);
+        source_sequence.min_value = None;
+        source_sequence.max_value = None;
+        source_sequence.start = None;
+        source_sequence.set_defaults();
+
+        // As inspected from the database
         let mut existing_database = Package::new();
-        existing_database.tables.push(base_table());
+        let mut existing_sequence = base_sequence();
+        existing_sequence.data_type = Some(SqlType::Simple(SimpleSqlType::Integer, None));
+        existing_sequence.increment = Some(-1);
+        existing_sequence.min_value = Some(-2147483648);
+        existing_sequence.max_value = Some(-1);
+        existing_sequence.start = Some(-1);
+        existing_database.sequences.push(existing_sequence);
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
@@ -2426,22 +8197,7 @@ mod tests {
         let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
-            &mut change_set,
-            &existing_database,
-            &capabilities,
-            &publish_profile,
-            &log,
-        );
-        assert!(change_set.is_empty());
-
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
-        }
-        .generate(
+        let result = (&source_sequence).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2449,81 +8205,29 @@ mod tests {
             &log,
         );
         assert!(result.is_ok());
-
-        // We should have a single instruction to create a new constraint
-        assert_eq!(change_set.len(), 1);
-        match change_set[0] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                match *constraint {
-                    TableConstraint::Foreign {
-                        name,
-                        columns,
-                        ref_table,
-                        ref_columns,
-                        match_type,
-                        events,
-                    } => {
-                        assert_eq!(name, "fk_my_contacts_my_companies");
-                        assert_eq!(columns.len(), 1);
-                        assert!(columns.contains(&"company_id".to_owned()));
-                        assert_eq!(ref_table.to_string(), "my.companies");
-                        assert_eq!(ref_columns.len(), 1);
-                        assert!(ref_columns.contains(&"id".to_owned()));
-                        assert!(match_type.is_some());
-                        assert_eq!(match_type.as_ref().unwrap(), &ForeignConstraintMatchType::Simple);
-                        assert!(events.is_some());
-                        assert_eq!(events.as_ref().unwrap().len(), 2); // We test this further below
-                    }
-                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
-                }
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
-        assert_eq!(
-            change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts\n\
-             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
-             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE CASCADE ON DELETE NO ACTION"
-        );
+        assert!(change_set.is_empty());
     }
 
     #[test]
-    fn it_can_remove_an_existing_foreign_key() {
+    fn it_can_modify_sequence_options_without_restarting_it() {
         let log = empty_logger();
-        let source_table = base_table();
+        let mut source_sequence = base_sequence();
+        source_sequence.increment = Some(5);
+        source_sequence.start = Some(1000);
+        source_sequence.cache = Some(20);
+        source_sequence.cycle = true;
 
-        // Create a database with the base table already defined.
         let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
-            },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
-        });
-        existing_database.tables.push(existing_table);
+        existing_database.sequences.push(base_sequence());
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        // This change_set gets generated at the table level
-        let result = (&source_table).generate(
+        let result = (&source_sequence).generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2532,370 +8236,427 @@ mod tests {
         );
         assert!(result.is_ok());
 
-        // We should have a single instruction to remove a constraint
         assert_eq!(change_set.len(), 1);
         match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(name, "fk_my_contacts_my_companies");
+            ChangeInstruction::ModifySequence(sequence) => {
+                assert_eq!(sequence.name.to_string(), "my.shared_id_seq");
             }
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
 
-        // Check the SQL generation
+        // START only changes the recorded start value, the current value is left untouched
+        let sql = change_set[0].to_sql(&log);
         assert_eq!(
-            change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts\nDROP CONSTRAINT fk_my_contacts_my_companies"
+            sql,
+            "ALTER SEQUENCE my.shared_id_seq AS bigint INCREMENT BY 5 MINVALUE 1 \
+             MAXVALUE 9223372036854775807 START WITH 1000 CACHE 20 CYCLE"
         );
+        assert!(!sql.contains("RESTART"));
     }
 
     #[test]
-    fn it_can_modify_an_existing_foreign_key() {
+    fn it_sets_sequence_ownership_once_the_table_exists() {
         let log = empty_logger();
-        let mut source_table = base_table();
-        source_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
-            },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::NoAction),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
-        });
-
-        // Create a database with the base table already defined.
-        let mut existing_database = Package::new();
-        let mut existing_table = base_table();
-        existing_table.constraints.push(TableConstraint::Foreign {
-            name: "fk_my_contacts_my_companies".to_owned(),
-            columns: vec!["company_id".into()],
-            ref_table: ObjectName {
-                schema: Some("my".into()),
-                name: "companies".into(),
+        let mut source_package = Package::new();
+        source_package.tables.push(base_table());
+        let mut sequence = base_sequence();
+        sequence.owned_by = Some(ast::SequenceOwner {
+            table: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
             },
-            ref_columns: vec!["id".into()],
-            match_type: Some(ForeignConstraintMatchType::Simple),
-            events: Some(vec![
-                ForeignConstraintEvent::Update(ForeignConstraintAction::Cascade),
-                ForeignConstraintEvent::Delete(ForeignConstraintAction::NoAction),
-            ]),
+            column: "company_id".to_owned(),
         });
-        existing_database.tables.push(existing_table);
+        source_package.sequences.push(sequence);
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_foreign_key_constraints = Toggle::Allow;
+        let publish_profile = PublishProfile::default();
 
-        let mut change_set = Vec::new();
-        // First, check that source table changes do nothing
-        let _ = (&source_table).generate(
-            &mut change_set,
-            &existing_database,
-            &capabilities,
-            &publish_profile,
+        let result = Delta::generate(
             &log,
-        );
-        assert!(change_set.is_empty());
-
-        // Now we check with a linked table constraint
-        let result = LinkedTableConstraint {
-            table: &source_table,
-            constraint: &source_table.constraints.first().unwrap(),
-        }
-        .generate(
-            &mut change_set,
-            &existing_database,
+            &source_package,
+            Some(Package::new()),
+            "dbname",
             &capabilities,
             &publish_profile,
-            &log,
         );
-        assert!(result.is_ok());
-
-        // Primary keys cannot be altered, so we drop/create
-        assert_eq!(change_set.len(), 2);
-        match change_set[0] {
-            ChangeInstruction::DropConstraint(ref table, ref name) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                assert_eq!(name, "fk_my_contacts_my_companies");
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-        match change_set[1] {
-            ChangeInstruction::AddConstraint(ref table, ref constraint) => {
-                assert_eq!(table.name.to_string(), "my.contacts");
-                match *constraint {
-                    TableConstraint::Foreign {
-                        name,
-                        columns,
-                        ref_table,
-                        ref_columns,
-                        match_type,
-                        events,
-                    } => {
-                        assert_eq!(*name, "fk_my_contacts_my_companies");
-                        assert_eq!(columns.len(), 1);
-                        assert!(columns.contains(&"company_id".to_owned()));
-                        assert_eq!(ref_table.to_string(), "my.companies");
-                        assert_eq!(ref_columns.len(), 1);
-                        assert!(ref_columns.contains(&"id".to_owned()));
-                        assert!(match_type.is_some());
-                        assert_eq!(match_type.as_ref().unwrap(), &ForeignConstraintMatchType::Simple);
-                        assert!(events.is_some());
-                        assert_eq!(events.as_ref().unwrap().len(), 2); // We test this further below
-                    }
-                    unexpected => panic!("Unexpected constraint: {:?}", unexpected),
-                }
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+
+        let position = |matcher: fn(&ChangeInstruction) -> bool| change_set.iter().position(matcher).unwrap();
+        let add_sequence = position(|c| matches!(c, ChangeInstruction::AddSequence(_)));
+        let add_table = position(|c| matches!(c, ChangeInstruction::AddTable(_)));
+        let set_owner = position(|c| matches!(c, ChangeInstruction::ModifySequenceOwner(_)));
+        assert!(add_sequence < add_table);
+        assert!(add_table < set_owner);
         assert_eq!(
-            change_set[0].to_sql(&log),
-            "ALTER TABLE my.contacts\nDROP CONSTRAINT fk_my_contacts_my_companies"
+            change_set[set_owner].to_sql(&log),
+            "ALTER SEQUENCE my.shared_id_seq OWNED BY my.contacts.company_id"
+        );
+
+        // Removing the owner from an existing sequence releases it
+        let mut source_package = Package::new();
+        source_package.sequences.push(base_sequence());
+        let mut existing_database = Package::new();
+        existing_database.sequences.push(source_package.sequences[0].clone());
+        existing_database.sequences[0].owned_by = Some(ast::SequenceOwner {
+            table: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            column: "company_id".to_owned(),
+        });
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
         );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert_eq!(change_set.len(), 2);
         assert_eq!(
             change_set[1].to_sql(&log),
-            "ALTER TABLE my.contacts\n\
-             ADD CONSTRAINT fk_my_contacts_my_companies FOREIGN KEY (company_id) \
-             REFERENCES my.companies (id) MATCH SIMPLE ON UPDATE NO ACTION ON DELETE NO ACTION"
+            "ALTER SEQUENCE my.shared_id_seq OWNED BY NONE"
         );
     }
 
-    #[test]
-    fn it_can_add_a_new_index() {
-        let log = empty_logger();
-        let source_index = IndexDefinition {
+    fn first_name_rename() -> RenameDefinition {
+        RenameDefinition::Column {
+            table: "my.contacts".to_owned(),
+            from: "first_name".to_owned(),
+            to: "given_name".to_owned(),
+        }
+    }
+
+    fn renamed_package() -> Package {
+        let mut table = base_table();
+        table.columns[2].name = "given_name".to_owned();
+        let mut package = Package::new();
+        package.tables.push(table);
+        package.indexes.push(IndexDefinition {
+            name: "idx_contacts_given_name".to_owned(),
+            table: table_name(),
+            columns: vec![IndexColumn {
+                name: "given_name".to_owned(),
+                order: Some(IndexOrder::Ascending),
+                null_position: Some(IndexPosition::Last),
+            }],
+            unique: false,
+            index_type: Some(IndexType::BTree),
+            storage_parameters: None,
+            comment: None,
+        });
+        package.renames.push(first_name_rename());
+        package.renames.push(RenameDefinition::Index {
+            from: "my.idx_contacts_first_name".to_owned(),
+            to: "idx_contacts_given_name".to_owned(),
+        });
+        package
+    }
+
+    fn existing_contacts_db() -> Package {
+        let mut existing_database = Package::new();
+        existing_database.tables.push(base_table());
+        existing_database.indexes.push(IndexDefinition {
             name: "idx_contacts_first_name".to_owned(),
-            table: ObjectName {
-                schema: Some("public".to_owned()),
-                name: "contacts".to_owned(),
-            },
+            table: table_name(),
             columns: vec![IndexColumn {
                 name: "first_name".to_owned(),
                 order: Some(IndexOrder::Ascending),
                 null_position: Some(IndexPosition::Last),
             }],
-            unique: true,
+            unique: false,
             index_type: Some(IndexType::BTree),
             storage_parameters: None,
-        };
+            comment: None,
+        });
+        existing_database
+    }
+
+    fn table_name() -> ObjectName {
+        ObjectName {
+            schema: Some("my".to_owned()),
+            name: "contacts".to_owned(),
+        }
+    }
+
+    #[test]
+    fn it_only_sets_comments_that_differ() {
+        let log = empty_logger();
+        let mut source_package = existing_contacts_db();
+        source_package.tables[0].comment = Some("People we know".to_owned());
+        source_package.tables[0].columns[2].comment = Some("Given name".to_owned());
+        source_package.indexes[0].comment = Some("It's for lookups".to_owned());
+
+        let mut existing_database = existing_contacts_db();
+        existing_database.tables[0].columns[1].comment = Some("Owning company".to_owned());
+        existing_database.tables[0].columns[2].comment = Some("Given name".to_owned());
+        existing_database.indexes[0].comment = Some("For lookups".to_owned());
 
-        // Create a database with no indexes defined.
-        let existing_database = Package::new();
-        let publish_profile = PublishProfile::default();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-
-        let mut change_set = Vec::new();
-        let result = (&source_index).generate(
-            &mut change_set,
-            &existing_database,
+        let publish_profile = PublishProfile::default();
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
             &capabilities,
             &publish_profile,
-            &log,
         );
         assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
 
-        // We should have a single instruction to create a new index
-        assert_eq!(change_set.len(), 1);
-        match change_set[0] {
-            ChangeInstruction::AddIndex(ref index, concurrently) => {
-                assert_eq!(index.name, "idx_contacts_first_name");
-                assert_eq!(index.table.to_string(), "public.contacts");
-                assert!(concurrently);
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-
-        // Check the SQL generation
+        // Nothing is rebuilt, including the index (first will be use database)
+        let sql = change_set.iter().skip(1).map(|c| c.to_sql(&log)).collect::<Vec<_>>();
         assert_eq!(
-            change_set[0].to_sql(&log),
-            "CREATE UNIQUE INDEX CONCURRENTLY idx_contacts_first_name \
-             ON public.contacts USING btree (first_name ASC NULLS LAST)"
+            sql,
+            vec![
+                "COMMENT ON TABLE my.contacts IS 'People we know'",
+                "COMMENT ON COLUMN my.contacts.company_id IS NULL",
+                "COMMENT ON INDEX my.idx_contacts_first_name IS 'It''s for lookups'",
+            ]
         );
     }
 
     #[test]
-    fn it_can_remove_an_existing_index() {
+    fn it_sets_the_comment_of_a_rebuilt_index_once_it_exists() {
         let log = empty_logger();
-        let source_package = Package::new();
+        let mut source_package = existing_contacts_db();
+        source_package.indexes[0].unique = true;
+        source_package.indexes[0].comment = Some("For lookups".to_owned());
+
+        let mut existing_database = existing_contacts_db();
+        existing_database.indexes[0].comment = Some("For lookups".to_owned());
 
-        // Create a database with the index already defined.
-        fn existing_db() -> Option<Package> {
-            let mut existing_database = Package::new();
-            existing_database.indexes.push(IndexDefinition {
-                name: "idx_contacts_first_name".to_owned(),
-                table: ObjectName {
-                    schema: Some("public".to_owned()),
-                    name: "contacts".to_owned(),
-                },
-                columns: vec![IndexColumn {
-                    name: "first_name".to_owned(),
-                    order: Some(IndexOrder::Ascending),
-                    null_position: Some(IndexPosition::Last),
-                }],
-                unique: true,
-                index_type: Some(IndexType::BTree),
-                storage_parameters: None,
-            });
-            Some(existing_database)
-        }
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
         let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_indexes = Toggle::Error;
-
-        // First of all, make sure an error is generated
+        publish_profile.generation_options.force_concurrent_indexes = true;
         let result = Delta::generate(
             &log,
             &source_package,
-            existing_db(),
+            Some(existing_database),
             "dbname",
             &capabilities,
             &publish_profile,
         );
-        assert!(result.is_err());
-        // Now run it again - it should be ok now
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+
+        assert_eq!(change_set.len(), 4);
+        assert_eq!(
+            change_set[1].to_sql(&log),
+            "DROP INDEX CONCURRENTLY IF EXISTS my.idx_contacts_first_name"
+        );
+        assert!(change_set[2]
+            .to_sql(&log)
+            .starts_with("CREATE UNIQUE INDEX CONCURRENTLY"));
+        assert_eq!(
+            change_set[3].to_sql(&log),
+            "COMMENT ON INDEX my.idx_contacts_first_name IS 'For lookups'"
+        );
+
+        // The index is built outside of the transaction so the comment needs to follow it
+        let phase = Phase::of(&change_set[3], capabilities.server_version);
+        assert!(matches!(phase, Phase::AfterTransaction));
+    }
+
+    #[test]
+    fn it_renames_a_column_and_index_instead_of_dropping_them() {
+        let log = empty_logger();
+        let source_package = renamed_package();
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let mut publish_profile = PublishProfile::default();
-        publish_profile.generation_options.drop_indexes = Toggle::Allow;
+
+        // Dropping columns and indexes is an error by default so this would fail without the renames
+        let publish_profile = PublishProfile::default();
         let result = Delta::generate(
             &log,
             &source_package,
-            existing_db(),
+            Some(existing_contacts_db()),
             "dbname",
             &capabilities,
             &publish_profile,
         );
         assert!(result.is_ok());
-        let change_set = match result.unwrap() {
-            Delta(c) => c,
-        };
+        let Delta(change_set, ..) = result.unwrap();
 
-        // We should have a single instruction to remove an index (first will be use database)
-        assert_eq!(change_set.len(), 2);
+        // We should only have the renames (first will be use database)
+        assert_eq!(change_set.len(), 3);
         match change_set[1] {
-            ChangeInstruction::DropIndex(ref index, concurrently) => {
-                assert_eq!(*index, "public.idx_contacts_first_name");
-                assert!(concurrently);
-            }
+            ChangeInstruction::Rename(rename) => assert_eq!(*rename, first_name_rename()),
             ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
         }
-
-        // Check the SQL generation
         assert_eq!(
             change_set[1].to_sql(&log),
-            "DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_first_name"
+            "ALTER TABLE my.contacts RENAME COLUMN first_name TO given_name;\n\
+             CREATE SCHEMA IF NOT EXISTS psqlpack;\n\
+             CREATE TABLE IF NOT EXISTS psqlpack.refactor_log (\
+             definition jsonb PRIMARY KEY, applied_at timestamptz NOT NULL DEFAULT now());\n\
+             INSERT INTO psqlpack.refactor_log (definition) VALUES (\
+             '{\"kind\":\"column\",\"table\":\"my.contacts\",\"from\":\"first_name\",\"to\":\"given_name\"}') \
+             ON CONFLICT DO NOTHING"
         );
+        assert!(change_set[2]
+            .to_sql(&log)
+            .starts_with("ALTER INDEX my.idx_contacts_first_name RENAME TO idx_contacts_given_name;"));
     }
 
     #[test]
-    fn it_can_modify_an_existing_index() {
+    fn it_does_not_apply_a_rename_that_has_already_been_recorded() {
         let log = empty_logger();
-        let source_index = IndexDefinition {
-            name: "idx_contacts_name".to_owned(),
-            table: ObjectName {
-                schema: Some("public".to_owned()),
-                name: "contacts".to_owned(),
-            },
-            columns: vec![
-                IndexColumn {
-                    name: "first_name".to_owned(),
-                    order: Some(IndexOrder::Ascending),
-                    null_position: Some(IndexPosition::Last),
-                },
-                IndexColumn {
-                    name: "last_name".to_owned(),
-                    order: Some(IndexOrder::Descending),
-                    null_position: Some(IndexPosition::First),
-                },
-            ],
-            unique: false,
-            index_type: Some(IndexType::BTree),
-            storage_parameters: None,
+        let source_package = renamed_package();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
         };
 
-        // Create a database with a single index defined.
-        let mut existing_database = Package::new();
-        existing_database.indexes.push(IndexDefinition {
-            name: "idx_contacts_name".to_owned(),
-            table: ObjectName {
-                schema: Some("public".to_owned()),
-                name: "contacts".to_owned(),
-            },
-            columns: vec![IndexColumn {
-                name: "first_name".to_owned(),
-                order: Some(IndexOrder::Ascending),
-                null_position: Some(IndexPosition::Last),
-            }],
-            unique: true,
-            index_type: Some(IndexType::BTree),
-            storage_parameters: None,
+        // If the rename has been recorded then an old column has since been added back in
+        let mut existing_database = existing_contacts_db();
+        existing_database.renames.push(first_name_rename());
+        existing_database.indexes.clear();
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_columns = Toggle::Allow;
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        );
+        assert!(result.is_ok());
+        let Delta(change_set, ..) = result.unwrap();
+        assert!(!change_set.iter().any(|c| matches!(c, ChangeInstruction::Rename(..))));
+        assert!(change_set
+            .iter()
+            .any(|c| matches!(c, ChangeInstruction::DropColumn(_, ref name) if name == "first_name")));
+        assert!(change_set
+            .iter()
+            .any(|c| matches!(c, ChangeInstruction::AddColumn(_, column) if column.name == "given_name")));
+    }
+
+    #[test]
+    fn it_isolates_changes_that_cannot_run_in_a_transaction_into_phases() {
+        let log = empty_logger();
+        let mut colors = base_type();
+        if let ast::TypeDefinitionKind::Enum(ref mut values) = colors.kind {
+            values.push("black".to_owned());
+        }
+        let mut source_package = existing_contacts_db();
+        source_package.types.push(colors);
+        source_package.scripts.push(ScriptDefinition {
+            name: "seed".to_owned(),
+            kind: ScriptKind::PostDeployment,
+            order: 0,
+            contents: "SELECT 1".to_owned(),
         });
+        source_package.scripts.push(ScriptDefinition {
+            name: "prepare".to_owned(),
+            kind: ScriptKind::PreDeployment,
+            order: 0,
+            contents: "SELECT 2".to_owned(),
+        });
+        let mut existing_database = existing_contacts_db();
+        existing_database.indexes.clear();
+        existing_database.types.push(base_type());
         let capabilities = Capabilities {
             server_version: Semver::new(9, 6, None),
             extensions: Vec::new(),
             database_exists: true,
         };
-        let publish_profile = PublishProfile::default();
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.transactional = true;
 
-        let mut change_set = Vec::new();
-        let result = (&source_index).generate(
-            &mut change_set,
-            &existing_database,
+        let result = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
             &capabilities,
             &publish_profile,
-            &log,
         );
         assert!(result.is_ok());
+        let delta = result.unwrap();
+        let phases = delta.phases().unwrap();
+        assert_eq!(phases.len(), 4);
+
+        // Pre-deployment scripts still come first, followed by adding an enum value which must happen before the
+        // transaction on older servers
+        assert_eq!(phases[0].0, Phase::PreDeployment);
+        assert!(matches!(phases[0].1[..], [ChangeInstruction::RunScript(script)] if script.name == "prepare"));
+        assert_eq!(phases[1].0, Phase::BeforeTransaction);
+        assert!(matches!(phases[1].1[..], [ChangeInstruction::ModifyType(..)]));
+        assert_eq!(phases[2].0, Phase::Transaction);
+        assert!(matches!(phases[2].1[..], [ChangeInstruction::RunScript(script)] if script.name == "seed"));
+        assert_eq!(phases[3].0, Phase::AfterTransaction);
+        assert!(matches!(phases[3].1[..], [ChangeInstruction::AddIndex(_, true)]));
+
+        let script = delta.to_script(&log);
+        assert!(script.starts_with(
+            "-- Using database `dbname`;\n\n-- Phase 1 of 4: pre-deployment\nBEGIN;\n\n-- Script: prepare\nSELECT 2\n;\n\n\
+             COMMIT;\n\n-- Phase 2 of 4: before transaction\n"
+        ));
+        assert!(script.contains("-- Phase 3 of 4: transaction\nBEGIN;\n\n-- Script: seed\nSELECT 1\n;\n\nCOMMIT;\n\n"));
+        assert!(script.contains("-- Phase 4 of 4: after transaction\nCREATE INDEX CONCURRENTLY"));
+    }
 
-        // We should have two instructions to drop/create a new index
-        assert_eq!(change_set.len(), 2);
-        match change_set[0] {
-            ChangeInstruction::DropIndex(ref index_name, concurrently) => {
-                assert_eq!(*index_name, "public.idx_contacts_name");
-                assert!(concurrently);
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
-        }
-        match change_set[1] {
-            ChangeInstruction::AddIndex(ref index, concurrently) => {
-                assert_eq!(index.name, "idx_contacts_name");
-                assert_eq!(index.table.to_string(), "public.contacts");
-                assert!(concurrently);
-            }
-            ref unexpected => panic!("Unexpected instruction type: {:?}", unexpected),
+    #[test]
+    fn it_adds_enum_values_within_the_transaction_on_newer_servers() {
+        let log = empty_logger();
+        let mut colors = base_type();
+        if let ast::TypeDefinitionKind::Enum(ref mut values) = colors.kind {
+            values.push("black".to_owned());
         }
+        let mut source_package = Package::new();
+        source_package.types.push(colors);
+        source_package.scripts.push(ScriptDefinition {
+            name: "prepare".to_owned(),
+            kind: ScriptKind::PreDeployment,
+            order: 0,
+            contents: "SELECT 2".to_owned(),
+        });
+        let mut existing_database = Package::new();
+        existing_database.types.push(base_type());
+        let capabilities = Capabilities {
+            server_version: Semver::new(12, 2, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.transactional = true;
 
-        // Check the SQL generation
-        assert_eq!(
-            change_set[0].to_sql(&log),
-            "DROP INDEX CONCURRENTLY IF EXISTS public.idx_contacts_name"
-        );
-        assert_eq!(
-            change_set[1].to_sql(&log),
-            "CREATE INDEX CONCURRENTLY idx_contacts_name \
-             ON public.contacts USING btree (first_name ASC NULLS LAST, last_name DESC NULLS FIRST)"
-        );
+        let delta = Delta::generate(
+            &log,
+            &source_package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        )
+        .unwrap();
+        // Pre-deployment scripts share the transaction when nothing needs to happen before it
+        let phases = delta.phases().unwrap();
+        assert_eq!(phases.len(), 1);
+        assert_eq!(phases[0].0, Phase::Transaction);
+        assert_eq!(phases[0].1.len(), 2);
     }
 
     #[test]
@@ -2920,7 +8681,7 @@ mod tests {
         let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -2967,7 +8728,7 @@ mod tests {
         let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -3011,7 +8772,7 @@ mod tests {
         let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -3045,7 +8806,7 @@ mod tests {
         let publish_profile = PublishProfile::default();
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -3091,7 +8852,7 @@ mod tests {
         publish_profile.generation_options.upgrade_extensions = Toggle::Allow;
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -3139,7 +8900,7 @@ mod tests {
         publish_profile.generation_options.upgrade_extensions = Toggle::Allow;
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -3183,7 +8944,7 @@ mod tests {
         publish_profile.generation_options.upgrade_extensions = Toggle::Allow;
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -3236,7 +8997,7 @@ mod tests {
         publish_profile.generation_options.upgrade_extensions = Toggle::Ignore;
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
@@ -3280,7 +9041,7 @@ mod tests {
         publish_profile.generation_options.upgrade_extensions = Toggle::Error;
 
         let mut change_set = Vec::new();
-        let result = (&requested_extension).generate(
+        let result = requested_extension.generate(
             &mut change_set,
             &existing_database,
             &capabilities,
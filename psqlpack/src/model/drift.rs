@@ -0,0 +1,462 @@
+//! Drift is any change made to a target database outside of psqlpack, such as a hotfix applied by hand.
+//! It is detected by generating the changes required to bring the target back in line with the package
+//! and describing the objects involved from the point of view of the target.
+
+use std::fmt;
+
+use serde::Serialize;
+use slog::Logger;
+
+use crate::errors::PsqlpackResult;
+use crate::model::delta::{ChangeInstruction, TypeModificationAction};
+use crate::model::{Capabilities, Delta, Package, PublishProfile, Toggle};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum DriftKind {
+    /// The object exists in the target but not in the package
+    Added,
+    /// The object exists in the package but not in the target
+    Removed,
+    /// The object exists in both however the definition differs
+    Modified,
+}
+
+impl fmt::Display for DriftKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DriftKind::Added => write!(f, "added"),
+            DriftKind::Removed => write!(f, "removed"),
+            DriftKind::Modified => write!(f, "modified"),
+        }
+    }
+}
+
+/// An object within the target that differs from the package.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Drift {
+    pub kind: DriftKind,
+    pub object: String,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.object, self.kind)
+    }
+}
+
+impl Drift {
+    pub(crate) fn detect(
+        log: &Logger,
+        package: &Package,
+        target: Package,
+        target_database_name: &str,
+        target_capabilities: &Capabilities,
+    ) -> PsqlpackResult<Vec<Drift>> {
+        let log = log.new(o!("drift" => "detect"));
+
+        // Every change needs to be generated so nothing can be ignored or considered unsafe
+        let profile = Drift::profile();
+        let delta = Delta::generate(
+            &log,
+            package,
+            Some(target),
+            target_database_name,
+            target_capabilities,
+            &profile,
+        )?;
+
+        let mut drift: Vec<Drift> = Vec::new();
+        for change in delta.changes() {
+            let (kind, object) = match Drift::describe(change) {
+                Some(described) => described,
+                None => continue,
+            };
+
+            // Modifications are sometimes made by dropping and adding an object again
+            if let Some(existing) = drift.iter_mut().find(|d| d.object.eq(&object)) {
+                if existing.kind != kind {
+                    existing.kind = DriftKind::Modified;
+                }
+                continue;
+            }
+            trace!(log, "Detected drift"; "object" => &object, "kind" => kind.to_string());
+            drift.push(Drift { kind, object });
+        }
+        Ok(drift)
+    }
+
+    fn profile() -> PublishProfile {
+        let mut profile = PublishProfile::default();
+        let options = &mut profile.generation_options;
+        options.always_recreate_database = false;
+        options.drop_enum_values = Toggle::Allow;
+        options.drop_tables = Toggle::Allow;
+        options.drop_partitions = Toggle::Allow;
+        options.drop_columns = Toggle::Allow;
+        options.drop_primary_key_constraints = Toggle::Allow;
+        options.drop_foreign_key_constraints = Toggle::Allow;
+        options.drop_unique_constraints = Toggle::Allow;
+        options.drop_check_constraints = Toggle::Allow;
+        options.drop_functions = Toggle::Allow;
+        options.drop_procedures = Toggle::Allow;
+        options.drop_indexes = Toggle::Allow;
+        options.drop_views = Toggle::Allow;
+        options.drop_triggers = Toggle::Allow;
+        options.drop_policies = Toggle::Allow;
+        options.revoke_privileges = Toggle::Allow;
+        // Roles are shared by every database within the cluster, so roles that the project doesn't declare aren't
+        // considered to be drift
+        options.drop_roles = Toggle::Ignore;
+        options.drop_schemas = Toggle::Allow;
+        options.drop_types = Toggle::Allow;
+        options.cascade_schema_drops = true;
+        // A newer extension being available isn't a change to the target
+        options.upgrade_extensions = Toggle::Ignore;
+        profile
+    }
+
+    // Describes the object affected by a change. Changes drop objects that were added outside of psqlpack
+    // and add objects that were removed outside of psqlpack.
+    fn describe(change: &ChangeInstruction) -> Option<(DriftKind, String)> {
+        use self::ChangeInstruction::*;
+
+        let described = match *change {
+            // Database level changes and scripts aren't part of the target definition
+            KillConnections(..) | DropDatabase(..) | CreateDatabase(..) | UseDatabase(..) | RunScript(..) => {
+                return None
+            }
+            // Constraints are validated immediately after being added
+            ValidateConstraint(..) => return None,
+
+            CreateExtension(ref name, _) => (DriftKind::Removed, format!("extension {}", name)),
+            UpgradeExtension(ref name, _) => (DriftKind::Modified, format!("extension {}", name)),
+            AddSchema(ref schema) => (DriftKind::Removed, format!("schema {}", schema.name)),
+            AlterSchemaOwner(ref name, _) => (DriftKind::Modified, format!("schema {}", name)),
+            DropSchema(ref name, _) => (DriftKind::Added, format!("schema {}", name)),
+            Rename(rename) => (DriftKind::Modified, format!("{} (rename not applied)", rename)),
+
+            AddSequence(sequence) => (DriftKind::Removed, format!("sequence {}", sequence.name)),
+            ModifySequence(sequence) | ModifySequenceOwner(sequence) => {
+                (DriftKind::Modified, format!("sequence {}", sequence.name))
+            }
+
+            AddType(ty) => (DriftKind::Removed, format!("type {}", ty.name)),
+            ModifyType(ty, ref action) => match *action {
+                TypeModificationAction::AddEnumValueBefore { ref value, .. }
+                | TypeModificationAction::AddEnumValueAfter { ref value, .. } => {
+                    (DriftKind::Removed, format!("enum value {} on type {}", value, ty.name))
+                }
+                TypeModificationAction::RemoveEnumValues { ref values, .. } => (
+                    DriftKind::Added,
+                    format!("enum values {} on type {}", values.join(", "), ty.name),
+                ),
+                TypeModificationAction::AddAttribute { ref name, .. } => {
+                    (DriftKind::Removed, format!("attribute {} on type {}", name, ty.name))
+                }
+                TypeModificationAction::AlterAttributeType { ref name, .. } => {
+                    (DriftKind::Modified, format!("attribute {} on type {}", name, ty.name))
+                }
+                TypeModificationAction::DropAttribute { ref name } => {
+                    (DriftKind::Added, format!("attribute {} on type {}", name, ty.name))
+                }
+                _ => (DriftKind::Modified, format!("type {}", ty.name)),
+            },
+            DropType(ref name) => (DriftKind::Added, format!("type {}", name)),
+
+            AddTable(table) => (DriftKind::Removed, format!("table {}", table.name)),
+            DropTable(ref name) | DropPartition(ref name) => (DriftKind::Added, format!("table {}", name)),
+            AttachPartition(table) => (DriftKind::Modified, format!("table {}", table.name)),
+            DetachPartition(ref name, _) => (DriftKind::Modified, format!("table {}", name)),
+
+            AddColumn(table, column) => (
+                DriftKind::Removed,
+                format!("column {} on table {}", column.name, table.name),
+            ),
+            ModifyColumnType(table, column)
+            | ModifyColumnNull(table, column)
+            | ModifyColumnDefault(table, column)
+            | AddColumnIdentity(table, column)
+            | ModifyColumnIdentity(table, column)
+            | DropColumnIdentity(table, column)
+            | ConvertSerialToIdentity(table, column)
+            | RecreateGeneratedColumn(table, column)
+            | DropColumnExpression(table, column)
+            | ModifyColumnUniqueConstraint(table, column)
+            | ModifyColumnPrimaryKeyConstraint(table, column) => (
+                DriftKind::Modified,
+                format!("column {} on table {}", column.name, table.name),
+            ),
+            DropColumn(table, ref name) => (DriftKind::Added, format!("column {} on table {}", name, table.name)),
+
+            AddConstraint(table, constraint, _) => (
+                DriftKind::Removed,
+                format!("constraint {} on table {}", constraint.name(), table.name),
+            ),
+            DropConstraint(table, ref name, _) => {
+                (DriftKind::Added, format!("constraint {} on table {}", name, table.name))
+            }
+
+            AddIndex(index, _) => (DriftKind::Removed, format!("index {}", index.fully_qualified_name())),
+            DropIndex(ref name, _) => (DriftKind::Added, format!("index {}", name)),
+
+            AddFunction(function) => (DriftKind::Removed, format!("function {}", function.signature())),
+            ModifyFunction(function) => (DriftKind::Modified, format!("function {}", function.signature())),
+            DropFunction(ref name) => (DriftKind::Added, format!("function {}", name)),
+            AddProcedure(procedure) => (DriftKind::Removed, format!("procedure {}", procedure.signature())),
+            ModifyProcedure(procedure) => (DriftKind::Modified, format!("procedure {}", procedure.signature())),
+            DropProcedure(ref name) => (DriftKind::Added, format!("procedure {}", name)),
+
+            AddView(view) => (DriftKind::Removed, format!("view {}", view.name)),
+            ReplaceView(view) => (DriftKind::Modified, format!("view {}", view.name)),
+            DropView(ref name) => (DriftKind::Added, format!("view {}", name)),
+            AddMaterializedView(view) => (DriftKind::Removed, format!("materialized view {}", view.name)),
+            ReplaceMaterializedView(view) => (DriftKind::Modified, format!("materialized view {}", view.name)),
+            DropMaterializedView(ref name) => (DriftKind::Added, format!("materialized view {}", name)),
+
+            AddTrigger(trigger) => (
+                DriftKind::Removed,
+                format!("trigger {} on table {}", trigger.name, trigger.table),
+            ),
+            ReplaceTrigger(trigger) => (
+                DriftKind::Modified,
+                format!("trigger {} on table {}", trigger.name, trigger.table),
+            ),
+            DropTrigger(ref name, ref table) => (DriftKind::Added, format!("trigger {} on table {}", name, table)),
+
+            ModifyRowSecurity(table) | ModifyForceRowSecurity(table) => {
+                (DriftKind::Modified, format!("table {}", table.name))
+            }
+            AddPolicy(policy) => (
+                DriftKind::Removed,
+                format!("policy {} on table {}", policy.name, policy.table),
+            ),
+            ModifyPolicy(policy) | ReplacePolicy(policy) => (
+                DriftKind::Modified,
+                format!("policy {} on table {}", policy.name, policy.table),
+            ),
+            DropPolicy(ref name, ref table) => (DriftKind::Added, format!("policy {} on table {}", name, table)),
+
+            Grant(ref grant) => (
+                DriftKind::Removed,
+                format!(
+                    "privileges of {} on {} {}",
+                    grant.grantee,
+                    grant.object.kind(),
+                    grant.object.name()
+                ),
+            ),
+            Revoke(ref revocation) => (
+                DriftKind::Added,
+                format!(
+                    "privileges of {} on {} {}",
+                    revocation.grantee,
+                    revocation.object.kind(),
+                    revocation.object.name()
+                ),
+            ),
+
+            SetComment(ref object, _) => (
+                DriftKind::Modified,
+                format!("comment on {} {}", object.kind(), object.name()),
+            ),
+
+            CreateRole(ref role) => (DriftKind::Removed, format!("role {}", role.name)),
+            AlterRole(ref role) => (DriftKind::Modified, format!("role {}", role.name)),
+            DropRole(ref name) => (DriftKind::Added, format!("role {}", name)),
+            GrantRole(ref group, ref member) => (DriftKind::Removed, format!("membership of {} in {}", member, group)),
+            RevokeRole(ref group, ref member) => (DriftKind::Added, format!("membership of {} in {}", member, group)),
+        };
+        Some(described)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::*;
+    use crate::sql::ast::*;
+    use crate::Semver;
+
+    use slog::{Discard, Drain, Logger};
+
+    fn empty_logger() -> Logger {
+        Logger::root(Discard.fuse(), o!())
+    }
+
+    fn object_name(name: &str) -> ObjectName {
+        ObjectName {
+            schema: Some("my".to_owned()),
+            name: name.to_owned(),
+        }
+    }
+
+    fn column(name: &str, sql_type: SimpleSqlType) -> ColumnDefinition {
+        ColumnDefinition {
+            name: name.to_owned(),
+            sql_type: SqlType::Simple(sql_type, None),
+            constraints: vec![ColumnConstraint::NotNull],
+            comment: None,
+        }
+    }
+
+    fn deployed_package(body: &str) -> Package {
+        let mut package = Package::new();
+        package.tables.push(TableDefinition {
+            name: object_name("contacts"),
+            columns: vec![
+                column("id", SimpleSqlType::BigInteger),
+                column("first_name", SimpleSqlType::VariableLengthString(100)),
+            ],
+            constraints: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            row_security: false,
+            force_row_security: false,
+            comment: None,
+        });
+        package.indexes.push(IndexDefinition {
+            name: "idx_contacts_first_name".to_owned(),
+            table: object_name("contacts"),
+            columns: vec![IndexColumn {
+                name: "first_name".to_owned(),
+                order: None,
+                null_position: None,
+            }],
+            unique: false,
+            index_type: None,
+            storage_parameters: None,
+            comment: None,
+        });
+        package.functions.push(FunctionDefinition {
+            name: object_name("contact_count"),
+            arguments: Vec::new(),
+            return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::BigInteger, None)),
+            body: body.to_owned(),
+            language: FunctionLanguage::SQL,
+            volatility: FunctionVolatility::Volatile,
+            strict: false,
+            security_definer: false,
+            parallel: FunctionParallel::Unsafe,
+            cost: None,
+            rows: None,
+            settings: Vec::new(),
+            comment: None,
+        });
+        package
+    }
+
+    #[test]
+    fn it_describes_changes_made_to_the_target_outside_of_psqlpack() {
+        let log = empty_logger();
+        let package = deployed_package("SELECT COUNT(*) FROM my.contacts");
+
+        // A hotfix added a table and column, widened a column and dropped an index
+        let mut target = deployed_package("\n  SELECT COUNT(*)\n  FROM my.contacts\n");
+        target.tables[0].columns[1].sql_type = SqlType::Simple(SimpleSqlType::VariableLengthString(200), None);
+        target.tables[0]
+            .columns
+            .push(column("nickname", SimpleSqlType::VariableLengthString(50)));
+        target.tables.push(TableDefinition {
+            name: object_name("hotfix"),
+            columns: vec![column("id", SimpleSqlType::BigInteger)],
+            constraints: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            row_security: false,
+            force_row_security: false,
+            comment: None,
+        });
+        target.indexes.clear();
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let result = Drift::detect(&log, &package, target, "dbname", &capabilities);
+        assert!(result.is_ok());
+        let drift = result.unwrap();
+        assert_eq!(
+            drift,
+            vec![
+                Drift {
+                    kind: DriftKind::Added,
+                    object: "table my.hotfix".to_owned(),
+                },
+                Drift {
+                    kind: DriftKind::Added,
+                    object: "column nickname on table my.contacts".to_owned(),
+                },
+                Drift {
+                    kind: DriftKind::Modified,
+                    object: "column first_name on table my.contacts".to_owned(),
+                },
+                Drift {
+                    kind: DriftKind::Removed,
+                    object: "index my.idx_contacts_first_name".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_detects_no_drift_when_the_target_matches_the_package() {
+        let log = empty_logger();
+        let package = deployed_package("SELECT COUNT(*) FROM my.contacts");
+        let target = deployed_package("SELECT COUNT(*) FROM my.contacts");
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let drift = Drift::detect(&log, &package, target, "dbname", &capabilities).unwrap();
+        assert!(drift.is_empty());
+
+        // A modified function body is reported as a modified function
+        let target = deployed_package("SELECT COUNT(*) + 1 FROM my.contacts");
+        let drift = Drift::detect(&log, &package, target, "dbname", &capabilities).unwrap();
+        assert_eq!(
+            drift,
+            vec![Drift {
+                kind: DriftKind::Modified,
+                object: "function my.contact_count()".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_ignores_roles_that_the_project_doesnt_declare() {
+        let log = empty_logger();
+        let role = |name: &str| RoleDefinition {
+            name: name.to_owned(),
+            login: false,
+            superuser: false,
+            create_db: false,
+            create_role: false,
+            inherit: true,
+            replication: false,
+            bypass_rls: false,
+            connection_limit: -1,
+            member_of: Vec::new(),
+        };
+        let mut package = deployed_package("SELECT COUNT(*) FROM my.contacts");
+        package.roles.push(role("app"));
+
+        // Other roles within the cluster, even those that psqlpack created for another project, aren't drift
+        let mut target = deployed_package("SELECT COUNT(*) FROM my.contacts");
+        target.roles.push(role("app"));
+        target.roles.push(role("reporting"));
+        target.roles.push(role("rds_admin"));
+        target.managed_roles.push("reporting".to_owned());
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+
+        let drift = Drift::detect(&log, &package, target, "dbname", &capabilities).unwrap();
+        assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+    }
+}
@@ -25,22 +25,43 @@ impl Extension {
         let context = capabilities.with_context(self);
         let schemas = context.schemata(&mut client, connection.database())?;
         let types = context.types(&mut client)?;
+        let sequences = context.sequences(&mut client)?;
         let functions = context.functions(&mut client)?;
+        let procedures = context.procedures(&mut client)?;
         let tables = context.tables(&mut client)?;
         let indexes = context.indexes(&mut client)?;
+        let views = context.views(&mut client)?;
+        let materialized_views = context.materialized_views(&mut client)?;
+        let triggers = context.triggers(&mut client)?;
+        let policies = context.policies(&mut client)?;
+        let grants = context.grants(&mut client)?;
 
         let mut package = Package {
             meta,
             extensions: Vec::new(),
             functions,
+            grants,
             indexes,
+            materialized_views,
+            policies,
+            procedures,
+            renames: Vec::new(), // Renames only apply to project deployments
+            roles: Vec::new(),   // Roles are global to the cluster rather than part of an extension
             schemas,
             scripts: Vec::new(),
+            sequences,
             tables,
+            triggers,
             types,
+            views,
+            alterations: Vec::new(),
+            revocations: Vec::new(),
+            comments: Vec::new(),
+            managed_roles: Vec::new(),
+            type_dependents: Vec::new(),
         };
 
-        package.promote_primary_keys_to_table_constraints();
+        package.promote_column_constraints_to_table_constraints();
         Ok(package)
     }
 }
@@ -0,0 +1,188 @@
+//! Every publish is recorded within the `psqlpack.__deployments` table of the target database so that it is
+//! possible to tell which package was deployed, when it was deployed and by whom.
+
+use std::fmt;
+
+use chrono::prelude::*;
+use postgres::row::Row;
+use postgres::GenericClient;
+use serde::Serialize;
+use slog::Logger;
+
+use crate::connection::Connection;
+use crate::errors::PsqlpackErrorKind::*;
+use crate::errors::{PsqlpackResult, PsqlpackResultExt};
+use crate::model::{Package, PublishProfile};
+use crate::semver::Semver;
+
+/// Describes the package and publish profile being deployed.
+#[derive(Debug)]
+pub struct DeploymentSummary {
+    pub package_hash: String,
+    pub package_version: Semver,
+    pub package_source: String,
+    pub profile: String,
+}
+
+impl DeploymentSummary {
+    pub fn new(package: &Package, publish_profile: &PublishProfile) -> PsqlpackResult<DeploymentSummary> {
+        let profile = serde_json::to_string(&publish_profile.generation_options)
+            .chain_err(|| GenerationError("Failed to summarize publish profile".into()))?;
+        Ok(DeploymentSummary {
+            package_hash: package.hash()?,
+            package_version: package.meta.version(),
+            package_source: package.meta.source().to_string(),
+            profile,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub enum DeploymentStatus {
+    InProgress,
+    Succeeded,
+    Failed,
+}
+
+impl DeploymentStatus {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            DeploymentStatus::InProgress => "in_progress",
+            DeploymentStatus::Succeeded => "succeeded",
+            DeploymentStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for DeploymentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A deployment that has been recorded against a target database.
+#[derive(Debug, Serialize)]
+pub struct Deployment {
+    pub id: i64,
+    pub package_hash: String,
+    pub package_version: String,
+    pub package_source: String,
+    pub profile: serde_json::Value,
+    pub deployed_by: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub status: DeploymentStatus,
+    pub script: String,
+}
+
+impl Deployment {
+    /// Lists the deployments recorded against the target database, most recent first.
+    pub fn history(log: &Logger, connection: &Connection) -> PsqlpackResult<Vec<Deployment>> {
+        let log = log.new(o!("history" => "list"));
+
+        trace!(log, "Connecting to database");
+        let mut client = connection.connect_database()?;
+        let exists = client
+            .query(Q_DEPLOYMENTS_EXISTS, &[])
+            .chain_err(|| QueryDeploymentsError)?
+            .iter()
+            .any(|row| row.get(0));
+        if !exists {
+            trace!(log, "No deployments have been recorded");
+            return Ok(Vec::new());
+        }
+
+        let deployments = client
+            .query(Q_DEPLOYMENTS, &[])
+            .chain_err(|| QueryDeploymentsError)?
+            .iter()
+            .map(|row| row.into())
+            .collect();
+        Ok(deployments)
+    }
+
+    pub(crate) fn start<C: GenericClient>(
+        client: &mut C,
+        summary: &DeploymentSummary,
+        script: &str,
+    ) -> PsqlpackResult<i64> {
+        client
+            .batch_execute(CREATE_DEPLOYMENTS)
+            .chain_err(|| RecordDeploymentError)?;
+        let row = client
+            .query_one(
+                INSERT_DEPLOYMENT,
+                &[
+                    &summary.package_hash,
+                    &summary.package_version.to_string(),
+                    &summary.package_source,
+                    &summary.profile,
+                    &DeploymentStatus::InProgress.as_str(),
+                    &script,
+                ],
+            )
+            .chain_err(|| RecordDeploymentError)?;
+        Ok(row.get(0))
+    }
+
+    pub(crate) fn finish<C: GenericClient>(client: &mut C, id: i64, status: DeploymentStatus) -> PsqlpackResult<()> {
+        client
+            .execute(UPDATE_DEPLOYMENT, &[&id, &status.as_str()])
+            .chain_err(|| RecordDeploymentError)?;
+        Ok(())
+    }
+}
+
+impl From<&Row> for Deployment {
+    fn from(row: &Row) -> Self {
+        let status: String = row.get(8);
+        Deployment {
+            id: row.get(0),
+            package_hash: row.get(1),
+            package_version: row.get(2),
+            package_source: row.get(3),
+            profile: row.get(4),
+            deployed_by: row.get(5),
+            started_at: row.get(6),
+            finished_at: row.get(7),
+            status: match &status[..] {
+                "succeeded" => DeploymentStatus::Succeeded,
+                "failed" => DeploymentStatus::Failed,
+                _ => DeploymentStatus::InProgress,
+            },
+            script: row.get(9),
+        }
+    }
+}
+
+// The `psqlpack` schema is reserved and excluded from all catalog queries
+static CREATE_DEPLOYMENTS: &str = "
+    CREATE SCHEMA IF NOT EXISTS psqlpack;
+    CREATE TABLE IF NOT EXISTS psqlpack.__deployments (
+        id bigserial PRIMARY KEY,
+        package_hash text NOT NULL,
+        package_version text NOT NULL,
+        package_source text NOT NULL,
+        profile jsonb NOT NULL,
+        deployed_by text NOT NULL DEFAULT current_user,
+        started_at timestamptz NOT NULL DEFAULT clock_timestamp(),
+        finished_at timestamptz,
+        status text NOT NULL,
+        script text NOT NULL
+    );";
+
+static INSERT_DEPLOYMENT: &str = "
+    INSERT INTO psqlpack.__deployments (package_hash, package_version, package_source, profile, status, script)
+    VALUES ($1, $2, $3, $4::text::jsonb, $5, $6)
+    RETURNING id";
+
+static UPDATE_DEPLOYMENT: &str = "
+    UPDATE psqlpack.__deployments SET status = $2, finished_at = clock_timestamp() WHERE id = $1";
+
+static Q_DEPLOYMENTS_EXISTS: &str = "SELECT to_regclass('psqlpack.__deployments') IS NOT NULL";
+
+static Q_DEPLOYMENTS: &str = "
+    SELECT id, package_hash, package_version, package_source, profile, deployed_by,
+           started_at, finished_at, status, script
+    FROM psqlpack.__deployments
+    ORDER BY id DESC";
@@ -9,15 +9,22 @@ macro_rules! dbtry {
 
 mod capabilities;
 mod delta;
+mod drift;
 mod extension;
+mod history;
 mod package;
 mod profiles;
 mod project;
+mod report;
 pub mod template;
 
 pub use self::capabilities::{Capabilities, DefinableCatalog};
 pub use self::delta::Delta;
+pub use self::drift::{Drift, DriftKind};
 pub use self::extension::Extension;
+pub use self::history::{Deployment, DeploymentStatus, DeploymentSummary};
 pub use self::package::{MetaInfo, Node, Package, SourceInfo, ValidationKind};
+pub(crate) use self::package::{TypeDependent, TypeDependentKind};
 pub use self::profiles::{GenerationOptions, PublishProfile, Toggle};
 pub use self::project::{Dependency, Project};
+pub use self::report::{Report, ReportChange, ReportFormat, ReportObject, ReportSummary, Risk, REPORT_VERSION};
@@ -6,7 +6,9 @@ use std::str::FromStr;
 
 use chrono::prelude::*;
 use glob::glob;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use slog::Logger;
 use zip::write::FileOptions;
 use zip::{ZipArchive, ZipWriter};
@@ -14,7 +16,7 @@ use zip::{ZipArchive, ZipWriter};
 use crate::connection::Connection;
 use crate::errors::PsqlpackErrorKind::*;
 use crate::errors::{PsqlpackResult, PsqlpackResultExt};
-use crate::model::{Capabilities, DefinableCatalog, Dependency, Project};
+use crate::model::{Capabilities, DefinableCatalog, Dependency, Drift, Project};
 use crate::semver::Semver;
 use crate::sql::ast::*;
 
@@ -29,11 +31,14 @@ macro_rules! ztry {
 
 macro_rules! zip_collection {
     ($zip:ident, $package:ident, $collection:ident) => {{
+        zip_collection!($zip, $package, $collection, name)
+    }};
+    ($zip:ident, $package:ident, $collection:ident, $($file_name:tt)+) => {{
         let collection_name = stringify!($collection);
         ztry!($zip.add_directory(format!("{}/", collection_name), FileOptions::default()));
         for item in &$package.$collection {
             ztry!($zip.start_file(
-                format!("{}/{}.json", collection_name, item.name),
+                format!("{}/{}.json", collection_name, item.$($file_name)+),
                 FileOptions::default()
             ));
             let json = match serde_json::to_string_pretty(&item) {
@@ -53,11 +58,48 @@ pub struct Package {
     pub meta: MetaInfo,
     pub extensions: Vec<Dependency>,
     pub functions: Vec<FunctionDefinition>,
+    pub grants: Vec<GrantDefinition>,
     pub indexes: Vec<IndexDefinition>,
+    pub materialized_views: Vec<MaterializedViewDefinition>,
+    pub policies: Vec<PolicyDefinition>,
+    pub procedures: Vec<ProcedureDefinition>,
+    pub renames: Vec<RenameDefinition>,
+    pub roles: Vec<RoleDefinition>,
     pub schemas: Vec<SchemaDefinition>,
     pub scripts: Vec<ScriptDefinition>,
+    pub sequences: Vec<SequenceDefinition>,
     pub tables: Vec<TableDefinition>,
+    pub triggers: Vec<TriggerDefinition>,
     pub types: Vec<TypeDefinition>,
+    pub views: Vec<ViewDefinition>,
+    // Table alterations within a project are applied to the tables once the project is loaded
+    pub(crate) alterations: Vec<(ObjectName, AlterTableAction)>,
+    // Likewise, revocations within a project remove privileges from the declared grants
+    pub(crate) revocations: Vec<GrantDefinition>,
+    // Comments within a project are attached to the objects they describe once the project is loaded
+    pub(crate) comments: Vec<CommentDefinition>,
+    // Roles that psqlpack created within a target database, which are the only roles it will drop
+    pub(crate) managed_roles: Vec<String>,
+    // Views and routines within a target database that depend on an enum, which need dropping to rebuild it
+    pub(crate) type_dependents: Vec<TypeDependent>,
+}
+
+// An object that depends on a type, either directly or through other dependents. The depth is the length of the
+// longest chain back to the type so that the deepest dependents can be dropped first.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TypeDependent {
+    pub type_name: ObjectName,
+    pub kind: TypeDependentKind,
+    pub name: String,
+    pub depth: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TypeDependentKind {
+    View,
+    MaterializedView,
+    Function,
+    Procedure,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -78,6 +120,14 @@ impl MetaInfo {
             publishable,
         }
     }
+
+    pub fn version(&self) -> Semver {
+        self.version
+    }
+
+    pub fn source(&self) -> &SourceInfo {
+        &self.source
+    }
 }
 
 fn crate_version() -> Semver {
@@ -109,7 +159,7 @@ impl fmt::Display for SourceInfo {
 
 impl Package {
     fn maybe_packaged_file(source_path: &Path) -> PsqlpackResult<bool> {
-        File::open(&source_path)
+        File::open(source_path)
             .chain_err(|| IOError(source_path.to_str().unwrap().into(), "Failed to open file".into()))
             .and_then(|file| {
                 let mut reader = BufReader::with_capacity(4, file);
@@ -146,18 +196,27 @@ impl Package {
 
     pub fn from_packaged_file(log: &Logger, source_path: &Path) -> PsqlpackResult<Package> {
         let _log = log.new(o!("package" => "from_packaged_file"));
-        let mut archive = File::open(&source_path)
+        let mut archive = File::open(source_path)
             .chain_err(|| PackageReadError(source_path.to_path_buf()))
             .and_then(|file| ZipArchive::new(file).chain_err(|| PackageUnarchiveError(source_path.to_path_buf())))?;
 
         let mut meta: Option<MetaInfo> = None;
         let mut extensions = Vec::new();
         let mut functions = Vec::new();
+        let mut grants = Vec::new();
         let mut indexes = Vec::new();
+        let mut materialized_views = Vec::new();
+        let mut policies = Vec::new();
+        let mut procedures = Vec::new();
+        let mut renames = Vec::new();
+        let mut roles = Vec::new();
         let mut schemas = Vec::new();
         let mut scripts = Vec::new();
+        let mut sequences = Vec::new();
         let mut tables = Vec::new();
+        let mut triggers = Vec::new();
         let mut types = Vec::new();
+        let mut views = Vec::new();
 
         for i in 0..archive.len() {
             let file = archive.by_index(i).unwrap();
@@ -171,20 +230,40 @@ impl Package {
                 }
                 let m = serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?;
                 meta = Some(m);
+            } else if name.starts_with("grants") {
+                // Grants are only unique per object, grantee and grant option so are kept together within the one file
+                grants = serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?;
+            } else if name.starts_with("renames") {
+                // Renames are applied in order so are kept together within the one file
+                renames = serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?;
             } else if name.starts_with("extensions/") {
                 extensions.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("functions/") {
                 functions.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("indexes") {
                 indexes.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("materialized_views/") {
+                materialized_views.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("policies/") {
+                policies.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("procedures/") {
+                procedures.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("roles/") {
+                roles.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("schemas/") {
                 schemas.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("scripts/") {
                 scripts.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("sequences/") {
+                sequences.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("tables/") {
                 tables.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("triggers/") {
+                triggers.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             } else if name.starts_with("types/") {
                 types.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
+            } else if name.starts_with("views/") {
+                views.push(serde_json::from_reader(file).chain_err(|| PackageInternalReadError(name))?);
             }
         }
 
@@ -197,13 +276,27 @@ impl Package {
             },
             extensions,
             functions,
+            grants,
             indexes,
+            materialized_views,
+            policies,
+            procedures,
+            renames,
+            roles,
             schemas,
             scripts,
+            sequences,
             tables,
+            triggers,
             types,
+            views,
+            alterations: Vec::new(),
+            revocations: Vec::new(),
+            comments: Vec::new(),
+            managed_roles: Vec::new(),
+            type_dependents: Vec::new(),
         };
-        package.promote_primary_keys_to_table_constraints();
+        package.promote_column_constraints_to_table_constraints();
         Ok(package)
     }
 
@@ -236,21 +329,46 @@ impl Package {
         // TODO: Refactor connection so we only need to pass through that
         let schemas = capabilities.schemata(&mut client, connection.database())?;
         let types = capabilities.types(&mut client)?;
+        let sequences = capabilities.sequences(&mut client)?;
         let functions = capabilities.functions(&mut client)?;
+        let procedures = capabilities.procedures(&mut client)?;
         let tables = capabilities.tables(&mut client)?;
         let indexes = capabilities.indexes(&mut client)?;
+        let views = capabilities.views(&mut client)?;
+        let materialized_views = capabilities.materialized_views(&mut client)?;
+        let triggers = capabilities.triggers(&mut client)?;
+        let policies = capabilities.policies(&mut client)?;
+        let grants = capabilities.grants(&mut client)?;
+        let renames = capabilities.renames(&mut client)?;
+        let roles = capabilities.roles(&mut client)?;
+        let managed_roles = capabilities.managed_roles(&mut client)?;
+        let type_dependents = capabilities.type_dependents(&mut client)?;
 
         let mut package = Package {
             meta: MetaInfo::new(SourceInfo::Database),
             extensions,
             functions,
+            grants,
             indexes,
+            materialized_views,
+            policies,
+            procedures,
+            renames,
+            roles,
             schemas,
             scripts: Vec::new(), // Scripts can't be known from a connection
+            sequences,
             tables,
+            triggers,
             types,
+            views,
+            alterations: Vec::new(),
+            revocations: Vec::new(),
+            comments: Vec::new(),
+            managed_roles,
+            type_dependents,
         };
-        package.promote_primary_keys_to_table_constraints();
+        package.promote_column_constraints_to_table_constraints();
 
         Ok(Some(package))
     }
@@ -263,7 +381,7 @@ impl Package {
             }
         }
 
-        File::create(&destination)
+        File::create(destination)
             .chain_err(|| GenerationError("Failed to write package".to_owned()))
             .and_then(|output_file| {
                 let mut zip = ZipWriter::new(output_file);
@@ -274,13 +392,39 @@ impl Package {
                     Err(e) => bail!(GenerationError(format!("Failed to write package: {}", e))),
                 };
                 ztry!(zip.write_all(json.as_bytes()));
+                ztry!(zip.start_file("renames.json", FileOptions::default()));
+                let json = match serde_json::to_string_pretty(&self.renames) {
+                    Ok(j) => j,
+                    Err(e) => bail!(GenerationError(format!("Failed to write package: {}", e))),
+                };
+                ztry!(zip.write_all(json.as_bytes()));
+                ztry!(zip.start_file("grants.json", FileOptions::default()));
+                let json = match serde_json::to_string_pretty(&self.grants) {
+                    Ok(j) => j,
+                    Err(e) => bail!(GenerationError(format!("Failed to write package: {}", e))),
+                };
+                ztry!(zip.write_all(json.as_bytes()));
                 zip_collection!(zip, self, extensions);
-                zip_collection!(zip, self, functions);
+                // Overloaded functions share the same name
+                zip_collection!(zip, self, functions, signature());
                 zip_collection!(zip, self, indexes);
+                zip_collection!(zip, self, materialized_views);
+                // Policy names are only unique per table
+                zip_collection!(zip, self, policies, fully_qualified_name());
+                zip_collection!(zip, self, procedures, signature());
+                // Roles are shared across the cluster so those extracted from a database are only used to compare
+                // against, otherwise publishing the package would manage every role within the cluster
+                if !matches!(self.meta.source, SourceInfo::Database) {
+                    zip_collection!(zip, self, roles);
+                }
                 zip_collection!(zip, self, schemas);
                 zip_collection!(zip, self, scripts);
+                zip_collection!(zip, self, sequences);
                 zip_collection!(zip, self, tables);
+                // Trigger names are only unique per table
+                zip_collection!(zip, self, triggers, fully_qualified_name());
                 zip_collection!(zip, self, types);
+                zip_collection!(zip, self, views);
 
                 ztry!(zip.finish());
 
@@ -288,20 +432,86 @@ impl Package {
             })
     }
 
+    /// Compares the package with the target database, describing any objects that have been added, removed or
+    /// modified within the target outside of psqlpack.
+    pub fn compare(&self, log: &Logger, connection: &Connection) -> PsqlpackResult<Vec<Drift>> {
+        let log = log.new(o!("package" => "compare"));
+
+        trace!(log, "Loading Server Capabilities");
+        let capabilities = Capabilities::from_connection(&log, connection)?;
+
+        trace!(log, "Loading Package from connection");
+        match Package::from_connection(&log, connection, &capabilities)? {
+            Some(target) => Drift::detect(&log, self, target, connection.database(), &capabilities),
+            None => bail!(DatabaseError(format!(
+                "Database {} does not exist",
+                connection.database()
+            ))),
+        }
+    }
+
+    // A digest of the package contents, excluding the meta information, so that deployments can be identified
+    pub fn hash(&self) -> PsqlpackResult<String> {
+        let mut hasher = Sha256::new();
+        macro_rules! hash_collection {
+            ($collection:ident) => {{
+                let json = serde_json::to_vec(&self.$collection)
+                    .chain_err(|| GenerationError("Failed to hash package".to_owned()))?;
+                hasher.update(stringify!($collection).as_bytes());
+                hasher.update(&json);
+            }};
+        }
+        hash_collection!(extensions);
+        hash_collection!(functions);
+        hash_collection!(grants);
+        hash_collection!(indexes);
+        hash_collection!(materialized_views);
+        hash_collection!(policies);
+        hash_collection!(procedures);
+        hash_collection!(renames);
+        hash_collection!(roles);
+        hash_collection!(schemas);
+        hash_collection!(scripts);
+        hash_collection!(sequences);
+        hash_collection!(tables);
+        hash_collection!(triggers);
+        hash_collection!(types);
+        hash_collection!(views);
+        Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
     pub fn new() -> Self {
         Package {
             // By default, our source is a project file
             meta: MetaInfo::new(SourceInfo::Project),
             extensions: Vec::new(),
             functions: Vec::new(),
+            grants: Vec::new(),
             indexes: Vec::new(),
+            materialized_views: Vec::new(),
+            policies: Vec::new(),
+            procedures: Vec::new(),
+            renames: Vec::new(),
+            roles: Vec::new(),
             schemas: Vec::new(),
             scripts: Vec::new(),
+            sequences: Vec::new(),
             tables: Vec::new(),
+            triggers: Vec::new(),
             types: Vec::new(),
+            views: Vec::new(),
+            alterations: Vec::new(),
+            revocations: Vec::new(),
+            comments: Vec::new(),
+            managed_roles: Vec::new(),
+            type_dependents: Vec::new(),
         }
     }
 
+    pub fn push_comment(&mut self, comment: CommentDefinition) {
+        self.comments.push(comment);
+    }
+
     pub fn push_extension(&mut self, extension: Dependency) {
         self.extensions.push(extension);
     }
@@ -310,10 +520,38 @@ impl Package {
         self.functions.push(function);
     }
 
+    pub fn push_procedure(&mut self, procedure: ProcedureDefinition) {
+        self.procedures.push(procedure);
+    }
+
+    pub fn push_grant(&mut self, grant: GrantDefinition) {
+        self.grants.push(grant);
+    }
+
     pub fn push_index(&mut self, index: IndexDefinition) {
         self.indexes.push(index);
     }
 
+    pub fn push_materialized_view(&mut self, view: MaterializedViewDefinition) {
+        self.materialized_views.push(view);
+    }
+
+    pub fn push_policy(&mut self, policy: PolicyDefinition) {
+        self.policies.push(policy);
+    }
+
+    pub fn push_revocation(&mut self, revocation: GrantDefinition) {
+        self.revocations.push(revocation);
+    }
+
+    pub fn push_rename(&mut self, rename: RenameDefinition) {
+        self.renames.push(rename);
+    }
+
+    pub fn push_role(&mut self, role: RoleDefinition) {
+        self.roles.push(role);
+    }
+
     pub fn push_script(&mut self, script: ScriptDefinition) {
         self.scripts.push(script);
     }
@@ -322,14 +560,30 @@ impl Package {
         self.schemas.push(schema);
     }
 
+    pub fn push_sequence(&mut self, sequence: SequenceDefinition) {
+        self.sequences.push(sequence);
+    }
+
     pub fn push_table(&mut self, table: TableDefinition) {
         self.tables.push(table);
     }
 
+    pub fn push_table_alteration(&mut self, table: ObjectName, action: AlterTableAction) {
+        self.alterations.push((table, action));
+    }
+
+    pub fn push_trigger(&mut self, trigger: TriggerDefinition) {
+        self.triggers.push(trigger);
+    }
+
     pub fn push_type(&mut self, def: TypeDefinition) {
         self.types.push(def);
     }
 
+    pub fn push_view(&mut self, view: ViewDefinition) {
+        self.views.push(view);
+    }
+
     pub fn set_defaults(&mut self, project: &Project) {
         // Make sure the public schema exists
         let mut has_public = false;
@@ -342,12 +596,63 @@ impl Package {
         if !has_public {
             self.schemas.push(SchemaDefinition {
                 name: project.default_schema.to_owned(),
+                owner: None,
+                comment: None,
             });
         }
+        fn ensure_custom_type_schema(sql_type: &mut SqlType, default_schema: &str) {
+            if let SqlType::Custom(ref mut custom_type, ref _opts, _dim) = *sql_type {
+                if custom_type.schema.is_none() {
+                    custom_type.schema = Some(default_schema.to_owned());
+                }
+            }
+        }
+
         for typ in &mut self.types {
             if typ.name.schema.is_none() {
                 typ.name.schema = Some(project.default_schema.clone());
             }
+            match typ.kind {
+                TypeDefinitionKind::Composite(ref mut attributes) => {
+                    for attribute in attributes {
+                        ensure_custom_type_schema(&mut attribute.sql_type, &project.default_schema);
+                    }
+                }
+                TypeDefinitionKind::Domain(ref mut domain) => {
+                    ensure_custom_type_schema(&mut domain.sql_type, &project.default_schema);
+
+                    // Name check constraints the same way Postgres does so that they can be matched
+                    let unnamed = domain.constraints.iter_mut().filter(|c| c.name.is_none());
+                    for (index, constraint) in unnamed.enumerate() {
+                        constraint.name = Some(if index == 0 {
+                            format!("{}_check", typ.name.name)
+                        } else {
+                            format!("{}_check{}", typ.name.name, index)
+                        });
+                    }
+                }
+                TypeDefinitionKind::Range(ref mut range) => {
+                    ensure_custom_type_schema(&mut range.subtype, &project.default_schema);
+                }
+                TypeDefinitionKind::Enum(_) | TypeDefinitionKind::UserDefined => {}
+            }
+        }
+
+        for rename in &mut self.renames {
+            rename.qualify(&project.default_schema);
+        }
+
+        // Set missing schema's and default options for sequences
+        for sequence in &mut self.sequences {
+            if sequence.name.schema.is_none() {
+                sequence.name.schema = Some(project.default_schema.clone());
+            }
+            if let Some(ref mut owner) = sequence.owned_by {
+                if owner.table.schema.is_none() {
+                    owner.table.schema = Some(project.default_schema.clone());
+                }
+            }
+            sequence.set_defaults();
         }
 
         fn ensure_not_null_column(column: &mut ColumnDefinition) {
@@ -369,6 +674,11 @@ impl Package {
             if table.name.schema.is_none() {
                 table.name.schema = Some(project.default_schema.clone());
             }
+            if let Some(ref mut partition_of) = table.partition_of {
+                if partition_of.parent.schema.is_none() {
+                    partition_of.parent.schema = Some(project.default_schema.clone());
+                }
+            }
 
             for constraint in table.constraints.iter_mut() {
                 match *constraint {
@@ -385,6 +695,12 @@ impl Package {
                             ref_table.schema = Some(project.default_schema.clone());
                         }
                     }
+                    TableConstraint::Exclude { ref mut index_type, .. } => {
+                        if index_type.is_none() {
+                            *index_type = Some(IndexType::BTree);
+                        }
+                    }
+                    TableConstraint::Unique { .. } | TableConstraint::Check { .. } => {}
                 }
             }
 
@@ -399,6 +715,17 @@ impl Package {
                     ensure_not_null_column(column);
                 }
 
+                // Identity columns are implicitly not null and take their sequence options from the column type
+                let sql_type = column.sql_type.clone();
+                let identity = column.constraints.iter_mut().find_map(|c| match c {
+                    ColumnConstraint::Identity(identity) => Some(identity),
+                    _ => None,
+                });
+                if let Some(identity) = identity {
+                    identity.set_defaults(&sql_type);
+                    ensure_not_null_column(column);
+                }
+
                 // Also, if the type is custom, then assume the default search path
                 if let SqlType::Custom(ref mut custom_type, ref _opts, _dim) = column.sql_type {
                     if custom_type.schema.is_none() {
@@ -436,11 +763,114 @@ impl Package {
             }
         }
 
+        // Set missing schema's for functions and procedures
+        for function in &mut self.functions {
+            if function.name.schema.is_none() {
+                function.name.schema = Some(project.default_schema.clone());
+            }
+        }
+        for procedure in &mut self.procedures {
+            if procedure.name.schema.is_none() {
+                procedure.name.schema = Some(project.default_schema.clone());
+            }
+        }
+
+        // Set missing schema's for views
+        for view in &mut self.views {
+            if view.name.schema.is_none() {
+                view.name.schema = Some(project.default_schema.clone());
+            }
+        }
+        for view in &mut self.materialized_views {
+            if view.name.schema.is_none() {
+                view.name.schema = Some(project.default_schema.clone());
+            }
+        }
+
+        // Set missing schema's for triggers
+        for trigger in &mut self.triggers {
+            if trigger.table.schema.is_none() {
+                trigger.table.schema = Some(project.default_schema.clone());
+            }
+            if trigger.function.schema.is_none() {
+                trigger.function.schema = Some(project.default_schema.clone());
+            }
+        }
+
+        // Set missing schema's for policies
+        for policy in &mut self.policies {
+            if policy.table.schema.is_none() {
+                policy.table.schema = Some(project.default_schema.clone());
+            }
+        }
+
+        // Set missing schema's for grants and revocations before combining them
+        let qualify = |grant: &mut GrantDefinition| match grant.object {
+            GrantObject::Table(ref mut name)
+            | GrantObject::Sequence(ref mut name)
+            | GrantObject::Function(ref mut name, _) => {
+                if name.schema.is_none() {
+                    name.schema = Some(project.default_schema.clone());
+                }
+            }
+            GrantObject::Schema(_) => {}
+        };
+        self.grants.iter_mut().for_each(qualify);
+        let mut revocations = std::mem::take(&mut self.revocations);
+        revocations.iter_mut().for_each(qualify);
+        // Functions named without their argument types are keyed by the signature of the function they refer to
+        let functions = &self.functions;
+        for grant in self.grants.iter_mut().chain(revocations.iter_mut()) {
+            if let GrantObject::Function(ref name, ref mut argument_types @ None) = grant.object {
+                let mut named = functions.iter().filter(|f| f.name.eq(name));
+                if let (Some(function), None) = (named.next(), named.next()) {
+                    *argument_types = Some(function.argument_types());
+                }
+            }
+        }
+        self.grants = combine_grants(std::mem::take(&mut self.grants), &revocations);
+
+        // Apply any table alterations in the order they were declared. Any for unknown tables are kept for validation.
+        let alterations = std::mem::take(&mut self.alterations);
+        for (mut name, action) in alterations {
+            if name.schema.is_none() {
+                name.schema = Some(project.default_schema.clone());
+            }
+            match self.tables.iter_mut().find(|t| t.name.eq(&name)) {
+                Some(table) => table.alter(action),
+                None => self.alterations.push((name, action)),
+            }
+        }
+
+        // Comments are applied in the order declared too, so the last comment on an object wins
+        let comments = std::mem::take(&mut self.comments);
+        for mut comment in comments {
+            match comment.object {
+                CommentObject::Column(ref mut name, _)
+                | CommentObject::Function(ref mut name, _)
+                | CommentObject::Procedure(ref mut name, _)
+                | CommentObject::Index(ref mut name)
+                | CommentObject::MaterializedView(ref mut name)
+                | CommentObject::Sequence(ref mut name)
+                | CommentObject::Table(ref mut name)
+                | CommentObject::Type(ref mut name)
+                | CommentObject::View(ref mut name) => {
+                    if name.schema.is_none() {
+                        name.schema = Some(project.default_schema.clone());
+                    }
+                }
+                CommentObject::Schema(_) => {}
+            }
+            if !self.apply_comment(&comment) {
+                self.comments.push(comment);
+            }
+        }
+
         // We also do the promotion here
-        self.promote_primary_keys_to_table_constraints();
+        self.promote_column_constraints_to_table_constraints();
     }
 
-    pub fn promote_primary_keys_to_table_constraints(&mut self) {
+    pub fn promote_column_constraints_to_table_constraints(&mut self) {
         // Set default schema's as well as marking primary key columns as not null
         for table in &mut self.tables {
             // Primary keys may also be specified against the column directly. We promote these to table constraints.`
@@ -467,11 +897,419 @@ impl Package {
                         });
                     }
                 }
+
+                // Unique columns are likewise promoted using the name Postgres would give them
+                let unique_pos = column.constraints.iter().position(|c| c.eq(&ColumnConstraint::Unique));
+                if let Some(unique_pos) = unique_pos {
+                    column.constraints.remove(unique_pos);
+                    let name = format!("{}_{}_key", table.name.name, column.name);
+                    if !table.constraints.iter().any(|c| c.name() == name) {
+                        table.constraints.push(TableConstraint::Unique {
+                            name,
+                            columns: vec![column.name.to_owned()],
+                            parameters: None,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Whether the object that privileges are granted on is defined within this package. Tables include views
+    // since they share the same privileges.
+    pub(crate) fn contains(&self, object: &GrantObject) -> bool {
+        match *object {
+            GrantObject::Schema(ref name) => self.schemas.iter().any(|s| s.name.eq(name)),
+            GrantObject::Table(ref name) => {
+                self.tables.iter().any(|t| t.name.eq(name))
+                    || self.views.iter().any(|v| v.name.eq(name))
+                    || self.materialized_views.iter().any(|v| v.name.eq(name))
+            }
+            GrantObject::Sequence(ref name) => self.sequences.iter().any(|s| s.name.eq(name)),
+            GrantObject::Function(ref name, ref argument_types) => {
+                self.find_function(name, argument_types.as_deref()).is_some()
+            }
+        }
+    }
+
+    // Finds a function by name and argument types. Without argument types the name needs to be unique.
+    fn find_function(&self, name: &ObjectName, argument_types: Option<&[SqlType]>) -> Option<usize> {
+        let mut positions = self
+            .functions
+            .iter()
+            .enumerate()
+            .filter(|&(_, f)| f.name.eq(name) && argument_types.is_none_or(|types| f.argument_types().eq(types)));
+        match (positions.next(), positions.next()) {
+            (Some((position, _)), None) => Some(position),
+            _ => None,
+        }
+    }
+
+    // Likewise for procedures
+    fn find_procedure(&self, name: &ObjectName, argument_types: Option<&[SqlType]>) -> Option<usize> {
+        let mut positions = self
+            .procedures
+            .iter()
+            .enumerate()
+            .filter(|&(_, p)| p.name.eq(name) && argument_types.is_none_or(|types| p.argument_types().eq(types)));
+        match (positions.next(), positions.next()) {
+            (Some((position, _)), None) => Some(position),
+            _ => None,
+        }
+    }
+
+    // Attaches a comment to the object it describes. Returns false when the object doesn't exist within the package.
+    pub(crate) fn apply_comment(&mut self, comment: &CommentDefinition) -> bool {
+        let target = match comment.object {
+            CommentObject::Column(ref table, ref column) => self
+                .tables
+                .iter_mut()
+                .find(|t| t.name.eq(table))
+                .and_then(|t| t.columns.iter_mut().find(|c| c.name.eq(column)))
+                .map(|c| &mut c.comment),
+            CommentObject::Function(ref name, ref argument_types) => {
+                match self.find_function(name, argument_types.as_deref()) {
+                    Some(position) => Some(&mut self.functions[position].comment),
+                    None => None,
+                }
+            }
+            CommentObject::Procedure(ref name, ref argument_types) => {
+                match self.find_procedure(name, argument_types.as_deref()) {
+                    Some(position) => Some(&mut self.procedures[position].comment),
+                    None => None,
+                }
+            }
+            CommentObject::Index(ref name) => self
+                .indexes
+                .iter_mut()
+                .find(|i| i.name.eq(&name.name) && i.table.schema.eq(&name.schema))
+                .map(|i| &mut i.comment),
+            CommentObject::MaterializedView(ref name) => self
+                .materialized_views
+                .iter_mut()
+                .find(|v| v.name.eq(name))
+                .map(|v| &mut v.comment),
+            CommentObject::Schema(ref name) => self
+                .schemas
+                .iter_mut()
+                .find(|s| s.name.eq(name))
+                .map(|s| &mut s.comment),
+            CommentObject::Sequence(ref name) => self
+                .sequences
+                .iter_mut()
+                .find(|s| s.name.eq(name))
+                .map(|s| &mut s.comment),
+            CommentObject::Table(ref name) => self.tables.iter_mut().find(|t| t.name.eq(name)).map(|t| &mut t.comment),
+            CommentObject::Type(ref name) => self.types.iter_mut().find(|t| t.name.eq(name)).map(|t| &mut t.comment),
+            CommentObject::View(ref name) => self.views.iter_mut().find(|v| v.name.eq(name)).map(|v| &mut v.comment),
+        };
+        match target {
+            Some(target) => {
+                *target = comment.comment.clone();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Every object that may be commented upon along with its current comment
+    pub(crate) fn comment_objects(&self) -> Vec<(CommentObject, Option<&String>)> {
+        let mut objects = Vec::new();
+        for schema in &self.schemas {
+            objects.push((CommentObject::Schema(schema.name.to_owned()), schema.comment.as_ref()));
+        }
+        for ty in &self.types {
+            objects.push((CommentObject::Type(ty.name.clone()), ty.comment.as_ref()));
+        }
+        for sequence in &self.sequences {
+            objects.push((
+                CommentObject::Sequence(sequence.name.clone()),
+                sequence.comment.as_ref(),
+            ));
+        }
+        for table in &self.tables {
+            objects.push((CommentObject::Table(table.name.clone()), table.comment.as_ref()));
+            for column in &table.columns {
+                objects.push((
+                    CommentObject::Column(table.name.clone(), column.name.to_owned()),
+                    column.comment.as_ref(),
+                ));
+            }
+        }
+        for function in &self.functions {
+            objects.push((
+                CommentObject::Function(function.name.clone(), Some(function.argument_types())),
+                function.comment.as_ref(),
+            ));
+        }
+        for procedure in &self.procedures {
+            objects.push((
+                CommentObject::Procedure(procedure.name.clone(), Some(procedure.argument_types())),
+                procedure.comment.as_ref(),
+            ));
+        }
+        for view in &self.views {
+            objects.push((CommentObject::View(view.name.clone()), view.comment.as_ref()));
+        }
+        for view in &self.materialized_views {
+            objects.push((
+                CommentObject::MaterializedView(view.name.clone()),
+                view.comment.as_ref(),
+            ));
+        }
+        for index in &self.indexes {
+            let name = ObjectName {
+                schema: index.table.schema.clone(),
+                name: index.name.to_owned(),
+            };
+            objects.push((CommentObject::Index(name), index.comment.as_ref()));
+        }
+        objects
+    }
+
+    // The comment of an object, or None if the object doesn't exist or has no comment
+    pub(crate) fn comment_of(&self, object: &CommentObject) -> Option<&String> {
+        match *object {
+            CommentObject::Column(ref table, ref column) => self
+                .tables
+                .iter()
+                .find(|t| t.name.eq(table))
+                .and_then(|t| t.columns.iter().find(|c| c.name.eq(column)))
+                .and_then(|c| c.comment.as_ref()),
+            CommentObject::Function(ref name, ref argument_types) => self
+                .find_function(name, argument_types.as_deref())
+                .and_then(|position| self.functions[position].comment.as_ref()),
+            CommentObject::Procedure(ref name, ref argument_types) => self
+                .find_procedure(name, argument_types.as_deref())
+                .and_then(|position| self.procedures[position].comment.as_ref()),
+            CommentObject::Index(ref name) => self
+                .indexes
+                .iter()
+                .find(|i| i.name.eq(&name.name) && i.table.schema.eq(&name.schema))
+                .and_then(|i| i.comment.as_ref()),
+            CommentObject::MaterializedView(ref name) => self
+                .materialized_views
+                .iter()
+                .find(|v| v.name.eq(name))
+                .and_then(|v| v.comment.as_ref()),
+            CommentObject::Schema(ref name) => self
+                .schemas
+                .iter()
+                .find(|s| s.name.eq(name))
+                .and_then(|s| s.comment.as_ref()),
+            CommentObject::Sequence(ref name) => self
+                .sequences
+                .iter()
+                .find(|s| s.name.eq(name))
+                .and_then(|s| s.comment.as_ref()),
+            CommentObject::Table(ref name) => self
+                .tables
+                .iter()
+                .find(|t| t.name.eq(name))
+                .and_then(|t| t.comment.as_ref()),
+            CommentObject::Type(ref name) => self
+                .types
+                .iter()
+                .find(|t| t.name.eq(name))
+                .and_then(|t| t.comment.as_ref()),
+            CommentObject::View(ref name) => self
+                .views
+                .iter()
+                .find(|v| v.name.eq(name))
+                .and_then(|v| v.comment.as_ref()),
+        }
+    }
+
+    // Applies a rename hint to this package in the same way Postgres would, including any dependent references.
+    // Returns false when the object to rename doesn't exist or the new name is already taken.
+    pub fn apply_rename(&mut self, rename: &RenameDefinition) -> bool {
+        match *rename {
+            RenameDefinition::Table { ref from, ref to } => {
+                let position = self.tables.iter().position(|t| t.name.to_string().eq(from));
+                let position = match position {
+                    Some(position) => position,
+                    None => return false,
+                };
+                let renamed = ObjectName {
+                    schema: self.tables[position].name.schema.clone(),
+                    name: to.to_owned(),
+                };
+                if self.tables.iter().any(|t| t.name.eq(&renamed)) {
+                    return false;
+                }
+                let original = std::mem::replace(&mut self.tables[position].name, renamed.clone());
+                let update = |name: &mut ObjectName| {
+                    if original.eq(name) {
+                        *name = renamed.clone();
+                    }
+                };
+                for table in &mut self.tables {
+                    for constraint in &mut table.constraints {
+                        if let TableConstraint::Foreign { ref mut ref_table, .. } = *constraint {
+                            update(ref_table);
+                        }
+                    }
+                }
+                self.indexes.iter_mut().for_each(|i| update(&mut i.table));
+                self.triggers.iter_mut().for_each(|t| update(&mut t.table));
+                self.policies.iter_mut().for_each(|p| update(&mut p.table));
+                for grant in &mut self.grants {
+                    if let GrantObject::Table(ref mut table) = grant.object {
+                        update(table);
+                    }
+                }
+                for sequence in &mut self.sequences {
+                    if let Some(ref mut owner) = sequence.owned_by {
+                        update(&mut owner.table);
+                    }
+                }
+                true
+            }
+            RenameDefinition::Column {
+                ref table,
+                ref from,
+                ref to,
+            } => {
+                let table_name = match self.tables.iter_mut().find(|t| t.name.to_string().eq(table)) {
+                    Some(definition) => {
+                        if definition.columns.iter().any(|c| c.name.eq(to)) {
+                            return false;
+                        }
+                        match definition.columns.iter_mut().find(|c| c.name.eq(from)) {
+                            Some(column) => column.name = to.to_owned(),
+                            None => return false,
+                        }
+                        definition.name.clone()
+                    }
+                    None => return false,
+                };
+                let update = |columns: &mut Vec<String>| {
+                    for column in columns.iter_mut() {
+                        if (*column).eq(from) {
+                            *column = to.to_owned();
+                        }
+                    }
+                };
+                for definition in &mut self.tables {
+                    let is_table = definition.name.eq(&table_name);
+                    for constraint in &mut definition.constraints {
+                        match *constraint {
+                            TableConstraint::Primary { ref mut columns, .. }
+                            | TableConstraint::Unique { ref mut columns, .. }
+                                if is_table =>
+                            {
+                                update(columns)
+                            }
+                            TableConstraint::Foreign {
+                                ref mut columns,
+                                ref ref_table,
+                                ref mut ref_columns,
+                                ..
+                            } => {
+                                if is_table {
+                                    update(columns);
+                                }
+                                if ref_table.eq(&table_name) {
+                                    update(ref_columns);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                for index in self.indexes.iter_mut().filter(|i| i.table.eq(&table_name)) {
+                    for column in &mut index.columns {
+                        if column.name.eq(from) {
+                            column.name = to.to_owned();
+                        }
+                    }
+                }
+                for sequence in &mut self.sequences {
+                    if let Some(ref mut owner) = sequence.owned_by {
+                        if owner.table.eq(&table_name) && owner.column.eq(from) {
+                            owner.column = to.to_owned();
+                        }
+                    }
+                }
+                true
+            }
+            RenameDefinition::Index { ref from, ref to } => {
+                let position = self.indexes.iter().position(|i| i.fully_qualified_name().eq(from));
+                let position = match position {
+                    Some(position) => position,
+                    None => return false,
+                };
+                let schema = self.indexes[position].schema().to_owned();
+                if self.indexes.iter().any(|i| i.schema() == schema && i.name.eq(to)) {
+                    return false;
+                }
+                self.indexes[position].name = to.to_owned();
+                true
+            }
+            RenameDefinition::Constraint {
+                ref table,
+                ref from,
+                ref to,
+            } => {
+                let definition = match self.tables.iter_mut().find(|t| t.name.to_string().eq(table)) {
+                    Some(definition) => definition,
+                    None => return false,
+                };
+                if definition.constraints.iter().any(|c| c.name() == to) {
+                    return false;
+                }
+                match definition.constraints.iter_mut().find(|c| c.name() == from) {
+                    Some(constraint) => match *constraint {
+                        TableConstraint::Primary { ref mut name, .. }
+                        | TableConstraint::Foreign { ref mut name, .. }
+                        | TableConstraint::Unique { ref mut name, .. }
+                        | TableConstraint::Check { ref mut name, .. }
+                        | TableConstraint::Exclude { ref mut name, .. } => *name = to.to_owned(),
+                    },
+                    None => return false,
+                }
+                true
+            }
+            RenameDefinition::Type { ref from, ref to } => {
+                let position = self.types.iter().position(|t| t.name.to_string().eq(from));
+                let position = match position {
+                    Some(position) => position,
+                    None => return false,
+                };
+                let renamed = ObjectName {
+                    schema: self.types[position].name.schema.clone(),
+                    name: to.to_owned(),
+                };
+                if self.types.iter().any(|t| t.name.eq(&renamed)) {
+                    return false;
+                }
+                let original = std::mem::replace(&mut self.types[position].name, renamed.clone());
+                let update = |sql_type: &mut SqlType| {
+                    if let SqlType::Custom(ref mut name, _, _) = *sql_type {
+                        if original.eq(name) {
+                            *name = renamed.clone();
+                        }
+                    }
+                };
+                for table in &mut self.tables {
+                    table.columns.iter_mut().for_each(|c| update(&mut c.sql_type));
+                }
+                for typ in &mut self.types {
+                    match typ.kind {
+                        TypeDefinitionKind::Composite(ref mut attributes) => {
+                            attributes.iter_mut().for_each(|a| update(&mut a.sql_type));
+                        }
+                        TypeDefinitionKind::Domain(ref mut domain) => update(&mut domain.sql_type),
+                        TypeDefinitionKind::Range(ref mut range) => update(&mut range.subtype),
+                        TypeDefinitionKind::Enum(_) | TypeDefinitionKind::UserDefined => {}
+                    }
+                }
+                true
             }
         }
     }
 
-    pub fn generate_dependency_graph(&self, log: &Logger) -> PsqlpackResult<Vec<Node>> {
+    pub fn generate_dependency_graph(&self, log: &Logger) -> PsqlpackResult<Vec<Node<'_>>> {
         let log = log.new(o!("graph" => "generate"));
 
         let mut graph = Graph::new();
@@ -492,6 +1330,21 @@ impl Package {
                 constraint.graph(&log, &mut graph, Some(&table_node));
             }
         }
+        trace!(log, "Scanning table partitions");
+        for table in &self.tables {
+            // Partitions take their columns from the partitioned table so need it to be complete
+            let parent = table
+                .partition_of
+                .as_ref()
+                .and_then(|partition_of| self.tables.iter().find(|t| t.name.eq(&partition_of.parent)));
+            if let Some(parent) = parent {
+                trace!(log, "Adding edge to partitioned table"; "table" => table.name.to_string(), "parent" => parent.name.to_string());
+                graph.add_edge(Node::Table(parent), Node::Table(table), ());
+                for column in &parent.columns {
+                    graph.add_edge(Node::Column(parent, column), Node::Table(table), ());
+                }
+            }
+        }
 
         trace!(log, "Scanning function dependencies");
         for function in &self.functions {
@@ -499,6 +1352,25 @@ impl Package {
             function.graph(&log, &mut graph, None);
         }
 
+        trace!(log, "Scanning view dependencies");
+        for view in &self.views {
+            let log = log.new(o!("view" => view.name.to_string()));
+            view.graph(&log, &mut graph, None);
+        }
+        for view in &self.materialized_views {
+            let log = log.new(o!("materialized view" => view.name.to_string()));
+            view.graph(&log, &mut graph, None);
+        }
+        // Views may reference any other object so we link them once everything is known
+        self.graph_view_references(&log, &mut graph);
+
+        trace!(log, "Scanning trigger dependencies");
+        for trigger in &self.triggers {
+            let log = log.new(o!("trigger" => trigger.fully_qualified_name()));
+            let trigger_node = trigger.graph(&log, &mut graph, None);
+            self.graph_trigger_references(&log, &mut graph, trigger_node, trigger);
+        }
+
         // Then generate the order
         trace!(log, "Sorting graph");
         match petgraph::algo::toposort(&graph, None) {
@@ -513,24 +1385,87 @@ impl Package {
         }
     }
 
-    // TODO: Stop moving string, consider making this a utility
-    fn expand_tilde(input: &str) -> String {
-        if let Some(after_tilde) = input.strip_prefix('~') {
-            if after_tilde.is_empty() || after_tilde.starts_with('/') {
-                if let Some(hd) = dirs::home_dir() {
-                    format!("{}{}", hd.display(), after_tilde)
-                } else {
-                    input.into()
+    fn graph_view_references<'graph, 'def: 'graph>(&'def self, log: &Logger, graph: &mut Graph<'graph>) {
+        let views = self
+            .views
+            .iter()
+            .map(|v| (Node::View(v), &v.query))
+            .chain(
+                self.materialized_views
+                    .iter()
+                    .map(|v| (Node::MaterializedView(v), &v.query)),
+            )
+            .collect::<Vec<_>>();
+        for &(view_node, query) in &views {
+            for table in &self.tables {
+                if query_references(query, &table.name) {
+                    trace!(log, "Adding edge to table"; "view" => view_node.to_string(), "table" => table.name.to_string());
+                    for column in &table.columns {
+                        graph.add_edge(Node::Column(table, column), view_node, ());
+                    }
+                    graph.add_edge(Node::Table(table), view_node, ());
                 }
-            } else {
-                input.into()
             }
-        } else {
-            input.into()
-        }
-    }
-
-    pub fn load_references(&self, project: &Project, log: &Logger) -> Vec<Package> {
+            for function in &self.functions {
+                if query_references(query, &function.name) {
+                    trace!(log, "Adding edge to function"; "view" => view_node.to_string(), "function" => function.name.to_string());
+                    graph.add_edge(Node::Function(function), view_node, ());
+                }
+            }
+            for &(other_node, _) in &views {
+                let other_name = match other_node {
+                    Node::View(other) => &other.name,
+                    Node::MaterializedView(other) => &other.name,
+                    _ => continue,
+                };
+                if other_node != view_node && query_references(query, other_name) {
+                    trace!(log, "Adding edge to view"; "view" => view_node.to_string(), "other" => other_name.to_string());
+                    graph.add_edge(other_node, view_node, ());
+                }
+            }
+        }
+    }
+
+    fn graph_trigger_references<'graph, 'def: 'graph>(
+        &'def self,
+        log: &Logger,
+        graph: &mut Graph<'graph>,
+        trigger_node: Node<'graph>,
+        trigger: &TriggerDefinition,
+    ) {
+        // Triggers come after the table (or view) they are defined on as well as the function they execute
+        if let Some(table) = self.tables.iter().find(|t| t.name.eq(&trigger.table)) {
+            trace!(log, "Adding edge to table"; "table" => table.name.to_string());
+            graph.add_edge(Node::Table(table), trigger_node, ());
+        }
+        if let Some(view) = self.views.iter().find(|v| v.name.eq(&trigger.table)) {
+            trace!(log, "Adding edge to view"; "view" => view.name.to_string());
+            graph.add_edge(Node::View(view), trigger_node, ());
+        }
+        if let Some(function) = self.functions.iter().find(|f| f.name.eq(&trigger.function)) {
+            trace!(log, "Adding edge to function"; "function" => function.name.to_string());
+            graph.add_edge(Node::Function(function), trigger_node, ());
+        }
+    }
+
+    // TODO: Stop moving string, consider making this a utility
+    fn expand_tilde(input: &str) -> String {
+        if let Some(after_tilde) = input.strip_prefix('~') {
+            if after_tilde.is_empty() || after_tilde.starts_with('/') {
+                if let Some(hd) = dirs::home_dir() {
+                    format!("{}{}", hd.display(), after_tilde)
+                } else {
+                    input.into()
+                }
+            } else {
+                input.into()
+            }
+        } else {
+            input.into()
+        }
+    }
+
+    pub fn load_references(&self, project: &Project, log: &Logger) -> Vec<Package> {
         let log = log.new(o!("package" => "load_references"));
 
         let mut references = Vec::new();
@@ -608,12 +1543,12 @@ impl Package {
                         break;
                     } else if found_packages.len() > 1 {
                         trace!(log, "Search for highest version");
-                        found_packages.sort_by(|a, b| a.meta.version.cmp(&b.meta.version));
+                        found_packages.sort_by_key(|a| a.meta.version);
                         references.extend(found_packages.drain(..1));
                         break;
                     } else {
                         // Only one item in there so just drain and extend
-                        references.extend(found_packages.drain(..));
+                        references.append(&mut found_packages);
                         found = true;
                         break;
                     }
@@ -637,6 +1572,10 @@ impl Package {
             .iter()
             .map(|t| &t.name)
             .chain(self.functions.iter().map(|f| &f.name))
+            .chain(self.procedures.iter().map(|p| &p.name))
+            .chain(self.views.iter().map(|v| &v.name))
+            .chain(self.materialized_views.iter().map(|v| &v.name))
+            .chain(self.sequences.iter().map(|s| &s.name))
             .collect::<Vec<_>>();
         let mut errors = names
             .iter()
@@ -665,7 +1604,7 @@ impl Package {
                 .iter()
                 .filter_map(|c| match c.sql_type {
                     SqlType::Custom(ref name, ref _opts, _dim) => {
-                        if !custom_types.contains(&&name) {
+                        if !custom_types.contains(&name) {
                             Some(ValidationKind::UnknownType {
                                 ty: name.to_owned(),
                                 table: t.name.to_string(),
@@ -701,9 +1640,9 @@ impl Package {
         errors.extend(
             foreign_keys
                 .iter()
-                .filter(|&&(_, _, ref table, _)| !self.tables.iter().any(|t| t.name.eq(table)))
+                .filter(|&(_, _, table, _)| !self.tables.iter().any(|t| t.name.eq(table)))
                 .map(
-                    |&(ref name, _, ref table, _)| ValidationKind::TableConstraintInvalidReferenceTable {
+                    |(name, _, table, _)| ValidationKind::TableConstraintInvalidReferenceTable {
                         constraint: name.to_owned(),
                         table: table.to_string(),
                     },
@@ -713,7 +1652,7 @@ impl Package {
         errors.extend(
             foreign_keys
                 .iter()
-                .filter(|&&(_, _, ref table, ref columns)| {
+                .filter(|&(_, _, table, columns)| {
                     let table = self.tables.iter().find(|t| t.name.eq(table));
                     match table {
                         Some(t) => !columns.iter().all(|rc| t.columns.iter().any(|c| c.name.eq(rc))),
@@ -721,7 +1660,7 @@ impl Package {
                     }
                 })
                 .map(
-                    |&(ref name, _, ref table, ref columns)| ValidationKind::TableConstraintInvalidReferenceColumns {
+                    |(name, _, table, columns)| ValidationKind::TableConstraintInvalidReferenceColumns {
                         constraint: name.to_owned(),
                         table: table.to_string(),
                         columns: columns.clone(),
@@ -732,7 +1671,7 @@ impl Package {
         errors.extend(
             foreign_keys
                 .iter()
-                .filter(|&&(ref constraint, ref columns, _, _)| {
+                .filter(|&(constraint, columns, _, _)| {
                     let table = self
                         .tables
                         .iter()
@@ -743,7 +1682,7 @@ impl Package {
                     }
                 })
                 .map(
-                    |&(ref name, ref columns, _, _)| ValidationKind::TableConstraintInvalidSourceColumns {
+                    |(name, columns, _, _)| ValidationKind::TableConstraintInvalidSourceColumns {
                         constraint: name.to_owned(),
                         columns: columns.clone(),
                     },
@@ -756,8 +1695,11 @@ impl Package {
         errors.extend(
             self.indexes
                 .iter()
-                .filter(|&index| !self.tables.iter().any(|t| t.name.eq(&index.table)))
-                .map(|ref index| ValidationKind::IndexInvalidReferenceTable {
+                .filter(|&index| {
+                    !self.tables.iter().any(|t| t.name.eq(&index.table))
+                        && !self.materialized_views.iter().any(|v| v.name.eq(&index.table))
+                })
+                .map(|index| ValidationKind::IndexInvalidReferenceTable {
                     index: index.name.to_string(),
                     table: index.table.to_string(),
                 }),
@@ -776,24 +1718,121 @@ impl Package {
                         None => false,
                     }
                 })
-                .map(|ref index| ValidationKind::IndexInvalidReferenceColumns {
+                .map(|index| ValidationKind::IndexInvalidReferenceColumns {
                     index: index.name.to_string(),
                     table: index.table.to_string(),
                     columns: index.columns.iter().map(|c| c.name.to_string()).collect(),
                 }),
         );
 
-        // 5. Validate function languages. For now, custom languages aren't supported
+        // 5. Validate triggers map to known tables or views
+        errors.extend(
+            self.triggers
+                .iter()
+                .filter(|&trigger| {
+                    !self.tables.iter().any(|t| t.name.eq(&trigger.table))
+                        && !self.views.iter().any(|v| v.name.eq(&trigger.table))
+                })
+                .map(|trigger| ValidationKind::TriggerInvalidReferenceTable {
+                    trigger: trigger.name.to_string(),
+                    table: trigger.table.to_string(),
+                }),
+        );
+
+        // 6. Validate sequences are owned by known table columns
+        errors.extend(
+            self.sequences
+                .iter()
+                .filter_map(|sequence| sequence.owned_by.as_ref().map(|owner| (sequence, owner)))
+                .filter(|&(_, owner)| {
+                    !self
+                        .tables
+                        .iter()
+                        .any(|t| t.name.eq(&owner.table) && t.columns.iter().any(|c| c.name.eq(&owner.column)))
+                })
+                .map(|(sequence, owner)| ValidationKind::SequenceInvalidOwner {
+                    sequence: sequence.name.to_string(),
+                    owner: owner.to_string(),
+                }),
+        );
+
+        // 7. Validate function and procedure languages. For now, custom languages aren't supported
         // until we can validate them.
         errors.extend(
             self.functions
                 .iter()
                 .filter(|&function| matches!(function.language, FunctionLanguage::Custom(_)))
-                .map(|ref function| ValidationKind::UnsupportedFunctionLanguage {
+                .map(|function| ValidationKind::UnsupportedFunctionLanguage {
                     language: function.language.clone(),
                     name: function.name.clone(),
                 }),
         );
+        errors.extend(
+            self.procedures
+                .iter()
+                .filter(|&procedure| matches!(procedure.language, FunctionLanguage::Custom(_)))
+                .map(|procedure| ValidationKind::UnsupportedFunctionLanguage {
+                    language: procedure.language.clone(),
+                    name: procedure.name.clone(),
+                }),
+        );
+
+        // 8. Validate partitions belong to known partitioned tables
+        errors.extend(
+            self.tables
+                .iter()
+                .filter_map(|table| table.partition_of.as_ref().map(|partition_of| (table, partition_of)))
+                .filter(|&(_, partition_of)| {
+                    !self
+                        .tables
+                        .iter()
+                        .any(|t| t.name.eq(&partition_of.parent) && t.partition_by.is_some())
+                })
+                .map(|(table, partition_of)| ValidationKind::PartitionInvalidParent {
+                    partition: table.name.to_string(),
+                    parent: partition_of.parent.to_string(),
+                }),
+        );
+
+        // 9. Validate policies map to known tables
+        errors.extend(
+            self.policies
+                .iter()
+                .filter(|&policy| !self.tables.iter().any(|t| t.name.eq(&policy.table)))
+                .map(|policy| ValidationKind::PolicyInvalidReferenceTable {
+                    policy: policy.name.to_string(),
+                    table: policy.table.to_string(),
+                }),
+        );
+
+        // 10. Validate table alterations map to known tables
+        errors.extend(
+            self.alterations
+                .iter()
+                .map(|(table, _)| ValidationKind::AlterTableInvalidReferenceTable {
+                    table: table.to_string(),
+                }),
+        );
+
+        // 11. Validate grants are for known objects
+        errors.extend(
+            self.grants
+                .iter()
+                .filter(|&grant| !self.contains(&grant.object))
+                .map(|grant| ValidationKind::GrantInvalidReferenceObject {
+                    grantee: grant.grantee.to_string(),
+                    object: grant.object.to_string(),
+                }),
+        );
+
+        // 12. Validate comments are for known objects
+        errors.extend(
+            self.comments
+                .iter()
+                .map(|comment| ValidationKind::CommentInvalidReferenceObject {
+                    object: comment.object.to_string(),
+                }),
+        );
 
         // If there are no errors then we're "ok"
         if errors.is_empty() {
@@ -804,6 +1843,62 @@ impl Package {
     }
 }
 
+// Combines grants so that each object and grantee has at most one entry with the grant option and one without.
+// Revocations are then applied to the combined privileges.
+fn combine_grants(grants: Vec<GrantDefinition>, revocations: &[GrantDefinition]) -> Vec<GrantDefinition> {
+    let mut privileges: Vec<(GrantObject, String, Privilege, bool)> = Vec::new();
+    for grant in grants {
+        for privilege in grant.privileges {
+            match privileges
+                .iter_mut()
+                .find(|(o, g, p, _)| o.eq(&grant.object) && g.eq(&grant.grantee) && *p == privilege)
+            {
+                Some(existing) => existing.3 |= grant.with_grant_option,
+                None => privileges.push((
+                    grant.object.clone(),
+                    grant.grantee.clone(),
+                    privilege,
+                    grant.with_grant_option,
+                )),
+            }
+        }
+    }
+    for revocation in revocations {
+        let revoked = |o: &GrantObject, g: &String, p: &Privilege| {
+            o.eq(&revocation.object) && g.eq(&revocation.grantee) && revocation.privileges.contains(p)
+        };
+        if revocation.with_grant_option {
+            // Only the grant option is revoked
+            privileges
+                .iter_mut()
+                .filter(|(o, g, p, _)| revoked(o, g, p))
+                .for_each(|existing| existing.3 = false);
+        } else {
+            privileges.retain(|(o, g, p, _)| !revoked(o, g, p));
+        }
+    }
+
+    let mut combined: Vec<GrantDefinition> = Vec::new();
+    for (object, grantee, privilege, with_grant_option) in privileges {
+        match combined
+            .iter_mut()
+            .find(|g| g.object.eq(&object) && g.grantee.eq(&grantee) && g.with_grant_option == with_grant_option)
+        {
+            Some(existing) => existing.privileges.push(privilege),
+            None => combined.push(GrantDefinition {
+                object,
+                grantee,
+                privileges: vec![privilege],
+                with_grant_option,
+            }),
+        }
+    }
+    for grant in &mut combined {
+        grant.privileges.sort();
+    }
+    combined
+}
+
 impl Default for Package {
     fn default() -> Self {
         Self::new()
@@ -812,6 +1907,16 @@ impl Default for Package {
 
 #[derive(Debug)]
 pub enum ValidationKind {
+    AlterTableInvalidReferenceTable {
+        table: String,
+    },
+    CommentInvalidReferenceObject {
+        object: String,
+    },
+    GrantInvalidReferenceObject {
+        grantee: String,
+        object: String,
+    },
     IndexInvalidReferenceTable {
         index: String,
         table: String,
@@ -834,10 +1939,26 @@ pub enum ValidationKind {
         constraint: String,
         columns: Vec<String>,
     },
+    PartitionInvalidParent {
+        partition: String,
+        parent: String,
+    },
+    PolicyInvalidReferenceTable {
+        policy: String,
+        table: String,
+    },
     SchemaMissing {
         schema: String,
         object: String,
     },
+    SequenceInvalidOwner {
+        sequence: String,
+        owner: String,
+    },
+    TriggerInvalidReferenceTable {
+        trigger: String,
+        table: String,
+    },
     UnknownType {
         ty: ObjectName,
         table: String,
@@ -851,6 +1972,16 @@ pub enum ValidationKind {
 impl fmt::Display for ValidationKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            ValidationKind::AlterTableInvalidReferenceTable { ref table } => {
+                write!(f, "Alter table statement uses unknown table `{}`", table)
+            }
+            ValidationKind::CommentInvalidReferenceObject { ref object } => {
+                write!(f, "Comment uses unknown object `{}`", object)
+            }
+            ValidationKind::GrantInvalidReferenceObject {
+                ref grantee,
+                ref object,
+            } => write!(f, "Grant to `{}` uses unknown object `{}`", grantee, object),
             ValidationKind::IndexInvalidReferenceTable { ref index, ref table } => {
                 write!(f, "Index `{}` uses unknown reference table `{}`", index, table)
             }
@@ -893,9 +2024,27 @@ impl fmt::Display for ValidationKind {
                 constraint,
                 columns.join("`, `")
             ),
+            ValidationKind::PartitionInvalidParent {
+                ref partition,
+                ref parent,
+            } => write!(
+                f,
+                "Partition `{}` uses unknown partitioned table `{}`",
+                partition, parent
+            ),
             ValidationKind::SchemaMissing { ref schema, ref object } => {
                 write!(f, "Schema `{}` missing for object `{}`", schema, object)
             }
+            ValidationKind::SequenceInvalidOwner {
+                ref sequence,
+                ref owner,
+            } => write!(f, "Sequence `{}` is owned by unknown column `{}`", sequence, owner),
+            ValidationKind::PolicyInvalidReferenceTable { ref policy, ref table } => {
+                write!(f, "Policy `{}` uses unknown reference table `{}`", policy, table)
+            }
+            ValidationKind::TriggerInvalidReferenceTable { ref trigger, ref table } => {
+                write!(f, "Trigger `{}` uses unknown reference table `{}`", trigger, table)
+            }
             ValidationKind::UnknownType { ref ty, ref table } => {
                 write!(f, "Unknown type `{}` used on table `{}`", ty, table)
             }
@@ -914,17 +2063,23 @@ pub enum Node<'def> {
     Column(&'def TableDefinition, &'def ColumnDefinition),
     Constraint(&'def TableDefinition, &'def TableConstraint),
     Function(&'def FunctionDefinition),
+    View(&'def ViewDefinition),
+    MaterializedView(&'def MaterializedViewDefinition),
+    Trigger(&'def TriggerDefinition),
 }
 
 impl<'def> fmt::Display for Node<'def> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Node::Table(table) => write!(f, "Table:      {}", table.name.to_string()),
-            Node::Column(table, column) => write!(f, "Column:     {}.{}", table.name.to_string(), column.name),
+            Node::Table(table) => write!(f, "Table:      {}", table.name),
+            Node::Column(table, column) => write!(f, "Column:     {}.{}", table.name, column.name),
             Node::Constraint(table, constraint) => {
-                write!(f, "Constraint: {}.{}", table.name.to_string(), constraint.name())
+                write!(f, "Constraint: {}.{}", table.name, constraint.name())
             }
-            Node::Function(function) => write!(f, "Function:   {}", function.name.to_string()),
+            Node::Function(function) => write!(f, "Function:   {}", function.name),
+            Node::View(view) => write!(f, "View:       {}", view.name),
+            Node::MaterializedView(view) => write!(f, "MatView:    {}", view.name),
+            Node::Trigger(trigger) => write!(f, "Trigger:    {}", trigger.fully_qualified_name()),
         }
     }
 }
@@ -993,6 +2148,65 @@ impl Graphable for FunctionDefinition {
     }
 }
 
+impl Graphable for ViewDefinition {
+    fn graph<'graph, 'def: 'graph>(
+        &'def self,
+        log: &Logger,
+        graph: &mut Graph<'graph>,
+        _: Option<&Node<'graph>>,
+    ) -> Node<'graph> {
+        // References are added once all nodes are known
+        trace!(log, "Adding");
+        graph.add_node(Node::View(self))
+    }
+}
+
+impl Graphable for MaterializedViewDefinition {
+    fn graph<'graph, 'def: 'graph>(
+        &'def self,
+        log: &Logger,
+        graph: &mut Graph<'graph>,
+        _: Option<&Node<'graph>>,
+    ) -> Node<'graph> {
+        // References are added once all nodes are known
+        trace!(log, "Adding");
+        graph.add_node(Node::MaterializedView(self))
+    }
+}
+
+impl Graphable for TriggerDefinition {
+    fn graph<'graph, 'def: 'graph>(
+        &'def self,
+        log: &Logger,
+        graph: &mut Graph<'graph>,
+        _: Option<&Node<'graph>>,
+    ) -> Node<'graph> {
+        // References are added once all nodes are known
+        trace!(log, "Adding");
+        graph.add_node(Node::Trigger(self))
+    }
+}
+
+// We don't parse view queries so references are found by looking for the object name.
+// This may add unnecessary edges however it won't miss any.
+fn query_references(query: &str, name: &ObjectName) -> bool {
+    let pattern = match name.schema {
+        Some(ref schema) => format!(
+            "(?i)(^|[^a-z0-9_.\"])(\"?{}\"?\\.)?\"?{}\"?($|[^a-z0-9_\"])",
+            regex::escape(schema),
+            regex::escape(&name.name)
+        ),
+        None => format!(
+            "(?i)(^|[^a-z0-9_.\"])\"?{}\"?($|[^a-z0-9_\"])",
+            regex::escape(&name.name)
+        ),
+    };
+    match Regex::new(&pattern) {
+        Ok(re) => re.is_match(query),
+        Err(_) => true,
+    }
+}
+
 impl Graphable for TableConstraint {
     fn graph<'graph, 'def: 'graph>(
         &'def self,
@@ -1000,9 +2214,9 @@ impl Graphable for TableConstraint {
         graph: &mut Graph<'graph>,
         parent: Option<&Node<'graph>>,
     ) -> Node<'graph> {
-        // We currently have two types of table constraints: Primary and Foreign
-        // Primary is easy with a direct dependency to the column
+        // Primary and Unique are easy with a direct dependency to the column
         // Foreign requires a weighted dependency
+        // Check and Exclude depend on the columns of the table
         // This does have a parent - namely the table
         let table_node = *parent.unwrap();
         let table = match table_node {
@@ -1061,11 +2275,13 @@ impl Graphable for TableConstraint {
                     let ref_column = table_def.columns.iter().find(|x| &x.name == ref_column_name).unwrap();
                     graph.add_edge(Node::Column(table_def, ref_column), constraint, ());
 
-                    // If required, add an edge to any primary keys.
-                    for primary in &table_def.constraints {
-                        if let TableConstraint::Primary { ref columns, .. } = *primary {
+                    // If required, add an edge to any primary keys or unique constraints.
+                    for key in &table_def.constraints {
+                        if let TableConstraint::Primary { ref columns, .. }
+                        | TableConstraint::Unique { ref columns, .. } = *key
+                        {
                             if columns.contains(ref_column_name) {
-                                graph.add_edge(Node::Constraint(table_def, primary), constraint, ());
+                                graph.add_edge(Node::Constraint(table_def, key), constraint, ());
                             }
                         }
                     }
@@ -1073,6 +2289,31 @@ impl Graphable for TableConstraint {
                 graph.add_edge(table_node, constraint, ());
                 constraint
             }
+            TableConstraint::Unique {
+                ref name, ref columns, ..
+            } => {
+                let log = log.new(o!("unique constraint" => name.to_owned()));
+                trace!(log, "Adding");
+                let constraint = graph.add_node(Node::Constraint(table, self));
+                for column_name in columns {
+                    trace!(log, "Adding edge to column"; "column" => &column_name);
+                    let column = table.columns.iter().find(|x| &x.name == column_name).unwrap();
+                    graph.add_edge(Node::Column(table, column), constraint, ());
+                }
+                graph.add_edge(table_node, constraint, ());
+                constraint
+            }
+            TableConstraint::Check { ref name, .. } | TableConstraint::Exclude { ref name, .. } => {
+                let log = log.new(o!("constraint" => name.to_owned()));
+                // We don't parse the expression so assume it may reference any column
+                trace!(log, "Adding");
+                let constraint = graph.add_node(Node::Constraint(table, self));
+                for column in &table.columns {
+                    graph.add_edge(Node::Column(table, column), constraint, ());
+                }
+                graph.add_edge(table_node, constraint, ());
+                constraint
+            }
         }
     }
 }
@@ -1097,12 +2338,26 @@ mod tests {
             Ok(statement_list) => {
                 for statement in statement_list {
                     match statement {
+                        ast::Statement::AlterTable(table, action) => package.push_table_alteration(table, action),
+                        ast::Statement::Comment(comment_definition) => package.push_comment(comment_definition),
                         ast::Statement::Error(kind) => panic!("Unhandled error detected: {}", kind),
                         ast::Statement::Function(function_definition) => package.push_function(function_definition),
+                        ast::Statement::Grant(grants) => grants.into_iter().for_each(|g| package.push_grant(g)),
                         ast::Statement::Index(index_definition) => package.push_index(index_definition),
+                        ast::Statement::MaterializedView(view_definition) => {
+                            package.push_materialized_view(view_definition)
+                        }
+                        ast::Statement::Policy(policy_definition) => package.push_policy(policy_definition),
+                        ast::Statement::Procedure(procedure_definition) => package.push_procedure(procedure_definition),
+                        ast::Statement::Revoke(revocations) => {
+                            revocations.into_iter().for_each(|r| package.push_revocation(r))
+                        }
+                        ast::Statement::Trigger(trigger_definition) => package.push_trigger(trigger_definition),
                         ast::Statement::Schema(schema_definition) => package.push_schema(schema_definition),
+                        ast::Statement::Sequence(sequence_definition) => package.push_sequence(sequence_definition),
                         ast::Statement::Table(table_definition) => package.push_table(table_definition),
                         ast::Statement::Type(type_definition) => package.push_type(type_definition),
+                        ast::Statement::View(view_definition) => package.push_view(view_definition),
                     }
                 }
             }
@@ -1172,6 +2427,28 @@ mod tests {
         };
     }
 
+    macro_rules! assert_view {
+        ($graph:ident,$index:expr,$name:expr) => {
+            match $graph[$index] {
+                Node::View(view) => {
+                    assert_eq!(view.name.to_string(), $name);
+                }
+                _ => panic!("Expected a view at index {}", $index),
+            }
+        };
+    }
+
+    macro_rules! assert_trigger {
+        ($graph:ident,$index:expr,$name:expr) => {
+            match $graph[$index] {
+                Node::Trigger(trigger) => {
+                    assert_eq!(trigger.fully_qualified_name(), $name);
+                }
+                _ => panic!("Expected a trigger at index {}", $index),
+            }
+        };
+    }
+
     #[test]
     fn it_sets_table_defaults() {
         let mut package = package_sql("CREATE TABLE hello_world(id int);");
@@ -1236,6 +2513,131 @@ mod tests {
         assert_eq!(col.null_position.as_ref().unwrap(), &ast::IndexPosition::Last);
     }
 
+    #[test]
+    fn it_promotes_unique_columns_to_table_constraints() {
+        let mut package = package_sql("CREATE TABLE person(id int PRIMARY KEY, email varchar(200) UNIQUE NOT NULL);");
+        let project = Project::default();
+
+        package.set_defaults(&project);
+        let table = &package.tables[0];
+        assert!(!table.columns[1].constraints.contains(&ast::ColumnConstraint::Unique));
+        assert_eq!(
+            table.constraints,
+            vec![
+                ast::TableConstraint::Primary {
+                    name: "person_pkey".into(),
+                    columns: vec!["id".into()],
+                    parameters: None,
+                },
+                ast::TableConstraint::Unique {
+                    name: "person_email_key".into(),
+                    columns: vec!["email".into()],
+                    parameters: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_hashes_the_package_contents_only() {
+        let sql = "CREATE TABLE person(id int PRIMARY KEY, email varchar(200) NOT NULL);";
+        let package = package_sql(sql);
+        let mut other = package_sql(sql);
+        other.meta = MetaInfo::new(SourceInfo::Database);
+        assert_eq!(package.hash().unwrap(), other.hash().unwrap());
+        assert_eq!(package.hash().unwrap().len(), 64);
+
+        other.tables[0].columns[1].name = "email_address".into();
+        assert_ne!(package.hash().unwrap(), other.hash().unwrap());
+    }
+
+    #[test]
+    fn it_only_writes_roles_declared_by_a_project() {
+        let log = Logger::root(Discard.fuse(), o!());
+        let sql = "CREATE TABLE person(id int PRIMARY KEY);";
+        let role = || serde_json::from_str(r#"{ "name": "app", "login": true }"#).unwrap();
+        let path = std::env::temp_dir().join(format!("psqlpack_roles_{}.psqlpack", std::process::id()));
+
+        let mut package = package_sql(sql);
+        package.push_role(role());
+        package.write_to(&path).unwrap();
+        let written = Package::from_packaged_file(&log, &path).unwrap();
+        assert_eq!(written.roles.len(), 1);
+        assert_eq!(written.roles[0].name, "app");
+
+        let mut extracted = package_sql(sql);
+        extracted.push_role(role());
+        extracted.meta = MetaInfo::new(SourceInfo::Database);
+        extracted.write_to(&path).unwrap();
+        let written = Package::from_packaged_file(&log, &path).unwrap();
+        assert!(written.roles.is_empty());
+        assert_eq!(written.tables.len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn it_renames_tables_and_columns_including_their_references() {
+        let mut package = package_sql(
+            "CREATE TABLE parent(id int PRIMARY KEY);
+             CREATE TABLE child(id int, parent_id int,
+               CONSTRAINT fk_child_parent FOREIGN KEY (parent_id) REFERENCES parent(id));
+             CREATE INDEX idx_child_parent ON child (parent_id);",
+        );
+        let mut project_renames = vec![
+            ast::RenameDefinition::Table {
+                from: "parent".into(),
+                to: "guardian".into(),
+            },
+            ast::RenameDefinition::Column {
+                table: "guardian".into(),
+                from: "id".into(),
+                to: "guardian_id".into(),
+            },
+            ast::RenameDefinition::Column {
+                table: "child".into(),
+                from: "parent_id".into(),
+                to: "guardian_id".into(),
+            },
+        ];
+        package.set_defaults(&Project::default());
+        for rename in &mut project_renames {
+            rename.qualify("public");
+            assert!(package.apply_rename(rename), "{}", rename);
+        }
+
+        // Once applied, the renames no longer match anything
+        for rename in &project_renames {
+            assert!(!package.apply_rename(rename), "{}", rename);
+        }
+
+        let guardian = package.tables.iter().find(|t| t.name.name.eq("guardian")).unwrap();
+        assert_eq!(guardian.columns[0].name, "guardian_id");
+        assert_eq!(
+            guardian.constraints,
+            vec![ast::TableConstraint::Primary {
+                name: "parent_pkey".into(),
+                columns: vec!["guardian_id".into()],
+                parameters: None,
+            }]
+        );
+        let child = package.tables.iter().find(|t| t.name.name.eq("child")).unwrap();
+        match child.constraints[0] {
+            ast::TableConstraint::Foreign {
+                ref columns,
+                ref ref_table,
+                ref ref_columns,
+                ..
+            } => {
+                assert_eq!(columns, &vec!["guardian_id".to_owned()]);
+                assert_eq!(ref_table.to_string(), "public.guardian");
+                assert_eq!(ref_columns, &vec!["guardian_id".to_owned()]);
+            }
+            ref unexpected => panic!("Unexpected constraint type: {:?}", unexpected),
+        }
+        assert_eq!(package.indexes[0].table.to_string(), "public.child");
+        assert_eq!(package.indexes[0].columns[0].name, "guardian_id");
+    }
+
     #[test]
     fn it_generates_a_simple_ordering() {
         let package = package_sql(
@@ -1312,6 +2714,49 @@ mod tests {
         assert_fk_constraint!(graph, 7, "public.transaction", "fk_public_transaction__allocation_id");
     }
 
+    #[test]
+    fn it_generates_a_view_ordering() {
+        let package = package_sql(
+            "CREATE VIEW my.active_parents AS SELECT id FROM my.recent_parents WHERE id > 0;
+             CREATE VIEW my.recent_parents AS SELECT id FROM my.parents;
+             CREATE TABLE my.parents(id int);",
+        );
+        let logger = empty_logger();
+        let graph = package.generate_dependency_graph(&logger);
+
+        // Views come after the tables they select from as well as any views they reference
+        assert!(graph.is_ok());
+        let graph = graph.unwrap();
+        assert_eq!(graph.len(), 4);
+        assert_table!(graph, 0, "my.parents");
+        assert_column!(graph, 1, "my.parents", "id");
+        assert_view!(graph, 2, "my.recent_parents");
+        assert_view!(graph, 3, "my.active_parents");
+    }
+
+    #[test]
+    fn it_generates_a_trigger_ordering() {
+        let package = package_sql(
+            "CREATE TRIGGER audit AFTER INSERT OR UPDATE ON my.parents
+             FOR EACH ROW EXECUTE FUNCTION my.audit();
+             CREATE TABLE my.parents(id int);
+             CREATE FUNCTION my.audit() RETURNS trigger AS $$ BEGIN RETURN NEW; END $$ LANGUAGE plpgsql;",
+        );
+        let logger = empty_logger();
+        let graph = package.generate_dependency_graph(&logger);
+
+        // Triggers come after both the table and the function
+        assert!(graph.is_ok());
+        let graph = graph.unwrap();
+        assert_eq!(graph.len(), 4);
+        let trigger = graph.iter().position(|n| matches!(n, Node::Trigger(_))).unwrap();
+        let table = graph.iter().position(|n| matches!(n, Node::Table(_))).unwrap();
+        let function = graph.iter().position(|n| matches!(n, Node::Function(_))).unwrap();
+        assert!(trigger > table);
+        assert!(trigger > function);
+        assert_trigger!(graph, trigger, "my.parents.audit");
+    }
+
     #[test]
     fn it_validates_missing_schema_references() {
         let mut package = package_sql("CREATE TABLE my.items(id int);");
@@ -1333,10 +2778,348 @@ mod tests {
         }
 
         // Add the schema and try again
-        package.schemas.push(ast::SchemaDefinition { name: "my".to_owned() });
+        package.schemas.push(ast::SchemaDefinition {
+            name: "my".to_owned(),
+            owner: None,
+            comment: None,
+        });
         assert!(package.validate(&Vec::new()).is_ok());
     }
 
+    #[test]
+    fn it_validates_unknown_trigger_tables() {
+        let mut package = package_sql(
+            "CREATE TRIGGER audit BEFORE DELETE ON parents FOR EACH ROW EXECUTE PROCEDURE audit();
+             CREATE FUNCTION audit() RETURNS trigger AS $$ BEGIN RETURN OLD; END $$ LANGUAGE plpgsql;",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        let result = package.validate(&Vec::new());
+
+        // `parents` is missing
+        assert!(result.is_err());
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_eq!(validation_errors.len(), 1);
+        match validation_errors[0] {
+            ValidationKind::TriggerInvalidReferenceTable { ref trigger, ref table } => {
+                assert_eq!(trigger, "audit");
+                assert_eq!(table, "public.parents");
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_applies_row_level_security_to_tables_declared_later() {
+        let mut package = package_sql(
+            "ALTER TABLE accounts ENABLE ROW LEVEL SECURITY;
+             ALTER TABLE accounts FORCE ROW LEVEL SECURITY;
+             CREATE POLICY owner_only ON accounts USING (owner = current_user);
+             CREATE TABLE accounts (id int NOT NULL, owner text NOT NULL);",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        assert!(package.validate(&Vec::new()).is_ok());
+
+        let table = &package.tables[0];
+        assert!(table.row_security);
+        assert!(table.force_row_security);
+        assert_eq!(package.policies[0].fully_qualified_name(), "public.accounts.owner_only");
+    }
+
+    #[test]
+    fn it_validates_unknown_policy_and_alteration_tables() {
+        let mut package = package_sql(
+            "ALTER TABLE accounts ENABLE ROW LEVEL SECURITY;
+             CREATE POLICY owner_only ON accounts USING (owner = current_user);",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        let result = package.validate(&Vec::new());
+
+        // `accounts` is missing
+        assert!(result.is_err());
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_eq!(validation_errors.len(), 2);
+        match validation_errors[0] {
+            ValidationKind::PolicyInvalidReferenceTable { ref policy, ref table } => {
+                assert_eq!(policy, "owner_only");
+                assert_eq!(table, "public.accounts");
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+        match validation_errors[1] {
+            ValidationKind::AlterTableInvalidReferenceTable { ref table } => {
+                assert_eq!(table, "public.accounts");
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_combines_grants_and_applies_revocations() {
+        let mut package = package_sql(
+            "CREATE TABLE contacts (id int NOT NULL);
+             GRANT SELECT ON contacts TO reader;
+             GRANT SELECT, UPDATE ON public.contacts TO reader WITH GRANT OPTION;
+             GRANT ALL ON contacts TO writer;
+             REVOKE TRUNCATE, TRIGGER ON contacts FROM writer;
+             REVOKE GRANT OPTION FOR UPDATE ON contacts FROM reader;
+             GRANT USAGE ON SCHEMA public TO reader;",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        assert!(package.validate(&Vec::new()).is_ok());
+
+        let contacts = ast::GrantObject::Table(ast::ObjectName {
+            schema: Some("public".to_owned()),
+            name: "contacts".to_owned(),
+        });
+        let grants = package
+            .grants
+            .iter()
+            .map(|g| {
+                (
+                    g.object.clone(),
+                    &g.grantee[..],
+                    g.privileges.clone(),
+                    g.with_grant_option,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            grants,
+            vec![
+                (contacts.clone(), "reader", vec![ast::Privilege::Select], true),
+                (contacts.clone(), "reader", vec![ast::Privilege::Update], false),
+                (
+                    contacts,
+                    "writer",
+                    vec![
+                        ast::Privilege::Select,
+                        ast::Privilege::Insert,
+                        ast::Privilege::Update,
+                        ast::Privilege::Delete,
+                        ast::Privilege::References,
+                    ],
+                    false
+                ),
+                (
+                    ast::GrantObject::Schema("public".to_owned()),
+                    "reader",
+                    vec![ast::Privilege::Usage],
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_keys_function_grants_by_signature() {
+        let mut package = package_sql(
+            "CREATE FUNCTION add(a int) RETURNS int AS $$ SELECT a $$ LANGUAGE sql;
+             CREATE FUNCTION add(a int, b int) RETURNS int AS $$ SELECT a + b $$ LANGUAGE sql;
+             CREATE FUNCTION total() RETURNS int AS $$ SELECT 1 $$ LANGUAGE sql;
+             GRANT EXECUTE ON FUNCTION add(integer, integer) TO reader;
+             GRANT EXECUTE ON FUNCTION total TO reader;",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        assert!(package.validate(&Vec::new()).is_ok());
+
+        // A function named without its argument types takes them from the only function with that name
+        let int = ast::SqlType::Simple(ast::SimpleSqlType::Integer, None);
+        let objects = package.grants.iter().map(|g| g.object.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            objects,
+            vec!["FUNCTION public.add(int, int)", "FUNCTION public.total()"]
+        );
+        assert_eq!(
+            package.grants[0].object,
+            ast::GrantObject::Function(
+                ast::ObjectName {
+                    schema: Some("public".to_owned()),
+                    name: "add".to_owned(),
+                },
+                Some(vec![int.clone(), int])
+            )
+        );
+    }
+
+    #[test]
+    fn it_validates_grants_on_unknown_objects() {
+        let mut package = package_sql(
+            "CREATE TABLE contacts (id int NOT NULL);
+             GRANT SELECT ON contacts, orders TO reader;",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        let result = package.validate(&Vec::new());
+
+        // `orders` is missing
+        assert!(result.is_err());
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_eq!(validation_errors.len(), 1);
+        match validation_errors[0] {
+            ValidationKind::GrantInvalidReferenceObject {
+                ref grantee,
+                ref object,
+            } => {
+                assert_eq!(grantee, "reader");
+                assert_eq!(object, "TABLE public.orders");
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_attaches_comments_to_objects_declared_later() {
+        let mut package = package_sql(
+            "COMMENT ON TABLE contacts IS 'People we know';
+             COMMENT ON COLUMN contacts.name IS 'Full name';
+             COMMENT ON COLUMN public.contacts.id IS 'Identifier';
+             COMMENT ON COLUMN contacts.id IS NULL;
+             COMMENT ON INDEX idx_contacts_name IS 'Lookups by name';
+             COMMENT ON SCHEMA public IS 'Application data';
+             CREATE TABLE contacts (id int NOT NULL, name text);
+             CREATE INDEX idx_contacts_name ON contacts (name);",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        assert!(package.validate(&Vec::new()).is_ok());
+
+        let table = &package.tables[0];
+        assert_eq!(table.comment, Some("People we know".to_owned()));
+        assert_eq!(table.columns[0].comment, None);
+        assert_eq!(table.columns[1].comment, Some("Full name".to_owned()));
+        assert_eq!(package.indexes[0].comment, Some("Lookups by name".to_owned()));
+        assert_eq!(package.schemas[0].comment, Some("Application data".to_owned()));
+    }
+
+    #[test]
+    fn it_validates_comments_on_unknown_objects() {
+        let mut package = package_sql(
+            "CREATE TABLE contacts (id int NOT NULL);
+             COMMENT ON COLUMN contacts.name IS 'Full name';
+             COMMENT ON VIEW contacts IS 'Not a view';",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        let result = package.validate(&Vec::new());
+
+        // `contacts.name` and the `contacts` view are missing
+        assert!(result.is_err());
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        let objects = validation_errors
+            .iter()
+            .map(|error| match *error {
+                ValidationKind::CommentInvalidReferenceObject { ref object } => &object[..],
+                ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(objects, vec!["COLUMN public.contacts.name", "VIEW public.contacts"]);
+    }
+
+    #[test]
+    fn it_attaches_comments_to_overloaded_functions_by_signature() {
+        let mut package = package_sql(
+            "CREATE FUNCTION add(a int, b int) RETURNS int AS $$ SELECT a + b $$ LANGUAGE SQL;
+             CREATE FUNCTION add(a text, b text) RETURNS text AS $$ SELECT a || b $$ LANGUAGE SQL;
+             COMMENT ON FUNCTION add(text, text) IS 'Concatenates';
+             COMMENT ON FUNCTION add IS 'Ambiguous';",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        let result = package.validate(&Vec::new());
+
+        // The overload is chosen by its argument types while the name alone is ambiguous
+        assert_eq!(package.functions[0].comment, None);
+        assert_eq!(package.functions[1].comment, Some("Concatenates".to_owned()));
+        assert!(result.is_err());
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_eq!(validation_errors.len(), 1);
+        match validation_errors[0] {
+            ValidationKind::CommentInvalidReferenceObject { ref object } => {
+                assert_eq!(object, "FUNCTION public.add");
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
+    #[test]
+    fn it_attaches_comments_to_procedures() {
+        let mut package = package_sql(
+            "COMMENT ON PROCEDURE archive(date) IS 'Archives old orders';
+             COMMENT ON PROCEDURE purge IS 'Removes archived orders';
+             CREATE PROCEDURE archive(before date) LANGUAGE SQL AS $$ SELECT 1 $$;
+             CREATE PROCEDURE purge() LANGUAGE SQL AS $$ SELECT 1 $$;",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+        assert!(package.validate(&Vec::new()).is_ok());
+
+        assert_eq!(package.procedures[0].comment, Some("Archives old orders".to_owned()));
+        assert_eq!(
+            package.procedures[1].comment,
+            Some("Removes archived orders".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_validates_unknown_sequence_owners() {
+        let mut package = package_sql(
+            "CREATE TABLE contacts (id int NOT NULL);
+             CREATE SEQUENCE contacts_seq OWNED BY contacts.id;
+             CREATE SEQUENCE parents_seq OWNED BY parents.id;",
+        );
+        let project = Project::default();
+        package.set_defaults(&project);
+
+        // Missing options are set to the Postgres defaults
+        let sequence = &package.sequences[0];
+        assert_eq!(sequence.name.to_string(), "public.contacts_seq");
+        assert_eq!(sequence.owned_by.as_ref().unwrap().to_string(), "public.contacts.id");
+        assert_eq!(sequence.increment, Some(1));
+        assert_eq!(sequence.min_value, Some(1));
+        assert_eq!(sequence.max_value, Some(i64::MAX));
+        assert_eq!(sequence.start, Some(1));
+        assert_eq!(sequence.cache, Some(1));
+
+        // `parents` is missing
+        let result = package.validate(&Vec::new());
+        assert!(result.is_err());
+        let validation_errors = match result.err().unwrap() {
+            PsqlpackError(ValidationError(errors), _) => errors,
+            unexpected => panic!("Expected validation error however saw {:?}", unexpected),
+        };
+        assert_eq!(validation_errors.len(), 1);
+        match validation_errors[0] {
+            ValidationKind::SequenceInvalidOwner {
+                ref sequence,
+                ref owner,
+            } => {
+                assert_eq!(sequence, "public.parents_seq");
+                assert_eq!(owner, "public.parents.id");
+            }
+            ref unexpected => panic!("Unexpected validation type: {:?}", unexpected),
+        }
+    }
+
     #[test]
     fn it_validates_unknown_types() {
         let mut package = package_sql(
@@ -1375,6 +3158,7 @@ mod tests {
                 name: "mytype".to_string(),
             },
             kind: ast::TypeDefinitionKind::Enum(Vec::new()),
+            comment: None,
         });
         assert!(package.validate(&Vec::new()).is_ok());
     }
@@ -1418,8 +3202,14 @@ mod tests {
                 name: "id".to_owned(),
                 sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Serial, None),
                 constraints: Vec::new(),
+                comment: None,
             }],
             constraints: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            row_security: false,
+            force_row_security: false,
+            comment: None,
         });
         assert!(package.validate(&Vec::new()).is_ok());
     }
@@ -1464,6 +3254,7 @@ mod tests {
                 name: "parent_id".to_owned(),
                 sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
                 constraints: Vec::new(),
+                comment: None,
             });
         }
         assert!(package.validate(&Vec::new()).is_ok());
@@ -1507,6 +3298,7 @@ mod tests {
                 name: "par_id".to_owned(),
                 sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
                 constraints: Vec::new(),
+                comment: None,
             });
         }
         assert!(package.validate(&Vec::new()).is_ok());
@@ -1547,14 +3339,21 @@ mod tests {
                     name: "id".to_owned(),
                     sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Serial, None),
                     constraints: Vec::new(),
+                    comment: None,
                 },
                 ast::ColumnDefinition {
                     name: "name".to_owned(),
                     sql_type: ast::SqlType::Simple(ast::SimpleSqlType::VariableLengthString(50), None),
                     constraints: Vec::new(),
+                    comment: None,
                 },
             ],
             constraints: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            row_security: false,
+            force_row_security: false,
+            comment: None,
         });
         assert!(package.validate(&Vec::new()).is_ok());
     }
@@ -1596,6 +3395,7 @@ mod tests {
                 name: "number".to_owned(),
                 sql_type: ast::SqlType::Simple(ast::SimpleSqlType::Integer, None),
                 constraints: Vec::new(),
+                comment: None,
             });
         }
         assert!(package.validate(&Vec::new()).is_ok());
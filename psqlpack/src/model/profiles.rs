@@ -3,6 +3,7 @@
 //! For instance, a `PublishProfile` might determine how unknown entities in the
 //! target are handled when performing a `publish` operation.
 
+use std::collections::BTreeMap;
 use std::default::Default;
 use std::fs::File;
 use std::io::{BufReader, Read};
@@ -19,6 +20,10 @@ pub struct PublishProfile {
     pub version: Semver,
     #[serde(alias = "generationOptions")]
     pub generation_options: GenerationOptions,
+    /// Substitutes the roles that privileges are granted to within the project, allowing each environment to use
+    /// its own role names.
+    #[serde(alias = "roleNames", default)]
+    pub role_names: BTreeMap<String, String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
@@ -53,7 +58,8 @@ pub struct GenerationOptions {
     #[serde(alias = "alwaysRecreateDatabase")]
     pub always_recreate_database: bool,
 
-    /// Enum values are typically unsafe to delete. If set to Allow, psqlpack will attempt to delete.
+    /// Enum values can't be removed in place. If set to Allow, psqlpack will rebuild the enum and convert the columns
+    /// using it, failing the publish if any rows still use a removed value.
     /// Default: Error
     #[serde(alias = "dropEnumValues", default = "Toggle::error")]
     pub drop_enum_values: Toggle,
@@ -61,6 +67,11 @@ pub struct GenerationOptions {
     /// Default: Error
     #[serde(alias = "dropTables", default = "Toggle::error")]
     pub drop_tables: Toggle,
+    /// Partitions may be created outside of the project, e.g. by a scheduled job. If set to Allow, psqlpack will drop
+    /// partitions that aren't in the project. If set to Ignore, they are left attached.
+    /// Default: Ignore
+    #[serde(alias = "dropPartitions", default = "Toggle::ignore")]
+    pub drop_partitions: Toggle,
     /// Columns may have data in them which may not be intended to be deleted. If set to Allow, psqlpack will drop the column.
     /// Default: Error
     #[serde(alias = "dropColumns", default = "Toggle::error")]
@@ -73,14 +84,68 @@ pub struct GenerationOptions {
     /// Default: Allow
     #[serde(alias = "dropForeignKeyConstraints", default = "Toggle::allow")]
     pub drop_foreign_key_constraints: Toggle,
+    /// Unique and exclusion constraints guard against conflicting rows. If set to Allow, psqlpack will drop the constraint.
+    /// Default: Allow
+    #[serde(alias = "dropUniqueConstraints", default = "Toggle::allow")]
+    pub drop_unique_constraints: Toggle,
+    /// Check constraints validate the values within a row. If set to Allow, psqlpack will drop the check constraint.
+    /// Default: Allow
+    #[serde(alias = "dropCheckConstraints", default = "Toggle::allow")]
+    pub drop_check_constraints: Toggle,
     /// Functions may not be intended to be deleted. If set to Allow, psqlpack will drop the function.
     /// Default: Error
     #[serde(alias = "dropFunctions", default = "Toggle::error")]
     pub drop_functions: Toggle,
+    /// Procedures may be created outside of the project, e.g. by a post-deployment script. If set to Allow, psqlpack
+    /// will drop the procedure.
+    /// Default: Ignore
+    #[serde(alias = "dropProcedures", default = "Toggle::ignore")]
+    pub drop_procedures: Toggle,
     /// Indexes may not be intended to be deleted. If set to Allow, psqlpack will drop the index.
     /// Default: Allow
     #[serde(alias = "dropIndexes", default = "Toggle::allow")]
     pub drop_indexes: Toggle,
+    /// Views and materialized views may be created outside of the project, e.g. by a post-deployment script. If set to
+    /// Allow, psqlpack will drop the view.
+    /// Default: Ignore
+    #[serde(alias = "dropViews", default = "Toggle::ignore")]
+    pub drop_views: Toggle,
+    /// Triggers may be created outside of the project, e.g. by a post-deployment script. If set to Allow, psqlpack will
+    /// drop the trigger.
+    /// Default: Ignore
+    #[serde(alias = "dropTriggers", default = "Toggle::ignore")]
+    pub drop_triggers: Toggle,
+    /// Policies restrict the rows that can be accessed on a table with row level security and may be created outside
+    /// of the project. If set to Allow, psqlpack will drop the policy.
+    /// Default: Ignore
+    #[serde(alias = "dropPolicies", default = "Toggle::ignore")]
+    pub drop_policies: Toggle,
+    /// Privileges may be granted outside of the project, e.g. by a DBA. If set to Allow, psqlpack will revoke any
+    /// privileges on objects within the project that the project doesn't grant.
+    /// Default: Ignore
+    #[serde(alias = "revokePrivileges", default = "Toggle::ignore")]
+    pub revoke_privileges: Toggle,
+    /// Roles are shared by every database within the cluster. If set to Allow, psqlpack will drop roles that it created
+    /// and that the project no longer declares. Superusers are never dropped and roles are only managed when the
+    /// project declares them.
+    /// Default: Ignore
+    #[serde(alias = "dropRoles", default = "Toggle::ignore")]
+    pub drop_roles: Toggle,
+    /// Schemas may be created outside of the project. If set to Allow, psqlpack will drop schemas that the project
+    /// doesn't declare once the objects within them have been dropped. The public schema is never dropped.
+    /// Default: Ignore
+    #[serde(alias = "dropSchemas", default = "Toggle::ignore")]
+    pub drop_schemas: Toggle,
+    /// Schemas that still contain objects can only be dropped using CASCADE, which also drops any objects that
+    /// would otherwise be left alone. If set to true, psqlpack will drop these schemas rather than raising an error.
+    /// Default: false
+    #[serde(alias = "cascadeSchemaDrops", default)]
+    pub cascade_schema_drops: bool,
+    /// Types may still be used outside of the project. If set to Allow, psqlpack will drop types that the project
+    /// doesn't declare once the tables and functions using them have been dropped.
+    /// Default: Ignore
+    #[serde(alias = "dropTypes", default = "Toggle::ignore")]
+    pub drop_types: Toggle,
 
     /// Extensions may not be intended to be upgraded automatically. If set to Allow, psqlpack will upgrade the extension as necessary.
     /// Default: Ignore
@@ -91,6 +156,12 @@ pub struct GenerationOptions {
     /// Default: true
     #[serde(alias = "forceConcurrentIndexes", default = "Bool::t")]
     pub force_concurrent_indexes: bool,
+
+    /// Applies all changes that can be made within a transaction as a single transaction so that a failure leaves
+    /// the target untouched. Changes that can't be made within a transaction are applied in phases either side.
+    /// Default: false
+    #[serde(default)]
+    pub transactional: bool,
 }
 
 impl Default for PublishProfile {
@@ -102,21 +173,45 @@ impl Default for PublishProfile {
 
                 drop_enum_values: Toggle::Error,
                 drop_tables: Toggle::Error,
+                drop_partitions: Toggle::Ignore,
                 drop_columns: Toggle::Error,
                 drop_primary_key_constraints: Toggle::Error,
                 drop_foreign_key_constraints: Toggle::Allow,
+                drop_unique_constraints: Toggle::Allow,
+                drop_check_constraints: Toggle::Allow,
                 drop_functions: Toggle::Error,
+                drop_procedures: Toggle::Ignore,
                 drop_indexes: Toggle::Allow,
+                drop_views: Toggle::Ignore,
+                drop_triggers: Toggle::Ignore,
+                drop_policies: Toggle::Ignore,
+                revoke_privileges: Toggle::Ignore,
+                drop_roles: Toggle::Ignore,
+                drop_schemas: Toggle::Ignore,
+                cascade_schema_drops: false,
+                drop_types: Toggle::Ignore,
 
                 upgrade_extensions: Toggle::Ignore,
 
                 force_concurrent_indexes: true,
+
+                transactional: false,
             },
+            role_names: BTreeMap::new(),
         }
     }
 }
 
 impl PublishProfile {
+    /// The name of the role to use within the target for a role named within the project.
+    pub fn role_name<'a>(&'a self, role: &'a str) -> &'a str {
+        self.role_names
+            .iter()
+            .find(|(from, _)| from.eq_ignore_ascii_case(role))
+            .map(|(_, to)| &to[..])
+            .unwrap_or(role)
+    }
+
     pub fn from_path(profile_path: &Path) -> PsqlpackResult<PublishProfile> {
         File::open(profile_path)
             .chain_err(|| PublishProfileReadError(profile_path.to_path_buf()))
@@ -160,12 +255,28 @@ mod tests {
                 "alwaysRecreateDatabase": false,
                 "dropEnumValues": "Error",
                 "dropFunctions": "Error",
+                "dropProcedures": "Allow",
                 "dropTables": "Error",
                 "dropColumns": "Error",
                 "dropPrimaryKeyConstraints": "Error",
                 "dropForeignKeyConstraints": "Allow",
+                "dropUniqueConstraints": "Error",
+                "dropCheckConstraints": "Ignore",
                 "dropIndexes": "Ignore",
-                "forceConcurrentIndexes": false
+                "dropViews": "Allow",
+                "dropTriggers": "Ignore",
+                "dropPartitions": "Ignore",
+                "dropPolicies": "Allow",
+                "revokePrivileges": "Error",
+                "dropRoles": "Allow",
+                "dropSchemas": "Allow",
+                "cascadeSchemaDrops": true,
+                "dropTypes": "Allow",
+                "forceConcurrentIndexes": false,
+                "transactional": true
+              },
+              "roleNames": {
+                "app_user": "app_user_prod"
               }
             }
         "#;
@@ -176,12 +287,26 @@ mod tests {
         assert!(!options.always_recreate_database);
         assert_eq!(options.drop_enum_values, Toggle::Error);
         assert_eq!(options.drop_functions, Toggle::Error);
+        assert_eq!(options.drop_procedures, Toggle::Allow);
         assert_eq!(options.drop_tables, Toggle::Error);
         assert_eq!(options.drop_columns, Toggle::Error);
         assert_eq!(options.drop_primary_key_constraints, Toggle::Error);
         assert_eq!(options.drop_foreign_key_constraints, Toggle::Allow);
+        assert_eq!(options.drop_unique_constraints, Toggle::Error);
+        assert_eq!(options.drop_check_constraints, Toggle::Ignore);
         assert_eq!(options.drop_indexes, Toggle::Ignore);
+        assert_eq!(options.drop_views, Toggle::Allow);
+        assert_eq!(options.drop_triggers, Toggle::Ignore);
+        assert_eq!(options.drop_partitions, Toggle::Ignore);
+        assert_eq!(options.drop_policies, Toggle::Allow);
+        assert_eq!(options.revoke_privileges, Toggle::Error);
+        assert_eq!(options.drop_roles, Toggle::Allow);
+        assert_eq!(options.drop_schemas, Toggle::Allow);
+        assert!(options.cascade_schema_drops);
+        assert_eq!(options.drop_types, Toggle::Allow);
         assert!(!options.force_concurrent_indexes);
+        assert!(options.transactional);
+        assert_eq!(publish_profile.role_names["app_user"], "app_user_prod");
     }
 
     #[test]
@@ -199,6 +324,10 @@ mod tests {
             drop_foreign_key_constraints = "Allow"
             drop_indexes = "Ignore"
             force_concurrent_indexes = false
+            transactional = true
+
+            [roleNames]
+            reader = "reporting"
         "#;
         let publish_profile = PublishProfile::from_reader(DATA.as_bytes());
         let publish_profile = publish_profile.unwrap();
@@ -207,11 +336,25 @@ mod tests {
         assert!(!options.always_recreate_database);
         assert_eq!(options.drop_enum_values, Toggle::Error);
         assert_eq!(options.drop_functions, Toggle::Error);
+        assert_eq!(options.drop_procedures, Toggle::Ignore);
         assert_eq!(options.drop_tables, Toggle::Error);
         assert_eq!(options.drop_columns, Toggle::Error);
         assert_eq!(options.drop_primary_key_constraints, Toggle::Error);
         assert_eq!(options.drop_foreign_key_constraints, Toggle::Allow);
+        assert_eq!(options.drop_unique_constraints, Toggle::Allow);
+        assert_eq!(options.drop_check_constraints, Toggle::Allow);
         assert_eq!(options.drop_indexes, Toggle::Ignore);
+        assert_eq!(options.drop_views, Toggle::Ignore);
+        assert_eq!(options.drop_triggers, Toggle::Ignore);
+        assert_eq!(options.drop_partitions, Toggle::Ignore);
+        assert_eq!(options.drop_policies, Toggle::Ignore);
+        assert_eq!(options.revoke_privileges, Toggle::Ignore);
+        assert_eq!(options.drop_roles, Toggle::Ignore);
+        assert_eq!(options.drop_schemas, Toggle::Ignore);
+        assert!(!options.cascade_schema_drops);
+        assert_eq!(options.drop_types, Toggle::Ignore);
         assert!(!options.force_concurrent_indexes);
+        assert!(options.transactional);
+        assert_eq!(publish_profile.role_names["reader"], "reporting");
     }
 }
@@ -73,6 +73,14 @@ pub struct Project {
     /// An array of search paths to look in outside of the standard paths (./lib, ~/.psqlpack/lib).
     #[serde(alias = "referenceSearchPaths", skip_serializing_if = "Option::is_none")]
     pub reference_search_paths: Option<Vec<String>>,
+
+    /// An ordered refactor log of objects that have been renamed. Each rename is only applied once to a target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renames: Option<Vec<RenameDefinition>>,
+
+    /// An array of roles required by the project. Roles are only created or altered when this is specified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roles: Option<Vec<RoleDefinition>>,
 }
 
 #[derive(Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -104,6 +112,8 @@ impl Default for Project {
             include_globs: None,
             exclude_globs: None,
             reference_search_paths: None,
+            renames: None,
+            roles: None,
         }
     }
 }
@@ -192,6 +202,20 @@ impl Project {
             }
         }
 
+        // Add the refactor log into package
+        if let Some(ref renames) = self.renames {
+            for rename in renames {
+                package.push_rename(rename.clone());
+            }
+        }
+
+        // Add the roles into package
+        if let Some(ref roles) = self.roles {
+            for role in roles {
+                package.push_role(role.clone());
+            }
+        }
+
         // Enumerate the glob paths
         for path in self.walk_files(&parent)? {
             let log = log.new(o!("file" => path.to_str().unwrap().to_owned()));
@@ -230,9 +254,9 @@ impl Project {
                             SyntaxError(
                                 format!("{}", path.display()),
                                 e.line.to_owned(),
-                                e.line_number as usize,
-                                e.start_pos as usize,
-                                e.end_pos as usize,
+                                e.line_number,
+                                e.start_pos,
+                                e.end_pos,
                             )
                             .into(),
                         );
@@ -250,14 +274,30 @@ impl Project {
                         for statement in statement_list {
                             dump_statement!(log, statement);
                             match statement {
+                                Statement::AlterTable(table, action) => package.push_table_alteration(table, action),
+                                Statement::Comment(comment_definition) => package.push_comment(comment_definition),
                                 Statement::Error(kind) => {
                                     errors.push(HandledParseError(kind).into());
                                 }
                                 Statement::Function(function_definition) => package.push_function(function_definition),
+                                Statement::Grant(grants) => grants.into_iter().for_each(|g| package.push_grant(g)),
                                 Statement::Index(index_definition) => package.push_index(index_definition),
+                                Statement::MaterializedView(view_definition) => {
+                                    package.push_materialized_view(view_definition)
+                                }
+                                Statement::Policy(policy_definition) => package.push_policy(policy_definition),
+                                Statement::Procedure(procedure_definition) => {
+                                    package.push_procedure(procedure_definition)
+                                }
+                                Statement::Revoke(revocations) => {
+                                    revocations.into_iter().for_each(|r| package.push_revocation(r))
+                                }
+                                Statement::Trigger(trigger_definition) => package.push_trigger(trigger_definition),
                                 Statement::Schema(schema_definition) => package.push_schema(schema_definition),
+                                Statement::Sequence(sequence_definition) => package.push_sequence(sequence_definition),
                                 Statement::Table(table_definition) => package.push_table(table_definition),
                                 Statement::Type(type_definition) => package.push_type(type_definition),
+                                Statement::View(view_definition) => package.push_view(view_definition),
                             }
                         }
                     }
@@ -328,6 +368,7 @@ impl Project {
 mod tests {
 
     use crate::model::project::Project;
+    use crate::sql::ast::RenameDefinition;
     use crate::{Dependency, Semver};
     use std::path::Path;
 
@@ -336,7 +377,7 @@ mod tests {
         // This test relies on the `simple` samples directory
         let parent = Path::new("../samples/simple");
         let project = Project::default();
-        let result = project.walk_files(&parent);
+        let result = project.walk_files(parent);
 
         // Check the expected files were returned
         assert!(result.is_ok());
@@ -375,8 +416,10 @@ mod tests {
             include_globs: None,
             exclude_globs: Some(vec!["**/*org*".into()]),
             reference_search_paths: None,
+            renames: None,
+            roles: None,
         };
-        let result = project.walk_files(&parent);
+        let result = project.walk_files(parent);
 
         // Check the expected files were returned
         assert!(result.is_ok());
@@ -403,8 +446,10 @@ mod tests {
             include_globs: None,
             exclude_globs: Some(vec!["**/geo/**/*.sql".into(), "**/geo.*".into()]),
             reference_search_paths: None,
+            renames: None,
+            roles: None,
         };
-        let result = project.walk_files(&parent);
+        let result = project.walk_files(parent);
 
         // Check the expected files were returned
         assert!(result.is_ok());
@@ -429,8 +474,10 @@ mod tests {
             include_globs: Some(vec!["**/*org*.sql".into()]),
             exclude_globs: None,
             reference_search_paths: None,
+            renames: None,
+            roles: None,
         };
-        let result = project.walk_files(&parent);
+        let result = project.walk_files(parent);
 
         // Check the expected files were returned
         assert!(result.is_ok());
@@ -461,6 +508,13 @@ mod tests {
                     { "name": "postgis" },
                     { "name": "postgis_topology" },
                     { "name": "postgis_tiger_geocoder" }
+                ],
+                "renames": [
+                    { "kind": "column", "table": "contacts", "from": "name", "to": "full_name" }
+                ],
+                "roles": [
+                    { "name": "app_readers" },
+                    { "name": "app_user", "login": true, "connectionLimit": 10, "memberOf": ["app_readers"] }
                 ]
             }
         "#;
@@ -506,6 +560,25 @@ mod tests {
                 version: None,
             }
         );
+        assert_eq!(
+            project.renames,
+            Some(vec![RenameDefinition::Column {
+                table: "contacts".into(),
+                from: "name".into(),
+                to: "full_name".into(),
+            }])
+        );
+        let roles = project.roles.unwrap();
+        assert_eq!(roles.len(), 2);
+        assert_eq!(roles[0].name, "app_readers");
+        assert!(!roles[0].login);
+        assert!(roles[0].inherit);
+        assert_eq!(roles[0].connection_limit, -1);
+        assert!(roles[0].member_of.is_empty());
+        assert_eq!(roles[1].name, "app_user");
+        assert!(roles[1].login);
+        assert_eq!(roles[1].connection_limit, 10);
+        assert_eq!(roles[1].member_of, vec!["app_readers".to_string()]);
     }
 
     #[test]
@@ -529,6 +602,12 @@ mod tests {
                 { name = "postgis_topology" },
                 { name = "postgis_tiger_geocoder" }
             ]
+            renames = [
+                { kind = "table", from = "people", to = "contacts" }
+            ]
+            roles = [
+                { name = "reporting", create_db = true }
+            ]
         "#;
         let project = Project::from_reader(DATA.as_bytes());
         let project = project.unwrap();
@@ -572,5 +651,34 @@ mod tests {
                 version: None,
             }
         );
+        assert_eq!(
+            project.renames,
+            Some(vec![RenameDefinition::Table {
+                from: "people".into(),
+                to: "contacts".into(),
+            }])
+        );
+        let roles = project.roles.unwrap();
+        assert_eq!(roles.len(), 1);
+        assert_eq!(roles[0].name, "reporting");
+        assert!(roles[0].create_db);
+        assert!(!roles[0].create_role);
+    }
+
+    #[test]
+    fn it_rejects_passwords_for_roles() {
+        const DATA: &str = r#"
+            {
+                "version": "1.0",
+                "defaultSchema": "public",
+                "preDeployScripts": [],
+                "postDeployScripts": [],
+                "roles": [
+                    { "name": "app_user", "login": true, "password": "secret" }
+                ]
+            }
+        "#;
+        let project = Project::from_reader(DATA.as_bytes());
+        assert!(project.is_err());
     }
 }
@@ -0,0 +1,785 @@
+//! A deployment report describes the changes a publish would make, grouped by the object being changed and
+//! classified by risk, so that a release can be reviewed without reading the generated SQL.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Serialize;
+use slog::Logger;
+
+use crate::errors::PsqlpackError;
+use crate::errors::PsqlpackErrorKind::*;
+use crate::model::delta::{ChangeInstruction, TypeModificationAction};
+use crate::model::DeploymentSummary;
+use crate::sql::ast::*;
+
+/// The version of the report schema. This is incremented whenever the shape of the report changes.
+pub const REPORT_VERSION: u32 = 1;
+
+/// The risk of applying a change, ordered from least to most severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Risk {
+    /// The change can be made without holding long running locks or losing data
+    Safe,
+    /// The change holds a lock that blocks reads or writes while the table is scanned or indexed
+    BlockingLock,
+    /// The change may rewrite the entire table while holding an exclusive lock
+    TableRewrite,
+    /// The change may lose data
+    DataLoss,
+}
+
+impl fmt::Display for Risk {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Risk::Safe => write!(f, "safe"),
+            Risk::BlockingLock => write!(f, "blocking lock"),
+            Risk::TableRewrite => write!(f, "table rewrite"),
+            Risk::DataLoss => write!(f, "data loss"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Json,
+    Html,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// Infers the format from the extension of the report file, defaulting to JSON.
+    pub fn from_path(path: &Path) -> ReportFormat {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| ext.parse().ok())
+            .unwrap_or(ReportFormat::Json)
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = PsqlpackError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match &s.to_lowercase()[..] {
+            "json" => Ok(ReportFormat::Json),
+            "html" | "htm" => Ok(ReportFormat::Html),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            unknown => Err(GenerationError(format!("Unknown report format: {}", unknown)).into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub version: u32,
+    pub database: Option<String>,
+    pub package_hash: String,
+    pub package_version: String,
+    pub summary: ReportSummary,
+    pub objects: Vec<ReportObject>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ReportSummary {
+    pub objects: usize,
+    pub changes: usize,
+    pub safe: usize,
+    pub blocking_lock: usize,
+    pub table_rewrite: usize,
+    pub data_loss: usize,
+}
+
+/// The changes made to a single object along with the most severe risk of those changes.
+#[derive(Debug, Serialize)]
+pub struct ReportObject {
+    pub object: String,
+    pub risk: Risk,
+    pub changes: Vec<ReportChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportChange {
+    pub description: String,
+    pub risk: Risk,
+    /// The generation options that allowed this change to be made
+    pub allowed_by: Vec<String>,
+    pub sql: String,
+}
+
+impl Report {
+    pub(crate) fn new(log: &Logger, changes: &[ChangeInstruction], summary: &DeploymentSummary) -> Report {
+        let log = log.new(o!("report" => "new"));
+
+        let mut report = Report {
+            version: REPORT_VERSION,
+            database: None,
+            package_hash: summary.package_hash.to_owned(),
+            package_version: summary.package_version.to_string(),
+            summary: ReportSummary::default(),
+            objects: Vec::new(),
+        };
+        for change in changes {
+            let object = match *change {
+                // Switching databases isn't a change in itself
+                ChangeInstruction::UseDatabase(ref database) => {
+                    report.database = Some(database.to_owned());
+                    continue;
+                }
+                ref change => object_of(change),
+            };
+            let risk = risk_of(change);
+            trace!(log, "Reporting change"; "object" => &object, "risk" => risk.to_string());
+
+            report.summary.changes += 1;
+            match risk {
+                Risk::Safe => report.summary.safe += 1,
+                Risk::BlockingLock => report.summary.blocking_lock += 1,
+                Risk::TableRewrite => report.summary.table_rewrite += 1,
+                Risk::DataLoss => report.summary.data_loss += 1,
+            }
+            let change = ReportChange {
+                description: change.to_string(),
+                risk,
+                allowed_by: allowed_by(change, changes).iter().map(|o| o.to_string()).collect(),
+                sql: change.to_sql(&log),
+            };
+            if let Some(existing) = report.objects.iter_mut().find(|o| o.object.eq(&object)) {
+                existing.risk = existing.risk.max(risk);
+                existing.changes.push(change);
+            } else {
+                report.objects.push(ReportObject {
+                    object,
+                    risk,
+                    changes: vec![change],
+                });
+            }
+        }
+        report.summary.objects = report.objects.len();
+        report
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# Deployment report\n\n");
+        if let Some(ref database) = self.database {
+            out.push_str(&format!("* Database: `{}`\n", database));
+        }
+        out.push_str(&format!(
+            "* Package: `{}` (psqlpack {})\n* Report version: {}\n\n",
+            self.package_hash, self.package_version, self.version
+        ));
+
+        out.push_str("## Summary\n\n| Risk | Changes |\n|------|---------|\n");
+        for &(risk, count) in self.summary.counts().iter() {
+            out.push_str(&format!("| {} | {} |\n", risk, count));
+        }
+        out.push_str(&format!(
+            "| **Total** | **{}** across {} objects |\n",
+            self.summary.changes, self.summary.objects
+        ));
+
+        for object in &self.objects {
+            out.push_str(&format!("\n## {} ({})\n\n", object.object, object.risk));
+            out.push_str("| Change | Risk | Allowed by |\n|--------|------|------------|\n");
+            for change in &object.changes {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    change.description.replace('|', "\\|"),
+                    change.risk,
+                    change
+                        .allowed_by
+                        .iter()
+                        .map(|o| format!("`{}`", o))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            out.push_str("\n```sql\n");
+            for change in &object.changes {
+                out.push_str(change.sql.trim_end());
+                out.push_str(";\n");
+            }
+            out.push_str("```\n");
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        out.push_str(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Deployment report</title>\n<style>\n\
+             body { font-family: sans-serif; margin: 2em; }\n\
+             table { border-collapse: collapse; margin-bottom: 1em; }\n\
+             th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }\n\
+             pre { background: #f6f6f6; padding: 0.6em; }\n\
+             .safe { color: #2e7d32; }\n\
+             .blocking_lock { color: #ef6c00; }\n\
+             .table_rewrite { color: #d84315; }\n\
+             .data_loss { color: #c62828; font-weight: bold; }\n\
+             </style>\n</head>\n<body>\n<h1>Deployment report</h1>\n<ul>\n",
+        );
+        if let Some(ref database) = self.database {
+            out.push_str(&format!("<li>Database: <code>{}</code></li>\n", escape_html(database)));
+        }
+        out.push_str(&format!(
+            "<li>Package: <code>{}</code> (psqlpack {})</li>\n<li>Report version: {}</li>\n</ul>\n",
+            escape_html(&self.package_hash),
+            escape_html(&self.package_version),
+            self.version
+        ));
+
+        out.push_str("<h2>Summary</h2>\n<table>\n<tr><th>Risk</th><th>Changes</th></tr>\n");
+        for &(risk, count) in self.summary.counts().iter() {
+            out.push_str(&format!(
+                "<tr><td class=\"{}\">{}</td><td>{}</td></tr>\n",
+                risk.class(),
+                risk,
+                count
+            ));
+        }
+        out.push_str(&format!(
+            "<tr><th>Total</th><th>{} across {} objects</th></tr>\n</table>\n",
+            self.summary.changes, self.summary.objects
+        ));
+
+        for object in &self.objects {
+            out.push_str(&format!(
+                "<h2>{} <span class=\"{}\">({})</span></h2>\n<table>\n\
+                 <tr><th>Change</th><th>Risk</th><th>Allowed by</th></tr>\n",
+                escape_html(&object.object),
+                object.risk.class(),
+                object.risk
+            ));
+            for change in &object.changes {
+                out.push_str(&format!(
+                    "<tr><td>{}</td><td class=\"{}\">{}</td><td>{}</td></tr>\n",
+                    escape_html(&change.description),
+                    change.risk.class(),
+                    change.risk,
+                    change
+                        .allowed_by
+                        .iter()
+                        .map(|o| format!("<code>{}</code>", o))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            out.push_str("</table>\n<pre><code>");
+            for change in &object.changes {
+                out.push_str(&escape_html(change.sql.trim_end()));
+                out.push_str(";\n");
+            }
+            out.push_str("</code></pre>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+impl ReportSummary {
+    fn counts(&self) -> [(Risk, usize); 4] {
+        [
+            (Risk::Safe, self.safe),
+            (Risk::BlockingLock, self.blocking_lock),
+            (Risk::TableRewrite, self.table_rewrite),
+            (Risk::DataLoss, self.data_loss),
+        ]
+    }
+}
+
+impl Risk {
+    fn class(&self) -> &'static str {
+        match *self {
+            Risk::Safe => "safe",
+            Risk::BlockingLock => "blocking_lock",
+            Risk::TableRewrite => "table_rewrite",
+            Risk::DataLoss => "data_loss",
+        }
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Changes to columns, constraints and triggers are grouped with their table
+fn object_of(change: &ChangeInstruction) -> String {
+    use self::ChangeInstruction::*;
+
+    match *change {
+        KillConnections(ref name) | DropDatabase(ref name) | CreateDatabase(ref name) | UseDatabase(ref name) => {
+            format!("database {}", name)
+        }
+        CreateExtension(ref name, _) | UpgradeExtension(ref name, _) => format!("extension {}", name),
+        AddSchema(ref schema) => format!("schema {}", schema.name),
+        AlterSchemaOwner(ref name, _) | DropSchema(ref name, _) => format!("schema {}", name),
+        RunScript(script) => format!("script {}", script.name),
+        Rename(rename) => match *rename {
+            RenameDefinition::Table { ref from, .. } => format!("table {}", from),
+            RenameDefinition::Column { ref table, .. } | RenameDefinition::Constraint { ref table, .. } => {
+                format!("table {}", table)
+            }
+            RenameDefinition::Index { ref from, .. } => format!("index {}", from),
+            RenameDefinition::Type { ref from, .. } => format!("type {}", from),
+        },
+        AddSequence(sequence) | ModifySequence(sequence) | ModifySequenceOwner(sequence) => {
+            format!("sequence {}", sequence.name)
+        }
+        AddType(ty) | ModifyType(ty, _) => format!("type {}", ty.name),
+        DropType(ref name) => format!("type {}", name),
+        AddTable(table)
+        | AttachPartition(table)
+        | AddColumn(table, _)
+        | ModifyColumnType(table, _)
+        | ModifyColumnNull(table, _)
+        | ModifyColumnDefault(table, _)
+        | AddColumnIdentity(table, _)
+        | ModifyColumnIdentity(table, _)
+        | DropColumnIdentity(table, _)
+        | ConvertSerialToIdentity(table, _)
+        | RecreateGeneratedColumn(table, _)
+        | DropColumnExpression(table, _)
+        | ModifyColumnUniqueConstraint(table, _)
+        | ModifyColumnPrimaryKeyConstraint(table, _)
+        | DropColumn(table, _)
+        | AddConstraint(table, _, _)
+        | ValidateConstraint(table, _)
+        | DropConstraint(table, _, _)
+        | ModifyRowSecurity(table)
+        | ModifyForceRowSecurity(table) => format!("table {}", table.name),
+        DropTable(ref name) | DetachPartition(ref name, _) | DropPartition(ref name) => format!("table {}", name),
+        AddIndex(index, _) => format!("index {}", index.fully_qualified_name()),
+        DropIndex(ref name, _) => format!("index {}", name),
+        AddFunction(function) | ModifyFunction(function) => format!("function {}", function.signature()),
+        DropFunction(ref name) => format!("function {}", name),
+        AddProcedure(procedure) | ModifyProcedure(procedure) => format!("procedure {}", procedure.signature()),
+        DropProcedure(ref name) => format!("procedure {}", name),
+        AddView(view) | ReplaceView(view) => format!("view {}", view.name),
+        DropView(ref name) => format!("view {}", name),
+        AddMaterializedView(view) | ReplaceMaterializedView(view) => format!("materialized view {}", view.name),
+        DropMaterializedView(ref name) => format!("materialized view {}", name),
+        AddTrigger(trigger) | ReplaceTrigger(trigger) => format!("table {}", trigger.table),
+        DropTrigger(_, ref table) => format!("table {}", table),
+        AddPolicy(policy) | ModifyPolicy(policy) | ReplacePolicy(policy) => format!("table {}", policy.table),
+        DropPolicy(_, ref table) => format!("table {}", table),
+        Grant(ref grant) | Revoke(ref grant) => format!("{} {}", grant.object.kind(), grant.object.name()),
+        SetComment(CommentObject::Column(ref table, _), _) => format!("table {}", table),
+        SetComment(ref object, _) => format!("{} {}", object.kind(), object.name()),
+        CreateRole(ref role) | AlterRole(ref role) => format!("role {}", role.name),
+        DropRole(ref name) | GrantRole(_, ref name) | RevokeRole(_, ref name) => format!("role {}", name),
+    }
+}
+
+fn risk_of(change: &ChangeInstruction) -> Risk {
+    use self::ChangeInstruction::*;
+
+    match *change {
+        DropDatabase(..) | DropTable(..) | DropPartition(..) | DropColumn(..) => Risk::DataLoss,
+        // Cascading drops everything left in the schema
+        DropSchema(_, true) => Risk::DataLoss,
+        ModifyType(_, ref action) => match *action {
+            TypeModificationAction::DropAttribute { .. } => Risk::DataLoss,
+            // Rows using a removed value fail the publish, although the columns using the enum are rewritten
+            TypeModificationAction::RemoveEnumValues { ref columns, .. } => {
+                if columns.is_empty() {
+                    Risk::Safe
+                } else {
+                    Risk::TableRewrite
+                }
+            }
+            // Columns using the type or domain are checked against the change
+            TypeModificationAction::AlterAttributeType { .. }
+            | TypeModificationAction::SetDomainNotNull
+            | TypeModificationAction::AddDomainConstraint { .. } => Risk::BlockingLock,
+            _ => Risk::Safe,
+        },
+
+        // Changing the type of a column typically rewrites the table, as does computing a stored generated column
+        ModifyColumnType(..) | RecreateGeneratedColumn(..) => Risk::TableRewrite,
+        // Prior to Postgres 11 (or for volatile defaults) adding a column with a default rewrites the table
+        AddColumn(_, column) => {
+            if column
+                .constraints
+                .iter()
+                .any(|c| matches!(c, ColumnConstraint::Default(_)))
+            {
+                Risk::TableRewrite
+            } else {
+                Risk::Safe
+            }
+        }
+        // Setting NOT NULL scans the entire table while holding an exclusive lock
+        ModifyColumnNull(_, column) => {
+            if column.constraints.iter().any(|c| ColumnConstraint::NotNull.eq(c)) {
+                Risk::BlockingLock
+            } else {
+                Risk::Safe
+            }
+        }
+        ModifyColumnUniqueConstraint(..) | ModifyColumnPrimaryKeyConstraint(..) => Risk::BlockingLock,
+        // Attaching scans the partition to validate its bound and detaching locks the partitioned table
+        AttachPartition(..) | DetachPartition(..) => Risk::BlockingLock,
+        // Constraints added as NOT VALID are validated separately without blocking writes
+        AddConstraint(_, _, not_valid) => {
+            if not_valid {
+                Risk::Safe
+            } else {
+                Risk::BlockingLock
+            }
+        }
+        AddIndex(_, concurrently) | DropIndex(_, concurrently) => {
+            if concurrently {
+                Risk::Safe
+            } else {
+                Risk::BlockingLock
+            }
+        }
+        KillConnections(..) | ReplaceMaterializedView(..) => Risk::BlockingLock,
+        _ => Risk::Safe,
+    }
+}
+
+// The generation options that need to be set for the change to be generated
+fn allowed_by(change: &ChangeInstruction, changes: &[ChangeInstruction]) -> Vec<&'static str> {
+    use self::ChangeInstruction::*;
+
+    match *change {
+        // Modifying an index drops it and adds it again which isn't governed by dropping indexes
+        DropIndex(ref name, concurrently)
+            if changes
+                .iter()
+                .any(|c| matches!(*c, AddIndex(index, _) if index.fully_qualified_name().eq(name))) =>
+        {
+            if concurrently {
+                vec!["forceConcurrentIndexes"]
+            } else {
+                Vec::new()
+            }
+        }
+        // Functions that can't be replaced are dropped and created again which isn't governed by dropping functions.
+        // Those dropped because they depend on a rebuilt enum are though.
+        DropFunction(ref signature)
+            if !drops_enum_dependent(change, changes)
+                && changes
+                    .iter()
+                    .any(|c| matches!(*c, AddFunction(function) if function.signature().eq(signature))) =>
+        {
+            Vec::new()
+        }
+        DropProcedure(ref signature)
+            if !drops_enum_dependent(change, changes)
+                && changes
+                    .iter()
+                    .any(|c| matches!(*c, AddProcedure(procedure) if procedure.signature().eq(signature))) =>
+        {
+            Vec::new()
+        }
+        KillConnections(..) | DropDatabase(..) => vec!["alwaysRecreateDatabase"],
+        UpgradeExtension(..) => vec!["upgradeExtensions"],
+        ModifyType(_, TypeModificationAction::RemoveEnumValues { .. }) => vec!["dropEnumValues"],
+        DropType(..) => vec!["dropTypes"],
+        ModifyType(_, TypeModificationAction::DropAttribute { .. }) | DropColumn(..) => vec!["dropColumns"],
+        DropTable(..) => vec!["dropTables"],
+        DropPartition(..) => vec!["dropPartitions"],
+        DropConstraint(_, _, kind) => match kind {
+            TableConstraintKind::Primary => vec!["dropPrimaryKeyConstraints"],
+            TableConstraintKind::Foreign => vec!["dropForeignKeyConstraints"],
+            TableConstraintKind::Unique | TableConstraintKind::Exclude => vec!["dropUniqueConstraints"],
+            TableConstraintKind::Check => vec!["dropCheckConstraints"],
+        },
+        AddIndex(_, true) => vec!["forceConcurrentIndexes"],
+        DropIndex(_, true) => vec!["dropIndexes", "forceConcurrentIndexes"],
+        DropIndex(_, false) => vec!["dropIndexes"],
+        DropFunction(..) => vec!["dropFunctions"],
+        DropProcedure(..) => vec!["dropProcedures"],
+        DropView(..) | DropMaterializedView(..) => vec!["dropViews"],
+        DropTrigger(..) => vec!["dropTriggers"],
+        DropPolicy(..) => vec!["dropPolicies"],
+        Revoke(..) | RevokeRole(..) => vec!["revokePrivileges"],
+        DropRole(..) => vec!["dropRoles"],
+        DropSchema(_, true) => vec!["dropSchemas", "cascadeSchemaDrops"],
+        DropSchema(_, false) => vec!["dropSchemas"],
+        _ => Vec::new(),
+    }
+}
+
+// Dependents of an enum are dropped ahead of rebuilding it, whereas anything else is dropped after types are modified
+fn drops_enum_dependent(change: &ChangeInstruction, changes: &[ChangeInstruction]) -> bool {
+    changes.iter().skip_while(|c| !std::ptr::eq(*c, change)).any(|c| {
+        matches!(
+            *c,
+            ChangeInstruction::ModifyType(_, TypeModificationAction::RemoveEnumValues { .. })
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::*;
+    use crate::Semver;
+
+    use slog::{Discard, Drain, Logger};
+
+    fn empty_logger() -> Logger {
+        Logger::root(Discard.fuse(), o!())
+    }
+
+    fn contacts(columns: &[(&str, SimpleSqlType)]) -> TableDefinition {
+        TableDefinition {
+            name: ObjectName {
+                schema: Some("my".to_owned()),
+                name: "contacts".to_owned(),
+            },
+            columns: columns
+                .iter()
+                .map(|(name, sql_type)| ColumnDefinition {
+                    name: name.to_string(),
+                    sql_type: SqlType::Simple(sql_type.clone(), None),
+                    constraints: vec![ColumnConstraint::NotNull],
+                    comment: None,
+                })
+                .collect(),
+            constraints: Vec::new(),
+            partition_by: None,
+            partition_of: None,
+            row_security: false,
+            force_row_security: false,
+            comment: None,
+        }
+    }
+
+    #[test]
+    fn it_groups_changes_by_object_and_classifies_their_risk() {
+        let log = empty_logger();
+        let mut package = Package::new();
+        package.tables.push(contacts(&[
+            ("id", SimpleSqlType::BigInteger),
+            ("first_name", SimpleSqlType::Text),
+        ]));
+        package.indexes.push(IndexDefinition {
+            name: "idx_contacts_first_name".to_owned(),
+            table: package.tables[0].name.clone(),
+            columns: vec![IndexColumn {
+                name: "first_name".to_owned(),
+                order: None,
+                null_position: None,
+            }],
+            unique: false,
+            index_type: None,
+            storage_parameters: None,
+            comment: None,
+        });
+        let mut existing_database = Package::new();
+        existing_database.tables.push(contacts(&[
+            ("id", SimpleSqlType::Integer),
+            ("first_name", SimpleSqlType::Text),
+            ("last_name", SimpleSqlType::Text),
+        ]));
+        let capabilities = Capabilities {
+            server_version: Semver::new(9, 6, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_columns = Toggle::Allow;
+
+        let delta = Delta::generate(
+            &log,
+            &package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        )
+        .unwrap();
+        let report = delta.report(&log);
+        assert_eq!(report.version, REPORT_VERSION);
+        assert_eq!(report.database, Some("dbname".to_owned()));
+        assert_eq!(
+            report.summary,
+            ReportSummary {
+                objects: 2,
+                changes: 3,
+                safe: 1,
+                blocking_lock: 0,
+                table_rewrite: 1,
+                data_loss: 1,
+            }
+        );
+
+        // Column changes are grouped with the table and the most severe risk wins
+        let table = &report.objects[0];
+        assert_eq!(table.object, "table my.contacts");
+        assert_eq!(table.risk, Risk::DataLoss);
+        assert_eq!(table.changes.len(), 2);
+        assert_eq!(
+            table.changes[0].description,
+            "Drop column: last_name on table: my.contacts"
+        );
+        assert_eq!(table.changes[0].risk, Risk::DataLoss);
+        assert_eq!(table.changes[0].allowed_by, vec!["dropColumns"]);
+        assert_eq!(table.changes[1].risk, Risk::TableRewrite);
+        assert!(table.changes[1].allowed_by.is_empty());
+
+        let index = &report.objects[1];
+        assert_eq!(index.object, "index my.idx_contacts_first_name");
+        assert_eq!(index.risk, Risk::Safe);
+        assert_eq!(index.changes[0].allowed_by, vec!["forceConcurrentIndexes"]);
+
+        // The serialized report uses snake case risks
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["objects"][0]["risk"], "data_loss");
+        assert_eq!(json["summary"]["table_rewrite"], 1);
+
+        let markdown = report.to_markdown();
+        assert!(markdown.contains("| data loss | 1 |\n"));
+        assert!(markdown.contains("\n## table my.contacts (data loss)\n"));
+        assert!(markdown.contains("| Drop column: last_name on table: my.contacts | data loss | `dropColumns` |\n"));
+
+        let html = report.to_html();
+        assert!(html.contains("<h2>table my.contacts <span class=\"data_loss\">(data loss)</span></h2>"));
+        assert!(html.contains("<td class=\"safe\">safe</td><td><code>forceConcurrentIndexes</code></td>"));
+    }
+
+    #[test]
+    fn it_attributes_dropping_the_dependents_of_a_rebuilt_enum_to_their_options() {
+        let log = empty_logger();
+        let colors_type = |values: &[&str]| TypeDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "colors".to_owned(),
+            },
+            kind: TypeDefinitionKind::Enum(values.iter().map(|v| v.to_string()).collect()),
+            comment: None,
+        };
+        let colors = || {
+            SqlType::Custom(
+                ObjectName {
+                    schema: Some("public".to_owned()),
+                    name: "colors".to_owned(),
+                },
+                Vec::new(),
+                None,
+            )
+        };
+        let view = || ViewDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "palette".to_owned(),
+            },
+            columns: None,
+            query: "SELECT 'green'::public.colors AS color".to_owned(),
+            comment: None,
+        };
+        let function = || FunctionDefinition {
+            name: ObjectName {
+                schema: Some("public".to_owned()),
+                name: "is_green".to_owned(),
+            },
+            arguments: vec![FunctionArgument {
+                mode: None,
+                name: Some("color".to_owned()),
+                sql_type: colors(),
+                default: None,
+            }],
+            return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Boolean, None)),
+            body: "SELECT color = 'green'".to_owned(),
+            language: FunctionLanguage::SQL,
+            volatility: FunctionVolatility::Immutable,
+            strict: false,
+            security_definer: false,
+            parallel: FunctionParallel::Unsafe,
+            cost: None,
+            rows: None,
+            settings: Vec::new(),
+            comment: None,
+        };
+        let mut package = Package::new();
+        package.types.push(colors_type(&["green", "blue"]));
+        package.views.push(view());
+        package.functions.push(function());
+        let mut existing_database = Package::new();
+        existing_database.types.push(colors_type(&["green", "blue", "red"]));
+        existing_database.views.push(view());
+        existing_database.functions.push(function());
+        existing_database.type_dependents = vec![
+            TypeDependent {
+                type_name: colors_type(&[]).name,
+                kind: TypeDependentKind::View,
+                name: "public.palette".to_owned(),
+                depth: 1,
+            },
+            TypeDependent {
+                type_name: colors_type(&[]).name,
+                kind: TypeDependentKind::Function,
+                name: "public.is_green(public.colors)".to_owned(),
+                depth: 1,
+            },
+        ];
+        let capabilities = Capabilities {
+            server_version: Semver::new(10, 0, None),
+            extensions: Vec::new(),
+            database_exists: true,
+        };
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_enum_values = Toggle::Allow;
+        publish_profile.generation_options.drop_views = Toggle::Allow;
+        publish_profile.generation_options.drop_functions = Toggle::Allow;
+
+        // The dependents are created again afterwards, however dropping them still relies on their options
+        let delta = Delta::generate(
+            &log,
+            &package,
+            Some(existing_database),
+            "dbname",
+            &capabilities,
+            &publish_profile,
+        )
+        .unwrap();
+        let report = delta.report(&log);
+        let allowed_by = |sql: &str| {
+            report
+                .objects
+                .iter()
+                .flat_map(|o| o.changes.iter())
+                .find(|c| c.sql.eq(sql))
+                .map(|c| c.allowed_by.clone())
+                .unwrap()
+        };
+        assert_eq!(allowed_by("DROP VIEW IF EXISTS public.palette"), vec!["dropViews"]);
+        assert_eq!(
+            allowed_by("DROP FUNCTION IF EXISTS public.is_green(public.colors)"),
+            vec!["dropFunctions"]
+        );
+    }
+
+    #[test]
+    fn it_infers_the_report_format_from_the_file_extension() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("out/report.html")),
+            ReportFormat::Html
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("out/report.MD")),
+            ReportFormat::Markdown
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("out/report.json")),
+            ReportFormat::Json
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("out/example.report")),
+            ReportFormat::Json
+        );
+        assert!("yaml".parse::<ReportFormat>().is_err());
+    }
+}
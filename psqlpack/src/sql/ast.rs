@@ -1,4 +1,5 @@
 #![allow(clippy::upper_case_acronyms)]
+use regex::Regex;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
@@ -23,12 +24,22 @@ impl fmt::Display for ErrorKind {
 
 #[derive(Debug, PartialEq)]
 pub enum Statement {
+    AlterTable(ObjectName, AlterTableAction),
+    Comment(CommentDefinition),
     Error(ErrorKind),
     Function(FunctionDefinition),
+    Grant(Vec<GrantDefinition>),
     Index(IndexDefinition),
+    MaterializedView(MaterializedViewDefinition),
+    Policy(PolicyDefinition),
+    Procedure(ProcedureDefinition),
+    Revoke(Vec<GrantDefinition>),
     Schema(SchemaDefinition),
+    Sequence(SequenceDefinition),
     Table(TableDefinition),
+    Trigger(TriggerDefinition),
     Type(TypeDefinition),
+    View(ViewDefinition),
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
@@ -47,11 +58,13 @@ pub enum TypeModifier {
 pub enum SimpleSqlType {
     FixedLengthString(u32),    // char(size)
     VariableLengthString(u32), // varchar(size)
+    UnsizedFixedLengthString,  // bpchar
     UnsizedVariableLengthString,
     Text, // text
 
     FixedLengthBitString(u32),    // bit(size)
     VariableLengthBitString(u32), // varbit(size)
+    UnsizedVariableLengthBitString,
 
     SmallInteger, // smallint
     Integer,      // int
@@ -68,25 +81,66 @@ pub enum SimpleSqlType {
 
     Boolean, // bool
 
-    Date,                 // date
-    DateTime,             // timestamp without time zone
-    DateTimeWithTimeZone, // timestamp with time zone
-    Time,                 // time
-    TimeWithTimeZone,     // time with time zone
+    Date,                              // date
+    DateTime(Option<u32>),             // timestamp(p) without time zone
+    DateTimeWithTimeZone(Option<u32>), // timestamp(p) with time zone
+    Time(Option<u32>),                 // time(p)
+    TimeWithTimeZone(Option<u32>),     // time(p) with time zone
+    Interval(Option<u32>),             // interval(p)
 
     Uuid, // uuid
 
     Json,
     Jsonb,
+
+    Binary,           // bytea
+    ObjectIdentifier, // oid
+    Xml,              // xml
+
+    Inet,        // inet
+    Cidr,        // cidr
+    MacAddress,  // macaddr
+    MacAddress8, // macaddr8
+
+    TextSearchVector, // tsvector
+    TextSearchQuery,  // tsquery
+
+    Point,       // point
+    Line,        // line
+    LineSegment, // lseg
+    Box,         // box
+    Path,        // path
+    Polygon,     // polygon
+    Circle,      // circle
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum ColumnConstraint {
-    Default(AnyValue),
+    Default(Expression),
     NotNull,
     Null,
     Unique,
     PrimaryKey,
+    Identity(IdentityDefinition),
+    // Generated columns are always stored
+    Generated(Expression),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum IdentityGeneration {
+    Always,
+    ByDefault,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct IdentityDefinition {
+    pub generation: IdentityGeneration,
+    pub increment: Option<i64>,
+    pub min_value: Option<i64>,
+    pub max_value: Option<i64>,
+    pub start: Option<i64>,
+    pub cache: Option<i64>,
+    pub cycle: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
@@ -100,6 +154,18 @@ pub enum AnyValue {
     Null(Option<SqlType>),
 }
 
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum Expression {
+    Literal(AnyValue),                                        // 1, 'text', NULL, ARRAY[1, 2]
+    Keyword(String),                                          // CURRENT_TIMESTAMP
+    Column(String),                                           // name
+    FunctionCall(ObjectName, Vec<Expression>),                // now()
+    UnaryOperator(String, Box<Expression>),                   // -x, NOT x
+    BinaryOperator(Box<Expression>, String, Box<Expression>), // x + y
+    Cast(Box<Expression>, SqlType),                           // x::type
+    Nested(Box<Expression>),                                  // (x)
+}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum IndexParameter {
     FillFactor(u32),
@@ -120,13 +186,111 @@ pub enum TableConstraint {
         match_type: Option<ForeignConstraintMatchType>,
         events: Option<Vec<ForeignConstraintEvent>>,
     },
+    Unique {
+        name: String,
+        columns: Vec<String>,
+        parameters: Option<Vec<IndexParameter>>,
+    },
+    Check {
+        name: String,
+        expression: String,
+        no_inherit: bool,
+    },
+    Exclude {
+        name: String,
+        index_type: Option<IndexType>,
+        elements: Vec<ExclusionElement>,
+        parameters: Option<Vec<IndexParameter>>,
+        condition: Option<String>,
+    },
 }
 
 impl TableConstraint {
     pub fn name(&self) -> &str {
         match *self {
-            TableConstraint::Primary { ref name, .. } | TableConstraint::Foreign { ref name, .. } => name,
+            TableConstraint::Primary { ref name, .. }
+            | TableConstraint::Foreign { ref name, .. }
+            | TableConstraint::Unique { ref name, .. }
+            | TableConstraint::Check { ref name, .. }
+            | TableConstraint::Exclude { ref name, .. } => name,
+        }
+    }
+
+    pub fn kind(&self) -> TableConstraintKind {
+        match *self {
+            TableConstraint::Primary { .. } => TableConstraintKind::Primary,
+            TableConstraint::Foreign { .. } => TableConstraintKind::Foreign,
+            TableConstraint::Unique { .. } => TableConstraintKind::Unique,
+            TableConstraint::Check { .. } => TableConstraintKind::Check,
+            TableConstraint::Exclude { .. } => TableConstraintKind::Exclude,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum TableConstraintKind {
+    Primary,
+    Foreign,
+    Unique,
+    Check,
+    Exclude,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct ExclusionElement {
+    pub expression: String,
+    pub operator: String,
+}
+
+impl ExclusionElement {
+    // Exclusion elements are captured verbatim, e.g. `room WITH =, during WITH &&`
+    pub fn parse_list(elements: &str) -> Vec<ExclusionElement> {
+        let mut items = Vec::new();
+        let mut item = String::new();
+        let mut depth = 0;
+        let mut quote = None;
+        for c in elements.chars() {
+            match quote {
+                Some(q) if q == c => quote = None,
+                Some(_) => {}
+                None => match c {
+                    '\'' | '"' => quote = Some(c),
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    ',' if depth == 0 => {
+                        items.push(item.clone());
+                        item.clear();
+                        continue;
+                    }
+                    _ => {}
+                },
+            }
+            item.push(c);
         }
+        items.push(item);
+
+        items
+            .iter()
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| {
+                let item = item.trim();
+                match item.to_lowercase().rfind(" with ") {
+                    Some(position) => ExclusionElement {
+                        expression: item[..position].trim().to_owned(),
+                        operator: item[position + 6..].trim().to_owned(),
+                    },
+                    None => ExclusionElement {
+                        expression: item.to_owned(),
+                        operator: String::new(),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    pub fn is_same_element(&self, other: &ExclusionElement) -> bool {
+        self.operator.eq(&other.operator)
+            && normalize_expression(&self.expression).eq(&normalize_expression(&other.expression))
     }
 }
 
@@ -158,6 +322,193 @@ pub struct ObjectName {
     pub name: String,
 }
 
+impl SqlType {
+    /// The type without any modifiers, sizes or array dimensions. Postgres ignores these when identifying a function
+    /// by its argument types.
+    pub fn identity(&self) -> SqlType {
+        match *self {
+            SqlType::Simple(ref simple_type, dim) => SqlType::Simple(simple_type.identity(), dim.map(|_| 1)),
+            SqlType::Custom(ref name, _, dim) => SqlType::Custom(name.clone(), Vec::new(), dim.map(|_| 1)),
+        }
+    }
+}
+
+impl SimpleSqlType {
+    fn identity(&self) -> SimpleSqlType {
+        match *self {
+            SimpleSqlType::FixedLengthString(_) => SimpleSqlType::UnsizedFixedLengthString,
+            SimpleSqlType::VariableLengthString(_) => SimpleSqlType::UnsizedVariableLengthString,
+            SimpleSqlType::FixedLengthBitString(_) => SimpleSqlType::FixedLengthBitString(1),
+            SimpleSqlType::VariableLengthBitString(_) => SimpleSqlType::UnsizedVariableLengthBitString,
+            SimpleSqlType::Numeric(_) => SimpleSqlType::Numeric(None),
+            SimpleSqlType::DateTime(_) => SimpleSqlType::DateTime(None),
+            SimpleSqlType::DateTimeWithTimeZone(_) => SimpleSqlType::DateTimeWithTimeZone(None),
+            SimpleSqlType::Time(_) => SimpleSqlType::Time(None),
+            SimpleSqlType::TimeWithTimeZone(_) => SimpleSqlType::TimeWithTimeZone(None),
+            SimpleSqlType::Interval(_) => SimpleSqlType::Interval(None),
+            ref simple_type => simple_type.clone(),
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            *self,
+            SimpleSqlType::SmallInteger
+                | SimpleSqlType::Integer
+                | SimpleSqlType::BigInteger
+                | SimpleSqlType::SmallSerial
+                | SimpleSqlType::Serial
+                | SimpleSqlType::BigSerial
+                | SimpleSqlType::Numeric(_)
+                | SimpleSqlType::Double
+                | SimpleSqlType::Single
+        )
+    }
+}
+
+impl AnyValue {
+    pub fn cast(&self) -> Option<&SqlType> {
+        match *self {
+            AnyValue::Array(_, ref sql_type)
+            | AnyValue::Boolean(_, ref sql_type)
+            | AnyValue::Decimal(_, ref sql_type)
+            | AnyValue::Integer(_, ref sql_type)
+            | AnyValue::String(_, ref sql_type)
+            | AnyValue::Null(ref sql_type) => sql_type.as_ref(),
+        }
+    }
+
+    pub fn with_cast(self, cast: Option<SqlType>) -> AnyValue {
+        match self {
+            AnyValue::Array(items, _) => AnyValue::Array(items, cast),
+            AnyValue::Boolean(b, _) => AnyValue::Boolean(b, cast),
+            AnyValue::Decimal(d, _) => AnyValue::Decimal(d, cast),
+            AnyValue::Integer(i, _) => AnyValue::Integer(i, cast),
+            AnyValue::String(s, _) => AnyValue::String(s, cast),
+            AnyValue::Null(_) => AnyValue::Null(cast),
+        }
+    }
+
+    // Postgres describes a negative number as a string cast to the numeric type, e.g. '-1'::integer
+    fn normalize(&self) -> AnyValue {
+        match *self {
+            AnyValue::Array(ref items, _) => AnyValue::Array(items.iter().map(|i| i.normalize()).collect(), None),
+            AnyValue::String(ref s, Some(SqlType::Simple(ref simple, None))) if simple.is_numeric() => {
+                if let Ok(i) = s.parse::<i32>() {
+                    AnyValue::Integer(i, None)
+                } else if let Ok(d) = s.parse::<Decimal>() {
+                    AnyValue::Decimal(d, None)
+                } else {
+                    AnyValue::String(s.to_owned(), None)
+                }
+            }
+            ref value => value.clone().with_cast(None),
+        }
+    }
+}
+
+impl Expression {
+    // A cast directly on a literal is kept with the literal, e.g. '{}'::text[]
+    pub(crate) fn cast(expression: Expression, sql_type: SqlType) -> Expression {
+        match expression {
+            Expression::Literal(value) if value.cast().is_none() => {
+                Expression::Literal(value.with_cast(Some(sql_type)))
+            }
+            Expression::Nested(nested) => match *nested {
+                Expression::Literal(value) if value.cast().is_none() => {
+                    Expression::Literal(value.with_cast(Some(sql_type)))
+                }
+                nested => Expression::Cast(Box::new(Expression::Nested(Box::new(nested))), sql_type),
+            },
+            expression => Expression::Cast(Box::new(expression), sql_type),
+        }
+    }
+
+    pub(crate) fn unary(operator: &str, expression: Expression) -> Expression {
+        Expression::UnaryOperator(operator.to_owned(), Box::new(expression))
+    }
+
+    pub(crate) fn binary(left: Expression, operator: &str, right: Expression) -> Expression {
+        Expression::BinaryOperator(Box::new(left), operator.to_owned(), Box::new(right))
+    }
+
+    /// Postgres describes an expression with explicit casts on literals, additional brackets and
+    /// qualified or alternate names for some functions. This reduces an expression to a form that
+    /// can be compared with an expression written within a project.
+    pub fn normalize(&self) -> Expression {
+        match *self {
+            Expression::Literal(ref value) => Expression::Literal(value.normalize()),
+            Expression::Keyword(ref keyword) => match &keyword[..] {
+                "CURRENT_TIMESTAMP" => Expression::FunctionCall(
+                    ObjectName {
+                        schema: None,
+                        name: "now".into(),
+                    },
+                    Vec::new(),
+                ),
+                _ => Expression::Keyword(keyword.to_owned()),
+            },
+            Expression::Column(ref name) => Expression::Column(name.to_lowercase()),
+            Expression::FunctionCall(ref name, ref arguments) => {
+                let schema = name
+                    .schema
+                    .as_ref()
+                    .map(|schema| schema.to_lowercase())
+                    .filter(|schema| schema != "pg_catalog");
+                let name = match &name.name.to_lowercase()[..] {
+                    "transaction_timestamp" => "now".to_owned(),
+                    name => name.to_owned(),
+                };
+                Expression::FunctionCall(
+                    ObjectName { schema, name },
+                    arguments.iter().map(|argument| argument.normalize()).collect(),
+                )
+            }
+            Expression::UnaryOperator(ref operator, ref expression) => match (&operator[..], expression.normalize()) {
+                ("+", expression) => expression,
+                ("-", Expression::Literal(AnyValue::Integer(i, None))) => {
+                    Expression::Literal(AnyValue::Integer(-i, None))
+                }
+                ("-", Expression::Literal(AnyValue::Decimal(d, None))) => {
+                    Expression::Literal(AnyValue::Decimal(-d, None))
+                }
+                (operator, expression) => Expression::unary(operator, expression),
+            },
+            Expression::BinaryOperator(ref left, ref operator, ref right) => {
+                Expression::binary(left.normalize(), operator, right.normalize())
+            }
+            Expression::Cast(ref expression, ref sql_type) => {
+                match Expression::cast(expression.normalize(), sql_type.clone()) {
+                    Expression::Literal(value) => Expression::Literal(value.normalize()),
+                    expression => expression,
+                }
+            }
+            Expression::Nested(ref expression) => expression.normalize(),
+        }
+    }
+
+    /// Postgres adds implicit casts to the expression of a generated column depending on the types of the
+    /// columns it references. These types aren't known to the expression so generated columns are compared
+    /// once normalized and without any casts.
+    pub fn without_casts(&self) -> Expression {
+        match *self {
+            Expression::Literal(ref value) => Expression::Literal(value.clone().with_cast(None)),
+            Expression::FunctionCall(ref name, ref arguments) => Expression::FunctionCall(
+                name.clone(),
+                arguments.iter().map(|argument| argument.without_casts()).collect(),
+            ),
+            Expression::UnaryOperator(ref operator, ref expression) => {
+                Expression::unary(operator, expression.without_casts())
+            }
+            Expression::BinaryOperator(ref left, ref operator, ref right) => {
+                Expression::binary(left.without_casts(), operator, right.without_casts())
+            }
+            Expression::Cast(ref expression, _) | Expression::Nested(ref expression) => expression.without_casts(),
+            ref expression => expression.clone(),
+        }
+    }
+}
+
 impl ObjectName {
     pub fn schema(&self) -> &str {
         if let Some(ref schema) = self.schema {
@@ -168,120 +519,1258 @@ impl ObjectName {
     }
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub struct TableDefinition {
-    pub name: ObjectName,
-    pub columns: Vec<ColumnDefinition>,
-    pub constraints: Vec<TableConstraint>,
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct TableDefinition {
+    pub name: ObjectName,
+    pub columns: Vec<ColumnDefinition>,
+    pub constraints: Vec<TableConstraint>,
+    pub partition_by: Option<PartitionKey>,
+    pub partition_of: Option<PartitionOf>,
+    pub row_security: bool,
+    pub force_row_security: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// The table alterations that are supported within a project. These are applied to the table definition once the
+/// project has been loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlterTableAction {
+    EnableRowSecurity,
+    DisableRowSecurity,
+    ForceRowSecurity,
+    NoForceRowSecurity,
+}
+
+impl TableDefinition {
+    pub fn alter(&mut self, action: AlterTableAction) {
+        match action {
+            AlterTableAction::EnableRowSecurity => self.row_security = true,
+            AlterTableAction::DisableRowSecurity => self.row_security = false,
+            AlterTableAction::ForceRowSecurity => self.force_row_security = true,
+            AlterTableAction::NoForceRowSecurity => self.force_row_security = false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct PartitionKey {
+    pub strategy: PartitionStrategy,
+    pub columns: Vec<Expression>,
+}
+
+impl PartitionKey {
+    pub fn normalize(&self) -> PartitionKey {
+        PartitionKey {
+            strategy: self.strategy,
+            columns: self.columns.iter().map(|c| c.normalize()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum PartitionStrategy {
+    Range,
+    List,
+    Hash,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct PartitionOf {
+    pub parent: ObjectName,
+    pub bound: PartitionBound,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum PartitionBound {
+    Range {
+        from: Vec<PartitionRangeValue>,
+        to: Vec<PartitionRangeValue>,
+    },
+    List(Vec<Expression>),
+    Hash {
+        modulus: i32,
+        remainder: i32,
+    },
+    Default,
+}
+
+impl PartitionBound {
+    pub fn normalize(&self) -> PartitionBound {
+        fn normalize_range(values: &[PartitionRangeValue]) -> Vec<PartitionRangeValue> {
+            values
+                .iter()
+                .map(|v| match v {
+                    PartitionRangeValue::Value(expr) => PartitionRangeValue::Value(expr.normalize()),
+                    other => other.clone(),
+                })
+                .collect()
+        }
+        match self {
+            PartitionBound::Range { from, to } => PartitionBound::Range {
+                from: normalize_range(from),
+                to: normalize_range(to),
+            },
+            PartitionBound::List(values) => PartitionBound::List(values.iter().map(|v| v.normalize()).collect()),
+            other => other.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum PartitionRangeValue {
+    MinValue,
+    MaxValue,
+    Value(Expression),
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub sql_type: SqlType,
+    pub constraints: Vec<ColumnConstraint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl ColumnDefinition {
+    pub fn default_value(&self) -> Option<&Expression> {
+        self.constraints.iter().find_map(|c| match c {
+            ColumnConstraint::Default(expr) => Some(expr),
+            _ => None,
+        })
+    }
+
+    pub fn identity(&self) -> Option<&IdentityDefinition> {
+        self.constraints.iter().find_map(|c| match c {
+            ColumnConstraint::Identity(identity) => Some(identity),
+            _ => None,
+        })
+    }
+
+    pub fn generated(&self) -> Option<&Expression> {
+        self.constraints.iter().find_map(|c| match c {
+            ColumnConstraint::Generated(expr) => Some(expr),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaDefinition {
+    pub name: String,
+    /// The role that owns the schema. Ownership is left alone when this isn't set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TypeDefinition {
+    pub name: ObjectName,
+    pub kind: TypeDefinitionKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum TypeDefinitionKind {
+    Composite(Vec<TypeAttribute>),
+    Domain(DomainDefinition),
+    Enum(Vec<String>),
+    Range(RangeDefinition),
+    UserDefined,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TypeAttribute {
+    pub name: String,
+    pub sql_type: SqlType,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RangeDefinition {
+    pub subtype: SqlType,
+    pub subtype_opclass: Option<String>,
+    pub collation: Option<String>,
+    pub canonical: Option<ObjectName>,
+    pub subtype_diff: Option<ObjectName>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DomainDefinition {
+    pub sql_type: SqlType,
+    pub default: Option<Expression>,
+    pub not_null: bool,
+    pub constraints: Vec<DomainConstraint>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DomainConstraint {
+    pub name: Option<String>,
+    pub expression: String,
+}
+
+impl DomainConstraint {
+    pub fn is_same_expression(&self, other: &DomainConstraint) -> bool {
+        normalize_expression(&self.expression).eq(&normalize_expression(&other.expression))
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScriptDefinition {
+    pub name: String,
+    pub kind: ScriptKind,
+    pub order: usize,
+    pub contents: String,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum ScriptKind {
+    PreDeployment,
+    PostDeployment,
+}
+
+/// A rename hint from the project refactor log. Object names are qualified with the default schema when the
+/// package is built; the new name is never schema qualified since renaming can't move an object between schemas.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum RenameDefinition {
+    Table { from: String, to: String },
+    Column { table: String, from: String, to: String },
+    Index { from: String, to: String },
+    Constraint { table: String, from: String, to: String },
+    Type { from: String, to: String },
+}
+
+impl RenameDefinition {
+    pub fn qualify(&mut self, default_schema: &str) {
+        let name = match *self {
+            RenameDefinition::Table { ref mut from, .. }
+            | RenameDefinition::Index { ref mut from, .. }
+            | RenameDefinition::Type { ref mut from, .. } => from,
+            RenameDefinition::Column { ref mut table, .. } | RenameDefinition::Constraint { ref mut table, .. } => {
+                table
+            }
+        };
+        if !name.contains('.') {
+            *name = format!("{}.{}", default_schema, name);
+        }
+    }
+}
+
+/// A `COMMENT ON` statement. Comments are attached to the objects they describe once the project is loaded, with a
+/// `NULL` comment removing any previous one.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommentDefinition {
+    pub object: CommentObject,
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CommentObject {
+    Column(ObjectName, String),
+    // The argument types identify an overloaded function. Without them the function name must be unique.
+    Function(ObjectName, Option<Vec<SqlType>>),
+    Index(ObjectName),
+    MaterializedView(ObjectName),
+    // Procedures are identified in the same way as functions
+    Procedure(ObjectName, Option<Vec<SqlType>>),
+    Schema(String),
+    Sequence(ObjectName),
+    Table(ObjectName),
+    Type(ObjectName),
+    View(ObjectName),
+}
+
+impl CommentObject {
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            CommentObject::Column(..) => "column",
+            CommentObject::Function(..) => "function",
+            CommentObject::Index(_) => "index",
+            CommentObject::MaterializedView(_) => "materialized view",
+            CommentObject::Procedure(..) => "procedure",
+            CommentObject::Schema(_) => "schema",
+            CommentObject::Sequence(_) => "sequence",
+            CommentObject::Table(_) => "table",
+            CommentObject::Type(_) => "type",
+            CommentObject::View(_) => "view",
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match *self {
+            CommentObject::Column(ref table, ref column) => format!("{}.{}", table, column),
+            CommentObject::Schema(ref name) => name.to_owned(),
+            CommentObject::Function(ref name, Some(ref argument_types))
+            | CommentObject::Procedure(ref name, Some(ref argument_types)) => signature(name, argument_types),
+            CommentObject::Function(ref name, None)
+            | CommentObject::Procedure(ref name, None)
+            | CommentObject::Index(ref name)
+            | CommentObject::MaterializedView(ref name)
+            | CommentObject::Sequence(ref name)
+            | CommentObject::Table(ref name)
+            | CommentObject::Type(ref name)
+            | CommentObject::View(ref name) => name.to_string(),
+        }
+    }
+}
+
+/// A role from the project roles section. Roles are global to the cluster so they're only managed when a project
+/// opts in by declaring them. Passwords are deliberately unsupported so that they never end up within a package.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoleDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub login: bool,
+    #[serde(default)]
+    pub superuser: bool,
+    #[serde(alias = "createDb", default)]
+    pub create_db: bool,
+    #[serde(alias = "createRole", default)]
+    pub create_role: bool,
+    #[serde(default = "RoleDefinition::default_inherit")]
+    pub inherit: bool,
+    #[serde(default)]
+    pub replication: bool,
+    #[serde(alias = "bypassRls", default)]
+    pub bypass_rls: bool,
+    #[serde(alias = "connectionLimit", default = "RoleDefinition::default_connection_limit")]
+    pub connection_limit: i32,
+    /// The roles that this role is a member of
+    #[serde(alias = "memberOf", default)]
+    pub member_of: Vec<String>,
+}
+
+impl RoleDefinition {
+    fn default_inherit() -> bool {
+        true
+    }
+
+    fn default_connection_limit() -> i32 {
+        -1
+    }
+
+    pub fn has_same_attributes(&self, other: &RoleDefinition) -> bool {
+        self.login == other.login
+            && self.superuser == other.superuser
+            && self.create_db == other.create_db
+            && self.create_role == other.create_role
+            && self.inherit == other.inherit
+            && self.replication == other.replication
+            && self.bypass_rls == other.bypass_rls
+            && self.connection_limit == other.connection_limit
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    pub name: ObjectName,
+    pub arguments: Vec<FunctionArgument>,
+    pub return_type: FunctionReturnType,
+    pub body: String,
+    pub language: FunctionLanguage,
+    #[serde(default)]
+    pub volatility: FunctionVolatility,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub security_definer: bool,
+    #[serde(default)]
+    pub parallel: FunctionParallel,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub settings: Vec<FunctionSetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl FunctionDefinition {
+    /// The types of the input arguments which, along with the name, identify the function
+    pub fn argument_types(&self) -> Vec<SqlType> {
+        FunctionArgument::identity(&self.arguments)
+    }
+
+    /// The name and argument types of the function, e.g. `public.add(int, int)`
+    pub fn signature(&self) -> String {
+        signature(&self.name, &self.argument_types())
+    }
+
+    /// Whether CREATE OR REPLACE can turn the existing function into this one. Postgres rejects changing the return
+    /// type, renaming an input argument or removing an argument default.
+    pub fn can_replace(&self, existing: &FunctionDefinition) -> bool {
+        self.return_type.identity() == existing.return_type.identity()
+            && FunctionArgument::can_replace(&self.arguments, &existing.arguments)
+    }
+
+    pub fn is_same_definition(&self, other: &FunctionDefinition) -> bool {
+        // Function bodies are compared ignoring whitespace differences and defaults once normalized
+        FunctionArgument::is_same_list(&self.arguments, &other.arguments)
+            && self.return_type.eq(&other.return_type)
+            && self.language.eq(&other.language)
+            && self.body.split_whitespace().eq(other.body.split_whitespace())
+            && self.volatility.eq(&other.volatility)
+            && self.strict == other.strict
+            && self.security_definer == other.security_definer
+            && self.parallel.eq(&other.parallel)
+            && self.estimated_cost() == other.estimated_cost()
+            && self.estimated_rows() == other.estimated_rows()
+            && self.settings.eq(&other.settings)
+    }
+
+    // Postgres assumes a cost of 1 for C and internal functions and 100 for everything else
+    fn estimated_cost(&self) -> Decimal {
+        self.cost.unwrap_or(match self.language {
+            FunctionLanguage::C | FunctionLanguage::Internal => Decimal::ONE,
+            _ => Decimal::ONE_HUNDRED,
+        })
+    }
+
+    // Only set returning functions estimate rows, which default to 1000
+    fn estimated_rows(&self) -> Decimal {
+        self.rows.unwrap_or(match self.return_type {
+            FunctionReturnType::SqlType(_) => Decimal::ZERO,
+            _ => Decimal::ONE_THOUSAND,
+        })
+    }
+
+    pub fn apply(&mut self, option: FunctionOption) {
+        match option {
+            FunctionOption::Body(body) => self.body = body,
+            FunctionOption::Language(language) => self.language = language,
+            FunctionOption::Volatility(volatility) => self.volatility = volatility,
+            FunctionOption::Strict(strict) => self.strict = strict,
+            FunctionOption::SecurityDefiner(security_definer) => self.security_definer = security_definer,
+            FunctionOption::Parallel(parallel) => self.parallel = parallel,
+            FunctionOption::Cost(cost) => self.cost = Some(cost),
+            FunctionOption::Rows(rows) => self.rows = Some(rows),
+            FunctionOption::Set(setting) => {
+                self.settings.retain(|s| s.name != setting.name);
+                self.settings.push(setting);
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Default, Serialize, Deserialize)]
+pub enum FunctionVolatility {
+    Immutable,
+    Stable,
+    #[default]
+    Volatile,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Default, Serialize, Deserialize)]
+pub enum FunctionParallel {
+    Safe,
+    Restricted,
+    #[default]
+    Unsafe,
+}
+
+// A configuration parameter set while the function runs. List parameters such as search_path have many values.
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct FunctionSetting {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct ProcedureDefinition {
+    pub name: ObjectName,
+    pub arguments: Vec<FunctionArgument>,
+    pub body: String,
+    pub language: FunctionLanguage,
+    #[serde(default)]
+    pub security_definer: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub settings: Vec<FunctionSetting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl ProcedureDefinition {
+    /// The types of the input arguments which, along with the name, identify the procedure
+    pub fn argument_types(&self) -> Vec<SqlType> {
+        FunctionArgument::identity(&self.arguments)
+    }
+
+    /// The name and argument types of the procedure, e.g. `public.archive(date)`
+    pub fn signature(&self) -> String {
+        signature(&self.name, &self.argument_types())
+    }
+
+    /// Whether CREATE OR REPLACE can turn the existing procedure into this one
+    pub fn can_replace(&self, existing: &ProcedureDefinition) -> bool {
+        FunctionArgument::can_replace(&self.arguments, &existing.arguments)
+    }
+
+    pub fn is_same_definition(&self, other: &ProcedureDefinition) -> bool {
+        FunctionArgument::is_same_list(&self.arguments, &other.arguments)
+            && self.language.eq(&other.language)
+            && self.body.split_whitespace().eq(other.body.split_whitespace())
+            && self.security_definer == other.security_definer
+            && self.settings.eq(&other.settings)
+    }
+
+    // Procedures share their options with functions however only some of them apply
+    pub fn apply(&mut self, option: FunctionOption) -> Result<(), &'static str> {
+        match option {
+            FunctionOption::Body(body) => self.body = body,
+            FunctionOption::Language(language) => self.language = language,
+            FunctionOption::SecurityDefiner(security_definer) => self.security_definer = security_definer,
+            FunctionOption::Set(setting) => {
+                self.settings.retain(|s| s.name != setting.name);
+                self.settings.push(setting);
+            }
+            _ => return Err("Procedures only support a body, language, security and SET clauses"),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FunctionOption {
+    Body(String),
+    Language(FunctionLanguage),
+    Volatility(FunctionVolatility),
+    Strict(bool),
+    SecurityDefiner(bool),
+    Parallel(FunctionParallel),
+    Cost(Decimal),
+    Rows(Decimal),
+    Set(FunctionSetting),
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct FunctionArgument {
+    pub mode: Option<FunctionArgumentMode>,
+    pub name: Option<String>,
+    pub sql_type: SqlType,
+    pub default: Option<Expression>,
+}
+
+impl FunctionArgument {
+    /// Output arguments aren't part of the function signature
+    pub fn is_input(&self) -> bool {
+        !matches!(self.mode, Some(FunctionArgumentMode::Out))
+    }
+
+    /// The types of the input arguments within the list
+    pub fn identity(arguments: &[FunctionArgument]) -> Vec<SqlType> {
+        arguments
+            .iter()
+            .filter(|a| a.is_input())
+            .map(|a| a.sql_type.identity())
+            .collect()
+    }
+
+    // Postgres rejects renaming an input argument or removing an argument default when replacing a routine
+    fn can_replace(arguments: &[FunctionArgument], existing: &[FunctionArgument]) -> bool {
+        let inputs = |arguments: &[FunctionArgument]| {
+            arguments
+                .iter()
+                .filter(|a| a.is_input())
+                .map(|a| (a.name.clone(), a.default.is_some()))
+                .collect::<Vec<_>>()
+        };
+        !inputs(arguments).into_iter().zip(inputs(existing)).any(
+            |((name, default), (existing_name, existing_default))| {
+                (existing_name.is_some() && name != existing_name) || (existing_default && !default)
+            },
+        )
+    }
+
+    fn is_same_list(arguments: &[FunctionArgument], other: &[FunctionArgument]) -> bool {
+        arguments.len() == other.len() && arguments.iter().zip(other.iter()).all(|(a, b)| a.is_same_definition(b))
+    }
+
+    pub fn is_same_definition(&self, other: &FunctionArgument) -> bool {
+        // IN is the default mode and is reported explicitly for procedures
+        let is_default = |mode: &&FunctionArgumentMode| FunctionArgumentMode::In.eq(*mode);
+        self.mode.as_ref().filter(|m| !is_default(m)) == other.mode.as_ref().filter(|m| !is_default(m))
+            && self.name.eq(&other.name)
+            && self.sql_type.eq(&other.sql_type)
+            && self.default.as_ref().map(|d| d.normalize()) == other.default.as_ref().map(|d| d.normalize())
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum FunctionArgumentMode {
+    In,
+    InOut,
+    Out,
+    Variadic,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum FunctionReturnType {
+    Table(Vec<ColumnDefinition>),
+    SetOf(SqlType),
+    SqlType(SqlType),
+}
+
+impl FunctionReturnType {
+    // The return type as Postgres sees it, ignoring type modifiers
+    fn identity(&self) -> FunctionReturnType {
+        match *self {
+            FunctionReturnType::Table(ref columns) => FunctionReturnType::Table(
+                columns
+                    .iter()
+                    .map(|c| ColumnDefinition {
+                        name: c.name.to_owned(),
+                        sql_type: c.sql_type.identity(),
+                        constraints: Vec::new(),
+                        comment: None,
+                    })
+                    .collect(),
+            ),
+            FunctionReturnType::SetOf(ref sql_type) => FunctionReturnType::SetOf(sql_type.identity()),
+            FunctionReturnType::SqlType(ref sql_type) => FunctionReturnType::SqlType(sql_type.identity()),
+        }
+    }
+}
+
+// Formats a function name along with its argument types
+pub(crate) fn signature(name: &ObjectName, argument_types: &[SqlType]) -> String {
+    let types = argument_types.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    format!("{}({})", name, types.join(", "))
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum FunctionLanguage {
+    C,
+    Internal,
+    PostgreSQL,
+    SQL,
+    Custom(String),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct IndexDefinition {
+    pub name: String,
+    pub table: ObjectName,
+    pub columns: Vec<IndexColumn>,
+
+    pub unique: bool,
+    pub index_type: Option<IndexType>,
+    pub storage_parameters: Option<Vec<IndexParameter>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl IndexDefinition {
+    pub fn fully_qualified_name(&self) -> String {
+        format!("{}.{}", self.schema(), self.name)
+    }
+
+    pub fn is_same_index(&self, other: &IndexDefinition) -> bool {
+        self.name.eq(&other.name) && self.schema().eq(other.schema())
+    }
+
+    // Comments can be changed without rebuilding the index so they aren't part of the definition
+    pub fn is_same_definition(&self, other: &IndexDefinition) -> bool {
+        self.table.eq(&other.table)
+            && self.columns.eq(&other.columns)
+            && self.unique == other.unique
+            && self.index_type.eq(&other.index_type)
+            && self.storage_parameters.eq(&other.storage_parameters)
+    }
+
+    pub fn schema(&self) -> &str {
+        self.table.schema()
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct ViewDefinition {
+    pub name: ObjectName,
+    pub columns: Option<Vec<String>>,
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl ViewDefinition {
+    pub fn is_same_query(&self, other: &ViewDefinition) -> bool {
+        normalize_query(&self.query).eq(&normalize_query(&other.query))
+    }
+
+    /// Without an explicit column list the columns are named by the query itself
+    pub fn is_same_definition(&self, other: &ViewDefinition) -> bool {
+        self.is_same_query(other)
+            && match (&self.columns, &other.columns) {
+                (Some(columns), Some(other_columns)) => columns.eq(other_columns),
+                _ => true,
+            }
+    }
+
+    /// Postgres only allows a view to be replaced when new columns are appended to the existing ones
+    pub fn can_replace(&self, existing: &ViewDefinition) -> bool {
+        match (&self.columns, &existing.columns) {
+            (Some(columns), Some(existing_columns)) => columns.starts_with(existing_columns),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct MaterializedViewDefinition {
+    pub name: ObjectName,
+    pub columns: Option<Vec<String>>,
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl MaterializedViewDefinition {
+    pub fn is_same_query(&self, other: &MaterializedViewDefinition) -> bool {
+        normalize_query(&self.query).eq(&normalize_query(&other.query))
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct TriggerDefinition {
+    pub name: String,
+    pub table: ObjectName,
+    pub timing: TriggerTiming,
+    pub events: Vec<TriggerEvent>,
+    pub for_each: TriggerForEach,
+    pub condition: Option<String>,
+    pub function: ObjectName,
+    pub arguments: Vec<String>,
+}
+
+impl TriggerDefinition {
+    pub fn fully_qualified_name(&self) -> String {
+        format!("{}.{}", self.table, self.name)
+    }
+
+    pub fn is_same_trigger(&self, other: &TriggerDefinition) -> bool {
+        self.name.eq(&other.name) && self.table.eq(&other.table)
+    }
+
+    pub fn is_same_definition(&self, other: &TriggerDefinition) -> bool {
+        // Postgres always describes the events in the same order
+        let mut events = self.events.clone();
+        let mut other_events = other.events.clone();
+        events.sort();
+        other_events.sort();
+
+        self.timing.eq(&other.timing)
+            && events.eq(&other_events)
+            && self.for_each.eq(&other.for_each)
+            && self.function.eq(&other.function)
+            && self.arguments.eq(&other.arguments)
+            && self.condition.as_ref().map(|c| normalize_expression(c))
+                == other.condition.as_ref().map(|c| normalize_expression(c))
+    }
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum TriggerTiming {
+    Before,
+    After,
+    InsteadOf,
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum TriggerEvent {
+    Insert,
+    Update(Option<Vec<String>>),
+    Delete,
+    Truncate,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum TriggerForEach {
+    Row,
+    Statement,
+}
+
+#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub struct PolicyDefinition {
+    pub name: String,
+    pub table: ObjectName,
+    pub permissive: bool,
+    pub command: PolicyCommand,
+    pub roles: Vec<String>,
+    pub using: Option<String>,
+    pub with_check: Option<String>,
+}
+
+impl PolicyDefinition {
+    pub fn fully_qualified_name(&self) -> String {
+        format!("{}.{}", self.table, self.name)
+    }
+
+    pub fn is_same_policy(&self, other: &PolicyDefinition) -> bool {
+        self.name.eq(&other.name) && self.table.eq(&other.table)
+    }
+
+    pub fn is_same_definition(&self, other: &PolicyDefinition) -> bool {
+        self.permissive == other.permissive
+            && self.command.eq(&other.command)
+            && self.normalized_roles().eq(&other.normalized_roles())
+            && self.using.as_ref().map(|e| normalize_expression(e))
+                == other.using.as_ref().map(|e| normalize_expression(e))
+            && self.with_check.as_ref().map(|e| normalize_expression(e))
+                == other.with_check.as_ref().map(|e| normalize_expression(e))
+    }
+
+    // Postgres ignores any other roles when PUBLIC is specified, which is also the default
+    pub fn normalized_roles(&self) -> Vec<String> {
+        let mut roles = self.roles.iter().map(|r| r.to_lowercase()).collect::<Vec<_>>();
+        if roles.is_empty() || roles.iter().any(|r| r == "public") {
+            return vec!["public".into()];
+        }
+        roles.sort();
+        roles.dedup();
+        roles
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum PolicyCommand {
+    All,
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+/// The privileges a single grantee holds on an object. Privileges that the grantee may grant to others are kept
+/// separately from those they can't, so an object and grantee has at most two entries.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GrantDefinition {
+    pub object: GrantObject,
+    pub grantee: String,
+    pub privileges: Vec<Privilege>,
+    pub with_grant_option: bool,
+}
+
+impl GrantDefinition {
+    // A GRANT or REVOKE statement applies each privilege to every object and grantee listed. Without any
+    // privileges it applies ALL privileges, which depends on the type of object.
+    pub fn expand(
+        objects: Vec<GrantObject>,
+        privileges: Option<Vec<Privilege>>,
+        grantees: Vec<String>,
+        with_grant_option: bool,
+    ) -> Vec<GrantDefinition> {
+        let mut definitions = Vec::new();
+        for object in objects {
+            let privileges = privileges.clone().unwrap_or_else(|| object.all_privileges());
+            for grantee in &grantees {
+                definitions.push(GrantDefinition {
+                    object: object.clone(),
+                    grantee: grantee.to_lowercase(),
+                    privileges: privileges.clone(),
+                    with_grant_option,
+                });
+            }
+        }
+        definitions
+    }
+
+    pub fn fully_qualified_name(&self) -> String {
+        format!("{}.{}", self.object.fully_qualified_name(), self.grantee)
+    }
+
+    pub fn is_same_grant(&self, other: &GrantDefinition) -> bool {
+        self.object.eq(&other.object) && self.grantee.eq(&other.grantee)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GrantObject {
+    Schema(String),
+    Table(ObjectName),
+    Sequence(ObjectName),
+    // The argument types identify an overloaded function. Without them the function name must be unique.
+    Function(ObjectName, Option<Vec<SqlType>>),
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub struct ColumnDefinition {
-    pub name: String,
-    pub sql_type: SqlType,
-    pub constraints: Vec<ColumnConstraint>,
+impl GrantObject {
+    pub fn all_privileges(&self) -> Vec<Privilege> {
+        match *self {
+            GrantObject::Schema(_) => vec![Privilege::Usage, Privilege::Create],
+            GrantObject::Table(_) => vec![
+                Privilege::Select,
+                Privilege::Insert,
+                Privilege::Update,
+                Privilege::Delete,
+                Privilege::Truncate,
+                Privilege::References,
+                Privilege::Trigger,
+            ],
+            GrantObject::Sequence(_) => vec![Privilege::Select, Privilege::Update, Privilege::Usage],
+            GrantObject::Function(..) => vec![Privilege::Execute],
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            GrantObject::Schema(_) => "schema",
+            GrantObject::Table(_) => "table",
+            GrantObject::Sequence(_) => "sequence",
+            GrantObject::Function(..) => "function",
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match *self {
+            GrantObject::Schema(ref name) => name.to_owned(),
+            GrantObject::Function(ref name, Some(ref argument_types)) => signature(name, argument_types),
+            GrantObject::Table(ref name) | GrantObject::Sequence(ref name) | GrantObject::Function(ref name, None) => {
+                name.to_string()
+            }
+        }
+    }
+
+    pub fn fully_qualified_name(&self) -> String {
+        format!("{}.{}", self.kind(), self.name())
+    }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct SchemaDefinition {
-    pub name: String,
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
+pub enum Privilege {
+    Select,
+    Insert,
+    Update,
+    Delete,
+    Truncate,
+    References,
+    Trigger,
+    Usage,
+    Create,
+    Execute,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct TypeDefinition {
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequenceDefinition {
     pub name: ObjectName,
-    pub kind: TypeDefinitionKind,
+    pub data_type: Option<SqlType>,
+    pub increment: Option<i64>,
+    pub min_value: Option<i64>,
+    pub max_value: Option<i64>,
+    pub start: Option<i64>,
+    pub cache: Option<i64>,
+    pub cycle: bool,
+    pub owned_by: Option<SequenceOwner>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub enum TypeDefinitionKind {
-    Composite,
-    Enum(Vec<String>),
-    Range,
-    UserDefined,
+// The range of values a sequence of the given type can produce
+fn sequence_range(data_type: &SqlType) -> (i64, i64) {
+    match *data_type {
+        SqlType::Simple(SimpleSqlType::SmallInteger, _) => (i64::from(i16::MIN), i64::from(i16::MAX)),
+        SqlType::Simple(SimpleSqlType::Integer, _) => (i64::from(i32::MIN), i64::from(i32::MAX)),
+        _ => (i64::MIN, i64::MAX),
+    }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct ScriptDefinition {
-    pub name: String,
-    pub kind: ScriptKind,
-    pub order: usize,
-    pub contents: String,
-}
+impl SequenceDefinition {
+    // Fills in any missing options using the same rules as Postgres so that they can be compared
+    // against an inspected sequence.
+    pub fn set_defaults(&mut self) {
+        let data_type = self
+            .data_type
+            .get_or_insert(SqlType::Simple(SimpleSqlType::BigInteger, None));
+        let (type_min, type_max) = sequence_range(data_type);
+        let increment = *self.increment.get_or_insert(1);
+        let ascending = increment > 0;
+        let min_value = *self.min_value.get_or_insert(if ascending { 1 } else { type_min });
+        let max_value = *self.max_value.get_or_insert(if ascending { type_max } else { -1 });
+        self.start.get_or_insert(if ascending { min_value } else { max_value });
+        self.cache.get_or_insert(1);
+    }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub enum ScriptKind {
-    PreDeployment,
-    PostDeployment,
+    pub fn is_same_definition(&self, other: &SequenceDefinition) -> bool {
+        self.data_type.eq(&other.data_type)
+            && self.increment.eq(&other.increment)
+            && self.min_value.eq(&other.min_value)
+            && self.max_value.eq(&other.max_value)
+            && self.start.eq(&other.start)
+            && self.cache.eq(&other.cache)
+            && self.cycle.eq(&other.cycle)
+    }
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub struct FunctionDefinition {
-    pub name: ObjectName,
-    pub arguments: Vec<FunctionArgument>,
-    pub return_type: FunctionReturnType,
-    pub body: String,
-    pub language: FunctionLanguage,
+impl IdentityDefinition {
+    pub fn new(generation: IdentityGeneration, options: Vec<SequenceOption>) -> Result<Self, &'static str> {
+        let mut identity = IdentityDefinition {
+            generation,
+            increment: None,
+            min_value: None,
+            max_value: None,
+            start: None,
+            cache: None,
+            cycle: false,
+        };
+        for option in options {
+            match option {
+                SequenceOption::Increment(increment) => identity.increment = Some(increment),
+                SequenceOption::MinValue(min_value) => identity.min_value = min_value,
+                SequenceOption::MaxValue(max_value) => identity.max_value = max_value,
+                SequenceOption::Start(start) => identity.start = Some(start),
+                SequenceOption::Cache(cache) => identity.cache = Some(cache),
+                SequenceOption::Cycle(cycle) => identity.cycle = cycle,
+                SequenceOption::DataType(_) | SequenceOption::OwnedBy(_) => {
+                    return Err("Identity columns take the type and ownership of the column");
+                }
+            }
+        }
+        Ok(identity)
+    }
+
+    // The sequence behind an identity column takes the type of the column
+    pub fn set_defaults(&mut self, sql_type: &SqlType) {
+        let (type_min, type_max) = sequence_range(sql_type);
+        let increment = *self.increment.get_or_insert(1);
+        let ascending = increment > 0;
+        let min_value = *self.min_value.get_or_insert(if ascending { 1 } else { type_min });
+        let max_value = *self.max_value.get_or_insert(if ascending { type_max } else { -1 });
+        self.start.get_or_insert(if ascending { min_value } else { max_value });
+        self.cache.get_or_insert(1);
+    }
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub struct FunctionArgument {
-    pub mode: Option<FunctionArgumentMode>,
-    pub name: Option<String>,
-    pub sql_type: SqlType,
-    pub default: Option<AnyValue>,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequenceOwner {
+    pub table: ObjectName,
+    pub column: String,
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub enum FunctionArgumentMode {
-    In,
-    InOut,
-    Out,
-    Variadic,
+// Sequence options may be specified in any order so they are collected before building the definition
+#[derive(Debug, PartialEq)]
+pub enum SequenceOption {
+    DataType(SqlType),
+    Increment(i64),
+    MinValue(Option<i64>),
+    MaxValue(Option<i64>),
+    Start(i64),
+    Cache(i64),
+    Cycle(bool),
+    OwnedBy(Option<SequenceOwner>),
 }
 
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub enum FunctionReturnType {
-    Table(Vec<ColumnDefinition>),
-    SetOf(SqlType),
-    SqlType(SqlType),
+lazy_static! {
+    static ref WITH_DATA: Regex = Regex::new("(?i)\\s+WITH\\s+(NO\\s+)?DATA$").unwrap();
+    static ref CAST: Regex = Regex::new(
+        "::(character varying|double precision|(timestamp|time) with(out)? time zone|[a-z_][a-z0-9_.]*)(\\(\\d+(,\\s*\\d+)?\\))?(\\[\\])*"
+    )
+    .unwrap();
+    static ref BRACKETED_OPERAND: Regex = Regex::new("(^|[^a-z0-9_])\\(([a-z0-9_.]+)\\)").unwrap();
 }
 
-#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
-pub enum FunctionLanguage {
-    C,
-    Internal,
-    PostgreSQL,
-    SQL,
-    Custom(String),
+// Queries are compared against the query recorded when the view was published, so we only ignore
+// differences in formatting: whitespace, a trailing semicolon and the materialized view WITH [NO] DATA clause.
+fn normalize_query(query: &str) -> String {
+    let query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    let query = query.trim_end_matches(';').trim_end();
+    WITH_DATA.replace(query, "").into_owned()
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub struct IndexDefinition {
-    pub name: String,
-    pub table: ObjectName,
-    pub columns: Vec<IndexColumn>,
+// Postgres lower cases identifiers, adds explicit casts and wraps the expression in additional
+// brackets when describing a condition or check constraint. Quoted text is left alone.
+pub(crate) fn normalize_expression(expression: &str) -> String {
+    fn normalize_segment(segment: &str) -> String {
+        let mut segment = CAST.replace_all(&segment.to_lowercase(), "").into_owned();
+        // Casting a constant also wraps it in brackets, e.g. (0)::numeric
+        loop {
+            let unwrapped = BRACKETED_OPERAND.replace_all(&segment, "${1}${2}").into_owned();
+            if unwrapped == segment {
+                return segment;
+            }
+            segment = unwrapped;
+        }
+    }
 
-    pub unique: bool,
-    pub index_type: Option<IndexType>,
-    pub storage_parameters: Option<Vec<IndexParameter>>,
+    let mut normalized = String::new();
+    let mut segment = String::new();
+    let mut quote = None;
+    for c in expression.split_whitespace().collect::<Vec<_>>().join(" ").chars() {
+        match quote {
+            Some(q) => {
+                normalized.push(c);
+                if q == c {
+                    quote = None;
+                }
+            }
+            None if c == '\'' || c == '"' => {
+                normalized.push_str(&normalize_segment(&segment));
+                segment.clear();
+                normalized.push(c);
+                quote = Some(c);
+            }
+            None => segment.push(c),
+        }
+    }
+    normalized.push_str(&normalize_segment(&segment));
+    let mut normalized = unwrap_boolean_operands(&unwrap_bracketed_calls(&normalized));
+
+    // Remove any brackets that enclose the entire expression
+    loop {
+        let trimmed = normalized.trim();
+        if !trimmed.starts_with('(') || !trimmed.ends_with(')') {
+            return trimmed.to_owned();
+        }
+        let mut depth = 0;
+        let encloses = trimmed.char_indices().all(|(i, c)| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            depth > 0 || i == trimmed.len() - 1
+        });
+        if !encloses {
+            return trimmed.to_owned();
+        }
+        normalized = trimmed[1..trimmed.len() - 1].to_owned();
+    }
 }
 
-impl IndexDefinition {
-    pub fn fully_qualified_name(&self) -> String {
-        format!("{}.{}", self.schema(), self.name)
+// Casting the result of a function call also wraps the call in brackets, e.g. (current_setting('a'))::integer
+fn unwrap_bracketed_calls(expression: &str) -> String {
+    let chars = expression.chars().collect::<Vec<_>>();
+
+    // Match up the brackets outside of any quoted text
+    let mut matching = vec![None; chars.len()];
+    let mut open = Vec::new();
+    let mut quote = None;
+    for (i, &c) in chars.iter().enumerate() {
+        match quote {
+            Some(q) if q == c => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == '(' => open.push(i),
+            None if c == ')' => {
+                if let Some(start) = open.pop() {
+                    matching[start] = Some(i);
+                }
+            }
+            None => {}
+        }
     }
 
-    pub fn is_same_index(&self, other: &IndexDefinition) -> bool {
-        self.name.eq(&other.name) && self.schema().eq(other.schema())
+    // Brackets used for grouping (rather than calling a function) are removed when they only contain a call
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    let mut removed = vec![false; chars.len()];
+    for (start, end) in matching
+        .iter()
+        .enumerate()
+        .filter_map(|(start, end)| end.map(|end| (start, end)))
+    {
+        if start > 0 && is_name(chars[start - 1]) {
+            continue;
+        }
+        let call = start + 1 + chars[start + 1..end].iter().take_while(|&&c| is_name(c)).count();
+        if call > start + 1 && call < end && chars[call] == '(' && matching[call] == Some(end - 1) {
+            removed[start] = true;
+            removed[end] = true;
+        }
     }
+    if !removed.iter().any(|&r| r) {
+        return expression.to_owned();
+    }
+    let unwrapped = chars
+        .iter()
+        .zip(removed)
+        .filter(|&(_, removed)| !removed)
+        .map(|(&c, _)| c)
+        .collect::<String>();
+    unwrap_bracketed_calls(&unwrapped)
+}
 
-    pub fn schema(&self) -> &str {
-        self.table.schema()
+// Postgres also brackets each operand of AND, OR and NOT. Comparisons bind more tightly than these so the
+// brackets can be removed as long as the operand doesn't contain AND or OR itself.
+fn unwrap_boolean_operands(expression: &str) -> String {
+    fn last_word(text: &str) -> &str {
+        text.rsplit(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or("")
+    }
+    fn first_word(text: &str) -> &str {
+        text.split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or("")
+    }
+    fn has_boolean_operator(text: &str) -> bool {
+        let mut depth = 0;
+        let mut quote = None;
+        let mut word = String::new();
+        for c in text.chars().chain(std::iter::once(' ')) {
+            match quote {
+                Some(q) if q == c => quote = None,
+                Some(_) => {}
+                None if c.is_alphanumeric() || c == '_' => {
+                    word.push(c);
+                    continue;
+                }
+                None => {
+                    if depth == 0 && (word == "and" || word == "or") {
+                        return true;
+                    }
+                    match c {
+                        '\'' | '"' => quote = Some(c),
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            word.clear();
+        }
+        false
+    }
+
+    let mut expression = expression.to_owned();
+    'scan: loop {
+        let mut starts = Vec::new();
+        let mut quote = None;
+        for (i, c) in expression.char_indices() {
+            match quote {
+                Some(q) if q == c => quote = None,
+                Some(_) => {}
+                None => match c {
+                    '\'' | '"' => quote = Some(c),
+                    '(' => starts.push(i),
+                    ')' => {
+                        let start = match starts.pop() {
+                            Some(start) => start,
+                            None => continue,
+                        };
+                        let before = expression[..start].trim_end();
+                        let after = expression[i + 1..].trim_start();
+                        let inner = &expression[start + 1..i];
+                        let operand_start = before.is_empty()
+                            || before.ends_with('(')
+                            || ["and", "or", "not"].contains(&last_word(before));
+                        let operand_end =
+                            after.is_empty() || after.starts_with(')') || ["and", "or"].contains(&first_word(after));
+                        if operand_start && operand_end && !has_boolean_operator(inner) {
+                            expression = format!("{}{}{}", &expression[..start], inner, &expression[i + 1..]);
+                            continue 'scan;
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+        return expression;
     }
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum IndexType {
     BTree,
     Hash,
@@ -338,7 +1827,7 @@ impl fmt::Display for AnyValue {
                 sql_type
             }
             AnyValue::String(ref s, ref sql_type) => {
-                write!(f, "'{}'", s)?;
+                write!(f, "'{}'", s.replace('\'', "''"))?;
                 sql_type
             }
             AnyValue::Null(ref sql_type) => {
@@ -353,6 +1842,38 @@ impl fmt::Display for AnyValue {
     }
 }
 
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Expression::Literal(ref value) => write!(f, "{}", value),
+            Expression::Keyword(ref keyword) => write!(f, "{}", keyword),
+            Expression::Column(ref name) => write!(f, "{}", name),
+            Expression::FunctionCall(ref name, ref arguments) => {
+                write!(f, "{}(", name)?;
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", argument)?;
+                }
+                write!(f, ")")
+            }
+            Expression::UnaryOperator(ref operator, ref expression) => {
+                if operator.chars().all(|c| c.is_ascii_alphabetic()) {
+                    write!(f, "{} {}", operator, expression)
+                } else {
+                    write!(f, "{}{}", operator, expression)
+                }
+            }
+            Expression::BinaryOperator(ref left, ref operator, ref right) => {
+                write!(f, "{} {} {}", left, operator, right)
+            }
+            Expression::Cast(ref expression, ref sql_type) => write!(f, "{}::{}", expression, sql_type),
+            Expression::Nested(ref expression) => write!(f, "({})", expression),
+        }
+    }
+}
+
 impl fmt::Display for ForeignConstraintMatchType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -375,10 +1896,74 @@ impl fmt::Display for ForeignConstraintAction {
     }
 }
 
+impl fmt::Display for PartitionKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.strategy {
+            PartitionStrategy::Range => write!(f, "RANGE (")?,
+            PartitionStrategy::List => write!(f, "LIST (")?,
+            PartitionStrategy::Hash => write!(f, "HASH (")?,
+        }
+        for (index, column) in self.columns.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            // Anything other than a column or function call needs to be bracketed
+            match column {
+                Expression::Column(_) | Expression::FunctionCall(..) | Expression::Nested(_) => {
+                    write!(f, "{}", column)?
+                }
+                _ => write!(f, "({})", column)?,
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+impl fmt::Display for PartitionBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_list<T: fmt::Display>(f: &mut fmt::Formatter, values: &[T]) -> fmt::Result {
+            write!(f, "(")?;
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", value)?;
+            }
+            write!(f, ")")
+        }
+        match *self {
+            PartitionBound::Range { ref from, ref to } => {
+                write!(f, "FOR VALUES FROM ")?;
+                write_list(f, from)?;
+                write!(f, " TO ")?;
+                write_list(f, to)
+            }
+            PartitionBound::List(ref values) => {
+                write!(f, "FOR VALUES IN ")?;
+                write_list(f, values)
+            }
+            PartitionBound::Hash { modulus, remainder } => {
+                write!(f, "FOR VALUES WITH (MODULUS {}, REMAINDER {})", modulus, remainder)
+            }
+            PartitionBound::Default => write!(f, "DEFAULT"),
+        }
+    }
+}
+
+impl fmt::Display for PartitionRangeValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PartitionRangeValue::MinValue => write!(f, "MINVALUE"),
+            PartitionRangeValue::MaxValue => write!(f, "MAXVALUE"),
+            PartitionRangeValue::Value(ref value) => write!(f, "{}", value),
+        }
+    }
+}
+
 impl fmt::Display for FunctionArgument {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(ref mode) = self.mode {
-            write!(f, "{}", mode)?;
+            write!(f, "{} ", mode)?;
         }
         if let Some(ref name) = self.name {
             write!(f, "{} {}", name, self.sql_type)?;
@@ -386,7 +1971,7 @@ impl fmt::Display for FunctionArgument {
             write!(f, "{}", self.sql_type)?;
         }
         if let Some(ref default) = self.default {
-            write!(f, "{}", default)?;
+            write!(f, " DEFAULT {}", default)?;
         }
         Ok(())
     }
@@ -415,6 +2000,146 @@ impl fmt::Display for FunctionLanguage {
     }
 }
 
+impl fmt::Display for FunctionVolatility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FunctionVolatility::Immutable => write!(f, "IMMUTABLE"),
+            FunctionVolatility::Stable => write!(f, "STABLE"),
+            FunctionVolatility::Volatile => write!(f, "VOLATILE"),
+        }
+    }
+}
+
+impl fmt::Display for FunctionParallel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FunctionParallel::Safe => write!(f, "PARALLEL SAFE"),
+            FunctionParallel::Restricted => write!(f, "PARALLEL RESTRICTED"),
+            FunctionParallel::Unsafe => write!(f, "PARALLEL UNSAFE"),
+        }
+    }
+}
+
+impl fmt::Display for FunctionSetting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values = self
+            .values
+            .iter()
+            .map(|v| format!("'{}'", v.replace('\'', "''")))
+            .collect::<Vec<_>>();
+        write!(f, "SET {} = {}", self.name, values.join(", "))
+    }
+}
+
+impl fmt::Display for PolicyCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PolicyCommand::All => write!(f, "ALL"),
+            PolicyCommand::Select => write!(f, "SELECT"),
+            PolicyCommand::Insert => write!(f, "INSERT"),
+            PolicyCommand::Update => write!(f, "UPDATE"),
+            PolicyCommand::Delete => write!(f, "DELETE"),
+        }
+    }
+}
+
+impl fmt::Display for GrantObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.kind().to_uppercase(), self.name())
+    }
+}
+
+impl fmt::Display for CommentObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommentObject::Column(ref table, ref column) => write!(f, "COLUMN {}.{}", table, column),
+            CommentObject::Function(..) => write!(f, "FUNCTION {}", self.name()),
+            CommentObject::Index(ref name) => write!(f, "INDEX {}", name),
+            CommentObject::MaterializedView(ref name) => write!(f, "MATERIALIZED VIEW {}", name),
+            CommentObject::Procedure(..) => write!(f, "PROCEDURE {}", self.name()),
+            CommentObject::Schema(ref name) => write!(f, "SCHEMA {}", name),
+            CommentObject::Sequence(ref name) => write!(f, "SEQUENCE {}", name),
+            CommentObject::Table(ref name) => write!(f, "TABLE {}", name),
+            CommentObject::Type(ref name) => write!(f, "TYPE {}", name),
+            CommentObject::View(ref name) => write!(f, "VIEW {}", name),
+        }
+    }
+}
+
+impl fmt::Display for Privilege {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Privilege::Select => write!(f, "SELECT"),
+            Privilege::Insert => write!(f, "INSERT"),
+            Privilege::Update => write!(f, "UPDATE"),
+            Privilege::Delete => write!(f, "DELETE"),
+            Privilege::Truncate => write!(f, "TRUNCATE"),
+            Privilege::References => write!(f, "REFERENCES"),
+            Privilege::Trigger => write!(f, "TRIGGER"),
+            Privilege::Usage => write!(f, "USAGE"),
+            Privilege::Create => write!(f, "CREATE"),
+            Privilege::Execute => write!(f, "EXECUTE"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerTiming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TriggerTiming::Before => write!(f, "BEFORE"),
+            TriggerTiming::After => write!(f, "AFTER"),
+            TriggerTiming::InsteadOf => write!(f, "INSTEAD OF"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TriggerEvent::Insert => write!(f, "INSERT"),
+            TriggerEvent::Update(Some(ref columns)) => write!(f, "UPDATE OF {}", columns.join(", ")),
+            TriggerEvent::Update(None) => write!(f, "UPDATE"),
+            TriggerEvent::Delete => write!(f, "DELETE"),
+            TriggerEvent::Truncate => write!(f, "TRUNCATE"),
+        }
+    }
+}
+
+impl fmt::Display for TriggerForEach {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TriggerForEach::Row => write!(f, "FOR EACH ROW"),
+            TriggerForEach::Statement => write!(f, "FOR EACH STATEMENT"),
+        }
+    }
+}
+
+impl fmt::Display for SequenceOwner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.table, self.column)
+    }
+}
+
+impl fmt::Display for RenameDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenameDefinition::Table { ref from, ref to } => write!(f, "table {} to {}", from, to),
+            RenameDefinition::Column {
+                ref table,
+                ref from,
+                ref to,
+            } => write!(f, "column {}.{} to {}", table, from, to),
+            RenameDefinition::Index { ref from, ref to } => write!(f, "index {} to {}", from, to),
+            RenameDefinition::Constraint {
+                ref table,
+                ref from,
+                ref to,
+            } => write!(f, "constraint {} on table {} to {}", from, table, to),
+            RenameDefinition::Type { ref from, ref to } => write!(f, "type {} to {}", from, to),
+        }
+    }
+}
+
 impl fmt::Display for ObjectName {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.schema {
@@ -463,8 +2188,9 @@ impl fmt::Display for TypeDefinitionKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             TypeDefinitionKind::Enum(_) => write!(f, "Enum"),
-            TypeDefinitionKind::Composite => write!(f, "Composite"),
-            TypeDefinitionKind::Range => write!(f, "Range"),
+            TypeDefinitionKind::Composite(_) => write!(f, "Composite"),
+            TypeDefinitionKind::Domain(_) => write!(f, "Domain"),
+            TypeDefinitionKind::Range(_) => write!(f, "Range"),
             TypeDefinitionKind::UserDefined => write!(f, "User Defined"),
         }
     }
@@ -472,14 +2198,23 @@ impl fmt::Display for TypeDefinitionKind {
 
 impl fmt::Display for SimpleSqlType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn precision(p: Option<u32>) -> String {
+            if let Some(p) = p {
+                format!("({})", p)
+            } else {
+                String::new()
+            }
+        }
         match *self {
             SimpleSqlType::FixedLengthString(size) => write!(f, "char({})", size),
             SimpleSqlType::VariableLengthString(size) => write!(f, "varchar({})", size),
+            SimpleSqlType::UnsizedFixedLengthString => write!(f, "bpchar"),
             SimpleSqlType::UnsizedVariableLengthString => write!(f, "varchar"),
             SimpleSqlType::Text => write!(f, "text"),
 
             SimpleSqlType::FixedLengthBitString(size) => write!(f, "bit({})", size),
             SimpleSqlType::VariableLengthBitString(size) => write!(f, "varbit({})", size),
+            SimpleSqlType::UnsizedVariableLengthBitString => write!(f, "varbit"),
 
             SimpleSqlType::SmallInteger => write!(f, "smallint"),
             SimpleSqlType::Integer => write!(f, "int"),
@@ -498,15 +2233,36 @@ impl fmt::Display for SimpleSqlType {
             SimpleSqlType::Boolean => write!(f, "bool"),
 
             SimpleSqlType::Date => write!(f, "date"),
-            SimpleSqlType::DateTime => write!(f, "timestamp without time zone"),
-            SimpleSqlType::DateTimeWithTimeZone => write!(f, "timestamp with time zone"),
-            SimpleSqlType::Time => write!(f, "time"),
-            SimpleSqlType::TimeWithTimeZone => write!(f, "time with time zone"),
+            SimpleSqlType::DateTime(p) => write!(f, "timestamp{} without time zone", precision(p)),
+            SimpleSqlType::DateTimeWithTimeZone(p) => write!(f, "timestamp{} with time zone", precision(p)),
+            SimpleSqlType::Time(p) => write!(f, "time{}", precision(p)),
+            SimpleSqlType::TimeWithTimeZone(p) => write!(f, "time{} with time zone", precision(p)),
+            SimpleSqlType::Interval(p) => write!(f, "interval{}", precision(p)),
 
             SimpleSqlType::Uuid => write!(f, "uuid"),
 
             SimpleSqlType::Json => write!(f, "json"),
             SimpleSqlType::Jsonb => write!(f, "jsonb"),
+
+            SimpleSqlType::Binary => write!(f, "bytea"),
+            SimpleSqlType::ObjectIdentifier => write!(f, "oid"),
+            SimpleSqlType::Xml => write!(f, "xml"),
+
+            SimpleSqlType::Inet => write!(f, "inet"),
+            SimpleSqlType::Cidr => write!(f, "cidr"),
+            SimpleSqlType::MacAddress => write!(f, "macaddr"),
+            SimpleSqlType::MacAddress8 => write!(f, "macaddr8"),
+
+            SimpleSqlType::TextSearchVector => write!(f, "tsvector"),
+            SimpleSqlType::TextSearchQuery => write!(f, "tsquery"),
+
+            SimpleSqlType::Point => write!(f, "point"),
+            SimpleSqlType::Line => write!(f, "line"),
+            SimpleSqlType::LineSegment => write!(f, "lseg"),
+            SimpleSqlType::Box => write!(f, "box"),
+            SimpleSqlType::Path => write!(f, "path"),
+            SimpleSqlType::Polygon => write!(f, "polygon"),
+            SimpleSqlType::Circle => write!(f, "circle"),
         }
     }
 }
@@ -18,6 +18,7 @@ pub struct LexicalError<'input> {
     pub line_number: usize,
     pub start_pos: usize,
     pub end_pos: usize,
+    #[allow(dead_code)]
     pub lexer_state: String,
     pub reason: String,
 }
@@ -37,12 +38,29 @@ mod context {
         LiteralStart,
         LiteralEnd,
         LiteralBody,
+
+        Query,
+        QueryQuoted(char),
+
+        ConditionStart,
+        Condition(u32),
     }
 
     #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum NormalVariant {
+        Alter,
         Any,
         Body,
+        Comment,
+        Function,
+        Grant,
+        Identity,
+        Partition,
+        Policy,
+        Sequence,
+        Trigger,
+        Type,
+        View,
     }
 
     pub struct Context {
@@ -92,8 +110,19 @@ mod context {
                     .iter()
                     .map(|s| match s {
                         LexerState::Normal(variant) => match variant {
+                            NormalVariant::Alter => "Normal(Alter)",
                             NormalVariant::Any => "Normal(Any)",
                             NormalVariant::Body => "Normal(Body)",
+                            NormalVariant::Comment => "Normal(Comment)",
+                            NormalVariant::Function => "Normal(Function)",
+                            NormalVariant::Grant => "Normal(Grant)",
+                            NormalVariant::Identity => "Normal(Identity)",
+                            NormalVariant::Partition => "Normal(Partition)",
+                            NormalVariant::Policy => "Normal(Policy)",
+                            NormalVariant::Sequence => "Normal(Sequence)",
+                            NormalVariant::Trigger => "Normal(Trigger)",
+                            NormalVariant::Type => "Normal(Type)",
+                            NormalVariant::View => "Normal(View)",
                         },
                         LexerState::Comment1 => "CommentLine",
                         LexerState::Comment2 => "CommentBlock",
@@ -102,6 +131,10 @@ mod context {
                         LexerState::LiteralStart => "LiteralBegin",
                         LexerState::LiteralBody => "Literal",
                         LexerState::LiteralEnd => "LiteralEnd",
+                        LexerState::Query => "Query",
+                        LexerState::QueryQuoted(_) => "QueryQuoted",
+                        LexerState::ConditionStart => "ConditionStart",
+                        LexerState::Condition(_) => "Condition",
                     })
                     .collect::<Vec<_>>()
                     .join(" -> "),
@@ -132,96 +165,210 @@ mod context {
     }
 }
 
+#[allow(non_camel_case_types)]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Token {
     ACTION,
+    AFTER,
+    ALL,
+    ALTER,
+    ALWAYS,
+    AND,
     ARRAY,
     AS,
     ASC,
+    AUTHORIZATION,
+    BEFORE,
     BIGINT,
     BIGSERIAL,
     BIT,
     BOOL,
     BOOLEAN,
+    BOX,
+    BPCHAR,
     BTREE,
+    BY,
+    BYTEA,
+    CACHE,
+    CALLED,
+    CANONICAL,
     CASCADE,
+    CAST,
+    CIDR,
+    CIRCLE,
+    COLUMN,
+    COMMENT,
     CONSTRAINT,
     CHAR,
     CHARACTER,
+    CHECK,
+    COLLATION,
+    COST,
     CREATE,
+    CURRENT_CATALOG,
+    CURRENT_DATE,
+    CURRENT_ROLE,
+    CURRENT_SCHEMA,
+    CURRENT_TIME,
+    CURRENT_TIMESTAMP,
+    CURRENT_USER,
+    CYCLE,
     DATE,
     DEFAULT,
+    DEFINER,
     DELETE,
     DESC,
+    DISABLE,
+    DOMAIN,
     DOUBLE,
+    EACH,
+    ENABLE,
     ENUM,
+    EXCLUDE,
+    EXECUTE,
     EXTENSION,
+    EXTERNAL,
     FILLFACTOR,
     FIRST,
+    FOR,
+    FORCE,
     FOREIGN,
+    FROM,
     FULL,
     FUNCTION,
+    GENERATED,
     GIN,
     GIST,
+    GRANT,
     HASH,
+    IDENTITY,
+    IMMUTABLE,
     IN,
+    INCREMENT,
     INDEX,
+    INET,
     INOUT,
+    INHERIT,
+    INPUT,
+    INSERT,
+    INSTEAD,
     INT,
     INT2,
     INT4,
     INT8,
     INTEGER,
+    INTERVAL,
+    INVOKER,
+    IS,
     JSON,
     JSONB,
     KEY,
     LANGUAGE,
     LAST,
+    LEVEL,
+    LINE,
+    LIST,
+    LOCALTIME,
+    LOCALTIMESTAMP,
+    LSEG,
+    MACADDR,
+    MACADDR8,
     MATCH,
+    MATERIALIZED,
+    MAXVALUE,
+    MINVALUE,
+    MODULUS,
     MONEY,
     NO,
+    NONE,
     NOT,
     NULL,
     NULLS,
     NUMERIC,
+    OF,
+    OID,
     ON,
+    OPTION,
     OR,
     OUT,
+    OWNED,
+    PARALLEL,
     PARTIAL,
+    PARTITION,
+    PATH,
+    PERMISSIVE,
+    POINT,
+    POLICY,
+    POLYGON,
     PRECISION,
     PRIMARY,
+    PRIVILEGES,
+    PROCEDURE,
+    RANGE,
     REAL,
     REFERENCES,
+    REMAINDER,
     REPLACE,
     RESTRICT,
+    RESTRICTED,
+    RESTRICTIVE,
     RETURNS,
+    REVOKE,
+    ROW,
+    ROWS,
+    SAFE,
     SCHEMA,
+    SECURITY,
+    SELECT,
+    SEQUENCE,
     SERIAL,
     SERIAL2,
     SERIAL4,
     SERIAL8,
+    SESSION_USER,
     SET,
     SETOF,
     SIMPLE,
     SMALLINT,
     SMALLSERIAL,
+    STABLE,
+    START,
+    STATEMENT,
+    STORED,
+    STRICT,
+    SUBTYPE,
+    SUBTYPE_DIFF,
+    SUBTYPE_OPCLASS,
     TABLE,
     TEXT,
     TIME,
     TIMESTAMP,
     TIMESTAMPTZ,
     TIMETZ,
+    TO,
+    TRIGGER,
+    TRUNCATE,
+    TSQUERY,
+    TSVECTOR,
     TYPE,
     UNIQUE,
+    UNSAFE,
     UPDATE,
+    USAGE,
     USING,
     UUID,
+    VALUES,
     VARBIT,
     VARCHAR,
     VARIADIC,
     VARYING,
+    VIEW,
+    VOLATILE,
+    WHEN,
+    WHERE,
     WITH,
     WITHOUT,
+    XML,
     ZONE,
 
     Identifier(String),
@@ -241,99 +388,226 @@ pub enum Token {
     Period,
     Semicolon,
     Equals,
+
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    Caret,
+    Concat,
+    LessThan,
+    GreaterThan,
+    LessThanOrEquals,
+    GreaterThanOrEquals,
+    NotEquals,
+    Operator(String),
 }
 
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Token::ACTION => write!(f, "ACTION"),
+            Token::AFTER => write!(f, "AFTER"),
+            Token::ALL => write!(f, "ALL"),
+            Token::ALTER => write!(f, "ALTER"),
+            Token::ALWAYS => write!(f, "ALWAYS"),
+            Token::AND => write!(f, "AND"),
             Token::ARRAY => write!(f, "ARRAY"),
             Token::AS => write!(f, "AS"),
             Token::ASC => write!(f, "ASC"),
+            Token::AUTHORIZATION => write!(f, "AUTHORIZATION"),
+            Token::BEFORE => write!(f, "BEFORE"),
             Token::BIGINT => write!(f, "BIGINT"),
             Token::BIGSERIAL => write!(f, "BIGSERIAL"),
             Token::BIT => write!(f, "BIT"),
             Token::BOOL => write!(f, "BOOL"),
             Token::BOOLEAN => write!(f, "BOOLEAN"),
+            Token::BOX => write!(f, "BOX"),
+            Token::BPCHAR => write!(f, "BPCHAR"),
             Token::BTREE => write!(f, "BTREE"),
+            Token::BY => write!(f, "BY"),
+            Token::BYTEA => write!(f, "BYTEA"),
+            Token::CACHE => write!(f, "CACHE"),
+            Token::CALLED => write!(f, "CALLED"),
+            Token::CANONICAL => write!(f, "CANONICAL"),
             Token::CASCADE => write!(f, "CASCADE"),
+            Token::CAST => write!(f, "CAST"),
+            Token::CIDR => write!(f, "CIDR"),
+            Token::CIRCLE => write!(f, "CIRCLE"),
+            Token::COLUMN => write!(f, "COLUMN"),
+            Token::COMMENT => write!(f, "COMMENT"),
             Token::CONSTRAINT => write!(f, "CONSTRAINT"),
             Token::CHAR => write!(f, "CHAR"),
             Token::CHARACTER => write!(f, "CHARACTER"),
+            Token::CHECK => write!(f, "CHECK"),
+            Token::COLLATION => write!(f, "COLLATION"),
+            Token::COST => write!(f, "COST"),
             Token::CREATE => write!(f, "CREATE"),
+            Token::CURRENT_CATALOG => write!(f, "CURRENT_CATALOG"),
+            Token::CURRENT_DATE => write!(f, "CURRENT_DATE"),
+            Token::CURRENT_ROLE => write!(f, "CURRENT_ROLE"),
+            Token::CURRENT_SCHEMA => write!(f, "CURRENT_SCHEMA"),
+            Token::CURRENT_TIME => write!(f, "CURRENT_TIME"),
+            Token::CURRENT_TIMESTAMP => write!(f, "CURRENT_TIMESTAMP"),
+            Token::CURRENT_USER => write!(f, "CURRENT_USER"),
+            Token::CYCLE => write!(f, "CYCLE"),
             Token::DATE => write!(f, "DATE"),
             Token::DEFAULT => write!(f, "DEFAULT"),
+            Token::DEFINER => write!(f, "DEFINER"),
             Token::DELETE => write!(f, "DELETE"),
             Token::DESC => write!(f, "DESC"),
+            Token::DISABLE => write!(f, "DISABLE"),
+            Token::DOMAIN => write!(f, "DOMAIN"),
             Token::DOUBLE => write!(f, "DOUBLE"),
+            Token::EACH => write!(f, "EACH"),
+            Token::ENABLE => write!(f, "ENABLE"),
             Token::ENUM => write!(f, "ENUM"),
+            Token::EXCLUDE => write!(f, "EXCLUDE"),
+            Token::EXECUTE => write!(f, "EXECUTE"),
             Token::EXTENSION => write!(f, "EXTENSION"),
+            Token::EXTERNAL => write!(f, "EXTERNAL"),
             Token::FILLFACTOR => write!(f, "FILLFACTOR"),
             Token::FIRST => write!(f, "FIRST"),
+            Token::FOR => write!(f, "FOR"),
+            Token::FORCE => write!(f, "FORCE"),
             Token::FOREIGN => write!(f, "FOREIGN"),
+            Token::FROM => write!(f, "FROM"),
             Token::FULL => write!(f, "FULL"),
             Token::FUNCTION => write!(f, "FUNCTION"),
+            Token::GENERATED => write!(f, "GENERATED"),
             Token::GIN => write!(f, "GIN"),
             Token::GIST => write!(f, "GIST"),
+            Token::GRANT => write!(f, "GRANT"),
             Token::HASH => write!(f, "HASH"),
+            Token::IDENTITY => write!(f, "IDENTITY"),
+            Token::IMMUTABLE => write!(f, "IMMUTABLE"),
             Token::IN => write!(f, "IN"),
+            Token::INCREMENT => write!(f, "INCREMENT"),
             Token::INDEX => write!(f, "INDEX"),
+            Token::INET => write!(f, "INET"),
             Token::INOUT => write!(f, "INOUT"),
+            Token::INHERIT => write!(f, "INHERIT"),
+            Token::INPUT => write!(f, "INPUT"),
+            Token::INSERT => write!(f, "INSERT"),
+            Token::INSTEAD => write!(f, "INSTEAD"),
             Token::INT => write!(f, "INT"),
             Token::INT2 => write!(f, "INT2"),
             Token::INT4 => write!(f, "INT4"),
             Token::INT8 => write!(f, "INT8"),
             Token::INTEGER => write!(f, "INTEGER"),
+            Token::INTERVAL => write!(f, "INTERVAL"),
+            Token::INVOKER => write!(f, "INVOKER"),
+            Token::IS => write!(f, "IS"),
             Token::JSON => write!(f, "JSON"),
             Token::JSONB => write!(f, "JSONB"),
             Token::KEY => write!(f, "KEY"),
             Token::LANGUAGE => write!(f, "LANGUAGE"),
             Token::LAST => write!(f, "LAST"),
+            Token::LEVEL => write!(f, "LEVEL"),
+            Token::LINE => write!(f, "LINE"),
+            Token::LIST => write!(f, "LIST"),
+            Token::LOCALTIME => write!(f, "LOCALTIME"),
+            Token::LOCALTIMESTAMP => write!(f, "LOCALTIMESTAMP"),
+            Token::LSEG => write!(f, "LSEG"),
+            Token::MACADDR => write!(f, "MACADDR"),
+            Token::MACADDR8 => write!(f, "MACADDR8"),
             Token::MATCH => write!(f, "MATCH"),
+            Token::MATERIALIZED => write!(f, "MATERIALIZED"),
+            Token::MAXVALUE => write!(f, "MAXVALUE"),
+            Token::MINVALUE => write!(f, "MINVALUE"),
+            Token::MODULUS => write!(f, "MODULUS"),
             Token::MONEY => write!(f, "MONEY"),
             Token::NO => write!(f, "NO"),
+            Token::NONE => write!(f, "NONE"),
             Token::NOT => write!(f, "NOT"),
             Token::NULL => write!(f, "NULL"),
             Token::NULLS => write!(f, "NULLS"),
             Token::NUMERIC => write!(f, "NUMERIC"),
+            Token::OF => write!(f, "OF"),
+            Token::OID => write!(f, "OID"),
             Token::ON => write!(f, "ON"),
+            Token::OPTION => write!(f, "OPTION"),
             Token::OR => write!(f, "OR"),
             Token::OUT => write!(f, "OUT"),
+            Token::OWNED => write!(f, "OWNED"),
+            Token::PARALLEL => write!(f, "PARALLEL"),
             Token::PARTIAL => write!(f, "PARTIAL"),
+            Token::PARTITION => write!(f, "PARTITION"),
+            Token::PATH => write!(f, "PATH"),
+            Token::PERMISSIVE => write!(f, "PERMISSIVE"),
+            Token::POINT => write!(f, "POINT"),
+            Token::POLICY => write!(f, "POLICY"),
+            Token::POLYGON => write!(f, "POLYGON"),
             Token::PRECISION => write!(f, "PRECISION"),
             Token::PRIMARY => write!(f, "PRIMARY"),
+            Token::PRIVILEGES => write!(f, "PRIVILEGES"),
+            Token::PROCEDURE => write!(f, "PROCEDURE"),
+            Token::RANGE => write!(f, "RANGE"),
             Token::REAL => write!(f, "REAL"),
             Token::REFERENCES => write!(f, "REFERENCES"),
+            Token::REMAINDER => write!(f, "REMAINDER"),
             Token::REPLACE => write!(f, "REPLACE"),
             Token::RESTRICT => write!(f, "RESTRICT"),
+            Token::RESTRICTED => write!(f, "RESTRICTED"),
+            Token::RESTRICTIVE => write!(f, "RESTRICTIVE"),
             Token::RETURNS => write!(f, "RETURNS"),
+            Token::REVOKE => write!(f, "REVOKE"),
+            Token::ROW => write!(f, "ROW"),
+            Token::ROWS => write!(f, "ROWS"),
+            Token::SAFE => write!(f, "SAFE"),
             Token::SCHEMA => write!(f, "SCHEMA"),
+            Token::SECURITY => write!(f, "SECURITY"),
+            Token::SELECT => write!(f, "SELECT"),
+            Token::SEQUENCE => write!(f, "SEQUENCE"),
             Token::SERIAL => write!(f, "SERIAL"),
             Token::SERIAL2 => write!(f, "SERIAL2"),
             Token::SERIAL4 => write!(f, "SERIAL4"),
             Token::SERIAL8 => write!(f, "SERIAL8"),
+            Token::SESSION_USER => write!(f, "SESSION_USER"),
             Token::SET => write!(f, "SET"),
             Token::SETOF => write!(f, "SETOF"),
             Token::SIMPLE => write!(f, "SIMPLE"),
             Token::SMALLINT => write!(f, "SMALLINT"),
             Token::SMALLSERIAL => write!(f, "SMALLSERIAL"),
+            Token::STABLE => write!(f, "STABLE"),
+            Token::START => write!(f, "START"),
+            Token::STATEMENT => write!(f, "STATEMENT"),
+            Token::STORED => write!(f, "STORED"),
+            Token::STRICT => write!(f, "STRICT"),
+            Token::SUBTYPE => write!(f, "SUBTYPE"),
+            Token::SUBTYPE_DIFF => write!(f, "SUBTYPE_DIFF"),
+            Token::SUBTYPE_OPCLASS => write!(f, "SUBTYPE_OPCLASS"),
             Token::TABLE => write!(f, "TABLE"),
             Token::TEXT => write!(f, "TEXT"),
             Token::TIME => write!(f, "TIME"),
             Token::TIMESTAMP => write!(f, "TIMESTAMP"),
             Token::TIMESTAMPTZ => write!(f, "TIMESTAMPTZ"),
             Token::TIMETZ => write!(f, "TIMETZ"),
+            Token::TO => write!(f, "TO"),
+            Token::TRIGGER => write!(f, "TRIGGER"),
+            Token::TRUNCATE => write!(f, "TRUNCATE"),
+            Token::TSQUERY => write!(f, "TSQUERY"),
+            Token::TSVECTOR => write!(f, "TSVECTOR"),
             Token::TYPE => write!(f, "TYPE"),
             Token::UNIQUE => write!(f, "UNIQUE"),
+            Token::UNSAFE => write!(f, "UNSAFE"),
             Token::UPDATE => write!(f, "UPDATE"),
+            Token::USAGE => write!(f, "USAGE"),
             Token::USING => write!(f, "USING"),
             Token::UUID => write!(f, "UUID"),
+            Token::VALUES => write!(f, "VALUES"),
             Token::VARBIT => write!(f, "VARBIT"),
             Token::VARCHAR => write!(f, "VARCHAR"),
             Token::VARIADIC => write!(f, "VARIADIC"),
             Token::VARYING => write!(f, "VARYING"),
+            Token::VIEW => write!(f, "VIEW"),
+            Token::VOLATILE => write!(f, "VOLATILE"),
+            Token::WHEN => write!(f, "WHEN"),
+            Token::WHERE => write!(f, "WHERE"),
             Token::WITH => write!(f, "WITH"),
             Token::WITHOUT => write!(f, "WITHOUT"),
+            Token::XML => write!(f, "XML"),
             Token::ZONE => write!(f, "ZONE"),
 
             Token::Identifier(ref ident) => write!(f, "Ident({})", ident),
@@ -353,20 +627,34 @@ impl fmt::Display for Token {
             Token::Period => write!(f, "."),
             Token::Semicolon => write!(f, ";"),
             Token::Equals => write!(f, "="),
+
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Star => write!(f, "*"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Caret => write!(f, "^"),
+            Token::Concat => write!(f, "||"),
+            Token::LessThan => write!(f, "<"),
+            Token::GreaterThan => write!(f, ">"),
+            Token::LessThanOrEquals => write!(f, "<="),
+            Token::GreaterThanOrEquals => write!(f, ">="),
+            Token::NotEquals => write!(f, "<>"),
+            Token::Operator(ref operator) => write!(f, "Op({})", operator),
         }
     }
 }
 
 lazy_static! {
     static ref IDENTIFIER: Regex = Regex::new("^[a-zA-Z][a-zA-Z0-9_]*$").unwrap();
-    static ref DECIMAL: Regex = Regex::new("^\\d+\\.\\d+$").unwrap();
-    static ref DIGIT: Regex = Regex::new("^\\d+$").unwrap();
+    static ref DECIMAL: Regex = Regex::new("^-?\\d+\\.\\d+$").unwrap();
+    static ref DIGIT: Regex = Regex::new("^-?\\d+$").unwrap();
 }
 
 macro_rules! tokenize_normal_buffer {
     ($context:ident, $line:ident, $tokens:ident) => {{
         if $context.buffer.len() > 0 {
-            let token = match self::create_normal_token(&mut $context) {
+            let token = match self::create_normal_token(&mut $context, &$tokens) {
                 Some(t) => t,
                 None => return Err($context.create_error($line, "unexpected token")),
             };
@@ -402,7 +690,84 @@ macro_rules! push_token {
     };
 }
 
-fn create_normal_token(context: &mut Context) -> Option<Token> {
+fn is_exclusion_start(tokens: &[Token]) -> bool {
+    matches!(
+        tokens,
+        [.., Token::EXCLUDE]
+            | [
+                ..,
+                Token::EXCLUDE,
+                Token::USING,
+                Token::BTREE | Token::GIST | Token::HASH | Token::GIN
+            ]
+    )
+}
+
+// PARTITION BY follows the column list whereas PARTITION OF follows the table name
+fn is_partition_start(tokens: &[Token]) -> bool {
+    matches!(
+        tokens,
+        [.., Token::RightBracket]
+            | [.., Token::TABLE, Token::Identifier(_)]
+            | [
+                ..,
+                Token::TABLE,
+                Token::Identifier(_),
+                Token::Period,
+                Token::Identifier(_)
+            ]
+    )
+}
+
+// Function attributes follow the return type, outside of any brackets (e.g. a returned table). Procedures don't
+// return anything so their attributes follow the arguments instead.
+fn is_function_attribute_position(tokens: &[Token]) -> bool {
+    let mut depth = 0;
+    let mut closed = false;
+    for token in tokens.iter().rev() {
+        match *token {
+            Token::RightBracket => {
+                depth += 1;
+                closed = true;
+            }
+            Token::LeftBracket => depth -= 1,
+            Token::RETURNS => return depth == 0,
+            Token::PROCEDURE => return depth == 0 && closed,
+            Token::FUNCTION | Token::Semicolon => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn is_operator_char(c: char) -> bool {
+    matches!(
+        c,
+        '+' | '-' | '*' | '/' | '<' | '>' | '=' | '~' | '!' | '@' | '#' | '%' | '^' | '&' | '|' | '`' | '?'
+    )
+}
+
+fn is_operator_buffer(buffer: &[char]) -> bool {
+    !buffer.is_empty() && buffer.iter().all(|c| is_operator_char(*c))
+}
+
+// A minus directly before a number is a negative number unless it follows something it could subtract from
+fn follows_operand(tokens: &[Token]) -> bool {
+    matches!(
+        tokens.last(),
+        Some(
+            Token::Identifier(_)
+                | Token::Digit(_)
+                | Token::Decimal(_)
+                | Token::Boolean(_)
+                | Token::StringValue(_)
+                | Token::RightBracket
+                | Token::RightSquare
+        )
+    )
+}
+
+fn create_normal_token(context: &mut Context, tokens: &[Token]) -> Option<Token> {
     let variant = if let LexerState::Normal(variant) = context.peek_state() {
         variant
     } else {
@@ -410,6 +775,23 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     };
 
     let value = String::from_iter(context.buffer.clone());
+    if value.chars().all(is_operator_char) {
+        return Some(match &value[..] {
+            "+" => Token::Plus,
+            "-" => Token::Minus,
+            "*" => Token::Star,
+            "/" => Token::Slash,
+            "%" => Token::Percent,
+            "^" => Token::Caret,
+            "||" => Token::Concat,
+            "<" => Token::LessThan,
+            ">" => Token::GreaterThan,
+            "<=" => Token::LessThanOrEquals,
+            ">=" => Token::GreaterThanOrEquals,
+            "<>" | "!=" => Token::NotEquals,
+            _ => Token::Operator(value),
+        });
+    }
     if "true".eq_ignore_ascii_case(&value[..]) {
         return Some(Token::Boolean(true));
     }
@@ -420,18 +802,205 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     // Keywords - this is very naive and should be generated.
     if let NormalVariant::Any = variant {
         match_keyword!(value, CREATE);
+        match_keyword!(value, MATERIALIZED);
         match_keyword!(value, OR);
         match_keyword!(value, REPLACE);
 
         // Any of the below will switch state. This only gets reset on statement end.
+        match_keyword_replace_state!(context, NormalVariant::Alter, value, ALTER);
+        match_keyword_replace_state!(context, NormalVariant::Comment, value, COMMENT);
+        match_keyword_replace_state!(context, NormalVariant::Body, value, DOMAIN);
         match_keyword_replace_state!(context, NormalVariant::Body, value, EXTENSION);
-        match_keyword_replace_state!(context, NormalVariant::Body, value, FUNCTION);
+        match_keyword_replace_state!(context, NormalVariant::Function, value, FUNCTION);
+        match_keyword_replace_state!(context, NormalVariant::Grant, value, GRANT);
         match_keyword_replace_state!(context, NormalVariant::Body, value, INDEX);
+        match_keyword_replace_state!(context, NormalVariant::Policy, value, POLICY);
+        match_keyword_replace_state!(context, NormalVariant::Function, value, PROCEDURE);
+        match_keyword_replace_state!(context, NormalVariant::Grant, value, REVOKE);
         match_keyword_replace_state!(context, NormalVariant::Body, value, SCHEMA);
+        match_keyword_replace_state!(context, NormalVariant::Sequence, value, SEQUENCE);
         match_keyword_replace_state!(context, NormalVariant::Body, value, TABLE);
+        match_keyword_replace_state!(context, NormalVariant::Trigger, value, TRIGGER);
+        match_keyword_replace_state!(context, NormalVariant::Type, value, TYPE);
+        match_keyword_replace_state!(context, NormalVariant::View, value, VIEW);
+    }
+
+    // Function attributes are common identifiers so they're only matched once the return type, or the arguments of
+    // a procedure, have been declared
+    if let NormalVariant::Function = variant {
+        if is_function_attribute_position(tokens) {
+            match_keyword!(value, CALLED);
+            match_keyword!(value, COST);
+            match_keyword!(value, DEFINER);
+            match_keyword!(value, EXTERNAL);
+            match_keyword!(value, IMMUTABLE);
+            match_keyword!(value, INPUT);
+            match_keyword!(value, INVOKER);
+            match_keyword!(value, PARALLEL);
+            match_keyword!(value, RESTRICTED);
+            match_keyword!(value, ROWS);
+            match_keyword!(value, SAFE);
+            match_keyword!(value, SECURITY);
+            match_keyword!(value, STABLE);
+            match_keyword!(value, STRICT);
+            match_keyword!(value, TO);
+            match_keyword!(value, UNSAFE);
+            match_keyword!(value, VOLATILE);
+        }
     }
 
+    // Trigger keywords are common identifiers so we only match them within a trigger statement.
+    if let NormalVariant::Trigger = variant {
+        match_keyword!(value, AFTER);
+        match_keyword!(value, BEFORE);
+        match_keyword!(value, EACH);
+        match_keyword!(value, EXECUTE);
+        match_keyword!(value, FOR);
+        match_keyword!(value, FUNCTION);
+        match_keyword!(value, INSERT);
+        match_keyword!(value, INSTEAD);
+        match_keyword!(value, OF);
+        match_keyword!(value, PROCEDURE);
+        match_keyword!(value, ROW);
+        match_keyword!(value, STATEMENT);
+        match_keyword!(value, TRUNCATE);
+        if "WHEN".eq_ignore_ascii_case(&value[..]) {
+            // The condition is captured verbatim
+            context.push_state(LexerState::ConditionStart);
+            return Some(Token::WHEN);
+        }
+    }
+
+    // Object kinds are only keywords directly after ON so that they can still be used as names within a comment.
+    if let NormalVariant::Comment = variant {
+        if let [.., Token::ON] | [.., Token::MATERIALIZED] = tokens {
+            match_keyword!(value, COLUMN);
+            match_keyword!(value, FUNCTION);
+            match_keyword!(value, INDEX);
+            match_keyword!(value, MATERIALIZED);
+            match_keyword!(value, PROCEDURE);
+            match_keyword!(value, SCHEMA);
+            match_keyword!(value, SEQUENCE);
+            match_keyword!(value, VIEW);
+        }
+        match_keyword!(value, IS);
+    }
+
+    // Row level security is only changed through an ALTER TABLE statement so these are only matched there.
+    if let NormalVariant::Alter = variant {
+        match_keyword!(value, DISABLE);
+        match_keyword!(value, ENABLE);
+        match_keyword!(value, FORCE);
+        match_keyword!(value, LEVEL);
+        match_keyword!(value, ROW);
+        match_keyword!(value, SECURITY);
+    }
+
+    // Policy keywords are common identifiers so we only match them within a policy statement. The USING and
+    // WITH CHECK expressions are captured verbatim.
+    if let NormalVariant::Policy = variant {
+        match_keyword!(value, ALL);
+        match_keyword!(value, FOR);
+        match_keyword!(value, INSERT);
+        match_keyword!(value, PERMISSIVE);
+        match_keyword!(value, RESTRICTIVE);
+        match_keyword!(value, SELECT);
+        match_keyword!(value, TO);
+        if "USING".eq_ignore_ascii_case(&value[..]) {
+            context.push_state(LexerState::ConditionStart);
+            return Some(Token::USING);
+        }
+    }
+
+    // Privileges are common identifiers so we only match them within a GRANT or REVOKE statement.
+    if let NormalVariant::Grant = variant {
+        match_keyword!(value, ALL);
+        match_keyword!(value, CREATE);
+        match_keyword!(value, EXECUTE);
+        match_keyword!(value, FOR);
+        match_keyword!(value, FROM);
+        match_keyword!(value, FUNCTION);
+        match_keyword!(value, GRANT);
+        match_keyword!(value, INSERT);
+        match_keyword!(value, OPTION);
+        match_keyword!(value, PRIVILEGES);
+        match_keyword!(value, SCHEMA);
+        match_keyword!(value, SELECT);
+        match_keyword!(value, SEQUENCE);
+        match_keyword!(value, TO);
+        match_keyword!(value, TRIGGER);
+        match_keyword!(value, TRUNCATE);
+        match_keyword!(value, USAGE);
+    }
+
+    // Sequence options are common identifiers so we only match them within a sequence statement or the
+    // sequence options of an identity column.
+    if let NormalVariant::Sequence | NormalVariant::Identity = variant {
+        match_keyword!(value, CACHE);
+        match_keyword!(value, CYCLE);
+        match_keyword!(value, INCREMENT);
+        match_keyword!(value, MAXVALUE);
+        match_keyword!(value, MINVALUE);
+        match_keyword!(value, NONE);
+        match_keyword!(value, OWNED);
+        match_keyword!(value, START);
+    }
+
+    // Partition clauses are only recognized after the table name or column list so that PARTITION can still
+    // be used as a column name. The rest of the statement then describes the partitioning.
+    if let NormalVariant::Body = variant {
+        if "PARTITION".eq_ignore_ascii_case(&value[..]) && is_partition_start(tokens) {
+            context.replace_state(LexerState::Normal(NormalVariant::Partition));
+            return Some(Token::PARTITION);
+        }
+    }
+    if let NormalVariant::Partition = variant {
+        match_keyword!(value, FOR);
+        match_keyword!(value, FROM);
+        match_keyword!(value, LIST);
+        match_keyword!(value, MAXVALUE);
+        match_keyword!(value, MINVALUE);
+        match_keyword!(value, MODULUS);
+        match_keyword!(value, OF);
+        match_keyword!(value, PARTITION);
+        match_keyword!(value, RANGE);
+        match_keyword!(value, REMAINDER);
+        match_keyword!(value, TO);
+        match_keyword!(value, VALUES);
+    }
+
+    // Range options are only keywords within a type statement.
+    if let NormalVariant::Type = variant {
+        match_keyword!(value, CANONICAL);
+        match_keyword!(value, COLLATION);
+        match_keyword!(value, RANGE);
+        match_keyword!(value, SUBTYPE);
+        match_keyword!(value, SUBTYPE_DIFF);
+        match_keyword!(value, SUBTYPE_OPCLASS);
+    }
+
+    // Check expressions and exclusion predicates are captured verbatim
+    if "CHECK".eq_ignore_ascii_case(&value[..]) {
+        context.push_state(LexerState::ConditionStart);
+        return Some(Token::CHECK);
+    }
+    if "WHERE".eq_ignore_ascii_case(&value[..]) {
+        context.push_state(LexerState::ConditionStart);
+        return Some(Token::WHERE);
+    }
+
+    // The query of a view is captured verbatim up until the end of the statement.
+    if let NormalVariant::View = variant {
+        if "AS".eq_ignore_ascii_case(&value[..]) {
+            context.replace_state(LexerState::Query);
+            return Some(Token::AS);
+        }
+    }
+
+    match_keyword!(value, AUTHORIZATION);
     match_keyword!(value, ACTION);
+    match_keyword!(value, ALWAYS);
+    match_keyword!(value, AND);
     match_keyword!(value, ARRAY);
     match_keyword!(value, AS);
     match_keyword!(value, ASC);
@@ -440,36 +1009,62 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     match_keyword!(value, BIT);
     match_keyword!(value, BOOL);
     match_keyword!(value, BOOLEAN);
+    match_keyword!(value, BOX);
+    match_keyword!(value, BPCHAR);
     match_keyword!(value, BTREE);
+    match_keyword!(value, BY);
+    match_keyword!(value, BYTEA);
     match_keyword!(value, CASCADE);
+    match_keyword!(value, CAST);
+    match_keyword!(value, CIDR);
+    match_keyword!(value, CIRCLE);
     match_keyword!(value, CONSTRAINT);
     match_keyword!(value, CHAR);
     match_keyword!(value, CHARACTER);
+    match_keyword!(value, CURRENT_CATALOG);
+    match_keyword!(value, CURRENT_DATE);
+    match_keyword!(value, CURRENT_ROLE);
+    match_keyword!(value, CURRENT_SCHEMA);
+    match_keyword!(value, CURRENT_TIME);
+    match_keyword!(value, CURRENT_TIMESTAMP);
+    match_keyword!(value, CURRENT_USER);
     match_keyword!(value, DATE);
     match_keyword!(value, DEFAULT);
     match_keyword!(value, DELETE);
     match_keyword!(value, DESC);
     match_keyword!(value, DOUBLE);
     match_keyword!(value, ENUM);
+    match_keyword!(value, EXCLUDE);
     match_keyword!(value, FILLFACTOR);
     match_keyword!(value, FIRST);
     match_keyword!(value, FOREIGN);
     match_keyword!(value, FULL);
+    match_keyword!(value, GENERATED);
     match_keyword!(value, GIN);
     match_keyword!(value, GIST);
     match_keyword!(value, HASH);
+    match_keyword!(value, IDENTITY);
     match_keyword!(value, IN);
+    match_keyword!(value, INET);
+    match_keyword!(value, INHERIT);
     match_keyword!(value, INOUT);
     match_keyword!(value, INT);
     match_keyword!(value, INT2);
     match_keyword!(value, INT4);
     match_keyword!(value, INT8);
     match_keyword!(value, INTEGER);
+    match_keyword!(value, INTERVAL);
     match_keyword!(value, JSON);
     match_keyword!(value, JSONB);
     match_keyword!(value, KEY);
     match_keyword!(value, LANGUAGE);
     match_keyword!(value, LAST);
+    match_keyword!(value, LINE);
+    match_keyword!(value, LOCALTIME);
+    match_keyword!(value, LOCALTIMESTAMP);
+    match_keyword!(value, LSEG);
+    match_keyword!(value, MACADDR);
+    match_keyword!(value, MACADDR8);
     match_keyword!(value, MATCH);
     match_keyword!(value, MONEY);
     match_keyword!(value, NO);
@@ -477,10 +1072,14 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     match_keyword!(value, NULL);
     match_keyword!(value, NULLS);
     match_keyword!(value, NUMERIC);
+    match_keyword!(value, OID);
     match_keyword!(value, ON);
     match_keyword!(value, OR);
     match_keyword!(value, OUT);
     match_keyword!(value, PARTIAL);
+    match_keyword!(value, PATH);
+    match_keyword!(value, POINT);
+    match_keyword!(value, POLYGON);
     match_keyword!(value, PRECISION);
     match_keyword!(value, PRIMARY);
     match_keyword!(value, REAL);
@@ -491,17 +1090,21 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     match_keyword!(value, SERIAL2);
     match_keyword!(value, SERIAL4);
     match_keyword!(value, SERIAL8);
+    match_keyword!(value, SESSION_USER);
     match_keyword!(value, SET);
     match_keyword!(value, SETOF);
     match_keyword!(value, SIMPLE);
     match_keyword!(value, SMALLINT);
     match_keyword!(value, SMALLSERIAL);
     match_keyword!(value, TABLE); // The one exception
+    match_keyword!(value, STORED);
     match_keyword!(value, TEXT);
     match_keyword!(value, TIME);
     match_keyword!(value, TIMESTAMP);
     match_keyword!(value, TIMESTAMPTZ);
     match_keyword!(value, TIMETZ);
+    match_keyword!(value, TSQUERY);
+    match_keyword!(value, TSVECTOR);
     match_keyword!(value, TYPE);
     match_keyword!(value, UNIQUE);
     match_keyword!(value, UPDATE);
@@ -513,6 +1116,7 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
     match_keyword!(value, VARYING);
     match_keyword!(value, WITH);
     match_keyword!(value, WITHOUT);
+    match_keyword!(value, XML);
     match_keyword!(value, ZONE);
 
     // Regex
@@ -523,22 +1127,30 @@ fn create_normal_token(context: &mut Context) -> Option<Token> {
         return Some(Token::Decimal(value.parse::<Decimal>().unwrap()));
     }
     if DIGIT.is_match(&value[..]) {
-        return Some(Token::Digit(value.parse::<i32>().unwrap()));
+        // Integers too large for a digit (e.g. sequence bounds) are kept as a decimal
+        return Some(match value.parse::<i32>() {
+            Ok(digit) => Token::Digit(digit),
+            Err(_) => Token::Decimal(value.parse::<Decimal>().ok()?),
+        });
     }
 
     // Error
     None
 }
 
-pub fn tokenize_body(text: &str) -> Result<Vec<Token>, LexicalError> {
+pub fn tokenize_body(text: &str) -> Result<Vec<Token>, LexicalError<'_>> {
     tokenize(text, NormalVariant::Body)
 }
 
-pub fn tokenize_stmt(text: &str) -> Result<Vec<Token>, LexicalError> {
+pub fn tokenize_partition(text: &str) -> Result<Vec<Token>, LexicalError<'_>> {
+    tokenize(text, NormalVariant::Partition)
+}
+
+pub fn tokenize_stmt(text: &str) -> Result<Vec<Token>, LexicalError<'_>> {
     tokenize(text, NormalVariant::Any)
 }
 
-fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError> {
+fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError<'_>> {
     // This tokenizer is whitespace dependent by default, i.e. whitespace is relevant.
     let mut tokens = Vec::new();
     let mut context = Context::new(start);
@@ -564,7 +1176,14 @@ fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError
                         tokenize_normal_buffer!(context, line, tokens);
                         context.push_state(LexerState::Comment2);
                     } else if c == '\'' {
-                        if context.buffer.is_empty() {
+                        if context.buffer.is_empty() && context.last_char == '\'' {
+                            // A quote immediately following a string is an escaped quote within the string
+                            if let Some(Token::StringValue(value)) = tokens.pop() {
+                                context.buffer.extend(value.chars());
+                                context.buffer.push(c);
+                            }
+                            context.push_state(LexerState::String);
+                        } else if context.buffer.is_empty() {
                             context.push_state(LexerState::String);
                         } else {
                             // Invalid state - must be something like xx'dd
@@ -592,11 +1211,28 @@ fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError
                         match c {
                             '(' => {
                                 tokenize_normal_buffer!(context, line, tokens);
-                                push_token!(tokens, Token::LeftBracket);
+                                // AS may have just switched us over to capturing a query
+                                match context.peek_state() {
+                                    LexerState::Query => context.buffer.push(c),
+                                    LexerState::ConditionStart => context.replace_state(LexerState::Condition(1)),
+                                    // Exclusion elements contain arbitrary operators so are captured verbatim
+                                    _ if is_exclusion_start(&tokens) => context.push_state(LexerState::Condition(1)),
+                                    // The sequence options of an identity column
+                                    _ if matches!(tokens.last(), Some(Token::IDENTITY)) => {
+                                        push_token!(tokens, Token::LeftBracket);
+                                        context.push_state(LexerState::Normal(NormalVariant::Identity));
+                                    }
+                                    _ => {
+                                        push_token!(tokens, Token::LeftBracket);
+                                    }
+                                }
                             }
                             ')' => {
                                 tokenize_normal_buffer!(context, line, tokens);
                                 push_token!(tokens, Token::RightBracket);
+                                if let LexerState::Normal(NormalVariant::Identity) = context.peek_state() {
+                                    context.pop_state();
+                                }
                             }
                             ',' => {
                                 tokenize_normal_buffer!(context, line, tokens);
@@ -611,13 +1247,20 @@ fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError
                                 push_token!(tokens, Token::Semicolon);
                                 context.replace_state(LexerState::Normal(NormalVariant::Any));
                             }
+                            // Equals may also be part of an operator such as <= or !=
+                            '=' if is_operator_buffer(&context.buffer) => context.buffer.push(c),
                             '=' => {
                                 tokenize_normal_buffer!(context, line, tokens);
                                 push_token!(tokens, Token::Equals);
                             }
                             '.' => {
                                 // If it is just a plain digit in the buffer, then allow it to continue.
-                                if context.buffer.iter().all(|c: &char| c.is_digit(10)) {
+                                if context
+                                    .buffer
+                                    .iter()
+                                    .enumerate()
+                                    .all(|(i, c)| c.is_ascii_digit() || (i == 0 && *c == '-'))
+                                {
                                     context.buffer.push(c);
                                 } else {
                                     tokenize_normal_buffer!(context, line, tokens);
@@ -632,8 +1275,72 @@ fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError
                                 tokenize_normal_buffer!(context, line, tokens);
                                 push_token!(tokens, Token::RightSquare);
                             }
-                            _ => context.buffer.push(c),
+                            // Operators are tokenized separately from any surrounding words
+                            _ if is_operator_char(c) => {
+                                if !is_operator_buffer(&context.buffer) {
+                                    tokenize_normal_buffer!(context, line, tokens);
+                                }
+                                context.buffer.push(c);
+                            }
+                            _ => {
+                                if is_operator_buffer(&context.buffer)
+                                    && !(c.is_ascii_digit() && context.buffer == ['-'] && !follows_operand(&tokens))
+                                {
+                                    tokenize_normal_buffer!(context, line, tokens);
+                                }
+                                context.buffer.push(c);
+                            }
+                        }
+                    }
+                }
+                LexerState::Query => {
+                    if context.last_char == '-' && c == '-' {
+                        context.buffer.pop();
+                        context.push_state(LexerState::Comment1);
+                    } else if context.last_char == '/' && c == '*' {
+                        context.buffer.pop();
+                        context.push_state(LexerState::Comment2);
+                    } else if c == ';' {
+                        let data = String::from_iter(context.buffer.clone());
+                        push_token!(tokens, Token::Literal(data.trim().into()));
+                        push_token!(tokens, Token::Semicolon);
+                        context.buffer.clear();
+                        context.replace_state(LexerState::Normal(NormalVariant::Any));
+                    } else {
+                        if c == '\'' || c == '"' {
+                            context.push_state(LexerState::QueryQuoted(c));
+                        }
+                        context.buffer.push(c);
+                    }
+                }
+                LexerState::ConditionStart => {
+                    if c == '(' {
+                        context.replace_state(LexerState::Condition(1));
+                    } else if !c.is_whitespace() {
+                        return Err(context.create_error(line, "( was expected"));
+                    }
+                }
+                LexerState::Condition(depth) => {
+                    if c == ')' && depth == 1 {
+                        let data = String::from_iter(context.buffer.clone());
+                        push_token!(tokens, Token::Literal(data.trim().into()));
+                        context.buffer.clear();
+                        context.pop_state();
+                    } else {
+                        match c {
+                            '(' => context.replace_state(LexerState::Condition(depth + 1)),
+                            ')' => context.replace_state(LexerState::Condition(depth - 1)),
+                            '\'' | '"' => context.push_state(LexerState::QueryQuoted(c)),
+                            _ => {}
                         }
+                        context.buffer.push(c);
+                    }
+                }
+                LexerState::QueryQuoted(quote) => {
+                    // Quoted text within a query is kept as is
+                    context.buffer.push(c);
+                    if c == quote {
+                        context.pop_state();
                     }
                 }
                 LexerState::Comment1 => {
@@ -743,6 +1450,9 @@ fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError
             LexerState::Comment1 => {
                 // End of a line finishes the comment
                 context.pop_state();
+                if let LexerState::Query = context.peek_state() {
+                    context.buffer.push('\n');
+                }
             }
             LexerState::Comment2 => {
                 // Do nothing at the end of a line - it's a multi-line comment
@@ -752,12 +1462,23 @@ fn tokenize(text: &str, start: NormalVariant) -> Result<Vec<Token>, LexicalError
                 // (e.g. at the moment we don't support multi-line strings)
                 return Err(context.create_error(line, "end of line was unexpected"));
             }
-            LexerState::LiteralBody => {
+            LexerState::ConditionStart => {
+                // The condition may start on the next line
+            }
+            LexerState::LiteralBody | LexerState::Query | LexerState::QueryQuoted(_) | LexerState::Condition(_) => {
                 // Add a new line onto the buffer
                 context.buffer.push('\n');
             }
         }
     }
 
+    // A query may be the last thing in the file without a terminating semicolon
+    if let LexerState::Query = context.peek_state() {
+        let data = String::from_iter(context.buffer.clone());
+        if !data.trim().is_empty() {
+            push_token!(tokens, Token::Literal(data.trim().into()));
+        }
+    }
+
     Ok(tokens)
 }
@@ -1,6 +1,11 @@
+use rust_decimal::Decimal;
+
 use crate::sql::ast::*;
 use crate::sql::lexer;
-use crate::sql::parser::{FunctionArgumentListParser, StatementListParser};
+use crate::sql::parser::{
+    ExpressionParser, FunctionArgumentListParser, PartitionBoundParser, PartitionKeyParser, SqlTypeParser,
+    StatementListParser,
+};
 
 #[test]
 fn it_can_parse_a_basic_function_definition() {
@@ -37,6 +42,849 @@ fn it_can_parse_a_basic_function_definition() {
             return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Integer, None)),
             body: "SELECT index".into(),
             language: FunctionLanguage::SQL,
+            volatility: FunctionVolatility::Volatile,
+            strict: false,
+            security_definer: false,
+            parallel: FunctionParallel::Unsafe,
+            cost: None,
+            rows: None,
+            settings: Vec::new(),
+            comment: None,
+        })
+    );
+}
+
+#[test]
+fn it_can_parse_a_view_definition() {
+    let sql = "CREATE OR REPLACE VIEW public.active_users (id, name) AS
+               SELECT id, name -- only active
+               FROM users
+               WHERE status = 'a;b' AND id > 0;
+               CREATE SCHEMA public;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 2);
+
+    assert_eq!(
+        statements[0],
+        Statement::View(ViewDefinition {
+            name: ObjectName {
+                schema: Some("public".into()),
+                name: "active_users".into(),
+            },
+            columns: Some(vec!["id".into(), "name".into()]),
+            query: "SELECT id, name \n               FROM users\n               WHERE status = 'a;b' AND id > 0".into(),
+            comment: None,
+        })
+    );
+    assert_eq!(
+        statements[1],
+        Statement::Schema(SchemaDefinition {
+            name: "public".into(),
+            owner: None,
+            comment: None
+        })
+    );
+}
+
+#[test]
+fn it_can_parse_a_schema_definition_with_an_owner() {
+    let sql = "CREATE SCHEMA reporting AUTHORIZATION app_owner;
+               CREATE SCHEMA AUTHORIZATION app_user;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 2);
+
+    assert_eq!(
+        statements[0],
+        Statement::Schema(SchemaDefinition {
+            name: "reporting".into(),
+            owner: Some("app_owner".into()),
+            comment: None
+        })
+    );
+    // The schema is named after the owner when no name is given
+    assert_eq!(
+        statements[1],
+        Statement::Schema(SchemaDefinition {
+            name: "app_user".into(),
+            owner: Some("app_user".into()),
+            comment: None
+        })
+    );
+}
+
+#[test]
+fn it_can_parse_a_materialized_view_definition() {
+    let sql = "CREATE MATERIALIZED VIEW totals AS (SELECT count(*) FROM orders) WITH NO DATA";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+
+    assert_eq!(
+        statements[0],
+        Statement::MaterializedView(MaterializedViewDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "totals".into(),
+            },
+            columns: None,
+            query: "(SELECT count(*) FROM orders) WITH NO DATA".into(),
+            comment: None,
+        })
+    );
+}
+
+#[test]
+fn it_can_parse_unique_check_and_exclusion_table_constraints() {
+    let sql = "CREATE TABLE bookings (
+                 id int,
+                 room int,
+                 starts timestamp,
+                 finishes timestamp,
+                 price numeric(10,2),
+                 cancelled bool,
+                 CONSTRAINT uq_bookings_room UNIQUE (room, starts) WITH (FILLFACTOR=70),
+                 CONSTRAINT ck_bookings_price CHECK (price > 0 AND (finishes > starts)) NO INHERIT,
+                 CONSTRAINT ex_bookings_room EXCLUDE USING gist (room WITH =, tsrange(starts, finishes) WITH &&)
+                   WHERE (NOT cancelled)
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+
+    let table = match statements[0] {
+        Statement::Table(ref table) => table,
+        ref unexpected => panic!("Expected a table however saw {:?}", unexpected),
+    };
+    assert_eq!(table.columns.len(), 6);
+    assert_eq!(
+        table.constraints,
+        vec![
+            TableConstraint::Unique {
+                name: "uq_bookings_room".into(),
+                columns: vec!["room".into(), "starts".into()],
+                parameters: Some(vec![IndexParameter::FillFactor(70)]),
+            },
+            TableConstraint::Check {
+                name: "ck_bookings_price".into(),
+                expression: "price > 0 AND (finishes > starts)".into(),
+                no_inherit: true,
+            },
+            TableConstraint::Exclude {
+                name: "ex_bookings_room".into(),
+                index_type: Some(IndexType::Gist),
+                elements: vec![
+                    ExclusionElement {
+                        expression: "room".into(),
+                        operator: "=".into(),
+                    },
+                    ExclusionElement {
+                        expression: "tsrange(starts, finishes)".into(),
+                        operator: "&&".into(),
+                    },
+                ],
+                parameters: None,
+                condition: Some("NOT cancelled".into()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn it_can_parse_a_trigger_definition() {
+    let sql = "CREATE TRIGGER audit_changes
+               BEFORE INSERT OR UPDATE OF name, status OR DELETE ON public.users
+               FOR EACH ROW
+               WHEN (OLD.status IS DISTINCT FROM NEW.status AND NEW.name <> ')')
+               EXECUTE FUNCTION audit.log_change('users', 1);
+               CREATE TABLE each (row int);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 2);
+
+    assert_eq!(
+        statements[0],
+        Statement::Trigger(TriggerDefinition {
+            name: "audit_changes".into(),
+            table: ObjectName {
+                schema: Some("public".into()),
+                name: "users".into(),
+            },
+            timing: TriggerTiming::Before,
+            events: vec![
+                TriggerEvent::Insert,
+                TriggerEvent::Update(Some(vec!["name".into(), "status".into()])),
+                TriggerEvent::Delete,
+            ],
+            for_each: TriggerForEach::Row,
+            condition: Some("OLD.status IS DISTINCT FROM NEW.status AND NEW.name <> ')'".into()),
+            function: ObjectName {
+                schema: Some("audit".into()),
+                name: "log_change".into(),
+            },
+            arguments: vec!["users".into(), "1".into()],
+        })
+    );
+
+    // Trigger keywords are still valid identifiers elsewhere
+    match statements[1] {
+        Statement::Table(ref table) => {
+            assert_eq!(table.name.name, "each");
+            assert_eq!(table.columns[0].name, "row");
+        }
+        ref unexpected => panic!("Expected a table however saw {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_can_parse_an_inspected_trigger_definition() {
+    // As returned by pg_get_triggerdef
+    let sql = "CREATE TRIGGER refresh INSTEAD OF TRUNCATE ON public.totals FOR EACH STATEMENT \
+               WHEN ((pg_trigger_depth() < 1)) EXECUTE PROCEDURE refresh_totals()";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+
+    match statements[0] {
+        Statement::Trigger(ref trigger) => {
+            assert_eq!(trigger.timing, TriggerTiming::InsteadOf);
+            assert_eq!(trigger.events, vec![TriggerEvent::Truncate]);
+            assert_eq!(trigger.for_each, TriggerForEach::Statement);
+            assert_eq!(trigger.condition, Some("(pg_trigger_depth() < 1)".into()));
+            assert_eq!(trigger.function.to_string(), "refresh_totals");
+            assert!(trigger.arguments.is_empty());
+        }
+        ref unexpected => panic!("Expected a trigger however saw {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_can_parse_row_level_security_and_policies() {
+    let sql = "ALTER TABLE tenants.accounts ENABLE ROW LEVEL SECURITY;
+               ALTER TABLE accounts FORCE ROW LEVEL SECURITY;
+               ALTER TABLE accounts NO FORCE ROW LEVEL SECURITY;
+               CREATE POLICY tenant_isolation ON tenants.accounts
+                 AS RESTRICTIVE
+                 FOR UPDATE
+                 TO app_user, CURRENT_USER
+                 USING (tenant_id = current_setting('app.tenant')::int)
+                 WITH CHECK (owner = current_user AND (status <> 'closed'));
+               CREATE POLICY read_all ON accounts USING (true);
+               CREATE TABLE policy (level int, security text, permissive bool, select int);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 6);
+
+    let accounts = ObjectName {
+        schema: None,
+        name: "accounts".into(),
+    };
+    assert_eq!(
+        statements[0],
+        Statement::AlterTable(
+            ObjectName {
+                schema: Some("tenants".into()),
+                name: "accounts".into(),
+            },
+            AlterTableAction::EnableRowSecurity
+        )
+    );
+    assert_eq!(
+        statements[1],
+        Statement::AlterTable(accounts.clone(), AlterTableAction::ForceRowSecurity)
+    );
+    assert_eq!(
+        statements[2],
+        Statement::AlterTable(accounts.clone(), AlterTableAction::NoForceRowSecurity)
+    );
+    assert_eq!(
+        statements[3],
+        Statement::Policy(PolicyDefinition {
+            name: "tenant_isolation".into(),
+            table: ObjectName {
+                schema: Some("tenants".into()),
+                name: "accounts".into(),
+            },
+            permissive: false,
+            command: PolicyCommand::Update,
+            roles: vec!["app_user".into(), "CURRENT_USER".into()],
+            using: Some("tenant_id = current_setting('app.tenant')::int".into()),
+            with_check: Some("owner = current_user AND (status <> 'closed')".into()),
+        })
+    );
+    assert_eq!(
+        statements[4],
+        Statement::Policy(PolicyDefinition {
+            name: "read_all".into(),
+            table: accounts,
+            permissive: true,
+            command: PolicyCommand::All,
+            roles: Vec::new(),
+            using: Some("true".into()),
+            with_check: None,
+        })
+    );
+
+    // Policy keywords are still valid identifiers elsewhere
+    match statements[5] {
+        Statement::Table(ref table) => {
+            assert_eq!(table.name.name, "policy");
+            let columns = table.columns.iter().map(|c| &c.name[..]).collect::<Vec<_>>();
+            assert_eq!(columns, vec!["level", "security", "permissive", "select"]);
+        }
+        ref unexpected => panic!("Expected a table however saw {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_compares_policies_described_by_postgres() {
+    let policy = |using: &str, with_check: Option<&str>, roles: &[&str]| PolicyDefinition {
+        name: "tenant_isolation".into(),
+        table: ObjectName {
+            schema: Some("public".into()),
+            name: "accounts".into(),
+        },
+        permissive: true,
+        command: PolicyCommand::All,
+        roles: roles.iter().map(|r| r.to_string()).collect(),
+        using: Some(using.into()),
+        with_check: with_check.map(|c| c.into()),
+    };
+
+    // The project definition on the left and the definition from pg_policy on the right
+    let source = policy(
+        "tenant_id = current_setting('app.tenant')::int",
+        Some("owner = CURRENT_USER AND tenant_id > 0"),
+        &[],
+    );
+    let target = policy(
+        "(tenant_id = (current_setting('app.tenant'::text))::integer)",
+        Some("((owner = CURRENT_USER) AND (tenant_id > 0))"),
+        &["public"],
+    );
+    assert!(source.is_same_definition(&target));
+
+    // PUBLIC includes every other role
+    let source = policy("true", None, &["Admin", "public"]);
+    let target = policy("true", None, &["public"]);
+    assert!(source.is_same_definition(&target));
+
+    let source = policy("true", None, &["reader", "admin"]);
+    let target = policy("true", None, &["admin", "reader"]);
+    assert!(source.is_same_definition(&target));
+    let target = policy("true", None, &["admin"]);
+    assert!(!source.is_same_definition(&target));
+
+    let source = policy("tenant_id = 1", None, &[]);
+    let target = policy("(tenant_id = 2)", None, &[]);
+    assert!(!source.is_same_definition(&target));
+    let target = policy("(tenant_id = 1)", Some("true"), &[]);
+    assert!(!source.is_same_definition(&target));
+}
+
+#[test]
+fn it_can_parse_grant_and_revoke_statements() {
+    let sql = "GRANT SELECT, insert ON contacts, my.orders TO reader, PUBLIC;
+               GRANT ALL PRIVILEGES ON SEQUENCE my.orders_id_seq TO writer WITH GRANT OPTION;
+               GRANT USAGE ON SCHEMA my TO Reader;
+               GRANT EXECUTE ON FUNCTION my.total(integer, text) TO reader;
+               REVOKE GRANT OPTION FOR ALL ON TABLE contacts FROM writer CASCADE;
+               CREATE TABLE grants (usage int, option text, privileges bool);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 6);
+
+    let contacts = ObjectName {
+        schema: None,
+        name: "contacts".into(),
+    };
+    let orders = ObjectName {
+        schema: Some("my".into()),
+        name: "orders".into(),
+    };
+    let grant =
+        |object: GrantObject, grantee: &str, privileges: Vec<Privilege>, with_grant_option: bool| GrantDefinition {
+            object,
+            grantee: grantee.into(),
+            privileges,
+            with_grant_option,
+        };
+
+    // Each object and grantee is expanded into its own grant
+    let select_insert = vec![Privilege::Select, Privilege::Insert];
+    assert_eq!(
+        statements[0],
+        Statement::Grant(vec![
+            grant(
+                GrantObject::Table(contacts.clone()),
+                "reader",
+                select_insert.clone(),
+                false
+            ),
+            grant(
+                GrantObject::Table(contacts.clone()),
+                "public",
+                select_insert.clone(),
+                false
+            ),
+            grant(
+                GrantObject::Table(orders.clone()),
+                "reader",
+                select_insert.clone(),
+                false
+            ),
+            grant(GrantObject::Table(orders), "public", select_insert, false),
+        ])
+    );
+
+    // ALL depends on the type of object
+    assert_eq!(
+        statements[1],
+        Statement::Grant(vec![grant(
+            GrantObject::Sequence(ObjectName {
+                schema: Some("my".into()),
+                name: "orders_id_seq".into(),
+            }),
+            "writer",
+            vec![Privilege::Select, Privilege::Update, Privilege::Usage],
+            true
+        )])
+    );
+    assert_eq!(
+        statements[2],
+        Statement::Grant(vec![grant(
+            GrantObject::Schema("my".into()),
+            "reader",
+            vec![Privilege::Usage],
+            false
+        )])
+    );
+    assert_eq!(
+        statements[3],
+        Statement::Grant(vec![grant(
+            GrantObject::Function(
+                ObjectName {
+                    schema: Some("my".into()),
+                    name: "total".into(),
+                },
+                Some(vec![
+                    SqlType::Simple(SimpleSqlType::Integer, None),
+                    SqlType::Simple(SimpleSqlType::Text, None)
+                ])
+            ),
+            "reader",
+            vec![Privilege::Execute],
+            false
+        )])
+    );
+    assert_eq!(
+        statements[4],
+        Statement::Revoke(vec![grant(
+            GrantObject::Table(contacts),
+            "writer",
+            GrantObject::Table(ObjectName {
+                schema: None,
+                name: "contacts".into()
+            })
+            .all_privileges(),
+            true
+        )])
+    );
+
+    // Privileges are still valid identifiers elsewhere
+    match statements[5] {
+        Statement::Table(ref table) => {
+            assert_eq!(table.name.name, "grants");
+            let columns = table.columns.iter().map(|c| &c.name[..]).collect::<Vec<_>>();
+            assert_eq!(columns, vec!["usage", "option", "privileges"]);
+        }
+        ref unexpected => panic!("Expected a table however saw {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_can_parse_comment_statements() {
+    let sql = "COMMENT ON TABLE contacts IS 'People we''ve met';
+               COMMENT ON COLUMN my.orders.index IS 'Position of the order';
+               COMMENT ON COLUMN contacts.comment IS NULL;
+               COMMENT ON FUNCTION my.total(integer, text) IS 'Sums the orders';
+               COMMENT ON INDEX my.idx_orders_total IS 'For reporting';
+               COMMENT ON MATERIALIZED VIEW my.order_totals IS 'Refreshed nightly';
+               COMMENT ON PROCEDURE my.archive(date) IS 'Archives old orders';
+               COMMENT ON SCHEMA my IS 'Everything about orders';
+               COMMENT ON SEQUENCE my.order_numbers IS 'Invoice numbers';
+               COMMENT ON TYPE my.status IS 'Order status';
+               COMMENT ON VIEW active_contacts IS 'Contacts seen recently';
+               CREATE TABLE comments (comment text, index int, view bool);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 12);
+
+    let name = |schema: Option<&str>, name: &str| ObjectName {
+        schema: schema.map(|s| s.into()),
+        name: name.into(),
+    };
+    let comment = |object: CommentObject, comment: Option<&str>| {
+        Statement::Comment(CommentDefinition {
+            object,
+            comment: comment.map(|c| c.into()),
+        })
+    };
+    assert_eq!(
+        statements[..11],
+        [
+            comment(CommentObject::Table(name(None, "contacts")), Some("People we've met")),
+            comment(
+                CommentObject::Column(name(Some("my"), "orders"), "index".into()),
+                Some("Position of the order")
+            ),
+            comment(CommentObject::Column(name(None, "contacts"), "comment".into()), None),
+            comment(
+                CommentObject::Function(
+                    name(Some("my"), "total"),
+                    Some(vec![
+                        SqlType::Simple(SimpleSqlType::Integer, None),
+                        SqlType::Simple(SimpleSqlType::Text, None)
+                    ])
+                ),
+                Some("Sums the orders")
+            ),
+            comment(
+                CommentObject::Index(name(Some("my"), "idx_orders_total")),
+                Some("For reporting")
+            ),
+            comment(
+                CommentObject::MaterializedView(name(Some("my"), "order_totals")),
+                Some("Refreshed nightly")
+            ),
+            comment(
+                CommentObject::Procedure(
+                    name(Some("my"), "archive"),
+                    Some(vec![SqlType::Simple(SimpleSqlType::Date, None)])
+                ),
+                Some("Archives old orders")
+            ),
+            comment(CommentObject::Schema("my".into()), Some("Everything about orders")),
+            comment(
+                CommentObject::Sequence(name(Some("my"), "order_numbers")),
+                Some("Invoice numbers")
+            ),
+            comment(CommentObject::Type(name(Some("my"), "status")), Some("Order status")),
+            comment(
+                CommentObject::View(name(None, "active_contacts")),
+                Some("Contacts seen recently")
+            ),
+        ]
+    );
+
+    // Object kinds are still valid identifiers elsewhere
+    match statements[11] {
+        Statement::Table(ref table) => {
+            let columns = table.columns.iter().map(|c| &c.name[..]).collect::<Vec<_>>();
+            assert_eq!(columns, vec!["comment", "index", "view"]);
+        }
+        ref unexpected => panic!("Expected a table however saw {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_can_parse_a_sequence_definition() {
+    let sql = "CREATE SEQUENCE public.shared_id_seq
+                 AS integer
+                 INCREMENT BY -2
+                 MINVALUE -9223372036854775808
+                 NO MAXVALUE
+                 START WITH -1
+                 CACHE 10
+                 CYCLE
+                 OWNED BY public.contacts.id;
+               CREATE SEQUENCE ticket_seq START 100 NO CYCLE OWNED BY NONE;
+               CREATE TABLE cycle (start int, cache int);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 3);
+
+    assert_eq!(
+        statements[0],
+        Statement::Sequence(SequenceDefinition {
+            name: ObjectName {
+                schema: Some("public".into()),
+                name: "shared_id_seq".into(),
+            },
+            data_type: Some(SqlType::Simple(SimpleSqlType::Integer, None)),
+            increment: Some(-2),
+            min_value: Some(i64::MIN),
+            max_value: None,
+            start: Some(-1),
+            cache: Some(10),
+            cycle: true,
+            owned_by: Some(SequenceOwner {
+                table: ObjectName {
+                    schema: Some("public".into()),
+                    name: "contacts".into(),
+                },
+                column: "id".into(),
+            }),
+            comment: None,
+        })
+    );
+    assert_eq!(
+        statements[1],
+        Statement::Sequence(SequenceDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "ticket_seq".into(),
+            },
+            data_type: None,
+            increment: None,
+            min_value: None,
+            max_value: None,
+            start: Some(100),
+            cache: None,
+            cycle: false,
+            owned_by: None,
+            comment: None,
+        })
+    );
+
+    // Sequence keywords are still valid identifiers elsewhere
+    match statements[2] {
+        Statement::Table(ref table) => {
+            assert_eq!(table.name.name, "cycle");
+            assert_eq!(table.columns[0].name, "start");
+            assert_eq!(table.columns[1].name, "cache");
+        }
+        ref unexpected => panic!("Expected a table however saw {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_can_parse_a_composite_type_definition() {
+    let sql = "CREATE TYPE public.address AS (
+                 street varchar(100),
+                 postcode int,
+                 tags text[]
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+
+    assert_eq!(
+        statements[0],
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: Some("public".into()),
+                name: "address".into(),
+            },
+            kind: TypeDefinitionKind::Composite(vec![
+                TypeAttribute {
+                    name: "street".into(),
+                    sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+                },
+                TypeAttribute {
+                    name: "postcode".into(),
+                    sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+                },
+                TypeAttribute {
+                    name: "tags".into(),
+                    sql_type: SqlType::Simple(SimpleSqlType::Text, Some(1)),
+                },
+            ]),
+            comment: None,
+        })
+    );
+}
+
+#[test]
+fn it_can_parse_a_range_type_definition() {
+    let sql = "CREATE TYPE floatrange AS RANGE (
+                 SUBTYPE = double precision,
+                 SUBTYPE_DIFF = float8mi
+               );
+               CREATE TYPE public.daterange2 AS RANGE (
+                 SUBTYPE = date,
+                 CANONICAL = public.daterange2_canonical,
+                 COLLATION = \"C\"
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 2);
+
+    assert_eq!(
+        statements[0],
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "floatrange".into(),
+            },
+            kind: TypeDefinitionKind::Range(RangeDefinition {
+                subtype: SqlType::Simple(SimpleSqlType::Double, None),
+                subtype_opclass: None,
+                collation: None,
+                canonical: None,
+                subtype_diff: Some(ObjectName {
+                    schema: None,
+                    name: "float8mi".into(),
+                }),
+            }),
+            comment: None,
+        })
+    );
+    assert_eq!(
+        statements[1],
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: Some("public".into()),
+                name: "daterange2".into(),
+            },
+            kind: TypeDefinitionKind::Range(RangeDefinition {
+                subtype: SqlType::Simple(SimpleSqlType::Date, None),
+                subtype_opclass: None,
+                collation: Some("C".into()),
+                canonical: Some(ObjectName {
+                    schema: Some("public".into()),
+                    name: "daterange2_canonical".into(),
+                }),
+                subtype_diff: None,
+            }),
+            comment: None,
+        })
+    );
+}
+
+#[test]
+fn it_can_parse_a_domain_definition() {
+    let sql = "CREATE DOMAIN public.us_postal_code AS text
+                 DEFAULT '00000'
+                 NOT NULL
+                 CONSTRAINT valid_code CHECK (VALUE ~ '^\\d{5}$' OR VALUE ~ '^\\d{5}-\\d{4}$')
+                 CHECK (VALUE <> ')');
+               CREATE DOMAIN positive int CHECK (VALUE > 0)";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 2);
+
+    assert_eq!(
+        statements[0],
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: Some("public".into()),
+                name: "us_postal_code".into(),
+            },
+            kind: TypeDefinitionKind::Domain(DomainDefinition {
+                sql_type: SqlType::Simple(SimpleSqlType::Text, None),
+                default: Some(Expression::Literal(AnyValue::String("00000".into(), None))),
+                not_null: true,
+                constraints: vec![
+                    DomainConstraint {
+                        name: Some("valid_code".into()),
+                        expression: "VALUE ~ '^\\d{5}$' OR VALUE ~ '^\\d{5}-\\d{4}$'".into(),
+                    },
+                    DomainConstraint {
+                        name: None,
+                        expression: "VALUE <> ')'".into(),
+                    },
+                ],
+            }),
+            comment: None,
+        })
+    );
+    assert_eq!(
+        statements[1],
+        Statement::Type(TypeDefinition {
+            name: ObjectName {
+                schema: None,
+                name: "positive".into(),
+            },
+            kind: TypeDefinitionKind::Domain(DomainDefinition {
+                sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+                default: None,
+                not_null: false,
+                constraints: vec![DomainConstraint {
+                    name: None,
+                    expression: "VALUE > 0".into(),
+                }],
+            }),
+            comment: None,
         })
     );
 }
@@ -71,6 +919,14 @@ fn it_can_parse_a_function_definition_with_simple_literals() {
             return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Integer, None)),
             body: "SELECT 1".into(),
             language: FunctionLanguage::SQL,
+            volatility: FunctionVolatility::Volatile,
+            strict: false,
+            security_definer: false,
+            parallel: FunctionParallel::Unsafe,
+            cost: None,
+            rows: None,
+            settings: Vec::new(),
+            comment: None,
         })
     );
 }
@@ -113,11 +969,13 @@ fn it_can_parse_a_function_definition_returning_table() {
                     name: "name".into(),
                     sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(80), None),
                     constraints: Vec::new(),
+                    comment: None,
                 },
                 ColumnDefinition {
                     name: "iso".into(),
                     sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(2), None),
                     constraints: Vec::new(),
+                    comment: None,
                 },
             ]),
             body: "SELECT countries.name, countries.iso
@@ -126,6 +984,14 @@ fn it_can_parse_a_function_definition_returning_table() {
                    ORDER BY countries.iso"
                 .into(),
             language: FunctionLanguage::SQL,
+            volatility: FunctionVolatility::Volatile,
+            strict: false,
+            security_definer: false,
+            parallel: FunctionParallel::Unsafe,
+            cost: None,
+            rows: None,
+            settings: Vec::new(),
+            comment: None,
         })
     );
 }
@@ -174,11 +1040,13 @@ fn it_can_parse_a_function_definition_with_parameters() {
                     name: "name".into(),
                     sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(80), None),
                     constraints: Vec::new(),
+                    comment: None,
                 },
                 ColumnDefinition {
                     name: "iso".into(),
                     sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(10), None),
                     constraints: Vec::new(),
+                    comment: None,
                 },
             ]),
             body: "SELECT states.name, states.iso
@@ -188,10 +1056,114 @@ fn it_can_parse_a_function_definition_with_parameters() {
                    ORDER BY states.iso"
                 .into(),
             language: FunctionLanguage::SQL,
+            volatility: FunctionVolatility::Volatile,
+            strict: false,
+            security_definer: false,
+            parallel: FunctionParallel::Unsafe,
+            cost: None,
+            rows: None,
+            settings: Vec::new(),
+            comment: None,
         })
     );
 }
 
+#[test]
+fn it_can_parse_function_attributes() {
+    let sql = "CREATE FUNCTION audit.recent(cost int)
+               RETURNS TABLE (rows bigint)
+               STABLE
+               SECURITY DEFINER
+               AS $$ SELECT count(*) FROM audit.log WHERE cost > 0 $$
+               RETURNS NULL ON NULL INPUT
+               PARALLEL RESTRICTED
+               COST 25.5 ROWS 10
+               SET search_path = audit, pg_temp
+               SET Work_Mem TO '64MB'
+               LANGUAGE SQL;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+    let function = match statements[0] {
+        Statement::Function(ref function) => function,
+        ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+    };
+
+    // Attribute keywords are still names within the arguments and returned table
+    assert_eq!(function.arguments[0].name, Some("cost".into()));
+    match function.return_type {
+        FunctionReturnType::Table(ref columns) => assert_eq!(columns[0].name, "rows"),
+        ref unexpected => panic!("Unexpected return type: {:?}", unexpected),
+    }
+    assert_eq!(function.language, FunctionLanguage::SQL);
+    assert_eq!(function.volatility, FunctionVolatility::Stable);
+    assert!(function.strict);
+    assert!(function.security_definer);
+    assert_eq!(function.parallel, FunctionParallel::Restricted);
+    assert_eq!(function.cost, Some(Decimal::new(255, 1)));
+    assert_eq!(function.rows, Some(Decimal::from(10)));
+    assert_eq!(
+        function.settings,
+        vec![
+            FunctionSetting {
+                name: "search_path".into(),
+                values: vec!["audit".into(), "pg_temp".into()],
+            },
+            FunctionSetting {
+                name: "work_mem".into(),
+                values: vec!["64MB".into()],
+            },
+        ]
+    );
+    assert_eq!(function.settings[0].to_string(), "SET search_path = 'audit', 'pg_temp'");
+}
+
+#[test]
+fn it_can_parse_a_procedure() {
+    let sql = "CREATE OR REPLACE PROCEDURE audit.archive(before date, INOUT archived int DEFAULT 0)
+               LANGUAGE plpgsql
+               SECURITY DEFINER
+               SET search_path = audit
+               AS $$ BEGIN DELETE FROM audit.log WHERE created < before; END $$;";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+    let procedure = match statements[0] {
+        Statement::Procedure(ref procedure) => procedure,
+        ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+    };
+    assert_eq!(procedure.name.to_string(), "audit.archive");
+    assert_eq!(procedure.arguments.len(), 2);
+    assert_eq!(procedure.signature(), "audit.archive(date, int)");
+    assert_eq!(procedure.language, FunctionLanguage::PostgreSQL);
+    assert!(procedure.security_definer);
+    assert_eq!(
+        procedure.settings,
+        vec![FunctionSetting {
+            name: "search_path".into(),
+            values: vec!["audit".into()],
+        }]
+    );
+
+    // Attributes that only apply to functions are rejected
+    let tokens =
+        lexer::tokenize_stmt("CREATE PROCEDURE audit.purge() STABLE AS $$ DELETE FROM audit.log $$ LANGUAGE SQL;")
+            .unwrap();
+    assert!(StatementListParser::new().parse(tokens).is_err());
+}
+
 #[test]
 fn it_can_parse_function_arguments() {
     let sql = "geom geometry,
@@ -246,62 +1218,485 @@ fn it_can_parse_function_arguments() {
                 mode: None,
                 name: Some("gridx".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Double, None),
-                default: Some(AnyValue::Null(Some(SqlType::Simple(SimpleSqlType::Double, None)))),
+                default: Some(Expression::Literal(AnyValue::Null(Some(SqlType::Simple(
+                    SimpleSqlType::Double,
+                    None
+                ))))),
             },
             FunctionArgument {
                 mode: None,
                 name: Some("gridy".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Double, None),
-                default: Some(AnyValue::Null(Some(SqlType::Simple(SimpleSqlType::Double, None)))),
+                default: Some(Expression::Literal(AnyValue::Null(Some(SqlType::Simple(
+                    SimpleSqlType::Double,
+                    None
+                ))))),
             },
             FunctionArgument {
                 mode: None,
                 name: Some("pixeltype".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Text, Some(1)),
-                default: Some(AnyValue::Array(
+                default: Some(Expression::Literal(AnyValue::Array(
                     vec![AnyValue::String(
                         "8BUI".into(),
                         Some(SqlType::Simple(SimpleSqlType::Text, None)),
                     )],
                     None,
-                )),
+                ))),
             },
             FunctionArgument {
                 mode: None,
                 name: Some("value".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Double, Some(1)),
-                default: Some(AnyValue::Array(
+                default: Some(Expression::Literal(AnyValue::Array(
                     vec![AnyValue::Integer(1, Some(SqlType::Simple(SimpleSqlType::Double, None)))],
                     None,
-                )),
+                ))),
             },
             FunctionArgument {
                 mode: None,
                 name: Some("nodataval".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Double, Some(1)),
-                default: Some(AnyValue::Array(
+                default: Some(Expression::Literal(AnyValue::Array(
                     vec![AnyValue::Integer(0, Some(SqlType::Simple(SimpleSqlType::Double, None)))],
                     None,
-                )),
+                ))),
             },
             FunctionArgument {
                 mode: None,
                 name: Some("skewx".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Double, None),
-                default: Some(AnyValue::Integer(0, None)),
+                default: Some(Expression::Literal(AnyValue::Integer(0, None))),
             },
             FunctionArgument {
                 mode: None,
                 name: Some("skewy".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Double, None),
-                default: Some(AnyValue::Integer(0, None)),
+                default: Some(Expression::Literal(AnyValue::Integer(0, None))),
             },
             FunctionArgument {
                 mode: None,
                 name: Some("touched".into()),
                 sql_type: SqlType::Simple(SimpleSqlType::Boolean, None),
-                default: Some(AnyValue::Boolean(false, None)),
+                default: Some(Expression::Literal(AnyValue::Boolean(false, None))),
             },
         ]
     );
 }
+
+#[test]
+fn it_can_parse_and_display_all_simple_types() {
+    let types = vec![
+        ("char(10)", SimpleSqlType::FixedLengthString(10), "char(10)"),
+        ("char", SimpleSqlType::FixedLengthString(1), "char(1)"),
+        ("bpchar", SimpleSqlType::UnsizedFixedLengthString, "bpchar"),
+        (
+            "character varying",
+            SimpleSqlType::UnsizedVariableLengthString,
+            "varchar",
+        ),
+        ("bit", SimpleSqlType::FixedLengthBitString(1), "bit(1)"),
+        ("bit varying", SimpleSqlType::UnsizedVariableLengthBitString, "varbit"),
+        ("varbit(8)", SimpleSqlType::VariableLengthBitString(8), "varbit(8)"),
+        ("time", SimpleSqlType::Time(None), "time"),
+        ("time(3) without time zone", SimpleSqlType::Time(Some(3)), "time(3)"),
+        (
+            "timetz(1)",
+            SimpleSqlType::TimeWithTimeZone(Some(1)),
+            "time(1) with time zone",
+        ),
+        (
+            "timestamp(2)",
+            SimpleSqlType::DateTime(Some(2)),
+            "timestamp(2) without time zone",
+        ),
+        (
+            "timestamp(4) with time zone",
+            SimpleSqlType::DateTimeWithTimeZone(Some(4)),
+            "timestamp(4) with time zone",
+        ),
+        ("interval", SimpleSqlType::Interval(None), "interval"),
+        ("interval(6)", SimpleSqlType::Interval(Some(6)), "interval(6)"),
+        ("bytea", SimpleSqlType::Binary, "bytea"),
+        ("oid", SimpleSqlType::ObjectIdentifier, "oid"),
+        ("xml", SimpleSqlType::Xml, "xml"),
+        ("inet", SimpleSqlType::Inet, "inet"),
+        ("cidr", SimpleSqlType::Cidr, "cidr"),
+        ("macaddr", SimpleSqlType::MacAddress, "macaddr"),
+        ("macaddr8", SimpleSqlType::MacAddress8, "macaddr8"),
+        ("tsvector", SimpleSqlType::TextSearchVector, "tsvector"),
+        ("tsquery", SimpleSqlType::TextSearchQuery, "tsquery"),
+        ("point", SimpleSqlType::Point, "point"),
+        ("line", SimpleSqlType::Line, "line"),
+        ("lseg", SimpleSqlType::LineSegment, "lseg"),
+        ("box", SimpleSqlType::Box, "box"),
+        ("path", SimpleSqlType::Path, "path"),
+        ("polygon", SimpleSqlType::Polygon, "polygon"),
+        ("circle", SimpleSqlType::Circle, "circle"),
+    ];
+
+    for (sql, expected, display) in types {
+        let tokens = lexer::tokenize_body(sql).unwrap();
+        let sql_type = SqlTypeParser::new().parse(tokens).unwrap();
+        assert_eq!(sql_type, SqlType::Simple(expected, None), "{}", sql);
+        assert_eq!(sql_type.to_string(), display);
+
+        // The displayed type must parse back to the same type
+        let tokens = lexer::tokenize_body(display).unwrap();
+        assert_eq!(SqlTypeParser::new().parse(tokens).unwrap(), sql_type, "{}", display);
+    }
+}
+
+#[test]
+fn it_can_use_unreserved_type_names_as_identifiers() {
+    let sql = "CREATE TABLE public.routes (
+                 path path NOT NULL,
+                 point point[],
+                 box geometry.box
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+
+    match statements[0] {
+        Statement::Table(ref table) => {
+            let columns = table
+                .columns
+                .iter()
+                .map(|column| (&column.name[..], &column.sql_type))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                columns,
+                vec![
+                    ("path", &SqlType::Simple(SimpleSqlType::Path, None)),
+                    ("point", &SqlType::Simple(SimpleSqlType::Point, Some(1))),
+                    (
+                        "box",
+                        &SqlType::Custom(
+                            ObjectName {
+                                schema: Some("geometry".into()),
+                                name: "box".into(),
+                            },
+                            vec![],
+                            None
+                        )
+                    ),
+                ]
+            );
+        }
+        ref unexpected => panic!("Unexpected statement: {:?}", unexpected),
+    }
+
+    let tokens = lexer::tokenize_body("path path, inet inet DEFAULT NULL").unwrap();
+    let arguments = FunctionArgumentListParser::new().parse(tokens).unwrap();
+    assert_eq!(arguments[0].name, Some("path".into()));
+    assert_eq!(arguments[0].sql_type, SqlType::Simple(SimpleSqlType::Path, None));
+    assert_eq!(arguments[1].name, Some("inet".into()));
+    assert_eq!(arguments[1].sql_type, SqlType::Simple(SimpleSqlType::Inet, None));
+}
+
+#[test]
+fn it_can_parse_default_expressions() {
+    let sql = "CREATE TABLE public.events (
+                 id uuid NOT NULL DEFAULT gen_random_uuid(),
+                 sequence bigint DEFAULT nextval('events_seq'::regclass),
+                 created_at timestamptz DEFAULT CURRENT_TIMESTAMP,
+                 priority int DEFAULT (1 + 2) * -3,
+                 label text DEFAULT 'it''s ' || lower('NEW'),
+                 tags text[] DEFAULT ARRAY['a', 'b'],
+                 retries int DEFAULT CAST('5' AS integer)
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+
+    match statements[0] {
+        Statement::Table(ref table) => {
+            let defaults = table
+                .columns
+                .iter()
+                .map(|column| column.default_value().map(|expr| expr.to_string()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                defaults,
+                vec![
+                    Some("gen_random_uuid()".to_owned()),
+                    Some("nextval('events_seq'::regclass)".to_owned()),
+                    Some("CURRENT_TIMESTAMP".to_owned()),
+                    Some("(1 + 2) * -3".to_owned()),
+                    Some("'it''s ' || lower('NEW')".to_owned()),
+                    Some("ARRAY ['a', 'b']".to_owned()),
+                    Some("'5'::int".to_owned()),
+                ]
+            );
+        }
+        _ => panic!("Unexpected statement"),
+    }
+}
+
+#[test]
+fn it_normalizes_default_expressions_described_by_postgres() {
+    fn parse(expr: &str) -> Expression {
+        let tokens = lexer::tokenize_body(expr).unwrap();
+        ExpressionParser::new().parse(tokens).unwrap()
+    }
+
+    // The project definition on the left and the definition returned by pg_get_expr on the right
+    let equivalent = [
+        ("now()", "CURRENT_TIMESTAMP"),
+        ("transaction_timestamp()", "now()"),
+        ("pg_catalog.NOW()", "now()"),
+        ("nextval('events_seq')", "nextval('events_seq'::regclass)"),
+        ("1 + 2", "(1 + 2)"),
+        ("'it''s'", "'it''s'::text"),
+        ("-1", "'-1'::integer"),
+        ("-1.5", "'-1.5'::numeric"),
+        ("ARRAY['a', 'b']", "ARRAY['a'::text, 'b'::text]"),
+        ("lower('ABC' || 'd')", "lower(('ABC'::text || 'd'::text))"),
+        ("CAST('5' AS integer)", "5"),
+        ("('{}')::jsonb", "'{}'::jsonb"),
+    ];
+    for &(source, target) in equivalent.iter() {
+        assert_eq!(
+            parse(source).normalize(),
+            parse(target).normalize(),
+            "{} = {}",
+            source,
+            target
+        );
+    }
+
+    let different = [("now()", "CURRENT_DATE"), ("1 + 2", "2 + 1"), ("'1'::text", "1")];
+    for &(source, target) in different.iter() {
+        assert_ne!(
+            parse(source).normalize(),
+            parse(target).normalize(),
+            "{} <> {}",
+            source,
+            target
+        );
+    }
+}
+
+#[test]
+fn it_can_parse_identity_and_generated_columns() {
+    let sql = "CREATE TABLE public.orders (
+                 id bigint GENERATED ALWAYS AS IDENTITY,
+                 reference int GENERATED BY DEFAULT AS IDENTITY (START WITH 1000 INCREMENT BY 10 NO CYCLE),
+                 quantity int NOT NULL,
+                 price numeric(10, 2) NOT NULL,
+                 total numeric GENERATED ALWAYS AS (price * quantity) STORED,
+                 stored boolean,
+                 start date
+               );";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 1);
+
+    match statements[0] {
+        Statement::Table(ref table) => {
+            let columns = table
+                .columns
+                .iter()
+                .map(|column| (&column.name[..], &column.constraints[..]))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                columns,
+                vec![
+                    (
+                        "id",
+                        &[ColumnConstraint::Identity(IdentityDefinition {
+                            generation: IdentityGeneration::Always,
+                            increment: None,
+                            min_value: None,
+                            max_value: None,
+                            start: None,
+                            cache: None,
+                            cycle: false,
+                        })][..]
+                    ),
+                    (
+                        "reference",
+                        &[ColumnConstraint::Identity(IdentityDefinition {
+                            generation: IdentityGeneration::ByDefault,
+                            increment: Some(10),
+                            min_value: None,
+                            max_value: None,
+                            start: Some(1000),
+                            cache: None,
+                            cycle: false,
+                        })][..]
+                    ),
+                    ("quantity", &[ColumnConstraint::NotNull][..]),
+                    ("price", &[ColumnConstraint::NotNull][..]),
+                    (
+                        "total",
+                        &[ColumnConstraint::Generated(Expression::BinaryOperator(
+                            Box::new(Expression::Column("price".into())),
+                            "*".into(),
+                            Box::new(Expression::Column("quantity".into())),
+                        ))][..]
+                    ),
+                    ("stored", &[][..]),
+                    ("start", &[][..]),
+                ]
+            );
+        }
+        _ => panic!("Unexpected statement"),
+    }
+
+    // The identity takes the type of the column
+    let sql = "CREATE TABLE public.orders (id bigint GENERATED ALWAYS AS IDENTITY (AS int));";
+    let tokens = lexer::tokenize_stmt(sql).unwrap();
+    assert!(StatementListParser::new().parse(tokens).is_err());
+}
+
+#[test]
+fn it_can_parse_partitioned_tables_and_partitions() {
+    let sql = "CREATE TABLE public.events (
+                 id bigint NOT NULL,
+                 created_at date NOT NULL,
+                 partition int
+               ) PARTITION BY RANGE (created_at);
+               CREATE TABLE public.events_2020 PARTITION OF public.events FOR VALUES FROM ('2020-01-01') TO ('2021-01-01');
+               CREATE TABLE public.events_old PARTITION OF public.events FOR VALUES FROM (MINVALUE) TO ('2020-01-01');
+               CREATE TABLE public.events_other PARTITION OF public.events DEFAULT;
+               CREATE TABLE tenants_eu PARTITION OF tenants FOR VALUES IN ('eu', 'uk') PARTITION BY HASH (id);
+               CREATE TABLE tenants_eu_0 PARTITION OF tenants_eu FOR VALUES WITH (MODULUS 2, REMAINDER 0);";
+
+    let tokens = lexer::tokenize_stmt(sql);
+    assert!(tokens.is_ok());
+    let tokens = tokens.unwrap();
+
+    let statements = StatementListParser::new().parse(tokens);
+    assert!(statements.is_ok());
+    let statements = statements.unwrap();
+    assert_eq!(statements.len(), 6);
+
+    let tables = statements
+        .iter()
+        .map(|statement| match statement {
+            Statement::Table(ref table) => table,
+            _ => panic!("Unexpected statement"),
+        })
+        .collect::<Vec<_>>();
+    let string = |value: &str| PartitionRangeValue::Value(Expression::Literal(AnyValue::String(value.into(), None)));
+
+    // Partition is still usable as a column name
+    assert_eq!(
+        tables[0].columns.iter().map(|c| &c.name[..]).collect::<Vec<_>>(),
+        vec!["id", "created_at", "partition"]
+    );
+    assert_eq!(
+        tables[0].partition_by,
+        Some(PartitionKey {
+            strategy: PartitionStrategy::Range,
+            columns: vec![Expression::Column("created_at".into())],
+        })
+    );
+    assert_eq!(tables[0].partition_of, None);
+
+    assert!(tables[1].columns.is_empty());
+    assert_eq!(
+        tables[1].partition_of,
+        Some(PartitionOf {
+            parent: ObjectName {
+                schema: Some("public".into()),
+                name: "events".into(),
+            },
+            bound: PartitionBound::Range {
+                from: vec![string("2020-01-01")],
+                to: vec![string("2021-01-01")],
+            },
+        })
+    );
+    assert_eq!(
+        tables[2].partition_of.as_ref().map(|p| &p.bound),
+        Some(&PartitionBound::Range {
+            from: vec![PartitionRangeValue::MinValue],
+            to: vec![string("2020-01-01")],
+        })
+    );
+    assert_eq!(
+        tables[3].partition_of.as_ref().map(|p| &p.bound),
+        Some(&PartitionBound::Default)
+    );
+
+    // Partitions may themselves be partitioned
+    assert_eq!(
+        tables[4].partition_of.as_ref().map(|p| &p.bound),
+        Some(&PartitionBound::List(vec![
+            Expression::Literal(AnyValue::String("eu".into(), None)),
+            Expression::Literal(AnyValue::String("uk".into(), None)),
+        ]))
+    );
+    assert_eq!(
+        tables[4].partition_by.as_ref().map(|p| p.strategy),
+        Some(PartitionStrategy::Hash)
+    );
+    assert_eq!(
+        tables[5].partition_of.as_ref().map(|p| &p.bound),
+        Some(&PartitionBound::Hash {
+            modulus: 2,
+            remainder: 0
+        })
+    );
+}
+
+#[test]
+fn it_normalizes_partitioning_described_by_postgres() {
+    let parse_key = |sql: &str| {
+        let tokens = lexer::tokenize_partition(sql).unwrap();
+        PartitionKeyParser::new().parse(tokens).unwrap().normalize()
+    };
+    let parse_bound = |sql: &str| {
+        let tokens = lexer::tokenize_partition(sql).unwrap();
+        PartitionBoundParser::new().parse(tokens).unwrap().normalize()
+    };
+
+    assert_eq!(parse_key("HASH (id, ((ts)::date))"), parse_key("HASH (id, (ts::date))"));
+    assert_eq!(parse_key("LIST (lower(region))"), parse_key("LIST (LOWER(region))"));
+    assert_eq!(
+        parse_bound("FOR VALUES WITH (modulus 4, remainder 1)"),
+        PartitionBound::Hash {
+            modulus: 4,
+            remainder: 1
+        }
+    );
+    assert_eq!(
+        parse_bound("FOR VALUES FROM (MINVALUE) TO (10)"),
+        parse_bound("FOR VALUES FROM (minvalue) TO ((10))")
+    );
+
+    // Partitioning is written out in a form Postgres understands
+    assert_eq!(
+        parse_key("HASH (id, ((ts)::date))").to_string(),
+        "HASH (id, (ts::date))"
+    );
+    assert_eq!(
+        parse_bound("FOR VALUES FROM (MINVALUE, 1) TO ('2020-01-01', MAXVALUE)").to_string(),
+        "FOR VALUES FROM (MINVALUE, 1) TO ('2020-01-01', MAXVALUE)"
+    );
+    assert_eq!(
+        parse_bound("FOR VALUES IN ('eu', 'uk')").to_string(),
+        "FOR VALUES IN ('eu', 'uk')"
+    );
+}
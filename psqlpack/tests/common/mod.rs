@@ -55,6 +55,8 @@ macro_rules! generate_simple_package {
             let mut package = Package::new();
             package.push_schema(SchemaDefinition {
                 name: $namespace.to_string(),
+                owner: None,
+                comment: None,
             });
             let table_name = ObjectName {
                 schema: Some($namespace.to_string()),
@@ -67,14 +69,21 @@ macro_rules! generate_simple_package {
                         name: "id".into(),
                         sql_type: SqlType::Simple(SimpleSqlType::Serial, None),
                         constraints: vec![ColumnConstraint::PrimaryKey, ColumnConstraint::NotNull],
+                        comment: None,
                     },
                     ColumnDefinition {
                         name: "name".into(),
                         sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(50), None),
                         constraints: vec![ColumnConstraint::NotNull],
+                        comment: None,
                     },
                 ],
                 constraints: Vec::new(),
+                partition_by: None,
+                partition_of: None,
+                row_security: false,
+                force_row_security: false,
+                comment: None,
             });
             package.push_index(IndexDefinition {
                 name: "idx_contacts_name".to_owned(),
@@ -87,6 +96,7 @@ macro_rules! generate_simple_package {
                 unique: false,
                 index_type: None,
                 storage_parameters: None,
+                comment: None,
             });
             package.set_defaults(&Project::default());
             package.validate(&Vec::new()).unwrap();
@@ -7,6 +7,7 @@ mod common;
 
 use psqlpack::ast::*;
 use psqlpack::*;
+use rust_decimal::Decimal;
 use slog::{Discard, Drain, Logger};
 
 macro_rules! publish_package {
@@ -14,8 +15,15 @@ macro_rules! publish_package {
         // Use the default publish profile
         let mut publish_profile = PublishProfile::default();
         publish_profile.generation_options.drop_tables = Toggle::Ignore; // We reuse the same database
+        publish_profile.generation_options.drop_partitions = Toggle::Ignore; // We reuse the same database
         publish_profile.generation_options.drop_columns = Toggle::Allow; // We allow this in some tests
         publish_profile.generation_options.drop_indexes = Toggle::Allow; // We allow this in some tests
+        publish_profile.generation_options.drop_views = Toggle::Ignore; // We reuse the same database
+        publish_profile.generation_options.drop_functions = Toggle::Ignore; // We reuse the same database
+        publish_profile.generation_options.drop_procedures = Toggle::Ignore; // We reuse the same database
+        publish_profile.generation_options.drop_triggers = Toggle::Ignore; // We reuse the same database
+        publish_profile.generation_options.drop_policies = Toggle::Ignore; // We reuse the same database
+        publish_profile.generation_options.drop_schemas = Toggle::Ignore; // We reuse the same database
 
         // Create a target package from connection string
         let log = Logger::root(Discard.fuse(), o!());
@@ -257,3 +265,2549 @@ fn it_can_drop_an_index_on_a_table() {
     let final_package = publish_package!(DB_NAME, connection, package);
     assert_simple_package!(final_package, NAMESPACE);
 }
+
+#[test]
+fn it_can_add_views_to_an_existing_database() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_views_to_an_existing_database";
+
+    // Preliminary: create a database without the views
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    client
+        .batch_execute(&format!(
+            "DROP MATERIALIZED VIEW IF EXISTS {0}.contact_count; DROP VIEW IF EXISTS {0}.named_contacts",
+            NAMESPACE
+        ))
+        .unwrap();
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Publish the simple package with a view and materialized view on top of it
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_view(ViewDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "named_contacts".to_string(),
+        },
+        columns: None,
+        query: format!("SELECT id, name FROM {}.contacts WHERE name <> ''", NAMESPACE),
+        comment: None,
+    });
+    package.push_materialized_view(MaterializedViewDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "contact_count".to_string(),
+        },
+        columns: Some(vec!["total".to_string()]),
+        query: format!("SELECT count(*) FROM {}.named_contacts", NAMESPACE),
+        comment: None,
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+
+    // Validate the views
+    let view = final_package
+        .views
+        .iter()
+        .find(|v| v.name.to_string().eq(&format!("{}.named_contacts", NAMESPACE)));
+    assert!(view.is_some());
+    let view = final_package
+        .materialized_views
+        .iter()
+        .find(|v| v.name.to_string().eq(&format!("{}.contact_count", NAMESPACE)));
+    assert!(view.is_some());
+    assert_eq!(view.unwrap().columns, Some(vec!["total".to_string()]));
+
+    // Publishing again doesn't change the views even though Postgres describes their queries differently
+    let log = Logger::root(Discard.fuse(), o!());
+    let changed_objects = |package: &Package| {
+        let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+        let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+        // Other tests share the database so nothing is dropped
+        let mut publish_profile = PublishProfile::default();
+        publish_profile.generation_options.drop_tables = Toggle::Ignore;
+        publish_profile.generation_options.drop_partitions = Toggle::Ignore;
+        publish_profile.generation_options.drop_views = Toggle::Ignore;
+        publish_profile.generation_options.drop_functions = Toggle::Ignore;
+        publish_profile.generation_options.drop_procedures = Toggle::Ignore;
+        publish_profile.generation_options.drop_triggers = Toggle::Ignore;
+        publish_profile.generation_options.drop_policies = Toggle::Ignore;
+        publish_profile.generation_options.drop_schemas = Toggle::Ignore;
+        let delta = Delta::generate(&log, package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+        delta
+            .report(&log)
+            .objects
+            .into_iter()
+            .map(|object| object.object)
+            .filter(|object| object.contains(NAMESPACE))
+            .collect::<Vec<_>>()
+    };
+    assert!(changed_objects(&package).is_empty());
+
+    // Whereas a view changed outside of psqlpack is replaced
+    client
+        .batch_execute(&format!(
+            "CREATE OR REPLACE VIEW {0}.named_contacts AS SELECT id, name FROM {0}.contacts",
+            NAMESPACE
+        ))
+        .unwrap();
+    assert_eq!(
+        changed_objects(&package),
+        vec![format!("view {}.named_contacts", NAMESPACE)]
+    );
+}
+
+#[test]
+fn it_recreates_views_whose_columns_cannot_be_replaced() {
+    const DB_NAME: &str = "psqlpack_view_db";
+    const NAMESPACE: &str = "it_recreates_views_whose_columns_cannot_be_replaced";
+
+    // Preliminary: start from a fresh database
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+
+    // Publish a view with a comment and a grant, both of which are lost when the view is dropped
+    let mut package = generate_simple_package!(NAMESPACE);
+    let name = ObjectName {
+        schema: Some(NAMESPACE.to_string()),
+        name: "contact_names".to_string(),
+    };
+    package.push_view(ViewDefinition {
+        name: name.clone(),
+        columns: Some(vec!["id".to_string(), "name".to_string()]),
+        query: format!("SELECT id, name FROM {}.contacts", NAMESPACE),
+        comment: Some("Contact names".to_string()),
+    });
+    package.grants.push(GrantDefinition {
+        object: GrantObject::Table(name.clone()),
+        grantee: "public".to_string(),
+        privileges: vec![Privilege::Select],
+        with_grant_option: false,
+    });
+    publish_package!(DB_NAME, connection, package);
+
+    // Renaming the columns can't be done with CREATE OR REPLACE
+    package.views[0].columns = Some(vec!["contact_id".to_string(), "contact_name".to_string()]);
+    let log = Logger::root(Discard.fuse(), o!());
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_views = Toggle::Error;
+    let result = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile);
+    match result {
+        Err(PsqlpackError(PsqlpackErrorKind::PublishUnsafeOperationError(_), _)) => {}
+        Err(unexpected) => panic!("Expected unsafe operation error however saw {:?}", unexpected),
+        Ok(_) => panic!("Expected the view to need dropping"),
+    }
+
+    // Once allowed, the view is dropped and created again along with its comment and grant
+    publish_profile.generation_options.drop_views = Toggle::Allow;
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let final_package = Package::from_connection(&log, &connection, &capabilities)
+        .unwrap()
+        .unwrap();
+    let view = final_package.views.iter().find(|v| v.name.eq(&name)).unwrap();
+    assert_eq!(
+        view.columns,
+        Some(vec!["contact_id".to_string(), "contact_name".to_string()])
+    );
+    assert_eq!(view.comment, Some("Contact names".to_string()));
+    assert!(final_package
+        .grants
+        .iter()
+        .any(|g| g.object.eq(&GrantObject::Table(name.clone())) && g.grantee.eq("public")));
+    let drift = package.compare(&log, &connection).unwrap();
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_add_triggers_to_an_existing_database() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_triggers_to_an_existing_database";
+
+    // Preliminary: create a database without the trigger
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Publish the simple package with a trigger to normalize names
+    let mut package = generate_simple_package!(NAMESPACE);
+    let function_name = ObjectName {
+        schema: Some(NAMESPACE.to_string()),
+        name: "normalize_name".to_string(),
+    };
+    package.push_function(FunctionDefinition {
+        name: function_name.clone(),
+        arguments: Vec::new(),
+        return_type: FunctionReturnType::SqlType(SqlType::Custom(
+            ObjectName {
+                schema: None,
+                name: "trigger".to_string(),
+            },
+            Vec::new(),
+            None,
+        )),
+        body: "BEGIN NEW.name := trim(NEW.name); RETURN NEW; END;".to_string(),
+        language: FunctionLanguage::PostgreSQL,
+        volatility: FunctionVolatility::Volatile,
+        strict: false,
+        security_definer: false,
+        parallel: FunctionParallel::Unsafe,
+        cost: None,
+        rows: None,
+        settings: Vec::new(),
+        comment: None,
+    });
+    package.push_trigger(TriggerDefinition {
+        name: "normalize_contact_name".to_string(),
+        table: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "contacts".to_string(),
+        },
+        timing: TriggerTiming::Before,
+        events: vec![
+            TriggerEvent::Update(Some(vec!["name".to_string()])),
+            TriggerEvent::Insert,
+        ],
+        for_each: TriggerForEach::Row,
+        condition: Some("NEW.name IS NOT NULL".to_string()),
+        function: function_name,
+        arguments: vec!["contacts".to_string()],
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+
+    // Validate the trigger was extracted as an equivalent definition
+    let trigger = final_package.triggers.iter().find(|t| {
+        t.fully_qualified_name()
+            .eq(&format!("{}.contacts.normalize_contact_name", NAMESPACE))
+    });
+    assert!(trigger.is_some());
+    assert!(trigger.unwrap().is_same_definition(&package.triggers[0]));
+
+    // And that it fires
+    client
+        .batch_execute(&format!(
+            "INSERT INTO {}.contacts (name) VALUES ('  Jane  ')",
+            NAMESPACE
+        ))
+        .unwrap();
+    let row = client
+        .query_one(&format!("SELECT name FROM {}.contacts", NAMESPACE)[..], &[])
+        .unwrap();
+    let name: String = row.get(0);
+    assert_eq!(name, "Jane");
+}
+
+#[test]
+fn it_leaves_triggers_cloned_onto_partitions_alone() {
+    const DB_NAME: &str = "psqlpack_partition_trigger_db";
+    const NAMESPACE: &str = "it_leaves_triggers_cloned_onto_partitions_alone";
+
+    // Preliminary: start from a fresh database
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+
+    // A row trigger on a partitioned table is cloned onto each of its partitions
+    let object_name = |name: &str| ObjectName {
+        schema: Some(NAMESPACE.to_string()),
+        name: name.to_string(),
+    };
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_table(TableDefinition {
+        name: object_name("events"),
+        columns: vec![ColumnDefinition {
+            name: "created_at".into(),
+            sql_type: SqlType::Simple(SimpleSqlType::Date, None),
+            constraints: vec![ColumnConstraint::NotNull],
+            comment: None,
+        }],
+        constraints: Vec::new(),
+        partition_by: Some(PartitionKey {
+            strategy: PartitionStrategy::Range,
+            columns: vec![Expression::Column("created_at".into())],
+        }),
+        partition_of: None,
+        row_security: false,
+        force_row_security: false,
+        comment: None,
+    });
+    let bound = |year: i32| {
+        vec![PartitionRangeValue::Value(Expression::Literal(AnyValue::String(
+            format!("{}-01-01", year),
+            None,
+        )))]
+    };
+    package.push_table(TableDefinition {
+        name: object_name("events_2024"),
+        columns: Vec::new(),
+        constraints: Vec::new(),
+        partition_by: None,
+        partition_of: Some(PartitionOf {
+            parent: object_name("events"),
+            bound: PartitionBound::Range {
+                from: bound(2024),
+                to: bound(2025),
+            },
+        }),
+        row_security: false,
+        force_row_security: false,
+        comment: None,
+    });
+    package.push_function(FunctionDefinition {
+        name: object_name("audit_event"),
+        arguments: Vec::new(),
+        return_type: FunctionReturnType::SqlType(SqlType::Custom(
+            ObjectName {
+                schema: None,
+                name: "trigger".to_string(),
+            },
+            Vec::new(),
+            None,
+        )),
+        body: "BEGIN RETURN NEW; END;".to_string(),
+        language: FunctionLanguage::PostgreSQL,
+        volatility: FunctionVolatility::Volatile,
+        strict: false,
+        security_definer: false,
+        parallel: FunctionParallel::Unsafe,
+        cost: None,
+        rows: None,
+        settings: Vec::new(),
+        comment: None,
+    });
+    package.push_trigger(TriggerDefinition {
+        name: "events_audit".to_string(),
+        table: object_name("events"),
+        timing: TriggerTiming::After,
+        events: vec![TriggerEvent::Insert],
+        for_each: TriggerForEach::Row,
+        condition: None,
+        function: object_name("audit_event"),
+        arguments: Vec::new(),
+    });
+    package.set_defaults(&Project::default());
+
+    // Only the trigger that was declared is extracted so publishing again, where dropping triggers is disabled,
+    // has nothing to do
+    let log = Logger::root(Discard.fuse(), o!());
+    let publish_profile = PublishProfile::default();
+    let publish = || {
+        let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+        let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+        let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+        let changes = delta.report(&log).objects.len();
+        delta.apply(&log, &connection).unwrap();
+        changes
+    };
+    assert!(publish() > 0);
+    assert_eq!(publish(), 0);
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let final_package = Package::from_connection(&log, &connection, &capabilities)
+        .unwrap()
+        .unwrap();
+    let triggers = final_package
+        .triggers
+        .iter()
+        .map(|t| t.fully_qualified_name())
+        .collect::<Vec<_>>();
+    assert_eq!(triggers, vec![format!("{}.events.events_audit", NAMESPACE)]);
+    let drift = package.compare(&log, &connection).unwrap();
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_publish_and_modify_function_attributes() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_publish_and_modify_function_attributes";
+
+    // Preliminary: remove the function from a previous run
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    client
+        .batch_execute(&format!("DROP SCHEMA IF EXISTS {} CASCADE", NAMESPACE))
+        .unwrap();
+
+    // Publish a security definer function with a fixed search path
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_function(FunctionDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "contact_count".to_string(),
+        },
+        arguments: Vec::new(),
+        return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::BigInteger, None)),
+        body: format!("SELECT count(*) FROM {}.contacts", NAMESPACE),
+        language: FunctionLanguage::SQL,
+        volatility: FunctionVolatility::Stable,
+        strict: true,
+        security_definer: true,
+        parallel: FunctionParallel::Safe,
+        cost: Some(Decimal::from(5)),
+        rows: None,
+        settings: vec![FunctionSetting {
+            name: "search_path".to_string(),
+            values: vec![NAMESPACE.to_string(), "pg_temp".to_string()],
+        }],
+        comment: None,
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let function = |package: &Package| {
+        package
+            .functions
+            .iter()
+            .find(|f| f.name.schema().eq(NAMESPACE))
+            .map(|f| {
+                (
+                    f.volatility.to_string(),
+                    f.strict,
+                    f.security_definer,
+                    f.parallel.to_string(),
+                    f.cost,
+                    f.settings.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+                )
+            })
+            .unwrap()
+    };
+    assert_eq!(
+        function(&final_package),
+        (
+            "STABLE".to_string(),
+            true,
+            true,
+            "PARALLEL SAFE".to_string(),
+            Some(Decimal::from(5)),
+            vec![format!("SET search_path = '{}', 'pg_temp'", NAMESPACE)],
+        )
+    );
+    let published = final_package.functions.iter().find(|f| f.name.schema().eq(NAMESPACE));
+    assert!(published.unwrap().is_same_definition(&package.functions[0]));
+
+    // Resetting the attributes is detected and published
+    let function_definition = &mut package.functions[0];
+    function_definition.volatility = FunctionVolatility::Volatile;
+    function_definition.strict = false;
+    function_definition.security_definer = false;
+    function_definition.parallel = FunctionParallel::Unsafe;
+    function_definition.cost = None;
+    function_definition.settings.clear();
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_eq!(
+        function(&final_package),
+        (
+            "VOLATILE".to_string(),
+            false,
+            false,
+            "PARALLEL UNSAFE".to_string(),
+            Some(Decimal::from(100)),
+            Vec::new(),
+        )
+    );
+}
+
+#[test]
+fn it_can_add_and_modify_composite_range_and_domain_types() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_and_modify_composite_range_and_domain_types";
+
+    // Preliminary: remove any types from a previous run
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    client
+        .batch_execute(&format!("DROP SCHEMA IF EXISTS {} CASCADE", NAMESPACE))
+        .unwrap();
+
+    let type_name = |name: &str| ObjectName {
+        schema: Some(NAMESPACE.to_string()),
+        name: name.to_string(),
+    };
+    fn find_type<'a>(package: &'a Package, name: &str) -> Option<&'a TypeDefinitionKind> {
+        package
+            .types
+            .iter()
+            .find(|t| t.name.to_string().eq(&format!("{}.{}", NAMESPACE, name)))
+            .map(|t| &t.kind)
+    }
+
+    // Publish the simple package with one of each type
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_type(TypeDefinition {
+        name: type_name("address"),
+        kind: TypeDefinitionKind::Composite(vec![
+            TypeAttribute {
+                name: "street".to_string(),
+                sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(50), None),
+            },
+            TypeAttribute {
+                name: "city".to_string(),
+                sql_type: SqlType::Simple(SimpleSqlType::Text, None),
+            },
+        ]),
+        comment: None,
+    });
+    package.push_type(TypeDefinition {
+        name: type_name("int_range"),
+        kind: TypeDefinitionKind::Range(RangeDefinition {
+            subtype: SqlType::Simple(SimpleSqlType::Integer, None),
+            subtype_opclass: None,
+            collation: None,
+            canonical: None,
+            subtype_diff: None,
+        }),
+        comment: None,
+    });
+    package.push_type(TypeDefinition {
+        name: type_name("quantity"),
+        kind: TypeDefinitionKind::Domain(DomainDefinition {
+            sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+            default: Some(Expression::Literal(AnyValue::Integer(1, None))),
+            not_null: true,
+            constraints: vec![DomainConstraint {
+                name: Some("quantity_positive".to_string()),
+                expression: "VALUE > 0".to_string(),
+            }],
+        }),
+        comment: None,
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+
+    // Validate the types were extracted with their bodies
+    assert_eq!(find_type(&final_package, "address"), Some(&package.types[0].kind));
+    assert_eq!(find_type(&final_package, "int_range"), Some(&package.types[1].kind));
+    match find_type(&final_package, "quantity") {
+        Some(TypeDefinitionKind::Domain(domain)) => {
+            assert_eq!(domain.sql_type, SqlType::Simple(SimpleSqlType::Integer, None));
+            assert!(domain.not_null);
+            assert_eq!(domain.constraints.len(), 1);
+            assert_eq!(domain.constraints[0].name, Some("quantity_positive".to_string()));
+            assert!(domain.constraints[0].is_same_expression(&DomainConstraint {
+                name: None,
+                expression: "VALUE > 0".to_string(),
+            }));
+        }
+        unexpected => panic!("Expected a domain however saw {:?}", unexpected),
+    }
+
+    // Modify the composite and domain in place
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_type(TypeDefinition {
+        name: type_name("address"),
+        kind: TypeDefinitionKind::Composite(vec![
+            TypeAttribute {
+                name: "street".to_string(),
+                sql_type: SqlType::Simple(SimpleSqlType::VariableLengthString(100), None),
+            },
+            TypeAttribute {
+                name: "postcode".to_string(),
+                sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+            },
+        ]),
+        comment: None,
+    });
+    package.push_type(TypeDefinition {
+        name: type_name("quantity"),
+        kind: TypeDefinitionKind::Domain(DomainDefinition {
+            sql_type: SqlType::Simple(SimpleSqlType::Integer, None),
+            default: None,
+            not_null: false,
+            constraints: vec![DomainConstraint {
+                name: Some("quantity_positive".to_string()),
+                expression: "VALUE >= 0".to_string(),
+            }],
+        }),
+        comment: None,
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_eq!(find_type(&final_package, "address"), Some(&package.types[0].kind));
+    match find_type(&final_package, "quantity") {
+        Some(TypeDefinitionKind::Domain(domain)) => {
+            assert_eq!(domain.default, None);
+            assert!(!domain.not_null);
+            assert_eq!(domain.constraints.len(), 1);
+            assert!(domain.constraints[0].is_same_expression(&DomainConstraint {
+                name: None,
+                expression: "VALUE >= 0".to_string(),
+            }));
+        }
+        unexpected => panic!("Expected a domain however saw {:?}", unexpected),
+    }
+}
+
+#[test]
+fn it_extracts_the_same_types_that_were_published() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_extracts_the_same_types_that_were_published";
+
+    // Preliminary: create a database without the table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // Add a column for each type, including one named after a type which is also indexed
+    let types = vec![
+        SimpleSqlType::FixedLengthString(1),
+        SimpleSqlType::UnsizedFixedLengthString,
+        SimpleSqlType::UnsizedVariableLengthString,
+        SimpleSqlType::FixedLengthBitString(1),
+        SimpleSqlType::UnsizedVariableLengthBitString,
+        SimpleSqlType::Time(Some(3)),
+        SimpleSqlType::TimeWithTimeZone(Some(1)),
+        SimpleSqlType::DateTime(Some(2)),
+        SimpleSqlType::DateTimeWithTimeZone(Some(4)),
+        SimpleSqlType::Interval(None),
+        SimpleSqlType::Interval(Some(6)),
+        SimpleSqlType::Binary,
+        SimpleSqlType::ObjectIdentifier,
+        SimpleSqlType::Xml,
+        SimpleSqlType::Cidr,
+        SimpleSqlType::MacAddress,
+        SimpleSqlType::MacAddress8,
+        SimpleSqlType::TextSearchVector,
+        SimpleSqlType::TextSearchQuery,
+        SimpleSqlType::Point,
+        SimpleSqlType::Line,
+        SimpleSqlType::LineSegment,
+        SimpleSqlType::Box,
+        SimpleSqlType::Path,
+        SimpleSqlType::Polygon,
+        SimpleSqlType::Circle,
+    ];
+    let mut package = generate_simple_package!(NAMESPACE);
+    for (index, sql_type) in types.iter().enumerate() {
+        package.tables[0].columns.push(ColumnDefinition {
+            name: format!("column_{}", index),
+            sql_type: SqlType::Simple(sql_type.clone(), None),
+            constraints: Vec::new(),
+            comment: None,
+        });
+    }
+    package.tables[0].columns.push(ColumnDefinition {
+        name: "point".into(),
+        sql_type: SqlType::Simple(SimpleSqlType::Inet, Some(1)),
+        constraints: Vec::new(),
+        comment: None,
+    });
+    package.indexes.push(IndexDefinition {
+        name: "idx_contacts_point".to_owned(),
+        table: package.tables[0].name.clone(),
+        columns: vec![IndexColumn {
+            name: "point".to_owned(),
+            order: None,
+            null_position: None,
+        }],
+        unique: false,
+        index_type: None,
+        storage_parameters: None,
+        comment: None,
+    });
+
+    // Every column should be extracted with the type it was published with
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.to_string() == format!("{}.contacts", NAMESPACE))
+        .unwrap();
+    assert_eq!(
+        table.columns.iter().map(|c| &c.sql_type).collect::<Vec<_>>(),
+        package.tables[0]
+            .columns
+            .iter()
+            .map(|c| &c.sql_type)
+            .collect::<Vec<_>>()
+    );
+    assert!(final_package
+        .indexes
+        .iter()
+        .any(|i| i.name == "idx_contacts_point" && i.columns[0].name == "point"));
+}
+
+#[test]
+fn it_can_add_unique_check_and_exclusion_constraints_to_an_existing_table() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_unique_check_and_exclusion_constraints";
+
+    // Preliminary: create a database with a populated table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+    let package = generate_simple_package!(NAMESPACE);
+    publish_package!(DB_NAME, connection, package);
+    client
+        .batch_execute(&format!("INSERT INTO {}.contacts (name) VALUES ('Jane')", NAMESPACE))
+        .unwrap();
+
+    // Add the constraints to the existing table
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.tables[0].constraints.extend(vec![
+        TableConstraint::Unique {
+            name: "uq_contacts_name".to_string(),
+            columns: vec!["name".to_string(), "id".to_string()],
+            parameters: Some(vec![IndexParameter::FillFactor(70)]),
+        },
+        TableConstraint::Check {
+            name: "ck_contacts_name".to_string(),
+            expression: "length(name) > 0 AND name <> 'Unknown'".to_string(),
+            no_inherit: false,
+        },
+        TableConstraint::Exclude {
+            name: "ex_contacts_name".to_string(),
+            index_type: Some(IndexType::BTree),
+            elements: vec![ExclusionElement {
+                expression: "lower(name)".to_string(),
+                operator: "=".to_string(),
+            }],
+            parameters: None,
+            condition: Some("id > 0".to_string()),
+        },
+    ]);
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    // Validate the constraints were extracted
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.to_string().eq(&format!("{}.contacts", NAMESPACE)))
+        .unwrap();
+    assert_eq!(table.constraints.len(), 4);
+    let constraint = |name: &str| table.constraints.iter().find(|c| c.name() == name).unwrap();
+    assert_eq!(constraint("uq_contacts_name"), &package.tables[0].constraints[1]);
+    match constraint("ck_contacts_name") {
+        TableConstraint::Check { no_inherit, .. } => assert!(!no_inherit),
+        unexpected => panic!("Expected a check constraint however saw {:?}", unexpected),
+    }
+    match constraint("ex_contacts_name") {
+        TableConstraint::Exclude {
+            index_type,
+            elements,
+            condition,
+            ..
+        } => {
+            assert_eq!(index_type, &Some(IndexType::BTree));
+            assert_eq!(elements.len(), 1);
+            assert!(elements[0].is_same_element(&ExclusionElement {
+                expression: "lower(name)".to_string(),
+                operator: "=".to_string(),
+            }));
+            assert!(condition.is_some());
+        }
+        unexpected => panic!("Expected an exclusion constraint however saw {:?}", unexpected),
+    }
+
+    // The backing indexes aren't treated as standalone indexes
+    assert!(!final_package.indexes.iter().any(|i| i.name == "uq_contacts_name"));
+    assert!(!final_package.indexes.iter().any(|i| i.name == "ex_contacts_name"));
+
+    // And that they're enforced
+    assert!(client
+        .batch_execute(&format!("INSERT INTO {}.contacts (name) VALUES ('Unknown')", NAMESPACE))
+        .is_err());
+    assert!(client
+        .batch_execute(&format!("INSERT INTO {}.contacts (name) VALUES ('JANE')", NAMESPACE))
+        .is_err());
+
+    // Publishing again is a no-op
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.to_string().eq(&format!("{}.contacts", NAMESPACE)))
+        .unwrap();
+    assert_eq!(table.constraints.len(), 4);
+}
+
+#[test]
+fn it_can_add_and_modify_a_sequence_without_restarting_it() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_and_modify_a_sequence";
+
+    // Preliminary: start without the table or sequence
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+    client
+        .batch_execute(&format!("DROP SEQUENCE IF EXISTS {}.shared_id_seq", NAMESPACE))
+        .ok();
+
+    // Publish the simple package with a sequence owned by one of its columns
+    let mut package = generate_simple_package!(NAMESPACE);
+    let mut shared = SequenceDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "shared_id_seq".to_string(),
+        },
+        data_type: Some(SqlType::Simple(SimpleSqlType::Integer, None)),
+        increment: Some(5),
+        min_value: None,
+        max_value: None,
+        start: Some(100),
+        cache: None,
+        cycle: false,
+        owned_by: Some(SequenceOwner {
+            table: ObjectName {
+                schema: Some(NAMESPACE.to_string()),
+                name: "contacts".to_string(),
+            },
+            column: "name".to_string(),
+        }),
+        comment: None,
+    };
+    shared.set_defaults();
+    package.push_sequence(shared);
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+
+    // The sequence is extracted with its options and owner
+    let sequence = final_package
+        .sequences
+        .iter()
+        .find(|s| s.name.to_string().eq(&format!("{}.shared_id_seq", NAMESPACE)))
+        .unwrap();
+    assert!(sequence.is_same_definition(&package.sequences[0]));
+    assert_eq!(sequence.owned_by, package.sequences[0].owned_by);
+
+    // Sequences backing serial columns are implied by the column
+    assert!(!final_package
+        .sequences
+        .iter()
+        .any(|s| s.name.to_string().eq(&format!("{}.contacts_id_seq", NAMESPACE))));
+
+    // Use the sequence a few times
+    let next_value = |client: &mut postgres::Client| -> i64 {
+        client
+            .query_one(&format!("SELECT nextval('{}.shared_id_seq')", NAMESPACE)[..], &[])
+            .unwrap()
+            .get(0)
+    };
+    assert_eq!(next_value(&mut client), 100);
+    assert_eq!(next_value(&mut client), 105);
+
+    // Modifying the increment continues on from the current value
+    let shared = package
+        .sequences
+        .iter_mut()
+        .find(|s| s.name.name == "shared_id_seq")
+        .unwrap();
+    shared.increment = Some(10);
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let sequence = final_package
+        .sequences
+        .iter()
+        .find(|s| s.name.to_string().eq(&format!("{}.shared_id_seq", NAMESPACE)))
+        .unwrap();
+    assert_eq!(sequence.increment, Some(10));
+    assert_eq!(next_value(&mut client), 115);
+}
+
+#[test]
+fn it_can_rename_a_table_column_and_index_without_losing_data() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_rename_a_table_column_and_index";
+
+    // Preliminary: start without either table or any recorded renames
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+    drop_table!(client, NAMESPACE, "people");
+    client
+        .batch_execute(&format!(
+            "DELETE FROM psqlpack.refactor_log WHERE definition::text LIKE '%{}.%'",
+            NAMESPACE
+        ))
+        .ok();
+
+    // Publish the original table and add some data
+    let package = generate_simple_package!(NAMESPACE);
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+    client
+        .batch_execute(&format!("INSERT INTO {}.contacts (name) VALUES ('Jane')", NAMESPACE))
+        .unwrap();
+
+    // Rename everything in the package
+    let mut package = generate_simple_package!(NAMESPACE);
+    let people = ObjectName {
+        schema: Some(NAMESPACE.to_string()),
+        name: "people".to_string(),
+    };
+    package.tables[0].name = people.clone();
+    package.tables[0].columns[1].name = "full_name".to_string();
+    package.tables[0].constraints = vec![TableConstraint::Primary {
+        name: "people_pkey".to_string(),
+        columns: vec!["id".to_string()],
+        parameters: None,
+    }];
+    package.indexes[0].name = "idx_people_full_name".to_string();
+    package.indexes[0].table = people;
+    package.indexes[0].columns[0].name = "full_name".to_string();
+    package.push_rename(RenameDefinition::Table {
+        from: format!("{}.contacts", NAMESPACE),
+        to: "people".to_string(),
+    });
+    package.push_rename(RenameDefinition::Column {
+        table: format!("{}.people", NAMESPACE),
+        from: "name".to_string(),
+        to: "full_name".to_string(),
+    });
+    package.push_rename(RenameDefinition::Constraint {
+        table: format!("{}.people", NAMESPACE),
+        from: "contacts_pkey".to_string(),
+        to: "people_pkey".to_string(),
+    });
+    package.push_rename(RenameDefinition::Index {
+        from: format!("{}.idx_contacts_name", NAMESPACE),
+        to: "idx_people_full_name".to_string(),
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+
+    // The renamed objects exist and the data has been kept
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.to_string().eq(&format!("{}.people", NAMESPACE)))
+        .unwrap();
+    assert_eq!(table.columns[1].name, "full_name");
+    assert_eq!(table.constraints[0].name(), "people_pkey");
+    assert!(!final_package
+        .tables
+        .iter()
+        .any(|t| t.name.to_string().eq(&format!("{}.contacts", NAMESPACE))));
+    assert!(final_package.indexes.iter().any(|i| i
+        .fully_qualified_name()
+        .eq(&format!("{}.idx_people_full_name", NAMESPACE))));
+    let full_name = |client: &mut postgres::Client| -> String {
+        client
+            .query_one(&format!("SELECT full_name FROM {}.people", NAMESPACE)[..], &[])
+            .unwrap()
+            .get(0)
+    };
+    assert_eq!(full_name(&mut client), "Jane");
+
+    // The renames are recorded so publishing again doesn't try to apply them twice
+    assert_eq!(
+        final_package
+            .renames
+            .iter()
+            .filter(|r| package.renames.contains(r))
+            .count(),
+        4
+    );
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert!(final_package
+        .tables
+        .iter()
+        .any(|t| t.name.to_string().eq(&format!("{}.people", NAMESPACE))));
+    assert_eq!(full_name(&mut client), "Jane");
+}
+
+#[test]
+fn it_records_each_deployment_in_the_target_database() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_records_each_deployment";
+
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // A successful publish is recorded along with the package and script
+    let log = Logger::root(Discard.fuse(), o!());
+    let package = generate_simple_package!(NAMESPACE);
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_simple_package!(final_package, NAMESPACE);
+    let package_hash = package.hash().unwrap();
+    let history = Deployment::history(&log, &connection).unwrap();
+    let deployment = history.iter().find(|d| d.package_hash.eq(&package_hash)).unwrap();
+    assert_eq!(deployment.status, DeploymentStatus::Succeeded);
+    assert_eq!(deployment.package_source, "project");
+    assert_eq!(deployment.deployed_by, "postgres");
+    assert!(deployment.finished_at.unwrap() >= deployment.started_at);
+    assert!(deployment
+        .script
+        .contains(&format!("CREATE TABLE {}.contacts", NAMESPACE)));
+    assert_eq!(deployment.profile["drop_tables"], "Ignore");
+
+    // A failed publish is also recorded
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_script(ScriptDefinition {
+        name: "fail.sql".into(),
+        kind: ScriptKind::PreDeployment,
+        order: 0,
+        contents: "SELECT 1 / 0;".into(),
+    });
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_tables = Toggle::Ignore; // We reuse the same database
+    publish_profile.generation_options.drop_partitions = Toggle::Ignore;
+    publish_profile.generation_options.drop_views = Toggle::Ignore;
+    publish_profile.generation_options.drop_functions = Toggle::Ignore;
+    publish_profile.generation_options.drop_procedures = Toggle::Ignore;
+    publish_profile.generation_options.drop_triggers = Toggle::Ignore;
+    publish_profile.generation_options.drop_policies = Toggle::Ignore;
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    assert!(delta.apply(&log, &connection).is_err());
+    let package_hash = package.hash().unwrap();
+    let history = Deployment::history(&log, &connection).unwrap();
+    let deployment = history.iter().find(|d| d.package_hash.eq(&package_hash)).unwrap();
+    assert_eq!(deployment.status, DeploymentStatus::Failed);
+    assert!(deployment.script.contains("SELECT 1 / 0;"));
+}
+
+#[test]
+fn it_rolls_back_a_failed_transactional_publish() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_rolls_back_a_failed_transactional_publish";
+
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // The post deployment script fails after the table has been created
+    let log = Logger::root(Discard.fuse(), o!());
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_script(ScriptDefinition {
+        name: "fail.sql".into(),
+        kind: ScriptKind::PostDeployment,
+        order: 0,
+        contents: "SELECT 1 / 0;".into(),
+    });
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_tables = Toggle::Ignore; // We reuse the same database
+    publish_profile.generation_options.drop_partitions = Toggle::Ignore;
+    publish_profile.generation_options.drop_views = Toggle::Ignore;
+    publish_profile.generation_options.drop_functions = Toggle::Ignore;
+    publish_profile.generation_options.drop_procedures = Toggle::Ignore;
+    publish_profile.generation_options.drop_triggers = Toggle::Ignore;
+    publish_profile.generation_options.drop_policies = Toggle::Ignore;
+    publish_profile.generation_options.transactional = true;
+    let deployments = |package: &Package| {
+        let package_hash = package.hash().unwrap();
+        Deployment::history(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.package_hash.eq(&package_hash))
+            .map(|d| d.status)
+            .collect::<Vec<_>>()
+    };
+    let previous = deployments(&package);
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    let error = delta.apply(&log, &connection).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Couldn't publish database during phase 1 of 2 (transaction)"
+    );
+
+    // Nothing should have been created, and the deployment is only recorded as having failed
+    let exists: bool = client
+        .query_one(
+            "SELECT to_regclass($1) IS NOT NULL",
+            &[&format!("{}.contacts", NAMESPACE)],
+        )
+        .unwrap()
+        .get(0);
+    assert!(!exists);
+    let history = deployments(&package);
+    assert_eq!(history.len(), previous.len() + 1);
+    assert_eq!(history[0], DeploymentStatus::Failed);
+
+    // Whereas a successful publish is recorded within the transaction
+    package.scripts.clear();
+    let previous = deployments(&package);
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let history = deployments(&package);
+    assert_eq!(history.len(), previous.len() + 1);
+    assert_eq!(history[0], DeploymentStatus::Succeeded);
+
+    // An index dropped concurrently after the transaction may have already gone along with its column
+    let table = package.tables.iter_mut().find(|t| t.name.name == "contacts").unwrap();
+    table.columns.retain(|c| c.name != "name");
+    package.indexes.retain(|i| i.name != "idx_contacts_name");
+    publish_profile.generation_options.drop_columns = Toggle::Allow;
+    publish_profile.generation_options.drop_indexes = Toggle::Allow;
+    publish_profile.generation_options.force_concurrent_indexes = true;
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let exists: bool = client
+        .query_one(
+            "SELECT to_regclass($1) IS NOT NULL",
+            &[&format!("{}.idx_contacts_name", NAMESPACE)],
+        )
+        .unwrap()
+        .get(0);
+    assert!(!exists);
+}
+
+#[test]
+fn it_detects_changes_made_outside_of_psqlpack() {
+    const DB_NAME: &str = "psqlpack_drift_db";
+    const NAMESPACE: &str = "it_detects_changes_made_outside_of_psqlpack";
+
+    // Preliminary: start from a fresh database
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+
+    // Nothing has changed straight after a publish
+    let log = Logger::root(Discard.fuse(), o!());
+    let package = generate_simple_package!(NAMESPACE);
+    publish_package!(DB_NAME, connection, package);
+    let drift = package.compare(&log, &connection).unwrap();
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // Apply a hotfix by hand
+    let mut client = connection.connect_database().unwrap();
+    client
+        .batch_execute(&format!(
+            "ALTER TABLE {0}.contacts ADD COLUMN nickname varchar(50);\
+             DROP INDEX {0}.idx_contacts_name;\
+             CREATE TABLE {0}.hotfix (id int);",
+            NAMESPACE
+        ))
+        .unwrap();
+    let drift = package
+        .compare(&log, &connection)
+        .unwrap()
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        drift,
+        vec![
+            format!("table {}.hotfix added", NAMESPACE),
+            format!("column nickname on table {}.contacts added", NAMESPACE),
+            format!("index {}.idx_contacts_name removed", NAMESPACE),
+        ]
+    );
+}
+
+#[test]
+fn it_can_add_and_modify_column_defaults_without_churn() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_and_modify_column_defaults";
+
+    // Preliminary: start without the table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "settings");
+
+    let column = |name: &str, sql_type: SimpleSqlType, default: Option<Expression>| {
+        let mut constraints = vec![ColumnConstraint::NotNull];
+        if let Some(default) = default {
+            constraints.push(ColumnConstraint::Default(default));
+        }
+        ColumnDefinition {
+            name: name.into(),
+            sql_type: SqlType::Simple(sql_type, None),
+            constraints,
+            comment: None,
+        }
+    };
+
+    // The database is shared with other tests so only consider drift within this namespace
+    let log = Logger::root(Discard.fuse(), o!());
+    let namespace_drift = |package: &Package| -> Vec<Drift> {
+        package
+            .compare(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.object.contains(NAMESPACE))
+            .collect()
+    };
+
+    // Postgres describes these defaults differently however they are still considered the same
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_table(TableDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "settings".to_string(),
+        },
+        columns: vec![
+            column("id", SimpleSqlType::Serial, None),
+            column(
+                "created_at",
+                SimpleSqlType::DateTimeWithTimeZone(None),
+                Some(Expression::FunctionCall(
+                    ObjectName {
+                        schema: None,
+                        name: "now".into(),
+                    },
+                    Vec::new(),
+                )),
+            ),
+            column(
+                "priority",
+                SimpleSqlType::Integer,
+                Some(Expression::Literal(AnyValue::Integer(-1, None))),
+            ),
+            column(
+                "label",
+                SimpleSqlType::Text,
+                Some(Expression::Literal(AnyValue::String("it's new".into(), None))),
+            ),
+        ],
+        constraints: Vec::new(),
+        partition_by: None,
+        partition_of: None,
+        row_security: false,
+        force_row_security: false,
+        comment: None,
+    });
+    publish_package!(DB_NAME, connection, package);
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // Modifying and removing defaults is applied to the existing columns
+    let settings = package.tables.iter_mut().find(|t| t.name.name == "settings").unwrap();
+    for definition in settings.columns.iter_mut() {
+        match &definition.name[..] {
+            "priority" => {
+                *definition = column(
+                    "priority",
+                    SimpleSqlType::Integer,
+                    Some(Expression::Literal(AnyValue::Integer(5, None))),
+                )
+            }
+            "label" => *definition = column("label", SimpleSqlType::Text, None),
+            _ => {}
+        }
+    }
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.to_string().eq(&format!("{}.settings", NAMESPACE)))
+        .unwrap();
+    let defaults = table
+        .columns
+        .iter()
+        .map(|c| c.default_value().map(|expr| expr.normalize().to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        defaults,
+        vec![None, Some("now()".to_owned()), Some("5".to_owned()), None]
+    );
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_convert_serial_to_identity_and_modify_generated_columns() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_convert_serial_to_identity";
+
+    // Preliminary: create a table using a serial column with some data
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "tickets");
+    client
+        .batch_execute(&format!(
+            "CREATE SCHEMA IF NOT EXISTS {0};\
+             CREATE TABLE {0}.tickets (\
+                id serial PRIMARY KEY NOT NULL,\
+                price numeric NOT NULL,\
+                quantity int NOT NULL,\
+                total numeric GENERATED ALWAYS AS (price * quantity) STORED\
+             );\
+             INSERT INTO {0}.tickets (price, quantity) VALUES (1.5, 2), (2, 3), (3, 4);",
+            NAMESPACE
+        ))
+        .unwrap();
+
+    fn multiply(left: Expression, right: Expression) -> Expression {
+        Expression::BinaryOperator(Box::new(left), "*".into(), Box::new(right))
+    }
+    let price_by_quantity = multiply(
+        Expression::Column("price".into()),
+        Expression::Column("quantity".into()),
+    );
+    let column = |name: &str, sql_type: SimpleSqlType, constraint: Option<ColumnConstraint>| {
+        let mut constraints = vec![ColumnConstraint::NotNull];
+        constraints.extend(constraint);
+        ColumnDefinition {
+            name: name.into(),
+            sql_type: SqlType::Simple(sql_type, None),
+            constraints,
+            comment: None,
+        }
+    };
+    let identity = |increment: i64| {
+        ColumnConstraint::Identity(
+            IdentityDefinition::new(IdentityGeneration::Always, vec![SequenceOption::Increment(increment)]).unwrap(),
+        )
+    };
+
+    // The database is shared with other tests so only consider drift within this namespace
+    let log = Logger::root(Discard.fuse(), o!());
+    let namespace_drift = |package: &Package| -> Vec<Drift> {
+        package
+            .compare(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.object.contains(NAMESPACE))
+            .collect()
+    };
+    let insert = |client: &mut postgres::Client| -> (i32, String) {
+        let row = client
+            .query_one(
+                &format!(
+                    "INSERT INTO {}.tickets (price, quantity) VALUES (10, 2) RETURNING id, total::text",
+                    NAMESPACE
+                )[..],
+                &[],
+            )
+            .unwrap();
+        (row.get(0), row.get(1))
+    };
+
+    // The serial column is converted in place and continues on from the existing sequence
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_table(TableDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "tickets".to_string(),
+        },
+        columns: vec![
+            column("id", SimpleSqlType::Integer, Some(identity(1))),
+            column("price", SimpleSqlType::Numeric(None), None),
+            column("quantity", SimpleSqlType::Integer, None),
+            ColumnDefinition {
+                name: "total".into(),
+                sql_type: SqlType::Simple(SimpleSqlType::Numeric(None), None),
+                constraints: vec![ColumnConstraint::Generated(price_by_quantity.clone())],
+                comment: None,
+            },
+        ],
+        constraints: vec![TableConstraint::Primary {
+            name: "tickets_pkey".into(),
+            columns: vec!["id".into()],
+            parameters: None,
+        }],
+        partition_by: None,
+        partition_of: None,
+        row_security: false,
+        force_row_security: false,
+        comment: None,
+    });
+    package.set_defaults(&Project::default());
+    publish_package!(DB_NAME, connection, package);
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+    assert_eq!(insert(&mut client), (4, "20".to_string()));
+    let result = client.batch_execute(&format!(
+        "INSERT INTO {}.tickets (id, price, quantity) VALUES (100, 1, 1)",
+        NAMESPACE
+    ));
+    assert!(
+        result.is_err(),
+        "Values can't be provided for an identity that is always generated"
+    );
+
+    // Modifying the identity keeps the current value and the generated column is computed again
+    let expression = multiply(
+        price_by_quantity.clone(),
+        Expression::Literal(AnyValue::Integer(2, None)),
+    );
+    let tickets = package.tables.iter_mut().find(|t| t.name.name == "tickets").unwrap();
+    for definition in tickets.columns.iter_mut() {
+        match &definition.name[..] {
+            "id" => *definition = column("id", SimpleSqlType::Integer, Some(identity(5))),
+            "total" => definition.constraints = vec![ColumnConstraint::Generated(expression.clone())],
+            _ => {}
+        }
+    }
+    package.set_defaults(&Project::default());
+    publish_package!(DB_NAME, connection, package);
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+    assert_eq!(insert(&mut client), (9, "40".to_string()));
+    let total: String = client
+        .query_one(
+            &format!("SELECT total::text FROM {}.tickets WHERE id = 1", NAMESPACE)[..],
+            &[],
+        )
+        .unwrap()
+        .get(0);
+    assert_eq!(total, "6.0");
+
+    // Removing the expression keeps the existing values
+    let tickets = package.tables.iter_mut().find(|t| t.name.name == "tickets").unwrap();
+    let total = tickets.columns.iter_mut().find(|c| c.name == "total").unwrap();
+    total.constraints.clear();
+    publish_package!(DB_NAME, connection, package);
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+    let total: String = client
+        .query_one(
+            &format!("SELECT total::text FROM {}.tickets WHERE id = 1", NAMESPACE)[..],
+            &[],
+        )
+        .unwrap()
+        .get(0);
+    assert_eq!(total, "6.0");
+}
+
+#[test]
+fn it_can_attach_and_detach_partitions_of_an_existing_table() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_attach_and_detach_partitions";
+
+    // Preliminary: create a partitioned table with a partition created outside of the project as well as a
+    // regular table with data that will become a partition
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "events_2021");
+    drop_table!(client, NAMESPACE, "events");
+    client
+        .batch_execute(&format!(
+            "CREATE SCHEMA IF NOT EXISTS {0};\
+             CREATE TABLE {0}.events (id bigint NOT NULL, created_at date NOT NULL) PARTITION BY RANGE (created_at);\
+             CREATE TABLE {0}.events_2022 PARTITION OF {0}.events FOR VALUES FROM ('2022-01-01') TO ('2023-01-01');\
+             CREATE TABLE {0}.events_2021 (id bigint NOT NULL, created_at date NOT NULL);\
+             INSERT INTO {0}.events_2021 VALUES (1, '2021-06-01');",
+            NAMESPACE
+        ))
+        .unwrap();
+
+    let object_name = |name: &str| ObjectName {
+        schema: Some(NAMESPACE.to_string()),
+        name: name.to_string(),
+    };
+    let columns = || {
+        vec![
+            ColumnDefinition {
+                name: "id".into(),
+                sql_type: SqlType::Simple(SimpleSqlType::BigInteger, None),
+                constraints: vec![ColumnConstraint::NotNull],
+                comment: None,
+            },
+            ColumnDefinition {
+                name: "created_at".into(),
+                sql_type: SqlType::Simple(SimpleSqlType::Date, None),
+                constraints: vec![ColumnConstraint::NotNull],
+                comment: None,
+            },
+        ]
+    };
+    let partition = |year: i32| {
+        let bound = |year: i32| {
+            vec![PartitionRangeValue::Value(Expression::Literal(AnyValue::String(
+                format!("{}-01-01", year),
+                None,
+            )))]
+        };
+        TableDefinition {
+            name: object_name(&format!("events_{}", year)),
+            columns: Vec::new(),
+            constraints: Vec::new(),
+            partition_by: None,
+            partition_of: Some(PartitionOf {
+                parent: object_name("events"),
+                bound: PartitionBound::Range {
+                    from: bound(year),
+                    to: bound(year + 1),
+                },
+            }),
+            row_security: false,
+            force_row_security: false,
+            comment: None,
+        }
+    };
+
+    // The database is shared with other tests so only consider drift within this namespace
+    let log = Logger::root(Discard.fuse(), o!());
+    let namespace_drift = |package: &Package| -> Vec<String> {
+        package
+            .compare(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.object.contains(NAMESPACE))
+            .map(|d| d.to_string())
+            .collect()
+    };
+    let count = |client: &mut postgres::Client, table: &str| -> i64 {
+        client
+            .query_one(&format!("SELECT COUNT(*) FROM {}.{}", NAMESPACE, table)[..], &[])
+            .unwrap()
+            .get(0)
+    };
+
+    // The regular table is attached with its data while the partition created by hand is left alone
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_table(TableDefinition {
+        name: object_name("events"),
+        columns: columns(),
+        constraints: Vec::new(),
+        partition_by: Some(PartitionKey {
+            strategy: PartitionStrategy::Range,
+            columns: vec![Expression::Column("created_at".into())],
+        }),
+        partition_of: None,
+        row_security: false,
+        force_row_security: false,
+        comment: None,
+    });
+    package.push_table(partition(2020));
+    package.push_table(partition(2021));
+    package.set_defaults(&Project::default());
+    publish_package!(DB_NAME, connection, package);
+    client
+        .batch_execute(&format!("INSERT INTO {}.events VALUES (2, '2020-03-01')", NAMESPACE))
+        .unwrap();
+    assert_eq!(count(&mut client, "events"), 2);
+    assert_eq!(count(&mut client, "events_2020"), 1);
+    assert_eq!(count(&mut client, "events_2022"), 0);
+    let drift = namespace_drift(&package);
+    assert_eq!(drift.len(), 1, "Unexpected drift: {:?}", drift);
+    assert!(drift[0].contains("events_2022"), "Unexpected drift: {:?}", drift);
+
+    // Detaching keeps the data of the partition
+    let events_2021 = package
+        .tables
+        .iter_mut()
+        .find(|t| t.name.name == "events_2021")
+        .unwrap();
+    events_2021.columns = columns();
+    events_2021.partition_of = None;
+    publish_package!(DB_NAME, connection, package);
+    assert_eq!(count(&mut client, "events"), 1);
+    assert_eq!(count(&mut client, "events_2021"), 1);
+    let drift = namespace_drift(&package);
+    assert_eq!(drift.len(), 1, "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_add_and_modify_row_level_security_policies() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_and_modify_row_level_security_policies";
+
+    // Preliminary: start without the table so that its policies are removed as well
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    // The database is shared with other tests so only consider drift within this namespace
+    let log = Logger::root(Discard.fuse(), o!());
+    let namespace_drift = |package: &Package| -> Vec<String> {
+        package
+            .compare(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.object.contains(NAMESPACE))
+            .map(|d| d.to_string())
+            .collect()
+    };
+
+    // Publish and confirm the policies are extracted as equivalent definitions
+    let mut package = generate_simple_package!(NAMESPACE);
+    let table_name = package.tables[0].name.clone();
+    package.tables[0].row_security = true;
+    package.tables[0].force_row_security = true;
+    package.push_policy(PolicyDefinition {
+        name: "minimum_id".into(),
+        table: table_name.clone(),
+        permissive: true,
+        command: PolicyCommand::All,
+        roles: Vec::new(),
+        using: Some("id > current_setting('app.min_id', true)::int".into()),
+        with_check: None,
+    });
+    package.push_policy(PolicyDefinition {
+        name: "named_only".into(),
+        table: table_name,
+        permissive: false,
+        command: PolicyCommand::Insert,
+        roles: vec!["postgres".into()],
+        using: None,
+        with_check: Some("name <> '' AND id > 0".into()),
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.to_string().eq(&format!("{}.contacts", NAMESPACE)))
+        .unwrap();
+    assert!(table.row_security);
+    assert!(table.force_row_security);
+    for policy in &package.policies {
+        let published = final_package
+            .policies
+            .iter()
+            .find(|p| p.is_same_policy(policy))
+            .unwrap();
+        assert!(published.is_same_definition(policy), "{:?} vs {:?}", published, policy);
+    }
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // Alter one policy in place, replace the other and stop forcing row level security
+    let contacts = package.tables.iter_mut().find(|t| t.name.name == "contacts").unwrap();
+    contacts.force_row_security = false;
+    for policy in package.policies.iter_mut() {
+        match &policy.name[..] {
+            "minimum_id" => policy.using = Some("id >= current_setting('app.min_id', true)::int".into()),
+            "named_only" => policy.command = PolicyCommand::Update,
+            _ => {}
+        }
+    }
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.to_string().eq(&format!("{}.contacts", NAMESPACE)))
+        .unwrap();
+    assert!(table.row_security);
+    assert!(!table.force_row_security);
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_grant_and_revoke_privileges() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_grant_and_revoke_privileges";
+
+    // Preliminary: start without the table and make sure the grantee exists
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+    client
+        .batch_execute("DO $$ BEGIN CREATE ROLE psqlpack_reader; EXCEPTION WHEN duplicate_object THEN NULL; END $$")
+        .unwrap();
+
+    let mut package = generate_simple_package!(NAMESPACE);
+    let table_name = package.tables[0].name.clone();
+    package.push_grant(GrantDefinition {
+        object: GrantObject::Table(table_name.clone()),
+        grantee: "psqlpack_reader".into(),
+        privileges: vec![Privilege::Select, Privilege::Insert],
+        with_grant_option: false,
+    });
+
+    // Only one overload of a function is granted to the reader
+    let int = || SqlType::Simple(SimpleSqlType::Integer, None);
+    let add = |arguments: Vec<SqlType>| FunctionDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "add".to_string(),
+        },
+        arguments: arguments
+            .into_iter()
+            .map(|sql_type| FunctionArgument {
+                mode: None,
+                name: None,
+                sql_type,
+                default: None,
+            })
+            .collect(),
+        return_type: FunctionReturnType::SqlType(int()),
+        body: "SELECT 1".to_string(),
+        language: FunctionLanguage::SQL,
+        volatility: FunctionVolatility::Immutable,
+        strict: false,
+        security_definer: false,
+        parallel: FunctionParallel::Unsafe,
+        cost: None,
+        rows: None,
+        settings: Vec::new(),
+        comment: None,
+    };
+    package.push_function(add(vec![int()]));
+    package.push_function(add(vec![int(), int()]));
+    let add_pair = GrantObject::Function(add(Vec::new()).name, Some(vec![int(), int()]));
+    package.push_grant(GrantDefinition {
+        object: add_pair.clone(),
+        grantee: "psqlpack_reader".into(),
+        privileges: vec![Privilege::Execute],
+        with_grant_option: false,
+    });
+    client
+        .batch_execute(&format!("DROP FUNCTION IF EXISTS {}.add(int, int)", NAMESPACE))
+        .unwrap();
+
+    // The database is shared with other tests so only consider drift within this namespace
+    let log = Logger::root(Discard.fuse(), o!());
+    let namespace_drift = |package: &Package| -> Vec<String> {
+        package
+            .compare(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.object.contains(NAMESPACE))
+            .map(|d| d.to_string())
+            .collect()
+    };
+
+    // Publish and confirm the grant is extracted as declared
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let grant = final_package
+        .grants
+        .iter()
+        .find(|g| g.is_same_grant(&package.grants[0]))
+        .unwrap();
+    assert_eq!(grant.privileges, vec![Privilege::Select, Privilege::Insert]);
+    assert!(!grant.with_grant_option);
+    let function_grants = final_package
+        .grants
+        .iter()
+        .filter(|g| g.grantee == "psqlpack_reader" && matches!(g.object, GrantObject::Function(..)))
+        .filter(|g| g.object.name().contains(NAMESPACE))
+        .map(|g| g.object.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(function_grants, vec![add_pair]);
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // A privilege granted by hand is reported but left alone by default
+    client
+        .batch_execute(&format!("GRANT DELETE ON {} TO psqlpack_reader", table_name))
+        .unwrap();
+    let drift = namespace_drift(&package);
+    assert_eq!(drift.len(), 1, "Unexpected drift: {:?}", drift);
+    publish_package!(DB_NAME, connection, package);
+    assert_eq!(namespace_drift(&package).len(), 1);
+
+    // Once revoking is allowed it is removed
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_tables = Toggle::Ignore;
+    publish_profile.generation_options.drop_partitions = Toggle::Ignore;
+    publish_profile.generation_options.drop_views = Toggle::Ignore;
+    publish_profile.generation_options.drop_functions = Toggle::Ignore;
+    publish_profile.generation_options.drop_procedures = Toggle::Ignore;
+    publish_profile.generation_options.drop_triggers = Toggle::Ignore;
+    publish_profile.generation_options.drop_policies = Toggle::Ignore;
+    publish_profile.generation_options.revoke_privileges = Toggle::Allow;
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile)
+        .unwrap()
+        .apply(&log, &connection)
+        .unwrap();
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_create_and_alter_roles() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_create_and_alter_roles";
+
+    // Preliminary: start without the roles
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    client
+        .batch_execute(&format!(
+            "DROP ROLE IF EXISTS {0}_app; DROP ROLE IF EXISTS {0}_readers; DROP ROLE IF EXISTS {0}_external;",
+            NAMESPACE
+        ))
+        .unwrap();
+
+    // The database is shared with other tests so only consider drift within this namespace
+    let log = Logger::root(Discard.fuse(), o!());
+    let namespace_drift = |package: &Package| -> Vec<String> {
+        package
+            .compare(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.object.contains(NAMESPACE))
+            .map(|d| d.to_string())
+            .collect()
+    };
+
+    // Publish and confirm the roles are extracted as declared
+    let role = |name: String, login: bool, member_of: Vec<String>| RoleDefinition {
+        name,
+        login,
+        superuser: false,
+        create_db: false,
+        create_role: false,
+        inherit: true,
+        replication: false,
+        bypass_rls: false,
+        connection_limit: -1,
+        member_of,
+    };
+    let mut package = generate_simple_package!(NAMESPACE);
+    let readers = format!("{}_readers", NAMESPACE);
+    package.push_role(role(format!("{}_app", NAMESPACE), true, vec![readers.clone()]));
+    package.push_role(role(readers, false, Vec::new()));
+    let final_package = publish_package!(DB_NAME, connection, package);
+    for role in &package.roles {
+        let published = final_package.roles.iter().find(|r| r.name.eq(&role.name)).unwrap();
+        assert_eq!(published, role);
+    }
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // Alter the roles in place
+    for role in &mut package.roles {
+        role.connection_limit = 10;
+    }
+    let final_package = publish_package!(DB_NAME, connection, package);
+    for role in &package.roles {
+        let published = final_package.roles.iter().find(|r| r.name.eq(&role.name)).unwrap();
+        assert_eq!(published.connection_limit, 10);
+    }
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // Only roles that psqlpack created are dropped, leaving a role created by hand alone
+    client
+        .batch_execute(&format!("CREATE ROLE {}_external", NAMESPACE))
+        .unwrap();
+    package.roles.retain(|r| r.name.ends_with("_app"));
+    for role in &mut package.roles {
+        role.member_of.clear();
+    }
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_tables = Toggle::Ignore; // We reuse the same database
+    publish_profile.generation_options.drop_functions = Toggle::Ignore; // We reuse the same database
+    publish_profile.generation_options.drop_roles = Toggle::Allow;
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let roles = Package::from_connection(&log, &connection, &capabilities)
+        .unwrap()
+        .unwrap()
+        .roles
+        .into_iter()
+        .map(|r| r.name)
+        .filter(|name| name.starts_with(NAMESPACE))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        roles,
+        vec![format!("{}_app", NAMESPACE), format!("{}_external", NAMESPACE)]
+    );
+
+    // Roles are shared across the cluster so they are removed again
+    client
+        .batch_execute(&format!("DROP ROLE {0}_app; DROP ROLE {0}_external;", NAMESPACE))
+        .unwrap();
+}
+
+#[test]
+fn it_can_add_modify_and_remove_comments() {
+    const DB_NAME: &str = "psqlpack_existing_db";
+    const NAMESPACE: &str = "it_can_add_modify_and_remove_comments";
+
+    // Preliminary: start without the table
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    dump_capabilities!(connection);
+    let mut client = create_db!(connection);
+    drop_table!(client, NAMESPACE, "contacts");
+
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.schemas[0].comment = Some("Comments on everything".into());
+    package.tables[0].comment = Some("People we've met".into());
+    package.tables[0].columns[1].comment = Some("Full name".into());
+    package.indexes[0].comment = Some("Lookups by name".into());
+
+    // The database is shared with other tests so only consider drift within this namespace
+    let log = Logger::root(Discard.fuse(), o!());
+    let namespace_drift = |package: &Package| -> Vec<String> {
+        package
+            .compare(&log, &connection)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.object.contains(NAMESPACE))
+            .map(|d| d.to_string())
+            .collect()
+    };
+
+    // Publish and confirm the comments are extracted as declared
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let table = final_package
+        .tables
+        .iter()
+        .find(|t| t.name.eq(&package.tables[0].name))
+        .unwrap();
+    assert_eq!(table.comment, Some("People we've met".into()));
+    assert_eq!(table.columns[0].comment, None);
+    assert_eq!(table.columns[1].comment, Some("Full name".into()));
+    let index = final_package
+        .indexes
+        .iter()
+        .find(|i| i.is_same_index(&package.indexes[0]))
+        .unwrap();
+    assert_eq!(index.comment, Some("Lookups by name".into()));
+    let schema = final_package.schemas.iter().find(|s| s.name.eq(NAMESPACE)).unwrap();
+    assert_eq!(schema.comment, Some("Comments on everything".into()));
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // Comments changed by hand are reported and put back without touching the data
+    client
+        .batch_execute(&format!(
+            "INSERT INTO {0}.contacts (name) VALUES ('Jane');
+             COMMENT ON TABLE {0}.contacts IS 'Changed';
+             COMMENT ON COLUMN {0}.contacts.id IS 'Added';",
+            NAMESPACE
+        ))
+        .unwrap();
+    let drift = namespace_drift(&package);
+    assert_eq!(drift.len(), 2, "Unexpected drift: {:?}", drift);
+    package.tables[0].columns[1].comment = None;
+    publish_package!(DB_NAME, connection, package);
+    let drift = namespace_drift(&package);
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+    let rows = client
+        .query(&format!("SELECT name FROM {}.contacts", NAMESPACE)[..], &[])
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+    let comment: Option<String> = client
+        .query_one(
+            &format!("SELECT col_description('{}.contacts'::regclass, 2)", NAMESPACE)[..],
+            &[],
+        )
+        .unwrap()
+        .get(0);
+    assert_eq!(comment, None);
+}
+
+#[test]
+fn it_can_change_schema_owners_and_drop_schemas() {
+    const DB_NAME: &str = "psqlpack_schema_db";
+    const NAMESPACE: &str = "it_can_change_schema_owners_and_drop_schemas";
+
+    // Preliminary: start from a fresh database with an owner role available
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+    client
+        .batch_execute(&format!(
+            "DROP ROLE IF EXISTS {0}_owner; CREATE ROLE {0}_owner;",
+            NAMESPACE
+        ))
+        .unwrap();
+
+    // Publish a package with a second schema owned by the role
+    let archive = format!("{}_archive", NAMESPACE);
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_schema(SchemaDefinition {
+        name: archive.clone(),
+        owner: Some(format!("{}_owner", NAMESPACE)),
+        comment: None,
+    });
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let schema = final_package.schemas.iter().find(|s| s.name.eq(&archive)).unwrap();
+    assert_eq!(schema.owner, Some(format!("{}_owner", NAMESPACE)));
+
+    // An owner changed by hand is reported and put back
+    let log = Logger::root(Discard.fuse(), o!());
+    let mut client = connection.connect_database().unwrap();
+    client
+        .batch_execute(&format!(
+            "ALTER SCHEMA {} OWNER TO postgres; CREATE TABLE {}.old_contacts (id int);",
+            archive, archive
+        ))
+        .unwrap();
+    let drift = package
+        .compare(&log, &connection)
+        .unwrap()
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        drift,
+        vec![
+            format!("table {}.old_contacts added", archive),
+            format!("schema {} modified", archive),
+        ]
+    );
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let schema = final_package.schemas.iter().find(|s| s.name.eq(&archive)).unwrap();
+    assert_eq!(schema.owner, Some(format!("{}_owner", NAMESPACE)));
+
+    // Dropping the schema is refused while it still contains the table
+    let package = generate_simple_package!(NAMESPACE);
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_tables = Toggle::Ignore;
+    publish_profile.generation_options.drop_schemas = Toggle::Allow;
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let result = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile);
+    match result {
+        Err(error) => assert!(error.to_string().contains("requires CASCADE"), "{}", error),
+        Ok(_) => panic!("Expected the schema drop to be refused"),
+    }
+
+    // Unless cascading is allowed
+    publish_profile.generation_options.cascade_schema_drops = true;
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let exists: bool = client
+        .query_one(
+            "SELECT EXISTS(SELECT 1 FROM pg_namespace WHERE nspname = $1)",
+            &[&archive],
+        )
+        .unwrap()
+        .get(0);
+    assert!(!exists);
+
+    // Roles are shared across the cluster so the owner is removed again
+    client.batch_execute(&format!("DROP ROLE {}_owner", NAMESPACE)).unwrap();
+}
+
+#[test]
+fn it_can_remove_enum_values_and_drop_types() {
+    const DB_NAME: &str = "psqlpack_enum_db";
+    const NAMESPACE: &str = "it_can_remove_enum_values_and_drop_types";
+
+    // Preliminary: start from a fresh database
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+
+    // Publish an enum used by a couple of columns, along with a type that will be dropped
+    let type_name = |name: &str| ObjectName {
+        schema: Some(NAMESPACE.to_string()),
+        name: name.to_string(),
+    };
+    let colors = |values: Vec<&str>| TypeDefinition {
+        name: type_name("colors"),
+        kind: TypeDefinitionKind::Enum(values.into_iter().map(|v| v.to_owned()).collect()),
+        comment: None,
+    };
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_type(colors(vec!["red", "green", "blue"]));
+    package.push_type(TypeDefinition {
+        name: type_name("shades"),
+        kind: TypeDefinitionKind::Enum(vec!["light".into(), "dark".into()]),
+        comment: None,
+    });
+    package.tables[0].columns.push(ColumnDefinition {
+        name: "color".into(),
+        sql_type: SqlType::Custom(type_name("colors"), Vec::new(), None),
+        constraints: vec![ColumnConstraint::Default(Expression::Literal(AnyValue::String(
+            "green".into(),
+            Some(SqlType::Custom(type_name("colors"), Vec::new(), None)),
+        )))],
+        comment: None,
+    });
+    package.tables[0].columns.push(ColumnDefinition {
+        name: "palette".into(),
+        sql_type: SqlType::Custom(type_name("colors"), Vec::new(), Some(1)),
+        constraints: Vec::new(),
+        comment: None,
+    });
+    publish_package!(DB_NAME, connection, package);
+    let mut client = connection.connect_database().unwrap();
+    client
+        .batch_execute(&format!(
+            "INSERT INTO {0}.contacts (name, color, palette) VALUES ('Jane', 'red', '{{blue,red}}');
+             INSERT INTO {0}.contacts (name, color, palette) VALUES ('John', 'blue', NULL);",
+            NAMESPACE
+        ))
+        .unwrap();
+
+    // Removing a value that rows still use fails with the columns using it
+    let log = Logger::root(Discard.fuse(), o!());
+    package.types[0] = colors(vec!["green", "blue", "purple"]);
+    package.types.truncate(1);
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_enum_values = Toggle::Allow;
+    publish_profile.generation_options.drop_types = Toggle::Allow;
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    let error = format!("{:?}", delta.apply(&log, &connection).unwrap_err());
+    assert!(
+        error.contains(&format!(
+            "still used by {0}.contacts.color (1 rows), {0}.contacts.palette (1 rows)",
+            NAMESPACE
+        )),
+        "Unexpected error: {}",
+        error
+    );
+
+    // Once the rows are updated the enum is rebuilt, keeping the data and defaults
+    client
+        .batch_execute(&format!(
+            "UPDATE {}.contacts SET color = 'green', palette = '{{blue}}' WHERE name = 'Jane'",
+            NAMESPACE
+        ))
+        .unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let final_package = Package::from_connection(&log, &connection, &capabilities)
+        .unwrap()
+        .unwrap();
+    let types = final_package
+        .types
+        .iter()
+        .filter(|t| t.name.schema().eq(NAMESPACE))
+        .collect::<Vec<_>>();
+    assert_eq!(types.len(), 1);
+    assert_eq!(types[0].kind, colors(vec!["green", "blue", "purple"]).kind);
+    let rows = client
+        .query(
+            &format!(
+                "SELECT color::text, palette::text FROM {}.contacts ORDER BY name",
+                NAMESPACE
+            )[..],
+            &[],
+        )
+        .unwrap();
+    let rows = rows
+        .iter()
+        .map(|row| (row.get::<_, String>(0), row.get::<_, Option<String>>(1)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            ("green".to_owned(), Some("{blue}".to_owned())),
+            ("blue".to_owned(), None),
+        ]
+    );
+    let drift = package.compare(&log, &connection).unwrap();
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_remove_values_from_an_enum_in_the_public_schema() {
+    const DB_NAME: &str = "psqlpack_public_enum_db";
+    const NAMESPACE: &str = "it_can_remove_values_from_an_enum_in_the_public_schema";
+
+    // Preliminary: start from a fresh database
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+
+    // Publish a public enum used by a column, a view and a function. Like the source, the extracted column type
+    // isn't qualified since the public schema is on the search path.
+    let moods = || {
+        SqlType::Custom(
+            ObjectName {
+                schema: None,
+                name: "moods".to_string(),
+            },
+            Vec::new(),
+            None,
+        )
+    };
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_type(TypeDefinition {
+        name: ObjectName {
+            schema: Some("public".to_string()),
+            name: "moods".to_string(),
+        },
+        kind: TypeDefinitionKind::Enum(vec!["happy".into(), "sad".into(), "angry".into()]),
+        comment: None,
+    });
+    package.tables[0].columns.push(ColumnDefinition {
+        name: "mood".into(),
+        sql_type: moods(),
+        constraints: Vec::new(),
+        comment: None,
+    });
+    package.push_view(ViewDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "contact_moods".to_string(),
+        },
+        columns: None,
+        query: format!("SELECT name, mood FROM {}.contacts", NAMESPACE),
+        comment: None,
+    });
+    // A view on top of that view depends on the enum as well, while one that only mentions its name doesn't
+    package.push_view(ViewDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "happy_contacts".to_string(),
+        },
+        columns: None,
+        query: format!("SELECT name FROM {}.contact_moods WHERE mood = 'happy'", NAMESPACE),
+        comment: None,
+    });
+    package.push_view(ViewDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "moods_legend".to_string(),
+        },
+        columns: None,
+        query: "SELECT 'moods' AS label".to_string(),
+        comment: None,
+    });
+    package.push_function(FunctionDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "is_happy".to_string(),
+        },
+        arguments: vec![FunctionArgument {
+            mode: None,
+            name: Some("mood".to_string()),
+            sql_type: moods(),
+            default: None,
+        }],
+        return_type: FunctionReturnType::SqlType(SqlType::Simple(SimpleSqlType::Boolean, None)),
+        body: "BEGIN RETURN mood = 'happy'; END;".to_string(),
+        language: FunctionLanguage::PostgreSQL,
+        volatility: FunctionVolatility::Immutable,
+        strict: false,
+        security_definer: false,
+        parallel: FunctionParallel::Unsafe,
+        cost: None,
+        rows: None,
+        settings: Vec::new(),
+        comment: None,
+    });
+    let published = publish_package!(DB_NAME, connection, package);
+    let table = published.tables.iter().find(|t| t.name.schema().eq(NAMESPACE)).unwrap();
+    assert_eq!(
+        table.columns.iter().find(|c| c.name == "mood").unwrap().sql_type,
+        moods()
+    );
+    let mut client = connection.connect_database().unwrap();
+    client
+        .batch_execute(&format!(
+            "INSERT INTO {0}.contacts (name, mood) VALUES ('Jane', 'happy'), ('John', 'angry');",
+            NAMESPACE
+        ))
+        .unwrap();
+
+    // Rows still using the removed value are found even though the column type isn't qualified
+    let log = Logger::root(Discard.fuse(), o!());
+    package.types[0].kind = TypeDefinitionKind::Enum(vec!["happy".into(), "sad".into()]);
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_enum_values = Toggle::Allow;
+    publish_profile.generation_options.drop_views = Toggle::Allow;
+    publish_profile.generation_options.drop_functions = Toggle::Allow;
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    let error = format!("{:?}", delta.apply(&log, &connection).unwrap_err());
+    assert!(
+        error.contains(&format!("still used by {}.contacts.mood (1 rows)", NAMESPACE)),
+        "Unexpected error: {}",
+        error
+    );
+
+    // Once the row is updated the enum is rebuilt, along with the views and function that depend on it
+    client
+        .batch_execute(&format!(
+            "UPDATE {}.contacts SET mood = 'sad' WHERE name = 'John'",
+            NAMESPACE
+        ))
+        .unwrap();
+    let legend_oid = format!("SELECT '{}.moods_legend'::regclass::oid", NAMESPACE);
+    let before: u32 = client.query_one(&legend_oid[..], &[]).unwrap().get(0);
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let after: u32 = client.query_one(&legend_oid[..], &[]).unwrap().get(0);
+    assert_eq!(
+        before, after,
+        "The view that only mentions the enum by name shouldn't be recreated"
+    );
+    let happy = client
+        .query(&format!("SELECT name FROM {}.happy_contacts", NAMESPACE)[..], &[])
+        .unwrap()
+        .iter()
+        .map(|row| row.get::<_, String>(0))
+        .collect::<Vec<_>>();
+    assert_eq!(happy, vec!["Jane".to_owned()]);
+    let rows = client
+        .query(
+            &format!(
+                "SELECT name, mood::text, {0}.is_happy(mood) FROM {0}.contact_moods ORDER BY name",
+                NAMESPACE
+            )[..],
+            &[],
+        )
+        .unwrap();
+    let rows = rows
+        .iter()
+        .map(|row| (row.get::<_, String>(0), row.get::<_, String>(1), row.get::<_, bool>(2)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rows,
+        vec![
+            ("Jane".to_owned(), "happy".to_owned(), true),
+            ("John".to_owned(), "sad".to_owned(), false),
+        ]
+    );
+    let drift = package.compare(&log, &connection).unwrap();
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_publish_overloaded_functions_and_change_return_types() {
+    const DB_NAME: &str = "psqlpack_function_db";
+    const NAMESPACE: &str = "it_can_publish_overloaded_functions_and_change_return_types";
+
+    // Preliminary: start from a fresh database
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+
+    // Publish two overloads of the same function
+    let add = |sql_type: SimpleSqlType, return_type: SimpleSqlType, body: &str| FunctionDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "add".to_string(),
+        },
+        arguments: vec!["a", "b"]
+            .into_iter()
+            .map(|name| FunctionArgument {
+                mode: None,
+                name: Some(name.to_string()),
+                sql_type: SqlType::Simple(sql_type.clone(), None),
+                default: None,
+            })
+            .collect(),
+        return_type: FunctionReturnType::SqlType(SqlType::Simple(return_type, None)),
+        body: body.to_string(),
+        language: FunctionLanguage::PostgreSQL,
+        volatility: FunctionVolatility::Volatile,
+        strict: false,
+        security_definer: false,
+        parallel: FunctionParallel::Unsafe,
+        cost: None,
+        rows: None,
+        settings: Vec::new(),
+        comment: None,
+    };
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_function(add(
+        SimpleSqlType::Integer,
+        SimpleSqlType::Integer,
+        "BEGIN RETURN a + b; END;",
+    ));
+    package.push_function(add(
+        SimpleSqlType::Text,
+        SimpleSqlType::Text,
+        "BEGIN RETURN a || b; END;",
+    ));
+    let final_package = publish_package!(DB_NAME, connection, package);
+    let signatures = |package: &Package| {
+        let mut signatures = package
+            .functions
+            .iter()
+            .filter(|f| f.name.schema().eq(NAMESPACE))
+            .map(|f| f.signature())
+            .collect::<Vec<_>>();
+        signatures.sort();
+        signatures
+    };
+    assert_eq!(
+        signatures(&final_package),
+        vec![
+            format!("{}.add(int, int)", NAMESPACE),
+            format!("{}.add(text, text)", NAMESPACE),
+        ]
+    );
+
+    // Changing the return type recreates the function while the removed overload is dropped
+    let log = Logger::root(Discard.fuse(), o!());
+    package.functions = vec![add(
+        SimpleSqlType::Integer,
+        SimpleSqlType::BigInteger,
+        "BEGIN RETURN a::bigint + b; END;",
+    )];
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_functions = Toggle::Allow;
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let final_package = Package::from_connection(&log, &connection, &capabilities)
+        .unwrap()
+        .unwrap();
+    assert_eq!(signatures(&final_package), vec![format!("{}.add(int, int)", NAMESPACE)]);
+    let mut client = connection.connect_database().unwrap();
+    let row = client
+        .query_one(&format!("SELECT {}.add(2, 3)", NAMESPACE)[..], &[])
+        .unwrap();
+    let total: i64 = row.get(0);
+    assert_eq!(total, 5);
+    let drift = package.compare(&log, &connection).unwrap();
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+}
+
+#[test]
+fn it_can_publish_modify_and_drop_procedures() {
+    const DB_NAME: &str = "psqlpack_procedure_db";
+    const NAMESPACE: &str = "it_can_publish_modify_and_drop_procedures";
+
+    // Preliminary: start from a fresh database
+    let connection = ConnectionBuilder::new(DB_NAME, "localhost", "postgres")
+        .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+        .build()
+        .unwrap();
+    let mut client = connection.connect_host().unwrap();
+    drop_db!(client, connection.database());
+
+    // Publish a procedure which adds a contact
+    let add_contact = |body: String| ProcedureDefinition {
+        name: ObjectName {
+            schema: Some(NAMESPACE.to_string()),
+            name: "add_contact".to_string(),
+        },
+        arguments: vec![FunctionArgument {
+            mode: None,
+            name: Some("contact_name".to_string()),
+            sql_type: SqlType::Simple(SimpleSqlType::Text, None),
+            default: None,
+        }],
+        body,
+        language: FunctionLanguage::PostgreSQL,
+        security_definer: false,
+        settings: Vec::new(),
+        comment: None,
+    };
+    let mut package = generate_simple_package!(NAMESPACE);
+    package.push_procedure(add_contact(format!(
+        "BEGIN INSERT INTO {}.contacts (name) VALUES (contact_name); END;",
+        NAMESPACE
+    )));
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert_eq!(final_package.procedures.len(), 1);
+    assert_eq!(
+        final_package.procedures[0].signature(),
+        format!("{}.add_contact(text)", NAMESPACE)
+    );
+    let mut client = connection.connect_database().unwrap();
+    client
+        .execute(&format!("CALL {}.add_contact('Alice')", NAMESPACE)[..], &[])
+        .unwrap();
+
+    // Changing the body and security replaces the procedure, while the comment is set separately
+    let mut procedure = add_contact(format!(
+        "BEGIN INSERT INTO {}.contacts (name) VALUES (upper(contact_name)); END;",
+        NAMESPACE
+    ));
+    procedure.security_definer = true;
+    procedure.comment = Some("Adds a contact by name".to_string());
+    package.procedures = vec![procedure];
+    let final_package = publish_package!(DB_NAME, connection, package);
+    assert!(final_package.procedures[0].security_definer);
+    assert_eq!(
+        final_package.procedures[0].comment,
+        Some("Adds a contact by name".to_string())
+    );
+    client
+        .execute(&format!("CALL {}.add_contact('Bob')", NAMESPACE)[..], &[])
+        .unwrap();
+    let names = client
+        .query(&format!("SELECT name FROM {}.contacts ORDER BY id", NAMESPACE)[..], &[])
+        .unwrap()
+        .iter()
+        .map(|row| row.get::<_, String>(0))
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["Alice", "BOB"]);
+    let log = Logger::root(Discard.fuse(), o!());
+    let drift = package.compare(&log, &connection).unwrap();
+    assert!(drift.is_empty(), "Unexpected drift: {:?}", drift);
+
+    // Removing the procedure drops it once allowed
+    package.procedures = Vec::new();
+    let mut publish_profile = PublishProfile::default();
+    publish_profile.generation_options.drop_procedures = Toggle::Allow;
+    let capabilities = Capabilities::from_connection(&log, &connection).unwrap();
+    let target_package = Package::from_connection(&log, &connection, &capabilities).unwrap();
+    let delta = Delta::generate(&log, &package, target_package, DB_NAME, &capabilities, &publish_profile).unwrap();
+    delta.apply(&log, &connection).unwrap();
+    let final_package = Package::from_connection(&log, &connection, &capabilities)
+        .unwrap()
+        .unwrap();
+    assert!(final_package.procedures.is_empty());
+}
@@ -0,0 +1,90 @@
+extern crate psqlpack;
+
+use std::path::PathBuf;
+
+use psqlpack::*;
+
+// These tests require a server configured with the certificates generated by `tests/tls/generate.sh` so are ignored
+// unless run explicitly with `--ignored`
+macro_rules! tls_dir {
+    () => {{
+        PathBuf::from(std::env::var("PSQLPACK_TLS_DIR").expect("PSQLPACK_TLS_DIR must be set to run the TLS tests"))
+    }};
+}
+
+macro_rules! connection {
+    ($host:expr, $tls_mode:expr) => {{
+        let mut builder = ConnectionBuilder::new("postgres", $host, "postgres");
+        builder
+            .with_password(std::env::var("POSTGRES_PASSWORD").unwrap_or("".into()))
+            .with_tls_mode($tls_mode);
+        builder
+    }};
+}
+
+// Returns whether the connection is using TLS along with the client certificate name if one was presented
+fn tls_status(builder: &ConnectionBuilder) -> (bool, Option<String>) {
+    let connection = builder.build().unwrap();
+    let mut client = connection.connect_database().unwrap();
+    let row = client
+        .query_one(
+            "SELECT ssl, client_dn FROM pg_stat_ssl WHERE pid = pg_backend_pid()",
+            &[],
+        )
+        .unwrap();
+    (row.get(0), row.get(1))
+}
+
+#[test]
+#[ignore = "requires a server configured for TLS"]
+fn it_only_uses_tls_when_not_disabled() {
+    assert_eq!(tls_status(&connection!("localhost", "disable")), (false, None));
+    assert_eq!(tls_status(&connection!("localhost", "prefer")), (true, None));
+    assert_eq!(tls_status(&connection!("localhost", "require")), (true, None));
+}
+
+#[test]
+#[ignore = "requires a server configured for TLS"]
+fn it_verifies_the_server_certificate_authority() {
+    let dir = tls_dir!();
+
+    // The server certificate is only valid for localhost however verify-ca doesn't check the host name
+    let mut builder = connection!("127.0.0.1", "verify-ca");
+    builder.with_root_certificate(dir.join("root.crt"));
+    assert_eq!(tls_status(&builder), (true, None));
+
+    let mut builder = connection!("localhost", "verify-ca");
+    builder.with_root_certificate(dir.join("untrusted.crt"));
+    assert!(builder.build().unwrap().connect_database().is_err());
+
+    // Providing a root certificate with require verifies the certificate authority like libpq
+    let mut builder = connection!("localhost", "require");
+    builder.with_root_certificate(dir.join("untrusted.crt"));
+    assert!(builder.build().unwrap().connect_database().is_err());
+}
+
+#[test]
+#[ignore = "requires a server configured for TLS"]
+fn it_verifies_the_server_host_name() {
+    let dir = tls_dir!();
+
+    let mut builder = connection!("localhost", "verify-full");
+    builder.with_root_certificate(dir.join("root.crt"));
+    assert_eq!(tls_status(&builder), (true, None));
+
+    let mut builder = connection!("127.0.0.1", "verify-full");
+    builder.with_root_certificate(dir.join("root.crt"));
+    assert!(builder.build().unwrap().connect_database().is_err());
+}
+
+#[test]
+#[ignore = "requires a server configured for TLS"]
+fn it_can_present_a_client_certificate() {
+    let dir = tls_dir!();
+
+    let mut builder = connection!("localhost", "verify-full");
+    builder
+        .with_root_certificate(dir.join("root.crt"))
+        .with_client_certificate(dir.join("client.crt"), dir.join("client.key"));
+    assert_eq!(tls_status(&builder), (true, Some("/CN=postgres".to_owned())));
+}
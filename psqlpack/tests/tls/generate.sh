@@ -0,0 +1,33 @@
+#!/usr/bin/env bash
+# Generates a self signed certificate authority along with server and client certificates for testing TLS
+# connections. The server certificate is only valid for `localhost`.
+#
+# Usage: generate.sh <output directory>
+#
+# Configure Postgres with:
+#   ssl = on
+#   ssl_cert_file = '<output directory>/server.crt'
+#   ssl_key_file = '<output directory>/server.key'
+#   ssl_ca_file = '<output directory>/root.crt'
+#
+# Then run the ignored tests with PSQLPACK_TLS_DIR=<output directory> cargo test -p psqlpack --test tls -- --ignored
+set -euo pipefail
+
+OUT=${1:?Output directory required}
+mkdir -p "$OUT"
+cd "$OUT"
+
+openssl req -new -x509 -days 3650 -nodes -subj "/CN=psqlpack test root" \
+    -keyout root.key -out root.crt
+openssl req -new -nodes -subj "/CN=localhost" -keyout server.key -out server.csr
+openssl x509 -req -days 3650 -in server.csr -CA root.crt -CAkey root.key -CAcreateserial \
+    -extfile <(printf "subjectAltName=DNS:localhost") -out server.crt
+openssl req -new -nodes -subj "/CN=postgres" -keyout client.key -out client.csr
+openssl x509 -req -days 3650 -in client.csr -CA root.crt -CAkey root.key -CAcreateserial -out client.crt
+
+# A second authority that hasn't issued the server certificate
+openssl req -new -x509 -days 3650 -nodes -subj "/CN=psqlpack untrusted root" \
+    -keyout untrusted.key -out untrusted.crt
+
+chmod 600 ./*.key
+rm -f ./*.csr ./*.srl